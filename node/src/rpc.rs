@@ -7,7 +7,7 @@
 
 use std::sync::Arc;
 
-use hydra_dx_runtime::{opaque::Block, AccountId, AssetId, Balance, BlockNumber, Hash, Index};
+use hydra_dx_runtime::{opaque::Block, AccountId, AssetId, Balance, BlockNumber, ExchangeCall, Hash, Index};
 use sc_consensus_babe::Epoch;
 use sc_consensus_babe_rpc::BabeRpcHandler;
 use sc_finality_grandpa::FinalityProofProvider;
@@ -72,12 +72,15 @@ where
 	C::Api: BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
 	C::Api: module_amm_rpc::AMMRuntimeApi<Block, AccountId, AssetId, Balance>,
-	P: TransactionPool + Sync + Send + 'static,
+	C::Api: module_exchange_rpc::ExchangeRuntimeApi<Block, AccountId, AssetId, Balance, Hash>,
+	C::Api: module_exchange_rpc::ExchangeSimulationRuntimeApi<Block, AccountId, AssetId, Balance, Hash, ExchangeCall>,
+	P: TransactionPool<Block = Block> + Sync + Send + 'static,
 	SC: SelectChain<Block> + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 	B::State: sc_client_api::StateBackend<sp_runtime::traits::HashFor<Block>>,
 {
 	use module_amm_rpc::{AMMApi, AMM};
+	use module_exchange_rpc::{Exchange, ExchangeApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 
@@ -106,7 +109,7 @@ where
 
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(
 		client.clone(),
-		pool,
+		pool.clone(),
 		deny_unsafe,
 	)));
 
@@ -121,6 +124,8 @@ where
 
 	io.extend_with(AMMApi::to_delegate(AMM::new(client.clone())));
 
+	io.extend_with(ExchangeApi::to_delegate(Exchange::new(client.clone(), pool)));
+
 	io.extend_with(sc_consensus_babe_rpc::BabeApi::to_delegate(BabeRpcHandler::new(
 		client,
 		shared_epoch_changes,
@@ -24,6 +24,56 @@ pub struct BalanceInfo<AssetId, Balance> {
 	pub asset: Option<AssetId>,
 }
 
+#[derive(Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RouteInfo<AssetId, Balance> {
+	pub route: Vec<AssetId>,
+
+	#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+	#[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+	#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+	#[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+	pub amount: Balance,
+}
+
+#[derive(Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct PositionInfo<AccountId, AssetId, Balance> {
+	pub position_id: u64,
+
+	pub pool_address: AccountId,
+
+	pub tick_lower: i32,
+
+	pub tick_upper: i32,
+
+	#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+	#[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+	#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+	#[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+	pub liquidity: Balance,
+
+	#[cfg_attr(
+		feature = "std",
+		serde(bound(
+			serialize = "AssetId: Serialize, Balance: std::fmt::Display",
+			deserialize = "AssetId: Deserialize<'de>, Balance: std::str::FromStr"
+		))
+	)]
+	pub amounts: Vec<BalanceInfo<AssetId, Balance>>,
+
+	#[cfg_attr(
+		feature = "std",
+		serde(bound(
+			serialize = "AssetId: Serialize, Balance: std::fmt::Display",
+			deserialize = "AssetId: Deserialize<'de>, Balance: std::str::FromStr"
+		))
+	)]
+	pub fees: Vec<BalanceInfo<AssetId, Balance>>,
+}
+
 #[cfg(feature = "std")]
 fn serialize_as_string<S: Serializer, T: std::fmt::Display>(t: &T, serializer: S) -> Result<S::Ok, S::Error> {
 	serializer.serialize_str(&t.to_string())
@@ -37,6 +87,8 @@ fn deserialize_from_string<'de, D: Deserializer<'de>, T: std::str::FromStr>(dese
 }
 
 sp_api::decl_runtime_apis! {
+	/// Version 2 adds route discovery, TWAP, LP fee and position queries.
+	#[api_version(2)]
 	pub trait AMMApi<AccountId, AssetId, Balance> where
 		AccountId: Codec,
 		AssetId: Codec,
@@ -63,5 +115,36 @@ sp_api::decl_runtime_apis! {
 		fn get_pool_balances(
 			pool_address: AccountId,
 		) -> Vec<BalanceInfo<AssetId, Balance>>;
+
+		fn get_best_route(
+			asset_in: AssetId,
+			asset_out: AssetId,
+			amount: Balance,
+			max_hops: u32,
+		) -> RouteInfo<AssetId, Balance>;
+
+		fn get_twap(
+			asset_a: AssetId,
+			asset_b: AssetId,
+			amount: Balance,
+			window: u32,
+		) -> Option<BalanceInfo<AssetId, Balance>>;
+
+		fn get_weighted_twap(
+			pool_assets: Vec<AssetId>,
+			asset_a: AssetId,
+			asset_b: AssetId,
+			amount: Balance,
+			window: u32,
+		) -> Option<BalanceInfo<AssetId, Balance>>;
+
+		fn get_accrued_fees(
+			who: AccountId,
+			pool_address: AccountId,
+		) -> Vec<BalanceInfo<AssetId, Balance>>;
+
+		fn get_positions(
+			who: AccountId,
+		) -> Vec<PositionInfo<AccountId, AssetId, Balance>>;
 	}
 }
@@ -1,7 +1,7 @@
 use codec::Codec;
 use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
 use jsonrpc_derive::rpc;
-use module_amm_rpc_runtime_api::BalanceInfo;
+use module_amm_rpc_runtime_api::{BalanceInfo, PositionInfo, RouteInfo};
 use serde::{Deserialize, Serialize};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
@@ -22,7 +22,7 @@ pub struct BalanceRequest<Balance> {
 }
 
 #[rpc]
-pub trait AMMApi<BlockHash, AccountId, AssetId, Balance, ResponseType> {
+pub trait AMMApi<BlockHash, AccountId, AssetId, Balance, ResponseType, RouteResponseType, PositionResponseType> {
 	#[rpc(name = "amm_getSpotPrice")]
 	fn get_spot_price(
 		&self,
@@ -52,6 +52,48 @@ pub trait AMMApi<BlockHash, AccountId, AssetId, Balance, ResponseType> {
 
 	#[rpc(name = "amm_getPoolBalances")]
 	fn get_pool_balances(&self, pool_address: AccountId, at: Option<BlockHash>) -> Result<Vec<ResponseType>>;
+
+	#[rpc(name = "amm_getBestRoute")]
+	fn get_best_route(
+		&self,
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount: Balance,
+		max_hops: u32,
+		at: Option<BlockHash>,
+	) -> Result<RouteResponseType>;
+
+	#[rpc(name = "amm_getTwap")]
+	fn get_twap(
+		&self,
+		asset_a: AssetId,
+		asset_b: AssetId,
+		amount: Balance,
+		window: u32,
+		at: Option<BlockHash>,
+	) -> Result<ResponseType>;
+
+	#[rpc(name = "amm_getWeightedTwap")]
+	fn get_weighted_twap(
+		&self,
+		pool_assets: Vec<AssetId>,
+		asset_a: AssetId,
+		asset_b: AssetId,
+		amount: Balance,
+		window: u32,
+		at: Option<BlockHash>,
+	) -> Result<ResponseType>;
+
+	#[rpc(name = "amm_getAccruedFees")]
+	fn get_accrued_fees(
+		&self,
+		who: AccountId,
+		pool_address: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Vec<ResponseType>>;
+
+	#[rpc(name = "amm_getPositions")]
+	fn get_positions(&self, who: AccountId, at: Option<BlockHash>) -> Result<Vec<PositionResponseType>>;
 }
 
 /// A struct that implements the [`AMMApi`].
@@ -73,18 +115,46 @@ impl<C, B> AMM<C, B> {
 pub enum Error {
 	/// The call to runtime failed.
 	RuntimeError,
+	/// Price history of the pool does not cover the requested window.
+	PriceNotAvailable,
 }
 
 impl From<Error> for i64 {
 	fn from(e: Error) -> i64 {
 		match e {
 			Error::RuntimeError => 1,
+			Error::PriceNotAvailable => 2,
 		}
 	}
 }
 
+/// Turn time weighted average price returned by runtime into RPC result.
+fn twap_result<AssetId, Balance, E: std::fmt::Debug>(
+	result: std::result::Result<Option<BalanceInfo<AssetId, Balance>>, E>,
+) -> Result<BalanceInfo<AssetId, Balance>> {
+	result
+		.map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to calculate time weighted average price.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?
+		.ok_or_else(|| RpcError {
+			code: ErrorCode::ServerError(Error::PriceNotAvailable.into()),
+			message: "Time weighted average price is not available.".into(),
+			data: None,
+		})
+}
+
 impl<C, Block, AccountId, AssetId, Balance>
-	AMMApi<<Block as BlockT>::Hash, AccountId, AssetId, Balance, BalanceInfo<AssetId, Balance>> for AMM<C, Block>
+	AMMApi<
+		<Block as BlockT>::Hash,
+		AccountId,
+		AssetId,
+		Balance,
+		BalanceInfo<AssetId, Balance>,
+		RouteInfo<AssetId, Balance>,
+		PositionInfo<AccountId, AssetId, Balance>,
+	> for AMM<C, Block>
 where
 	Block: BlockT,
 	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
@@ -166,4 +236,93 @@ where
 			data: Some(format!("{:?}", e).into()),
 		})
 	}
+
+	fn get_best_route(
+		&self,
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount: Balance,
+		max_hops: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<RouteInfo<AssetId, Balance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.get_best_route(&at, asset_in, asset_out, amount, max_hops)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to find best route.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+
+	fn get_twap(
+		&self,
+		asset_a: AssetId,
+		asset_b: AssetId,
+		amount: Balance,
+		window: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<BalanceInfo<AssetId, Balance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		twap_result(api.get_twap(&at, asset_a, asset_b, amount, window))
+	}
+
+	fn get_weighted_twap(
+		&self,
+		pool_assets: Vec<AssetId>,
+		asset_a: AssetId,
+		asset_b: AssetId,
+		amount: Balance,
+		window: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<BalanceInfo<AssetId, Balance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		twap_result(api.get_weighted_twap(&at, pool_assets, asset_a, asset_b, amount, window))
+	}
+
+	fn get_accrued_fees(
+		&self,
+		who: AccountId,
+		pool_address: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<BalanceInfo<AssetId, Balance>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.get_accrued_fees(&at, who, pool_address).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to retrieve accrued fees.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn get_positions(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<PositionInfo<AccountId, AssetId, Balance>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.get_positions(&at, who).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to retrieve positions.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
 }
@@ -48,7 +48,7 @@ benchmarks! {
 		let amount : Balance = 10 * 1_000_000_000;
 		let initial_price : Price = Price::from(2);
 
-	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, initial_price)
+	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, initial_price, fee::Fee::default(), PoolType::ConstantProduct)
 	verify {
 		assert_eq!(T::Currency::free_balance(asset_a, &caller), 999990000000000);
 	}
@@ -62,14 +62,30 @@ benchmarks! {
 		let amount : Balance = 10 * 1_000_000_000;
 		let max_limit : Balance = 10 * 1_000_000_000_000;
 
-		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a,asset_b, 1_000_000_000, Price::from(1))?;
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a,asset_b, 1_000_000_000, Price::from(1), fee::Fee::default(), PoolType::ConstantProduct)?;
 
-	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, max_limit)
+	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, max_limit, None)
 	verify {
 		assert_eq!(T::Currency::free_balance(asset_a, &caller), 999990000000000);
 		assert_eq!(T::Currency::free_balance(asset_b, &caller), 999990000000000);
 	}
 
+	add_liquidity_single_asset {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+		let amount : Balance = 10 * 1_000_000_000;
+
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1_000_000_000_000, Price::from(2), fee::Fee::default(), PoolType::ConstantProduct)?;
+
+	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, 0, None)
+	verify {
+		assert!(T::Currency::free_balance(asset_a, &caller) <= 999990000000002);
+		assert!(T::Currency::free_balance(AMM::<T>::share_token(AMM::<T>::get_pair_id(&asset_a, &asset_b)), &caller) > 0);
+	}
+
 	remove_liquidity {
 		let maker = funded_account::<T>("maker", 0);
 		let caller = funded_account::<T>("caller", 0);
@@ -78,18 +94,35 @@ benchmarks! {
 		let asset_b: AssetId = 2;
 		let amount : Balance = 1_000_000_000;
 
-		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), 1, 2, 10_000_000_000, Price::from(2))?;
-		AMM::<T>::add_liquidity(RawOrigin::Signed(caller.clone()).into(), 1, 2, 5_000_000_000, 10_000_000_000)?;
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), 1, 2, 10_000_000_000, Price::from(2), fee::Fee::default(), PoolType::ConstantProduct)?;
+		AMM::<T>::add_liquidity(RawOrigin::Signed(caller.clone()).into(), 1, 2, 5_000_000_000, 10_000_000_000, None)?;
 
 		assert_eq!(T::Currency::free_balance(asset_a, &caller), 999995000000000);
 		assert_eq!(T::Currency::free_balance(asset_b, &caller), 999990000000000);
 
-	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount)
+	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, 1_000_000_000, 2_000_000_000, None)
 	verify {
 		assert_eq!(T::Currency::free_balance(asset_a, &caller), 999996000000000);
 		assert_eq!(T::Currency::free_balance(asset_b, &caller), 999992000000000);
 	}
 
+	remove_liquidity_single_asset {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+		let amount : Balance = 1_000_000_000;
+
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), 1, 2, 10_000_000_000, Price::from(2), fee::Fee::default(), PoolType::ConstantProduct)?;
+		AMM::<T>::add_liquidity(RawOrigin::Signed(caller.clone()).into(), 1, 2, 5_000_000_000, 10_000_000_000, None)?;
+
+	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, 1_500_000_000, None)
+	verify {
+		assert!(T::Currency::free_balance(asset_a, &caller) > 999996500000000);
+		assert_eq!(T::Currency::free_balance(asset_b, &caller), 999990000000000);
+	}
+
 	sell {
 		let maker = funded_account::<T>("maker", 0);
 		let caller = funded_account::<T>("caller", 0);
@@ -101,9 +134,9 @@ benchmarks! {
 
 		let min_bought: Balance = 10 * 1_000;
 
-		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1 * 1_000_000_000_000, Price::from(3))?;
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1 * 1_000_000_000_000, Price::from(3), fee::Fee::default(), PoolType::ConstantProduct)?;
 
-	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, min_bought, discount)
+	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, min_bought, discount, None)
 	verify{
 		assert_eq!(T::Currency::free_balance(asset_a, &caller), 999999000000000);
 		assert_eq!(T::Currency::free_balance(asset_b, &caller), 1000002991014968);
@@ -120,13 +153,187 @@ benchmarks! {
 
 		let max_sold: Balance = 6_000_000_000;
 
-		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1 * 1_000_000_000_000, Price::from(3))?;
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1 * 1_000_000_000_000, Price::from(3), fee::Fee::default(), PoolType::ConstantProduct)?;
 
-	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, max_sold, discount)
+	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, amount, max_sold, discount, None)
 	verify{
 		assert_eq!(T::Currency::free_balance(asset_a, &caller), 1000001000000000);
 		assert_eq!(T::Currency::free_balance(asset_b, &caller), 999996990984966);
 	}
+
+	set_pool_fee {
+		let maker = funded_account::<T>("maker", 0);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+		let fee = fee::Fee { numerator: 1, denominator: 1000 };
+
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1 * 1_000_000_000_000, Price::from(3), fee::Fee::default(), PoolType::ConstantProduct)?;
+
+	}: _(RawOrigin::Root, asset_a, asset_b, fee)
+	verify{
+		assert_eq!(AMM::<T>::pool_fee(AMM::<T>::get_pair_id(&asset_a, &asset_b)), fee);
+	}
+
+	claim_fees {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1 * 1_000_000_000_000, Price::from(3), fee::Fee::default(), PoolType::ConstantProduct)?;
+		AMM::<T>::sell(RawOrigin::Signed(caller.clone()).into(), asset_a, asset_b, 1_000_000_000, 10_000, false, None)?;
+		AMM::<T>::buy(RawOrigin::Signed(caller.clone()).into(), asset_a, asset_b, 1_000_000_000, 6_000_000_000, false, None)?;
+
+	}: _(RawOrigin::Signed(maker.clone()), asset_a, asset_b)
+	verify{
+		let pair_account = AMM::<T>::get_pair_id(&asset_a, &asset_b);
+		let share_token = AMM::<T>::share_token(&pair_account);
+		assert_eq!(AMM::<T>::accrued_fees((share_token, asset_a), &maker), 0);
+		assert_eq!(AMM::<T>::accrued_fees((share_token, asset_b), &maker), 0);
+	}
+
+	create_weighted_pool {
+		let caller = funded_account::<T>("caller", 0);
+
+		let amount : Balance = 10 * 1_000_000_000;
+		let assets = vec![(1, Permill::from_percent(80), amount), (2, Permill::from_percent(20), amount)];
+
+	}: _(RawOrigin::Signed(caller.clone()), assets, fee::Fee::default())
+	verify {
+		assert_eq!(T::Currency::free_balance(1, &caller), 999990000000000);
+		assert_eq!(T::Currency::free_balance(2, &caller), 999990000000000);
+	}
+
+	add_weighted_liquidity_single_asset {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let pool_assets: Vec<AssetId> = vec![1, 2];
+		let amount : Balance = 1_000_000_000;
+
+		AMM::<T>::create_weighted_pool(RawOrigin::Signed(maker.clone()).into(), vec![(1, Permill::from_percent(80), 1_000_000_000_000), (2, Permill::from_percent(20), 1_000_000_000_000)], fee::Fee::default())?;
+
+	}: _(RawOrigin::Signed(caller.clone()), pool_assets.clone(), 1, amount, 0, None)
+	verify {
+		assert_eq!(T::Currency::free_balance(1, &caller), 999999000000000);
+		assert!(AMM::<T>::total_liquidity(AMM::<T>::get_weighted_pool_id(&pool_assets)) > 1_000_000_000_000);
+	}
+
+	remove_weighted_liquidity_single_asset {
+		let maker = funded_account::<T>("maker", 0);
+
+		let pool_assets: Vec<AssetId> = vec![1, 2];
+		let shares : Balance = 1_000_000_000;
+
+		AMM::<T>::create_weighted_pool(RawOrigin::Signed(maker.clone()).into(), vec![(1, Permill::from_percent(80), 1_000_000_000_000), (2, Permill::from_percent(20), 1_000_000_000_000)], fee::Fee::default())?;
+
+	}: _(RawOrigin::Signed(maker.clone()), pool_assets.clone(), 1, shares, 0, None)
+	verify {
+		assert_eq!(AMM::<T>::total_liquidity(AMM::<T>::get_weighted_pool_id(&pool_assets)), 999_000_000_000);
+		assert!(T::Currency::free_balance(1, &maker) > 999000000000000);
+	}
+
+	remove_weighted_liquidity {
+		let maker = funded_account::<T>("maker", 0);
+
+		let pool_assets: Vec<AssetId> = vec![1, 2];
+		let shares : Balance = 1_000_000_000_000;
+
+		AMM::<T>::create_weighted_pool(RawOrigin::Signed(maker.clone()).into(), vec![(1, Permill::from_percent(80), 1_000_000_000_000), (2, Permill::from_percent(20), 1_000_000_000_000)], fee::Fee::default())?;
+
+	}: _(RawOrigin::Signed(maker.clone()), pool_assets.clone(), shares, None)
+	verify {
+		assert_eq!(T::Currency::free_balance(1, &maker), 1000000000000000);
+		assert_eq!(T::Currency::free_balance(2, &maker), 1000000000000000);
+		assert!(!WeightedPoolAssets::<T>::contains_key(AMM::<T>::get_weighted_pool_id(&pool_assets)));
+	}
+
+	weighted_sell {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let pool_assets: Vec<AssetId> = vec![1, 2];
+		let amount : Balance = 1_000_000_000;
+
+		AMM::<T>::create_weighted_pool(RawOrigin::Signed(maker.clone()).into(), vec![(1, Permill::from_percent(80), 1_000_000_000_000), (2, Permill::from_percent(20), 1_000_000_000_000)], fee::Fee::default())?;
+
+	}: _(RawOrigin::Signed(caller.clone()), pool_assets, 1, 2, amount, 0, None)
+	verify {
+		assert_eq!(T::Currency::free_balance(1, &caller), 999999000000000);
+		assert!(T::Currency::free_balance(2, &caller) > 1000000000000000);
+	}
+
+	weighted_buy {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let pool_assets: Vec<AssetId> = vec![1, 2];
+		let amount : Balance = 1_000_000_000;
+
+		AMM::<T>::create_weighted_pool(RawOrigin::Signed(maker.clone()).into(), vec![(1, Permill::from_percent(80), 1_000_000_000_000), (2, Permill::from_percent(20), 1_000_000_000_000)], fee::Fee::default())?;
+
+	}: _(RawOrigin::Signed(caller.clone()), pool_assets, 2, 1, amount, 1_000_000_000, None)
+	verify {
+		assert_eq!(T::Currency::free_balance(2, &caller), 1000001000000000);
+		assert!(T::Currency::free_balance(1, &caller) < 1000000000000000);
+	}
+
+	mint_position {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+		let amount : Balance = 1_000_000_000;
+
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1_000_000_000_000, Price::from(1), fee::Fee::default(), PoolType::Concentrated(10))?;
+
+	}: _(RawOrigin::Signed(caller.clone()), asset_a, asset_b, -1_000, 1_000, amount, amount, None)
+	verify {
+		assert!(AMM::<T>::positions(&caller, 1).is_some());
+		assert!(T::Currency::free_balance(asset_a, &caller) < 1000000000000000);
+		assert!(T::Currency::free_balance(asset_b, &caller) < 1000000000000000);
+	}
+
+	remove_position_liquidity {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+		let amount : Balance = 1_000_000_000;
+
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1_000_000_000_000, Price::from(1), fee::Fee::default(), PoolType::Concentrated(10))?;
+		AMM::<T>::mint_position(RawOrigin::Signed(caller.clone()).into(), asset_a, asset_b, -1_000, 1_000, amount, amount, None)?;
+		AMM::<T>::sell(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, amount, 0, false, None)?;
+
+		let liquidity = AMM::<T>::positions(&caller, 1).map(|position| position.liquidity).unwrap_or_default();
+
+	}: _(RawOrigin::Signed(caller.clone()), 1, liquidity, None)
+	verify {
+		assert!(AMM::<T>::positions(&caller, 1).is_none());
+	}
+
+	collect_position_fees {
+		let maker = funded_account::<T>("maker", 0);
+		let caller = funded_account::<T>("caller", 0);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+		let amount : Balance = 1_000_000_000;
+
+		AMM::<T>::create_pool(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, 1_000_000_000_000, Price::from(1), fee::Fee::default(), PoolType::Concentrated(10))?;
+		AMM::<T>::mint_position(RawOrigin::Signed(caller.clone()).into(), asset_a, asset_b, -1_000, 1_000, amount, amount, None)?;
+		AMM::<T>::sell(RawOrigin::Signed(maker.clone()).into(), asset_a, asset_b, amount, 0, false, None)?;
+
+		let balance = T::Currency::free_balance(asset_a, &caller);
+
+	}: _(RawOrigin::Signed(caller.clone()), 1)
+	verify {
+		assert!(T::Currency::free_balance(asset_a, &caller) > balance);
+	}
 }
 
 #[cfg(test)]
@@ -140,9 +347,22 @@ mod tests {
 		new_test_ext().execute_with(|| {
 			assert_ok!(test_benchmark_create_pool::<Test>());
 			assert_ok!(test_benchmark_add_liquidity::<Test>());
+			assert_ok!(test_benchmark_add_liquidity_single_asset::<Test>());
 			assert_ok!(test_benchmark_remove_liquidity::<Test>());
+			assert_ok!(test_benchmark_remove_liquidity_single_asset::<Test>());
 			assert_ok!(test_benchmark_sell::<Test>());
 			assert_ok!(test_benchmark_buy::<Test>());
+			assert_ok!(test_benchmark_set_pool_fee::<Test>());
+			assert_ok!(test_benchmark_claim_fees::<Test>());
+			assert_ok!(test_benchmark_create_weighted_pool::<Test>());
+			assert_ok!(test_benchmark_add_weighted_liquidity_single_asset::<Test>());
+			assert_ok!(test_benchmark_remove_weighted_liquidity_single_asset::<Test>());
+			assert_ok!(test_benchmark_remove_weighted_liquidity::<Test>());
+			assert_ok!(test_benchmark_weighted_sell::<Test>());
+			assert_ok!(test_benchmark_weighted_buy::<Test>());
+			assert_ok!(test_benchmark_mint_position::<Test>());
+			assert_ok!(test_benchmark_remove_position_liquidity::<Test>());
+			assert_ok!(test_benchmark_collect_position_fees::<Test>());
 		});
 	}
 }
@@ -0,0 +1,661 @@
+//! Concentrated liquidity pricing.
+//!
+//! Liquidity is provided in price ranges bounded by ticks, price at tick `i` is `1.0001^i`.
+//! Within a range the pool behaves like a constant product pool with virtual reserves
+//! `x = L / sqrt(P)` and `y = L * sqrt(P)`, where `L` is the liquidity active in the range.
+//!
+//! Prices refer to the asset with lower id (token 0) denominated in the other asset (token 1)
+//! and are kept as square roots in Q64.64 fixed point format.
+
+use codec::{Decode, Encode};
+use core::convert::TryFrom;
+use frame_support::sp_runtime::{FixedPointNumber, RuntimeDebug};
+use primitive_types::{U256, U512};
+use primitives::{AssetId, Balance, Price};
+use sp_std::vec::Vec;
+
+pub type PositionId = u64;
+
+/// Square root of price in Q64.64 fixed point format.
+pub type SqrtPrice = u128;
+
+/// Fee growth per unit of liquidity in Q64.64 fixed point format.
+///
+/// Fee growth is expected to overflow, only differences of values are meaningful.
+pub type FeeGrowth = u128;
+
+pub const MIN_TICK: i32 = -400_000;
+pub const MAX_TICK: i32 = 400_000;
+
+/// Max allowed distance between ticks of a concentrated liquidity pool.
+pub const MAX_TICK_SPACING: u32 = 1_000;
+
+/// Min liquidity of a position.
+pub const MIN_POSITION_LIQUIDITY: Balance = 1_000_000_000;
+
+/// Max number of steps of a swap. In each step the swap either crosses an initialized tick
+/// or moves to the end of a word of the tick bitmap.
+pub const MAX_SWAP_STEPS: u32 = 128;
+
+/// Number of ticks tracked by one word of the tick bitmap.
+const TICKS_PER_WORD: i32 = 128;
+
+const RESOLUTION: usize = 64;
+const Q64: u128 = 1 << RESOLUTION;
+
+/// `sqrt(1.0001)` in Q64.64 fixed point format.
+const SQRT_TICK_BASE: u128 = 18_447_666_387_855_959_850;
+
+/// State of a concentrated liquidity pool.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct ConcentratedPool {
+	/// Current square root of price
+	pub sqrt_price: SqrtPrice,
+	/// Tick of the current price - greatest tick which price is not above the current price
+	pub tick: i32,
+	/// Liquidity of positions which are in range
+	pub liquidity: Balance,
+	/// Total fee growth of token 0 since the pool was created
+	pub fee_growth_0: FeeGrowth,
+	/// Total fee growth of token 1 since the pool was created
+	pub fee_growth_1: FeeGrowth,
+}
+
+/// Liquidity referencing a tick as a bound of a position.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct TickInfo {
+	/// Total liquidity of positions bounded by the tick
+	pub liquidity_gross: Balance,
+	/// Liquidity added when the price crosses the tick from left to right
+	pub liquidity_net: i128,
+	/// Fee growth of token 0 on the other side of the tick from the current price
+	pub fee_growth_outside_0: FeeGrowth,
+	/// Fee growth of token 1 on the other side of the tick from the current price
+	pub fee_growth_outside_1: FeeGrowth,
+}
+
+/// Liquidity provided to a concentrated liquidity pool in a price range.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct Position<AccountId> {
+	pub pool: AccountId,
+	pub tick_lower: i32,
+	pub tick_upper: i32,
+	pub liquidity: Balance,
+	/// Fee growth inside the range of the position when fees were last settled
+	pub fee_growth_inside_0: FeeGrowth,
+	pub fee_growth_inside_1: FeeGrowth,
+	/// Settled fees which were not collected yet
+	pub fees_owed_0: Balance,
+	pub fees_owed_1: Balance,
+}
+
+/// Position with amounts of assets backing its liquidity and its uncollected fees.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PositionDetails<AccountId> {
+	pub position_id: PositionId,
+	pub position: Position<AccountId>,
+	pub amounts: Vec<(AssetId, Balance)>,
+	pub fees: Vec<(AssetId, Balance)>,
+}
+
+/// Result of a swap.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct SwapResult {
+	pub amount_in: Balance,
+	pub amount_out: Balance,
+	/// Pool state after the swap
+	pub pool: ConcentratedPool,
+	/// Ticks crossed by the swap with fee growth of token 0 and token 1 at the time of crossing
+	pub crossed_ticks: Vec<(i32, FeeGrowth, FeeGrowth)>,
+}
+
+fn mul_div(a: U256, b: U256, c: U256, round_up: bool) -> Option<U256> {
+	if c.is_zero() {
+		return None;
+	}
+
+	let product = a.full_mul(b);
+	let c = U512::from(c);
+
+	let mut result = product / c;
+	if round_up && !(product % c).is_zero() {
+		result = result.checked_add(U512::one())?;
+	}
+
+	U256::try_from(result).ok()
+}
+
+fn to_u128(value: U256) -> Option<u128> {
+	if value > U256::from(u128::max_value()) {
+		None
+	} else {
+		Some(value.low_u128())
+	}
+}
+
+fn saturating_to_u128(value: U256) -> u128 {
+	to_u128(value).unwrap_or_else(u128::max_value)
+}
+
+/// Return word index and bit position of `tick` in the tick bitmap.
+///
+/// Only multiples of `tick_spacing` are tracked by the bitmap.
+pub fn tick_bitmap_position(tick: i32, tick_spacing: i32) -> (i32, u32) {
+	let compressed = tick.div_euclid(tick_spacing);
+
+	(
+		compressed.div_euclid(TICKS_PER_WORD),
+		compressed.rem_euclid(TICKS_PER_WORD) as u32,
+	)
+}
+
+/// Find the next initialized tick at or below `tick` if `lte`, above `tick` otherwise,
+/// searching only the word of the tick bitmap which contains it.
+///
+/// `word` returns word of the tick bitmap with given index.
+/// Returns the tick found, or the last tick of the word in the direction of search, and whether it is initialized.
+pub fn next_initialized_tick_within_word<W: Fn(i32) -> u128>(
+	tick: i32,
+	tick_spacing: i32,
+	lte: bool,
+	word: W,
+) -> (i32, bool) {
+	let compressed = tick.div_euclid(tick_spacing);
+
+	if lte {
+		let (word_index, bit) = tick_bitmap_position(tick, tick_spacing);
+
+		// Bits at or below the current tick
+		let mask = if bit == 127 {
+			u128::max_value()
+		} else {
+			(1u128 << (bit + 1)) - 1
+		};
+		let masked = word(word_index) & mask;
+
+		if masked != 0 {
+			let msb = 127 - masked.leading_zeros();
+			((compressed - (bit - msb) as i32) * tick_spacing, true)
+		} else {
+			((compressed - bit as i32) * tick_spacing, false)
+		}
+	} else {
+		let next = compressed + 1;
+		let (word_index, bit) = tick_bitmap_position(next * tick_spacing, tick_spacing);
+
+		// Bits at or above the next tick
+		let mask = !((1u128 << bit) - 1);
+		let masked = word(word_index) & mask;
+
+		if masked != 0 {
+			let lsb = masked.trailing_zeros();
+			((next + (lsb - bit) as i32) * tick_spacing, true)
+		} else {
+			((next + (127 - bit) as i32) * tick_spacing, false)
+		}
+	}
+}
+
+/// Add signed liquidity delta to liquidity.
+pub fn add_liquidity_delta(liquidity: Balance, delta: i128) -> Option<Balance> {
+	if delta >= 0 {
+		liquidity.checked_add(delta as u128)
+	} else {
+		liquidity.checked_sub(delta.checked_neg()? as u128)
+	}
+}
+
+/// Calculate square root of price at given tick.
+pub fn sqrt_price_at_tick(tick: i32) -> Option<SqrtPrice> {
+	if tick < MIN_TICK || tick > MAX_TICK {
+		return None;
+	}
+
+	let q64 = U256::from(Q64);
+
+	let mut exponent = (tick as i64).abs() as u64;
+	let mut base = U256::from(SQRT_TICK_BASE);
+	let mut result = q64;
+
+	while exponent > 0 {
+		if exponent % 2 == 1 {
+			result = result.checked_mul(base)? >> RESOLUTION;
+		}
+		exponent /= 2;
+		if exponent > 0 {
+			base = base.checked_mul(base)? >> RESOLUTION;
+		}
+	}
+
+	if tick < 0 {
+		result = (q64 << RESOLUTION).checked_div(result)?;
+	}
+
+	to_u128(result)
+}
+
+/// Calculate the greatest tick which square root of price is not above `sqrt_price`.
+pub fn tick_at_sqrt_price(sqrt_price: SqrtPrice) -> Option<i32> {
+	if sqrt_price < sqrt_price_at_tick(MIN_TICK)? || sqrt_price >= sqrt_price_at_tick(MAX_TICK)? {
+		return None;
+	}
+
+	let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+
+	while high - low > 1 {
+		let middle = low + (high - low) / 2;
+		if sqrt_price_at_tick(middle)? <= sqrt_price {
+			low = middle;
+		} else {
+			high = middle;
+		}
+	}
+
+	Some(low)
+}
+
+/// Calculate square root of price `numerator / denominator`.
+pub fn sqrt_price_from_ratio(numerator: Balance, denominator: Balance) -> Option<SqrtPrice> {
+	if denominator == 0 {
+		return None;
+	}
+
+	let ratio = (U512::from(numerator) << (2 * RESOLUTION)) / U512::from(denominator);
+
+	U256::try_from(ratio.integer_sqrt()).ok().and_then(to_u128)
+}
+
+/// Calculate spot price of token 0 denominated in token 1 or, if `inverse`, of token 1 in token 0.
+pub fn spot_price(sqrt_price: SqrtPrice, inverse: bool) -> Option<Price> {
+	let squared = U256::from(sqrt_price).checked_mul(U256::from(sqrt_price))?;
+	let accuracy = U256::from(Price::accuracy());
+	let q128 = U256::from(Q64) << RESOLUTION;
+
+	let inner = if inverse {
+		mul_div(q128, accuracy, squared, false)?
+	} else {
+		mul_div(squared, accuracy, q128, false)?
+	};
+
+	to_u128(inner).map(Price::from_inner)
+}
+
+/// Calculate amount of token 0 between two prices for given liquidity.
+pub fn amount_0_delta(
+	sqrt_price_a: SqrtPrice,
+	sqrt_price_b: SqrtPrice,
+	liquidity: Balance,
+	round_up: bool,
+) -> Option<Balance> {
+	let (lower, upper) = if sqrt_price_a < sqrt_price_b {
+		(sqrt_price_a, sqrt_price_b)
+	} else {
+		(sqrt_price_b, sqrt_price_a)
+	};
+
+	let numerator = U256::from(liquidity) << RESOLUTION;
+
+	let amount = mul_div(numerator, U256::from(upper - lower), U256::from(upper), round_up)?;
+	let amount = mul_div(amount, U256::one(), U256::from(lower), round_up)?;
+
+	to_u128(amount)
+}
+
+/// Calculate amount of token 1 between two prices for given liquidity.
+pub fn amount_1_delta(
+	sqrt_price_a: SqrtPrice,
+	sqrt_price_b: SqrtPrice,
+	liquidity: Balance,
+	round_up: bool,
+) -> Option<Balance> {
+	let difference = if sqrt_price_a < sqrt_price_b {
+		sqrt_price_b - sqrt_price_a
+	} else {
+		sqrt_price_a - sqrt_price_b
+	};
+
+	to_u128(mul_div(
+		U256::from(liquidity),
+		U256::from(difference),
+		U256::from(Q64),
+		round_up,
+	)?)
+}
+
+/// Calculate amounts of token 0 and token 1 backing `liquidity` of a position in given range.
+///
+/// Amounts are rounded up when liquidity is added and down when it is removed.
+pub fn amounts_for_liquidity(
+	pool: &ConcentratedPool,
+	tick_lower: i32,
+	tick_upper: i32,
+	liquidity: Balance,
+	round_up: bool,
+) -> Option<(Balance, Balance)> {
+	let sqrt_price_lower = sqrt_price_at_tick(tick_lower)?;
+	let sqrt_price_upper = sqrt_price_at_tick(tick_upper)?;
+
+	if pool.tick < tick_lower {
+		Some((
+			amount_0_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+			0,
+		))
+	} else if pool.tick < tick_upper {
+		Some((
+			amount_0_delta(pool.sqrt_price, sqrt_price_upper, liquidity, round_up)?,
+			amount_1_delta(sqrt_price_lower, pool.sqrt_price, liquidity, round_up)?,
+		))
+	} else {
+		Some((
+			0,
+			amount_1_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+		))
+	}
+}
+
+/// Calculate max liquidity of a position in given range which can be backed by given amounts.
+pub fn liquidity_for_amounts(
+	pool: &ConcentratedPool,
+	tick_lower: i32,
+	tick_upper: i32,
+	amount_0: Balance,
+	amount_1: Balance,
+) -> Option<Balance> {
+	let sqrt_price_lower = sqrt_price_at_tick(tick_lower)?;
+	let sqrt_price_upper = sqrt_price_at_tick(tick_upper)?;
+
+	let liquidity_0 = |lower: SqrtPrice, upper: SqrtPrice| -> Option<Balance> {
+		let amount = mul_div(U256::from(amount_0), U256::from(lower), U256::from(Q64), false)?;
+		to_u128(mul_div(amount, U256::from(upper), U256::from(upper - lower), false)?)
+	};
+	let liquidity_1 = |lower: SqrtPrice, upper: SqrtPrice| -> Option<Balance> {
+		to_u128(mul_div(
+			U256::from(amount_1),
+			U256::from(Q64),
+			U256::from(upper - lower),
+			false,
+		)?)
+	};
+
+	if pool.tick < tick_lower {
+		liquidity_0(sqrt_price_lower, sqrt_price_upper)
+	} else if pool.tick < tick_upper {
+		Some(sp_std::cmp::min(
+			liquidity_0(pool.sqrt_price, sqrt_price_upper)?,
+			liquidity_1(sqrt_price_lower, pool.sqrt_price)?,
+		))
+	} else {
+		liquidity_1(sqrt_price_lower, sqrt_price_upper)
+	}
+}
+
+/// Calculate square root of price after `amount` of token 0 is added to (or removed from) the pool.
+///
+/// Result is rounded up, so the price moves less when adding and more when removing.
+fn next_sqrt_price_from_amount_0(
+	sqrt_price: SqrtPrice,
+	liquidity: Balance,
+	amount: Balance,
+	add: bool,
+) -> Option<SqrtPrice> {
+	let numerator = U256::from(liquidity) << RESOLUTION;
+	let product = U256::from(amount).checked_mul(U256::from(sqrt_price))?;
+
+	let denominator = if add {
+		numerator.checked_add(product)?
+	} else {
+		numerator.checked_sub(product)?
+	};
+
+	to_u128(mul_div(numerator, U256::from(sqrt_price), denominator, true)?)
+}
+
+/// Calculate square root of price after `amount` of token 1 is added to (or removed from) the pool.
+///
+/// Result is rounded down, so the price moves less when adding and more when removing.
+fn next_sqrt_price_from_amount_1(
+	sqrt_price: SqrtPrice,
+	liquidity: Balance,
+	amount: Balance,
+	add: bool,
+) -> Option<SqrtPrice> {
+	let quotient = to_u128(mul_div(
+		U256::from(amount),
+		U256::from(Q64),
+		U256::from(liquidity),
+		!add,
+	)?)?;
+
+	if add {
+		sqrt_price.checked_add(quotient)
+	} else {
+		sqrt_price.checked_sub(quotient)
+	}
+}
+
+/// Calculate price and amounts of a swap within single range of constant liquidity,
+/// towards `target` price and limited by `amount_remaining`.
+///
+/// Returns new square root of price, amount in and amount out.
+fn compute_swap_step(
+	sqrt_price: SqrtPrice,
+	target: SqrtPrice,
+	liquidity: Balance,
+	amount_remaining: Balance,
+	zero_for_one: bool,
+	exact_input: bool,
+) -> Option<(SqrtPrice, Balance, Balance)> {
+	// Rounding must not move the price beyond the target
+	let clamp = |next: SqrtPrice| {
+		if zero_for_one {
+			sp_std::cmp::max(next, target)
+		} else {
+			sp_std::cmp::min(next, target)
+		}
+	};
+
+	let amount_in_to = |next: SqrtPrice| {
+		if zero_for_one {
+			amount_0_delta(next, sqrt_price, liquidity, true)
+		} else {
+			amount_1_delta(sqrt_price, next, liquidity, true)
+		}
+	};
+	let amount_out_to = |next: SqrtPrice| {
+		if zero_for_one {
+			amount_1_delta(next, sqrt_price, liquidity, false)
+		} else {
+			amount_0_delta(sqrt_price, next, liquidity, false)
+		}
+	};
+
+	if exact_input {
+		let max_in = amount_in_to(target)?;
+
+		if amount_remaining >= max_in {
+			return Some((target, max_in, amount_out_to(target)?));
+		}
+
+		let next = if zero_for_one {
+			next_sqrt_price_from_amount_0(sqrt_price, liquidity, amount_remaining, true)?
+		} else {
+			next_sqrt_price_from_amount_1(sqrt_price, liquidity, amount_remaining, true)?
+		};
+		let next = clamp(next);
+
+		Some((next, amount_remaining, amount_out_to(next)?))
+	} else {
+		let max_out = amount_out_to(target)?;
+
+		if amount_remaining >= max_out {
+			return Some((target, amount_in_to(target)?, max_out));
+		}
+
+		let next = if zero_for_one {
+			next_sqrt_price_from_amount_1(sqrt_price, liquidity, amount_remaining, false)?
+		} else {
+			next_sqrt_price_from_amount_0(sqrt_price, liquidity, amount_remaining, false)?
+		};
+		let next = clamp(next);
+
+		Some((next, amount_in_to(next)?, amount_remaining))
+	}
+}
+
+/// Swap `amount` of token in for token out, or token in for `amount` of token out if not `exact_input`.
+///
+/// `fee` is distributed to liquidity active during the swap, in proportion to the specified amount
+/// swapped in each range. It is denominated in token in for exact input and in token out otherwise.
+///
+/// `next_initialized_tick` searches the tick bitmap as `next_initialized_tick_within_word` and `liquidity_net`
+/// returns net liquidity of a tick. Returns `None` if the pool does not have enough liquidity or the swap
+/// would take more than `MAX_SWAP_STEPS` steps.
+pub fn swap<N: Fn(i32, bool) -> (i32, bool), F: Fn(i32) -> i128>(
+	pool: &ConcentratedPool,
+	zero_for_one: bool,
+	exact_input: bool,
+	amount: Balance,
+	fee: Balance,
+	next_initialized_tick: N,
+	liquidity_net: F,
+) -> Option<SwapResult> {
+	let mut pool = pool.clone();
+	let mut amount_remaining = amount;
+	let mut fee_remaining = fee;
+
+	let mut result_in: Balance = 0;
+	let mut result_out: Balance = 0;
+	let mut crossed_ticks = Vec::new();
+
+	// Fee is paid in token 0 when selling token 0 or buying token 1
+	let fee_in_token_0 = zero_for_one == exact_input;
+
+	let mut steps: u32 = 0;
+
+	while amount_remaining > 0 {
+		steps += 1;
+		if steps > MAX_SWAP_STEPS {
+			return None;
+		}
+
+		let (next_tick, initialized) = next_initialized_tick(pool.tick, zero_for_one);
+
+		let target_tick = sp_std::cmp::min(sp_std::cmp::max(next_tick, MIN_TICK), MAX_TICK);
+		let initialized = initialized && target_tick == next_tick;
+		let price_bound = target_tick == MIN_TICK || target_tick == MAX_TICK;
+
+		let target = sqrt_price_at_tick(target_tick)?;
+
+		let (next_sqrt_price, step_in, step_out) = compute_swap_step(
+			pool.sqrt_price,
+			target,
+			pool.liquidity,
+			amount_remaining,
+			zero_for_one,
+			exact_input,
+		)?;
+
+		let step_amount = if exact_input { step_in } else { step_out };
+
+		amount_remaining = amount_remaining.checked_sub(step_amount)?;
+		result_in = result_in.checked_add(step_in)?;
+		result_out = result_out.checked_add(step_out)?;
+
+		let step_fee = if amount_remaining == 0 {
+			fee_remaining
+		} else {
+			to_u128(mul_div(
+				U256::from(fee),
+				U256::from(step_amount),
+				U256::from(amount),
+				false,
+			)?)?
+		};
+		fee_remaining = fee_remaining.checked_sub(step_fee)?;
+
+		if step_fee > 0 && pool.liquidity > 0 {
+			// Growth saturates rather than failing the swap when liquidity in range is very small
+			let growth = saturating_to_u128(mul_div(
+				U256::from(step_fee),
+				U256::from(Q64),
+				U256::from(pool.liquidity),
+				false,
+			)?);
+
+			if fee_in_token_0 {
+				pool.fee_growth_0 = pool.fee_growth_0.wrapping_add(growth);
+			} else {
+				pool.fee_growth_1 = pool.fee_growth_1.wrapping_add(growth);
+			}
+		}
+
+		if next_sqrt_price == target && initialized {
+			crossed_ticks.push((target_tick, pool.fee_growth_0, pool.fee_growth_1));
+
+			let net = liquidity_net(target_tick);
+			let net = if zero_for_one { net.checked_neg()? } else { net };
+
+			pool.liquidity = add_liquidity_delta(pool.liquidity, net)?;
+			pool.tick = if zero_for_one { target_tick - 1 } else { target_tick };
+		} else if next_sqrt_price == target && !price_bound {
+			// End of a word of the tick bitmap reached
+			pool.tick = if zero_for_one { target_tick - 1 } else { target_tick };
+		} else if next_sqrt_price == target && amount_remaining > 0 {
+			// Price bound reached
+			return None;
+		} else {
+			pool.tick = tick_at_sqrt_price(next_sqrt_price)?;
+		}
+
+		pool.sqrt_price = next_sqrt_price;
+	}
+
+	Some(SwapResult {
+		amount_in: result_in,
+		amount_out: result_out,
+		pool,
+		crossed_ticks,
+	})
+}
+
+/// Calculate fee growth inside of the range of a position.
+pub fn fee_growth_inside(
+	pool: &ConcentratedPool,
+	lower: &TickInfo,
+	upper: &TickInfo,
+	tick_lower: i32,
+	tick_upper: i32,
+) -> (FeeGrowth, FeeGrowth) {
+	let below = |global: FeeGrowth, outside: FeeGrowth| {
+		if pool.tick >= tick_lower {
+			outside
+		} else {
+			global.wrapping_sub(outside)
+		}
+	};
+	let above = |global: FeeGrowth, outside: FeeGrowth| {
+		if pool.tick < tick_upper {
+			outside
+		} else {
+			global.wrapping_sub(outside)
+		}
+	};
+
+	(
+		pool.fee_growth_0
+			.wrapping_sub(below(pool.fee_growth_0, lower.fee_growth_outside_0))
+			.wrapping_sub(above(pool.fee_growth_0, upper.fee_growth_outside_0)),
+		pool.fee_growth_1
+			.wrapping_sub(below(pool.fee_growth_1, lower.fee_growth_outside_1))
+			.wrapping_sub(above(pool.fee_growth_1, upper.fee_growth_outside_1)),
+	)
+}
+
+/// Calculate fees earned by `liquidity` since fee growth inside of its range was `last`.
+pub fn fees_earned(fee_growth_inside: FeeGrowth, last: FeeGrowth, liquidity: Balance) -> Balance {
+	let growth = fee_growth_inside.wrapping_sub(last);
+
+	U256::try_from(U256::from(growth).full_mul(U256::from(liquidity)) >> RESOLUTION)
+		.map(saturating_to_u128)
+		.unwrap_or_else(|_| u128::max_value())
+}
@@ -1,15 +1,29 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
 use frame_support::sp_runtime::{
-	traits::{Hash, Zero},
-	DispatchError, FixedPointNumber,
+	helpers_128bit::multiply_by_rational,
+	traits::{CheckedDiv, CheckedSub, DispatchInfoOf, Hash, Saturating, SignedExtension, UniqueSaturatedInto, Zero},
+	transaction_validity::{InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction},
+	DispatchError, FixedPointNumber, FixedU128, Permill, RuntimeDebug,
 };
 use frame_support::{
-	decl_error, decl_event, decl_module, decl_storage, dispatch, dispatch::DispatchResult, ensure, traits::Get,
+	decl_error, decl_event, decl_module, decl_storage, dispatch,
+	dispatch::DispatchResult,
+	ensure,
+	storage::{IterableStorageDoubleMap, IterableStorageMap},
+	traits::{EnsureOrigin, Get, IsSubType},
+	transactional,
+	weights::Weight,
 };
 use frame_system::{self as system, ensure_signed};
-use primitives::{fee, traits::AMM, AssetId, Balance, Price, MAX_IN_RATIO, MAX_OUT_RATIO};
-use sp_std::{marker::PhantomData, vec, vec::Vec};
+use primitive_types::U256;
+use primitives::{
+	fee,
+	traits::{TWAPOracle, AMM},
+	AssetId, Balance, Price, MAX_IN_RATIO, MAX_OUT_RATIO, MAX_ROUTE_LENGTH,
+};
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData, vec, vec::Vec};
 
 use frame_support::sp_runtime::app_crypto::sp_core::crypto::UncheckedFrom;
 use orml_traits::{MultiCurrency, MultiCurrencyExtended};
@@ -25,8 +39,12 @@ mod benchmarking;
 #[cfg(test)]
 mod tests;
 
+pub mod concentrated;
+pub mod stableswap;
+pub mod weighted;
 pub mod weights;
 
+use concentrated::{ConcentratedPool, Position, PositionDetails, PositionId};
 use weights::WeightInfo;
 
 /// The pallet's configuration trait.
@@ -40,12 +58,66 @@ pub trait Config: frame_system::Config + pallet_asset_registry::Config {
 	/// Weight information for the extrinsics.
 	type WeightInfo: WeightInfo;
 
-	/// Trading fee rate
-	type GetExchangeFee: Get<fee::Fee>;
+	/// Maximum trading fee rate a pool can be created with or updated to
+	type MaxExchangeFee: Get<fee::Fee>;
+
+	/// Origin which can update trading fee rate of a pool
+	type UpdatePoolFeeOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Share of the trading fee which is taken by the protocol
+	type ProtocolFee: Get<Permill>;
+
+	/// Account which receives the protocol share of trading fees (e.g. treasury)
+	type ProtocolFeeReceiver: Get<Self::AccountId>;
+
+	/// Maximum number of cumulative price records kept for each pair of pool assets
+	type PriceHistoryLength: Get<u32>;
+}
+
+/// Price history of a pair of pool assets - pool account, asset a, asset b.
+pub type PriceHistoryKey<AccountId> = (AccountId, AssetId, AssetId);
+
+/// Weight of recording cumulative prices of `pairs` pairs of pool assets.
+fn price_history_weight<T: Config>(pairs: usize) -> Weight {
+	T::DbWeight::get().reads_writes(2, 2).saturating_mul(pairs as Weight)
+}
+
+/// Number of hops of a route charged in the weight of a route trade.
+/// The route is validated only after the weight is charged, so its length is bounded by `MAX_ROUTE_LENGTH`.
+fn route_hops(route: &[AssetId]) -> Weight {
+	sp_std::cmp::min(route.len(), MAX_ROUTE_LENGTH).saturating_sub(1) as Weight
+}
+
+/// Worst case weight of the tick search of a swap in a concentrated liquidity pool - a bitmap word and
+/// a tick are read in each step and a tick is updated when it is crossed.
+fn concentrated_swap_weight<T: Config>() -> Weight {
+	T::DbWeight::get()
+		.reads_writes(2, 1)
+		.saturating_mul(concentrated::MAX_SWAP_STEPS as Weight)
+}
+
+/// Pricing curve of a pool
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum PoolType {
+	/// Constant product `x * y = k` pool
+	ConstantProduct,
+	/// Stable swap pool for pegged assets with given amplification
+	StableSwap(u32),
+	/// Concentrated liquidity pool with given tick spacing
+	Concentrated(u32),
+}
+
+impl Default for PoolType {
+	fn default() -> Self {
+		PoolType::ConstantProduct
+	}
 }
 
 pub trait AssetPairAccountIdFor<AssetId: Sized, AccountId: Sized> {
 	fn from_assets(asset_a: AssetId, asset_b: AssetId) -> AccountId;
+
+	/// Return account of a pool of given set of assets, in any order.
+	fn from_asset_set(assets: &[AssetId]) -> AccountId;
 }
 
 pub struct AssetPairAccountId<T: Config>(PhantomData<T>);
@@ -66,6 +138,18 @@ where
 		}
 		T::AccountId::unchecked_from(T::Hashing::hash(&buf[..]))
 	}
+
+	fn from_asset_set(assets: &[AssetId]) -> T::AccountId {
+		let mut sorted = assets.to_vec();
+		sorted.sort_unstable();
+
+		let mut buf = Vec::new();
+		buf.extend_from_slice(b"hydradx-weighted");
+		for asset in sorted {
+			buf.extend_from_slice(&asset.to_le_bytes());
+		}
+		T::AccountId::unchecked_from(T::Hashing::hash(&buf[..]))
+	}
 }
 
 impl<T: Config> Module<T> {
@@ -91,6 +175,67 @@ decl_storage! {
 		TotalLiquidity get(fn total_liquidity): map hasher(blake2_128_concat) T::AccountId => Balance;
 
 		PoolAssets get(fn pool_assets): map hasher(blake2_128_concat) T::AccountId => (AssetId, AssetId);
+
+		/// Trading fee rate of each pool
+		PoolFee get(fn pool_fee): map hasher(blake2_128_concat) T::AccountId => fee::Fee;
+
+		/// Pricing curve of each pool
+		PoolTypes get(fn pool_type): map hasher(blake2_128_concat) T::AccountId => PoolType;
+
+		/// State of each concentrated liquidity pool
+		ConcentratedPools get(fn concentrated_pool): map hasher(blake2_128_concat) T::AccountId => ConcentratedPool;
+
+		/// Ticks of concentrated liquidity pools referenced by positions
+		Ticks get(fn ticks): double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) i32 => concentrated::TickInfo;
+
+		/// Bitmap of ticks of concentrated liquidity pools referenced by positions, indexed by word
+		TickBitmap get(fn tick_bitmap): double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) i32 => u128;
+
+		/// Concentrated liquidity positions of each account
+		Positions get(fn positions): double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) PositionId => Option<Position<T::AccountId>>;
+
+		/// Id of the next concentrated liquidity position
+		NextPositionId get(fn next_position_id): PositionId;
+
+		/// Assets and their normalized weights of each weighted pool, sorted by asset id
+		WeightedPoolAssets get(fn weighted_pool_assets): map hasher(blake2_128_concat) T::AccountId => Vec<(AssetId, Permill)>;
+
+		/// Trading fees accumulated per one pool share since the share token was created.
+		/// Keyed by (share token, fee asset).
+		AccumulatedFeePerShare get(fn accumulated_fee_per_share): map hasher(blake2_128_concat) (AssetId, AssetId) => FixedU128;
+
+		/// Total amount of trading fees which are still owed to liquidity providers.
+		/// Keyed by (share token, fee asset).
+		UnclaimedFees get(fn unclaimed_fees): map hasher(blake2_128_concat) (AssetId, AssetId) => Balance;
+
+		/// Value of `AccumulatedFeePerShare` when the fees of a liquidity provider were last settled.
+		/// Keyed by (share token, fee asset), liquidity provider.
+		FeeCheckpoint get(fn fee_checkpoint): double_map hasher(blake2_128_concat) (AssetId, AssetId), hasher(blake2_128_concat) T::AccountId => FixedU128;
+
+		/// Settled fees which can be claimed by a liquidity provider.
+		/// Keyed by (share token, fee asset), liquidity provider.
+		AccruedFees get(fn accrued_fees): double_map hasher(blake2_128_concat) (AssetId, AssetId), hasher(blake2_128_concat) T::AccountId => Balance;
+
+		/// Shares of a liquidity provider registered to earn fees when its fees were last settled.
+		/// Shares received by a plain transfer are registered at the next settlement of the receiver,
+		/// once the sender has been settled, and earn only fees collected after that.
+		/// Keyed by share token, liquidity provider.
+		FeeShares get(fn fee_shares): double_map hasher(blake2_128_concat) AssetId, hasher(blake2_128_concat) T::AccountId => Balance;
+
+		/// Sum of `FeeShares` of all liquidity providers, keyed by share token.
+		/// Fees of pool shares which are not registered stay in the pool reserves.
+		TotalFeeShares get(fn total_fee_shares): map hasher(blake2_128_concat) AssetId => Balance;
+
+		/// Total amount of trading fees collected by the protocol in each asset
+		ProtocolRevenue get(fn protocol_revenue): map hasher(blake2_128_concat) AssetId => Balance;
+
+		/// Cumulative prices of pairs of pool assets recorded in blocks in which the pool reserves changed.
+		/// Ring buffer of `PriceHistoryLength` records, record with index `i` is stored at `i % PriceHistoryLength`.
+		/// Block number, cumulative price of asset a in asset b, cumulative price of asset b in asset a.
+		PriceHistory get(fn price_history): double_map hasher(blake2_128_concat) PriceHistoryKey<T::AccountId>, hasher(twox_64_concat) u32 => Option<(T::BlockNumber, Price, Price)>;
+
+		/// Number of cumulative price records written to the price history of a pair of pool assets.
+		PriceHistoryHead get(fn price_history_head): map hasher(blake2_128_concat) PriceHistoryKey<T::AccountId> => u32;
 	}
 }
 
@@ -105,8 +250,15 @@ decl_event!(
 		/// AddLiquidity
 		/// who, asset_a, asset_b, amount_a, amount_b
 		AddLiquidity(AccountId, AssetId, AssetId, Balance, Balance),
-		/// who, asset_a, asset_b, shares
-		RemoveLiquidity(AccountId, AssetId, AssetId, Balance),
+		/// who, asset_a, asset_b, shares, amount_a, amount_b
+		RemoveLiquidity(AccountId, AssetId, AssetId, Balance, Balance, Balance),
+
+		/// Liquidity added in single asset - who, asset a, asset b, amount of asset a swapped, amount of asset b bought,
+		/// amount of asset a added, amount of asset b added, shares
+		SingleAssetLiquidityAdded(AccountId, AssetId, AssetId, Balance, Balance, Balance, Balance, Balance),
+
+		/// Liquidity removed in single asset - who, asset a, asset b, shares, amount of asset b sold, amount of asset a received
+		SingleAssetLiquidityRemoved(AccountId, AssetId, AssetId, Balance, Balance, Balance),
 
 		/// Pool creation - who, asset a, asset b, liquidity
 		CreatePool(AccountId, AssetId, AssetId, Balance),
@@ -119,6 +271,57 @@ decl_event!(
 
 		/// Buy token - who, asset buy, asset sell, amount, buy price
 		Buy(AccountId, AssetId, AssetId, Balance, Balance),
+
+		/// Routed sell - who, route, amount sold, amount bought
+		RouteSell(AccountId, Vec<AssetId>, Balance, Balance),
+
+		/// Routed buy - who, route, amount bought, amount sold
+		RouteBuy(AccountId, Vec<AssetId>, Balance, Balance),
+
+		/// Pool trading fee updated - asset a, asset b, fee
+		PoolFeeUpdated(AssetId, AssetId, fee::Fee),
+
+		/// Trading fees claimed - who, asset a, asset b, amount a, amount b
+		FeesClaimed(AccountId, AssetId, AssetId, Balance, Balance),
+
+		/// Fees of liquidity provider settled and its shares registered - who, asset a, asset b, registered shares
+		FeeSharesUpdated(AccountId, AssetId, AssetId, Balance),
+
+		/// Protocol share of trading fee collected - pool, asset, amount, receiver
+		ProtocolFeeCollected(AccountId, AssetId, Balance, AccountId),
+
+		/// Weighted pool creation - who, assets, liquidity
+		CreateWeightedPool(AccountId, Vec<AssetId>, Balance),
+
+		/// Weighted pool destroyed - who, pool
+		WeightedPoolDestroyed(AccountId, AccountId),
+
+		/// Single asset liquidity added to weighted pool - who, pool, asset, amount, shares
+		WeightedLiquidityAdded(AccountId, AccountId, AssetId, Balance, Balance),
+
+		/// Single asset liquidity removed from weighted pool - who, pool, asset, amount, shares
+		WeightedLiquidityRemoved(AccountId, AccountId, AssetId, Balance, Balance),
+
+		/// Liquidity removed from weighted pool in all assets - who, pool, shares, amounts
+		WeightedPoolLiquidityRemoved(AccountId, AccountId, Balance, Vec<(AssetId, Balance)>),
+
+		/// Weighted pool sell - who, pool, asset sell, asset buy, amount, sale price
+		WeightedSell(AccountId, AccountId, AssetId, AssetId, Balance, Balance),
+
+		/// Weighted pool buy - who, pool, asset buy, asset sell, amount, buy price
+		WeightedBuy(AccountId, AccountId, AssetId, AssetId, Balance, Balance),
+
+		/// Concentrated liquidity position created - who, position, pool, tick lower, tick upper
+		PositionCreated(AccountId, PositionId, AccountId, i32, i32),
+
+		/// Liquidity added to position - who, position, liquidity, amount of the asset with lower id, amount of the other asset
+		PositionLiquidityAdded(AccountId, PositionId, Balance, Balance, Balance),
+
+		/// Liquidity removed from position - who, position, liquidity, amount of the asset with lower id, amount of the other asset
+		PositionLiquidityRemoved(AccountId, PositionId, Balance, Balance, Balance),
+
+		/// Position fees collected - who, position, fee in the asset with lower id, fee in the other asset
+		PositionFeesCollected(AccountId, PositionId, Balance, Balance),
 	}
 );
 
@@ -131,6 +334,7 @@ decl_error! {
 		CannotCreatePoolWithZeroLiquidity,
 		CannotCreatePoolWithZeroInitialPrice,
 		CannotRemoveLiquidityWithZero,
+		CannotRemoveAllLiquidityInSingleAsset,
 
 		CannotAddZeroLiquidity,
 
@@ -142,6 +346,9 @@ decl_error! {
 		InvalidSharesDivResult,
 		InvalidMintedLiquidity,
 
+		/// Liquidity of a position is below the minimum
+		InsufficientPositionLiquidity,
+
 		NextAssetIdUnavailable,
 
 		TokenPoolNotFound,
@@ -164,6 +371,30 @@ decl_error! {
 		MaxOutRatioExceeded,
 
 		MaxInRatioExceeded,
+
+		InvalidRoute,
+		MaxRouteLengthExceeded,
+
+		InvalidFee,
+		MaxExchangeFeeExceeded,
+
+		NoFeesToClaim,
+
+		InvalidAmplification,
+
+		InvalidPoolAssetCount,
+		InvalidPoolWeights,
+		AssetNotInPool,
+		/// Asset cannot be traded for itself
+		CannotTradeSameAsset,
+
+		InvalidTickSpacing,
+		InvalidTickRange,
+		PositionNotFound,
+		UnsupportedPoolType,
+
+		/// Deadline of the call has passed
+		DeadlineExpired,
 	}
 }
 
@@ -181,12 +412,15 @@ decl_module! {
 		fn deposit_event() = default;
 
 		#[weight =  <T as Config>::WeightInfo::create_pool()]
+		#[transactional]
 		pub fn create_pool(
 			origin,
 			asset_a: AssetId,
 			asset_b: AssetId,
 			amount: Balance,
-			initial_price: Price
+			initial_price: Price,
+			fee: fee::Fee,
+			pool_type: PoolType,
 		) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 
@@ -209,6 +443,22 @@ decl_module! {
 				Error::<T>::TokenPoolAlreadyExists
 			);
 
+			Self::validate_fee(fee)?;
+
+			if let PoolType::StableSwap(amplification) = pool_type {
+				ensure!(
+					amplification >= 1 && amplification <= stableswap::MAX_AMPLIFICATION,
+					Error::<T>::InvalidAmplification
+				);
+			}
+
+			if let PoolType::Concentrated(tick_spacing) = pool_type {
+				ensure!(
+					tick_spacing >= 1 && tick_spacing <= concentrated::MAX_TICK_SPACING,
+					Error::<T>::InvalidTickSpacing
+				);
+			}
+
 			let asset_b_amount = initial_price.checked_mul_int(amount).ok_or(Error::<T>::CreatePoolAssetAmountInvalid)?;
 			let shares_added = if asset_a < asset_b { amount } else { asset_b_amount };
 
@@ -234,17 +484,37 @@ decl_module! {
 
 					<ShareToken<T>>::insert(&pair_account, &share_token);
 					<PoolAssets<T>>::insert(&pair_account, (asset_a, asset_b));
+					<PoolFee<T>>::insert(&pair_account, fee);
+					<PoolTypes<T>>::insert(&pair_account, pool_type);
+
+					Self::update_price_history(&pair_account);
+
 					share_token
 				}
 			};
 
+			// Liquidity of concentrated pools is provided in positions instead of shares
+			if let PoolType::Concentrated(tick_spacing) = pool_type {
+				let (amount_0, amount_1) = if asset_a < asset_b { (amount, asset_b_amount) } else { (asset_b_amount, amount) };
+
+				let liquidity = Self::initialize_concentrated_pool(&who, &pair_account, amount_0, amount_1, tick_spacing)?;
+
+				Self::deposit_event(RawEvent::CreatePool(who, asset_a, asset_b, liquidity));
+
+				return Ok(());
+			}
+
 			T::Currency::transfer(asset_a, &who, &pair_account, amount)?;
 			T::Currency::transfer(asset_b, &who, &pair_account, asset_b_amount)?;
 
+			Self::settle_fees(&who, &pair_account, share_token);
+
 			T::Currency::deposit(share_token, &who, shares_added)?;
 
 			<TotalLiquidity<T>>::insert(&pair_account, shares_added);
 
+			Self::record_fee_shares(&who, &pair_account, share_token);
+
 			Self::deposit_event(RawEvent::CreatePool(who, asset_a, asset_b, shares_added));
 
 			Ok(())
@@ -256,10 +526,13 @@ decl_module! {
 			asset_a: AssetId,
 			asset_b: AssetId,
 			amount_a: Balance,
-			amount_b_max_limit: Balance
+			amount_b_max_limit: Balance,
+			deadline: Option<T::BlockNumber>,
 		) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 
+			Self::ensure_not_expired(deadline)?;
+
 			ensure!(
 				Self::exists(asset_a, asset_b),
 				Error::<T>::TokenPoolNotFound
@@ -287,6 +560,11 @@ decl_module! {
 
 			let pair_account = Self::get_pair_id(&asset_a , &asset_b);
 
+			ensure!(
+				!Self::is_concentrated(&pair_account),
+				Error::<T>::UnsupportedPoolType
+			);
+
 			let share_token = Self::share_token(&pair_account);
 
 			let asset_a_reserve = T::Currency::free_balance(asset_a, &pair_account);
@@ -318,100 +596,222 @@ decl_module! {
 				Error::<T>::InsufficientAssetBalance
 			);
 
+			Self::update_price_history(&pair_account);
+
 			T::Currency::transfer(asset_a, &who, &pair_account, amount_a)?;
 			T::Currency::transfer(asset_b, &who, &pair_account, amount_b_required)?;
 
+			Self::settle_fees(&who, &pair_account, share_token);
+
 			T::Currency::deposit(share_token, &who, shares_added)?;
 
 			<TotalLiquidity<T>>::insert(&pair_account, liquidity_amount);
 
+			Self::record_fee_shares(&who, &pair_account, share_token);
+
 			Self::deposit_event(RawEvent::AddLiquidity(who, asset_a, asset_b, amount_a, amount_b_required));
 
 			Ok(())
 		}
 
-		#[weight =  <T as Config>::WeightInfo::remove_liquidity()]
-		pub fn remove_liquidity(
+		/// Add liquidity to the pool of `asset_a` and `asset_b` providing only `amount_a` of `asset_a`.
+		///
+		/// Portion of `amount_a` is sold for `asset_b` in the same pool so that the rest of `amount_a`
+		/// and the amount bought can be added in the current ratio of the pool. Any dust left after
+		/// the deposit stays with origin.
+		///
+		/// Fails if less than `min_shares` of pool shares would be minted.
+		#[weight =  <T as Config>::WeightInfo::add_liquidity_single_asset()]
+		#[transactional]
+		pub fn add_liquidity_single_asset(
 			origin,
 			asset_a: AssetId,
 			asset_b: AssetId,
-			liquidity_amount: Balance
+			amount_a: Balance,
+			min_shares: Balance,
+			deadline: Option<T::BlockNumber>,
 		) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 
-			ensure!(
-				!liquidity_amount.is_zero(),
-				Error::<T>::CannotRemoveLiquidityWithZero
-			);
+			Self::ensure_not_expired(deadline)?;
 
 			ensure!(
 				Self::exists(asset_a, asset_b),
 				Error::<T>::TokenPoolNotFound
 			);
 
-			let pair_account = Self::get_pair_id(&asset_a , &asset_b );
-
-			let share_token = Self::share_token(&pair_account);
-
-			let total_shares = Self::total_liquidity(&pair_account);
+			ensure!(
+				!amount_a.is_zero(),
+				Error::<T>::CannotAddZeroLiquidity
+			);
 
 			ensure!(
-				total_shares >= liquidity_amount,
+				T::Currency::free_balance(asset_a, &who) >= amount_a,
 				Error::<T>::InsufficientAssetBalance
 			);
 
+			let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+
 			ensure!(
-				T::Currency::free_balance(share_token, &who) >= liquidity_amount,
-				Error::<T>::InsufficientAssetBalance
+				!Self::is_concentrated(&pair_account),
+				Error::<T>::UnsupportedPoolType
 			);
 
+			let amount_swapped = Self::calculate_single_asset_swap_amount(asset_a, asset_b, amount_a)?;
+
 			ensure!(
-				!total_shares.is_zero(),
-				Error::<T>::CannotRemoveLiquidityWithZero
+				!amount_swapped.is_zero(),
+				Error::<T>::InvalidMintedLiquidity
 			);
 
+			let transfer = Self::validate_sell(&who, asset_a, asset_b, amount_swapped, Zero::zero(), false)?;
+			Self::execute_sell(&transfer)?;
+
+			let amount_bought = transfer.amount_out;
+			let amount_a_remaining = amount_a - amount_swapped;
+
 			let asset_a_reserve = T::Currency::free_balance(asset_a, &pair_account);
 			let asset_b_reserve = T::Currency::free_balance(asset_b, &pair_account);
 
-			let liquidity_out = hydra_dx_math::calculate_liquidity_out(asset_a_reserve,
+			let amount_b_required = hydra_dx_math::calculate_liquidity_in(asset_a_reserve,
 				asset_b_reserve,
-				liquidity_amount,
-				total_shares).ok_or(Error::<T>::RemoveAssetAmountInvalid)?;
+				amount_a_remaining).ok_or(Error::<T>::AddAssetAmountInvalid)?;
+
+			// Rounding of the swap may leave slightly less of asset b than needed for the rest of asset a
+			let (amount_a_added, amount_b_added) = if amount_b_required <= amount_bought {
+				(amount_a_remaining, amount_b_required)
+			} else {
+				let amount_a_required = hydra_dx_math::calculate_liquidity_in(asset_b_reserve,
+					asset_a_reserve,
+					amount_bought).ok_or(Error::<T>::AddAssetAmountInvalid)?;
 
-			let (remove_amount_a, remove_amount_b) = liquidity_out;
+				(sp_std::cmp::min(amount_a_required, amount_a_remaining), amount_bought)
+			};
+
+			let shares_added = if asset_a < asset_b { amount_a_added } else { amount_b_added };
 
 			ensure!(
-				T::Currency::free_balance(asset_a, &pair_account) >= remove_amount_a,
-				Error::<T>::InsufficientPoolAssetBalance
+				!shares_added.is_zero(),
+				Error::<T>::InvalidMintedLiquidity
 			);
+
 			ensure!(
-				T::Currency::free_balance(asset_b, &pair_account) >= remove_amount_b,
-				Error::<T>::InsufficientPoolAssetBalance
+				shares_added >= min_shares,
+				Error::<T>::AssetBalanceLimitExceeded
 			);
 
-			// Note: this check is not really needed as we already check if amount to remove >= liquidity in pool
-			let liquidity_left = total_shares.checked_sub(liquidity_amount).ok_or(Error::<T>::InvalidLiquidityAmount)?;
+			let liquidity_amount = Self::total_liquidity(&pair_account)
+				.checked_add(shares_added)
+				.ok_or(Error::<T>::InvalidLiquidityAmount)?;
 
-			T::Currency::transfer(asset_a, &pair_account, &who, remove_amount_a)?;
-			T::Currency::transfer(asset_b, &pair_account, &who, remove_amount_b)?;
+			T::Currency::transfer(asset_a, &who, &pair_account, amount_a_added)?;
+			T::Currency::transfer(asset_b, &who, &pair_account, amount_b_added)?;
 
-			T::Currency::withdraw(share_token, &who, liquidity_amount)?;
+			let share_token = Self::share_token(&pair_account);
+
+			Self::settle_fees(&who, &pair_account, share_token);
 
-			<TotalLiquidity<T>>::insert(&pair_account, liquidity_left);
+			T::Currency::deposit(share_token, &who, shares_added)?;
+
+			<TotalLiquidity<T>>::insert(&pair_account, liquidity_amount);
 
-			Self::deposit_event(RawEvent::RemoveLiquidity(who.clone(), asset_a, asset_b, liquidity_amount));
+			Self::record_fee_shares(&who, &pair_account, share_token);
 
-			if liquidity_left == 0 {
-				<ShareToken<T>>::remove(&pair_account);
-				<PoolAssets<T>>::remove(&pair_account);
+			Self::deposit_event(RawEvent::SingleAssetLiquidityAdded(
+				who,
+				asset_a,
+				asset_b,
+				amount_swapped,
+				amount_bought,
+				amount_a_added,
+				amount_b_added,
+				shares_added,
+			));
 
-				Self::deposit_event(RawEvent::PoolDestroyed(who, asset_a, asset_b));
-			}
+			Ok(())
+		}
+
+		/// Remove `liquidity_amount` of shares from the pool of `asset_a` and `asset_b`.
+		///
+		/// Fails if less than `min_amount_a` of `asset_a` or `min_amount_b` of `asset_b` would be returned.
+		#[weight =  <T as Config>::WeightInfo::remove_liquidity()]
+		#[transactional]
+		pub fn remove_liquidity(
+			origin,
+			asset_a: AssetId,
+			asset_b: AssetId,
+			liquidity_amount: Balance,
+			min_amount_a: Balance,
+			min_amount_b: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			Self::do_remove_liquidity(&who, asset_a, asset_b, liquidity_amount, min_amount_a, min_amount_b)?;
+
+			Ok(())
+		}
+
+		/// Remove `liquidity_amount` of shares from the pool of `asset_a` and `asset_b` and receive only `asset_a`.
+		///
+		/// Amount of `asset_b` returned by the pool is sold for `asset_a` in the same pool.
+		/// Fails if less than `min_amount_a` of `asset_a` would be received in total.
+		#[weight =  <T as Config>::WeightInfo::remove_liquidity_single_asset()]
+		#[transactional]
+		pub fn remove_liquidity_single_asset(
+			origin,
+			asset_a: AssetId,
+			asset_b: AssetId,
+			liquidity_amount: Balance,
+			min_amount_a: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			ensure!(
+				Self::exists(asset_a, asset_b),
+				Error::<T>::TokenPoolNotFound
+			);
+
+			ensure!(
+				Self::total_liquidity(Self::get_pair_id(&asset_a, &asset_b)) > liquidity_amount,
+				Error::<T>::CannotRemoveAllLiquidityInSingleAsset
+			);
+
+			let (amount_a_removed, amount_b_removed) = Self::do_remove_liquidity(&who, asset_a, asset_b, liquidity_amount, Zero::zero(), Zero::zero())?;
+
+			let amount_bought = if amount_b_removed.is_zero() {
+				Zero::zero()
+			} else {
+				let transfer = Self::validate_sell(&who, asset_b, asset_a, amount_b_removed, Zero::zero(), false)?;
+				Self::execute_sell(&transfer)?;
+				transfer.amount_out
+			};
+
+			let amount_a_received = amount_a_removed.checked_add(amount_bought).ok_or(Error::<T>::RemoveAssetAmountInvalid)?;
+
+			ensure!(
+				amount_a_received >= min_amount_a,
+				Error::<T>::AssetBalanceLimitExceeded
+			);
+
+			Self::deposit_event(RawEvent::SingleAssetLiquidityRemoved(
+				who,
+				asset_a,
+				asset_b,
+				liquidity_amount,
+				amount_b_removed,
+				amount_a_received,
+			));
 
 			Ok(())
 		}
 
-		#[weight =  <T as Config>::WeightInfo::sell()]
+		#[weight =  <T as Config>::WeightInfo::sell().saturating_add(concentrated_swap_weight::<T>())]
 		pub fn sell(
 			origin,
 			asset_sell: AssetId,
@@ -419,13 +819,16 @@ decl_module! {
 			amount_sell: Balance,
 			max_limit: Balance,
 			discount: bool,
+			deadline: Option<T::BlockNumber>,
 		) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 
+			Self::ensure_not_expired(deadline)?;
+
 			<Self as AMM<_,_,_>>::sell(&who, asset_sell, asset_buy, amount_sell, max_limit, discount)
 		}
 
-		#[weight =  <T as Config>::WeightInfo::buy()]
+		#[weight =  <T as Config>::WeightInfo::buy().saturating_add(concentrated_swap_weight::<T>())]
 		pub fn buy(
 			origin,
 			asset_buy: AssetId,
@@ -433,103 +836,2206 @@ decl_module! {
 			amount_buy: Balance,
 			max_limit: Balance,
 			discount: bool,
+			deadline: Option<T::BlockNumber>,
 		) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 
+			Self::ensure_not_expired(deadline)?;
+
 			<Self as AMM<_,_,_>>::buy(&who, asset_buy, asset_sell, amount_buy, max_limit, discount)
 		}
-	}
-}
 
-impl<T: Config> Module<T> {
-	pub fn get_spot_price(asset_a: AssetId, asset_b: AssetId, amount: Balance) -> Balance {
-		match Self::exists(asset_a, asset_b) {
-			true => Self::get_spot_price_unchecked(asset_a, asset_b, amount),
-			false => 0,
-		}
-	}
+		/// Claim trading fees accrued to the liquidity provided by origin to the pool of `asset_a` and `asset_b`.
+		///
+		/// Pool shares are not affected. Not supported by weighted pools, whose fees stay in the reserves.
+		/// Shares received by a plain transfer are registered with `update_fee_shares`.
+		#[weight =  <T as Config>::WeightInfo::claim_fees()]
+		#[transactional]
+		pub fn claim_fees(
+			origin,
+			asset_a: AssetId,
+			asset_b: AssetId,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
 
-	pub fn get_sell_price(asset_a: AssetId, asset_b: AssetId, amount: Balance) -> Balance {
-		match Self::exists(asset_a, asset_b) {
-			true => {
-				let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+			ensure!(
+				Self::exists(asset_a, asset_b),
+				Error::<T>::TokenPoolNotFound
+			);
 
-				let asset_a_reserve = T::Currency::free_balance(asset_a, &pair_account);
-				let asset_b_reserve = T::Currency::free_balance(asset_b, &pair_account);
+			let pair_account = Self::get_pair_id(&asset_a, &asset_b);
 
-				hydra_dx_math::calculate_sell_price(asset_a_reserve, asset_b_reserve, amount)
-					.or(Some(0))
-					.unwrap()
-			}
-			false => 0,
-		}
-	}
+			ensure!(
+				!Self::is_concentrated(&pair_account),
+				Error::<T>::UnsupportedPoolType
+			);
 
-	pub fn get_buy_price(asset_a: AssetId, asset_b: AssetId, amount: Balance) -> Balance {
-		match Self::exists(asset_a, asset_b) {
-			true => {
-				let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+			let share_token = Self::share_token(&pair_account);
 
-				let asset_a_reserve = T::Currency::free_balance(asset_a, &pair_account);
-				let asset_b_reserve = T::Currency::free_balance(asset_b, &pair_account);
-				hydra_dx_math::calculate_buy_price(asset_b_reserve, asset_a_reserve, amount)
-					.or(Some(0))
-					.unwrap()
-			}
-			false => 0,
-		}
-	}
+			Self::settle_fees(&who, &pair_account, share_token);
 
-	pub fn get_pool_balances(pool_address: T::AccountId) -> Option<Vec<(AssetId, Balance)>> {
-		let mut balances = Vec::new();
+			let share_balance = T::Currency::free_balance(share_token, &who);
 
-		if let Some(assets) = Self::get_pool_assets(&pool_address) {
-			for item in &assets {
-				let reserve = T::Currency::free_balance(*item, &pool_address);
-				balances.push((*item, reserve));
-			}
-		}
-		Some(balances)
-	}
+			let fees_a = Self::take_accrued_fees(&who, share_token, asset_a, share_balance, share_balance)?;
+			let fees_b = Self::take_accrued_fees(&who, share_token, asset_b, share_balance, share_balance)?;
 
-	fn calculate_fees(amount: Balance, discount: bool, hdx_fee: &mut Balance) -> Result<Balance, DispatchError> {
-		match discount {
-			true => {
-				let transfer_fee = amount
-					.discounted_fee()
-					.ok_or::<Error<T>>(Error::<T>::FeeAmountInvalid)?;
-				*hdx_fee = transfer_fee;
-				Ok(transfer_fee)
-			}
-			false => {
-				*hdx_fee = 0;
-				Ok(amount
-					.just_fee(T::GetExchangeFee::get())
-					.ok_or::<Error<T>>(Error::<T>::FeeAmountInvalid)?)
-			}
-		}
-	}
-}
+			ensure!(
+				!fees_a.is_zero() || !fees_b.is_zero(),
+				Error::<T>::NoFeesToClaim
+			);
 
-impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
-	fn exists(asset_a: AssetId, asset_b: AssetId) -> bool {
-		let pair_account = T::AssetPairAccountId::from_assets(asset_a, asset_b);
-		<ShareToken<T>>::contains_key(&pair_account)
-	}
+			Self::update_price_history(&pair_account);
 
-	fn get_pair_id(asset_a: &AssetId, asset_b: &AssetId) -> T::AccountId {
-		T::AssetPairAccountId::from_assets(*asset_a, *asset_b)
-	}
+			T::Currency::transfer(asset_a, &pair_account, &who, fees_a)?;
+			T::Currency::transfer(asset_b, &pair_account, &who, fees_b)?;
 
-	fn get_pool_assets(pool_account_id: &T::AccountId) -> Option<Vec<AssetId>> {
-		match <PoolAssets<T>>::contains_key(pool_account_id) {
-			true => {
-				let assets = Self::pool_assets(pool_account_id);
-				Some(vec![assets.0, assets.1])
-			}
-			false => None,
-		}
-	}
+			Self::deposit_event(RawEvent::FeesClaimed(who, asset_a, asset_b, fees_a, fees_b));
+
+			Ok(())
+		}
+
+		/// Settle trading fees of `who` in the pool of `asset_a` and `asset_b` and register its current share
+		/// balance to earn fees.
+		///
+		/// Can be called by anyone. Receiver of shares transferred by a plain transfer registers them with this call,
+		/// after the sender is settled to release the shares it no longer holds.
+		#[weight =  <T as Config>::WeightInfo::claim_fees()]
+		pub fn update_fee_shares(
+			origin,
+			who: T::AccountId,
+			asset_a: AssetId,
+			asset_b: AssetId,
+		) -> dispatch::DispatchResult {
+			ensure_signed(origin)?;
+
+			ensure!(
+				Self::exists(asset_a, asset_b),
+				Error::<T>::TokenPoolNotFound
+			);
+
+			let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+
+			ensure!(
+				!Self::is_concentrated(&pair_account),
+				Error::<T>::UnsupportedPoolType
+			);
+
+			let share_token = Self::share_token(&pair_account);
+
+			Self::settle_fees(&who, &pair_account, share_token);
+
+			let fee_shares = Self::fee_shares(share_token, &who);
+
+			Self::deposit_event(RawEvent::FeeSharesUpdated(who, asset_a, asset_b, fee_shares));
+
+			Ok(())
+		}
+
+		/// Update trading fee rate of the pool of `asset_a` and `asset_b`.
+		///
+		/// Can be called only by `UpdatePoolFeeOrigin`.
+		#[weight =  <T as Config>::WeightInfo::set_pool_fee()]
+		pub fn set_pool_fee(
+			origin,
+			asset_a: AssetId,
+			asset_b: AssetId,
+			fee: fee::Fee
+		) -> dispatch::DispatchResult {
+			T::UpdatePoolFeeOrigin::ensure_origin(origin)?;
+
+			ensure!(
+				Self::exists(asset_a, asset_b),
+				Error::<T>::TokenPoolNotFound
+			);
+
+			Self::validate_fee(fee)?;
+
+			let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+
+			<PoolFee<T>>::insert(&pair_account, fee);
+
+			Self::deposit_event(RawEvent::PoolFeeUpdated(asset_a, asset_b, fee));
+
+			Ok(())
+		}
+
+		/// Sell `amount_sell` of the first asset in `route` for the last asset in `route`,
+		/// trading through the pool of each consecutive pair of assets.
+		///
+		/// `min_bought` is applied to the final amount only. All hops are reverted if any of them fails.
+		#[weight =  <T as Config>::WeightInfo::sell()
+			.saturating_add(concentrated_swap_weight::<T>())
+			.saturating_mul(route_hops(&route))]
+		#[transactional]
+		pub fn sell_route(
+			origin,
+			route: Vec<AssetId>,
+			amount_sell: Balance,
+			min_bought: Balance,
+			discount: bool,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			Self::validate_route(&route)?;
+
+			let mut amount = amount_sell;
+
+			for hop in route.windows(2) {
+				let transfer = <Self as AMM<_,_,_>>::validate_sell(&who, hop[0], hop[1], amount, Balance::zero(), discount)?;
+				<Self as AMM<_,_,_>>::execute_sell(&transfer)?;
+				amount = transfer.amount_out;
+			}
+
+			ensure!(min_bought <= amount, Error::<T>::AssetBalanceLimitExceeded);
+
+			Self::deposit_event(RawEvent::RouteSell(who, route, amount_sell, amount));
+
+			Ok(())
+		}
+
+		/// Buy `amount_buy` of the last asset in `route` with the first asset in `route`,
+		/// trading through the pool of each consecutive pair of assets.
+		///
+		/// `max_sold` is applied to the amount of the first asset only. All hops are reverted if any of them fails.
+		#[weight =  <T as Config>::WeightInfo::buy()
+			.saturating_add(concentrated_swap_weight::<T>())
+			.saturating_mul(route_hops(&route))]
+		#[transactional]
+		pub fn buy_route(
+			origin,
+			route: Vec<AssetId>,
+			amount_buy: Balance,
+			max_sold: Balance,
+			discount: bool,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			Self::validate_route(&route)?;
+
+			// Work out backwards how much of each asset has to be bought in order to end up with `amount_buy`.
+			let mut amounts = vec![Balance::zero(); route.len()];
+			amounts[route.len() - 1] = amount_buy;
+
+			for idx in (0..route.len() - 1).rev() {
+				amounts[idx] = Self::calculate_buy_amount_with_fee(route[idx + 1], route[idx], amounts[idx + 1], discount)?;
+			}
+
+			let amount_sold = amounts[0];
+
+			ensure!(max_sold >= amount_sold, Error::<T>::AssetBalanceLimitExceeded);
+
+			for idx in 0..route.len() - 1 {
+				let transfer = <Self as AMM<_,_,_>>::validate_buy(&who, route[idx + 1], route[idx], amounts[idx + 1], amounts[idx], discount)?;
+				<Self as AMM<_,_,_>>::execute_buy(&transfer)?;
+			}
+
+			Self::deposit_event(RawEvent::RouteBuy(who, route, amount_buy, amount_sold));
+
+			Ok(())
+		}
+
+		/// Create weighted pool of two or more assets.
+		///
+		/// Each entry of `assets` contains asset, its normalized weight and initial liquidity.
+		/// Weights must add up to 100%. Pool account is derived from the sorted set of assets.
+		///
+		/// Shares are minted in the amount of initial liquidity of the asset with the lowest id.
+		///
+		/// Liquidity provider part of trading fees of weighted pools is not tracked for `claim_fees`,
+		/// it stays in the reserves and increases the value of pool shares instead.
+		#[weight =  <T as Config>::WeightInfo::create_weighted_pool()
+			.saturating_add(price_history_weight::<T>(assets.len().saturating_mul(assets.len())))]
+		#[transactional]
+		pub fn create_weighted_pool(
+			origin,
+			assets: Vec<(AssetId, Permill, Balance)>,
+			fee: fee::Fee,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut assets = assets;
+			assets.sort_by_key(|&(asset, _, _)| asset);
+
+			Self::validate_weighted_pool_assets(&assets)?;
+
+			Self::validate_fee(fee)?;
+
+			let asset_ids: Vec<AssetId> = assets.iter().map(|&(asset, _, _)| asset).collect();
+
+			let pool_account = Self::get_weighted_pool_id(&asset_ids);
+
+			ensure!(
+				!<WeightedPoolAssets<T>>::contains_key(&pool_account),
+				Error::<T>::TokenPoolAlreadyExists
+			);
+
+			for &(asset, _, amount) in assets.iter() {
+				ensure!(
+					T::Currency::free_balance(asset, &who) >= amount,
+					Error::<T>::InsufficientAssetBalance
+				);
+			}
+
+			let token_name = Self::get_weighted_pool_token_name(&asset_ids);
+
+			let share_token = <pallet_asset_registry::Module<T>>::create_asset(token_name)?.into();
+
+			let shares_added = assets[0].2;
+
+			for &(asset, _, amount) in assets.iter() {
+				T::Currency::transfer(asset, &who, &pool_account, amount)?;
+			}
+
+			T::Currency::deposit(share_token, &who, shares_added)?;
+
+			<ShareToken<T>>::insert(&pool_account, &share_token);
+			<WeightedPoolAssets<T>>::insert(&pool_account, assets.iter().map(|&(asset, weight, _)| (asset, weight)).collect::<Vec<_>>());
+			<PoolFee<T>>::insert(&pool_account, fee);
+			<TotalLiquidity<T>>::insert(&pool_account, shares_added);
+
+			Self::update_weighted_price_history(&pool_account, &asset_ids);
+
+			Self::deposit_event(RawEvent::CreateWeightedPool(who, asset_ids, shares_added));
+
+			Ok(())
+		}
+
+		/// Add liquidity to the weighted pool of `pool_assets` in single asset.
+		///
+		/// Part of the deposit which is effectively swapped for other pool assets is charged trading fee.
+		#[weight =  <T as Config>::WeightInfo::add_weighted_liquidity_single_asset()
+			.saturating_add(price_history_weight::<T>(pool_assets.len()))]
+		#[transactional]
+		pub fn add_weighted_liquidity_single_asset(
+			origin,
+			pool_assets: Vec<AssetId>,
+			asset: AssetId,
+			amount: Balance,
+			min_shares: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			ensure!(
+				!amount.is_zero(),
+				Error::<T>::CannotAddZeroLiquidity
+			);
+
+			let (pool_account, weight) = Self::get_weighted_pool_asset(&pool_assets, asset)?;
+
+			ensure!(
+				T::Currency::free_balance(asset, &who) >= amount,
+				Error::<T>::InsufficientAssetBalance
+			);
+
+			let reserve = T::Currency::free_balance(asset, &pool_account);
+
+			ensure!(
+				amount <= reserve / MAX_IN_RATIO,
+				Error::<T>::MaxInRatioExceeded
+			);
+
+			let transfer_fee = amount
+				.saturating_sub(weight * amount)
+				.just_fee(Self::pool_fee(&pool_account))
+				.ok_or(Error::<T>::FeeAmountInvalid)?;
+
+			let total_liquidity = Self::total_liquidity(&pool_account);
+
+			let shares_added = weighted::calculate_shares_for_deposit(
+				reserve,
+				weight,
+				total_liquidity,
+				amount - transfer_fee,
+			).ok_or(Error::<T>::AddSharesAmountInvalid)?;
+
+			ensure!(
+				!shares_added.is_zero(),
+				Error::<T>::InvalidMintedLiquidity
+			);
+
+			ensure!(
+				shares_added >= min_shares,
+				Error::<T>::AssetBalanceLimitExceeded
+			);
+
+			let liquidity_amount = total_liquidity.checked_add(shares_added).ok_or(Error::<T>::InvalidLiquidityAmount)?;
+
+			Self::update_weighted_price_history(&pool_account, &[asset]);
+
+			T::Currency::transfer(asset, &who, &pool_account, amount)?;
+
+			Self::collect_protocol_fee(&pool_account, asset, transfer_fee)?;
+
+			T::Currency::deposit(Self::share_token(&pool_account), &who, shares_added)?;
+
+			<TotalLiquidity<T>>::insert(&pool_account, liquidity_amount);
+
+			Self::deposit_event(RawEvent::WeightedLiquidityAdded(who, pool_account, asset, amount, shares_added));
+
+			Ok(())
+		}
+
+		/// Remove `liquidity_amount` of shares from the weighted pool of `pool_assets` and receive single asset.
+		///
+		/// Part of the withdrawal which is effectively swapped from other pool assets is charged trading fee.
+		#[weight =  <T as Config>::WeightInfo::remove_weighted_liquidity_single_asset()
+			.saturating_add(price_history_weight::<T>(pool_assets.len()))]
+		#[transactional]
+		pub fn remove_weighted_liquidity_single_asset(
+			origin,
+			pool_assets: Vec<AssetId>,
+			asset: AssetId,
+			liquidity_amount: Balance,
+			min_amount: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			ensure!(
+				!liquidity_amount.is_zero(),
+				Error::<T>::CannotRemoveLiquidityWithZero
+			);
+
+			let (pool_account, weight) = Self::get_weighted_pool_asset(&pool_assets, asset)?;
+
+			let share_token = Self::share_token(&pool_account);
+
+			ensure!(
+				T::Currency::free_balance(share_token, &who) >= liquidity_amount,
+				Error::<T>::InsufficientAssetBalance
+			);
+
+			let reserve = T::Currency::free_balance(asset, &pool_account);
+			let total_liquidity = Self::total_liquidity(&pool_account);
+
+			let amount = weighted::calculate_withdrawal(reserve, weight, total_liquidity, liquidity_amount)
+				.ok_or(Error::<T>::RemoveAssetAmountInvalid)?;
+
+			let transfer_fee = amount
+				.saturating_sub(weight * amount)
+				.just_fee(Self::pool_fee(&pool_account))
+				.ok_or(Error::<T>::FeeAmountInvalid)?;
+
+			let amount_out = amount - transfer_fee;
+
+			ensure!(
+				amount_out <= reserve / MAX_OUT_RATIO,
+				Error::<T>::MaxOutRatioExceeded
+			);
+
+			ensure!(
+				amount_out >= min_amount,
+				Error::<T>::AssetBalanceLimitExceeded
+			);
+
+			let liquidity_left = total_liquidity.checked_sub(liquidity_amount).ok_or(Error::<T>::InvalidLiquidityAmount)?;
+
+			Self::update_weighted_price_history(&pool_account, &[asset]);
+
+			T::Currency::transfer(asset, &pool_account, &who, amount_out)?;
+
+			Self::collect_protocol_fee(&pool_account, asset, transfer_fee)?;
+
+			T::Currency::withdraw(share_token, &who, liquidity_amount)?;
+
+			<TotalLiquidity<T>>::insert(&pool_account, liquidity_left);
+
+			Self::deposit_event(RawEvent::WeightedLiquidityRemoved(who, pool_account, asset, amount_out, liquidity_amount));
+
+			Ok(())
+		}
+
+		/// Remove `liquidity_amount` of shares from the weighted pool of `pool_assets` and receive
+		/// proportional amount of each pool asset.
+		///
+		/// Pool is destroyed when all shares are removed.
+		#[weight =  <T as Config>::WeightInfo::remove_weighted_liquidity()
+			.saturating_add(price_history_weight::<T>(pool_assets.len().saturating_mul(pool_assets.len())))]
+		#[transactional]
+		pub fn remove_weighted_liquidity(
+			origin,
+			pool_assets: Vec<AssetId>,
+			liquidity_amount: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			ensure!(
+				!liquidity_amount.is_zero(),
+				Error::<T>::CannotRemoveLiquidityWithZero
+			);
+
+			let pool_account = Self::get_weighted_pool_id(&pool_assets);
+
+			ensure!(
+				<WeightedPoolAssets<T>>::contains_key(&pool_account),
+				Error::<T>::TokenPoolNotFound
+			);
+
+			let share_token = Self::share_token(&pool_account);
+
+			ensure!(
+				T::Currency::free_balance(share_token, &who) >= liquidity_amount,
+				Error::<T>::InsufficientAssetBalance
+			);
+
+			let total_liquidity = Self::total_liquidity(&pool_account);
+
+			let liquidity_left = total_liquidity.checked_sub(liquidity_amount).ok_or(Error::<T>::InvalidLiquidityAmount)?;
+
+			let mut amounts = Vec::new();
+
+			for (asset, _) in Self::weighted_pool_assets(&pool_account) {
+				let reserve = T::Currency::free_balance(asset, &pool_account);
+
+				let amount = multiply_by_rational(reserve, liquidity_amount, total_liquidity)
+					.map_err(|_| Error::<T>::RemoveAssetAmountInvalid)?;
+
+				T::Currency::transfer(asset, &pool_account, &who, amount)?;
+
+				amounts.push((asset, amount));
+			}
+
+			T::Currency::withdraw(share_token, &who, liquidity_amount)?;
+
+			<TotalLiquidity<T>>::insert(&pool_account, liquidity_left);
+
+			Self::deposit_event(RawEvent::WeightedPoolLiquidityRemoved(who.clone(), pool_account.clone(), liquidity_amount, amounts));
+
+			if liquidity_left.is_zero() {
+				let assets = Self::weighted_pool_assets(&pool_account);
+				for (idx, &(asset_a, _)) in assets.iter().enumerate() {
+					for &(asset_b, _) in assets[idx + 1..].iter() {
+						Self::remove_price_history(&(pool_account.clone(), asset_a, asset_b));
+					}
+				}
+
+				<ShareToken<T>>::remove(&pool_account);
+				<WeightedPoolAssets<T>>::remove(&pool_account);
+				<PoolFee<T>>::remove(&pool_account);
+				<TotalLiquidity<T>>::remove(&pool_account);
+
+				Self::deposit_event(RawEvent::WeightedPoolDestroyed(who, pool_account));
+			}
+
+			Ok(())
+		}
+
+		/// Sell `amount_sell` of `asset_sell` for `asset_buy` in the weighted pool of `pool_assets`.
+		#[weight =  <T as Config>::WeightInfo::weighted_sell()
+			.saturating_add(price_history_weight::<T>(pool_assets.len().saturating_mul(2)))]
+		#[transactional]
+		pub fn weighted_sell(
+			origin,
+			pool_assets: Vec<AssetId>,
+			asset_sell: AssetId,
+			asset_buy: AssetId,
+			amount_sell: Balance,
+			min_bought: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			ensure!(
+				asset_sell != asset_buy,
+				Error::<T>::CannotTradeSameAsset
+			);
+
+			let (pool_account, weight_sell) = Self::get_weighted_pool_asset(&pool_assets, asset_sell)?;
+			let (_, weight_buy) = Self::get_weighted_pool_asset(&pool_assets, asset_buy)?;
+
+			ensure!(
+				T::Currency::free_balance(asset_sell, &who) >= amount_sell,
+				Error::<T>::InsufficientAssetBalance
+			);
+
+			let asset_sell_reserve = T::Currency::free_balance(asset_sell, &pool_account);
+			let asset_buy_reserve = T::Currency::free_balance(asset_buy, &pool_account);
+
+			ensure!(
+				amount_sell <= asset_sell_reserve / MAX_IN_RATIO,
+				Error::<T>::MaxInRatioExceeded
+			);
+
+			let transfer_fee = amount_sell
+				.just_fee(Self::pool_fee(&pool_account))
+				.ok_or(Error::<T>::FeeAmountInvalid)?;
+
+			let sale_price = weighted::calculate_out_given_in(
+				asset_sell_reserve,
+				weight_sell,
+				asset_buy_reserve,
+				weight_buy,
+				amount_sell - transfer_fee,
+			).ok_or(Error::<T>::SellAssetAmountInvalid)?;
+
+			ensure!(
+				min_bought <= sale_price,
+				Error::<T>::AssetBalanceLimitExceeded
+			);
+
+			Self::update_weighted_price_history(&pool_account, &[asset_sell, asset_buy]);
+
+			T::Currency::transfer(asset_sell, &who, &pool_account, amount_sell)?;
+			T::Currency::transfer(asset_buy, &pool_account, &who, sale_price)?;
+
+			Self::collect_protocol_fee(&pool_account, asset_sell, transfer_fee)?;
+
+			Self::deposit_event(RawEvent::WeightedSell(who, pool_account, asset_sell, asset_buy, amount_sell, sale_price));
+
+			Ok(())
+		}
+
+		/// Buy `amount_buy` of `asset_buy` with `asset_sell` in the weighted pool of `pool_assets`.
+		#[weight =  <T as Config>::WeightInfo::weighted_buy()
+			.saturating_add(price_history_weight::<T>(pool_assets.len().saturating_mul(2)))]
+		#[transactional]
+		pub fn weighted_buy(
+			origin,
+			pool_assets: Vec<AssetId>,
+			asset_buy: AssetId,
+			asset_sell: AssetId,
+			amount_buy: Balance,
+			max_sold: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			ensure!(
+				asset_sell != asset_buy,
+				Error::<T>::CannotTradeSameAsset
+			);
+
+			let (pool_account, weight_buy) = Self::get_weighted_pool_asset(&pool_assets, asset_buy)?;
+			let (_, weight_sell) = Self::get_weighted_pool_asset(&pool_assets, asset_sell)?;
+
+			let asset_buy_reserve = T::Currency::free_balance(asset_buy, &pool_account);
+			let asset_sell_reserve = T::Currency::free_balance(asset_sell, &pool_account);
+
+			let transfer_fee = amount_buy
+				.just_fee(Self::pool_fee(&pool_account))
+				.ok_or(Error::<T>::FeeAmountInvalid)?;
+
+			let amount_buy_with_fee = amount_buy.checked_add(transfer_fee).ok_or(Error::<T>::BuyAssetAmountInvalid)?;
+
+			ensure!(
+				amount_buy_with_fee <= asset_buy_reserve / MAX_OUT_RATIO,
+				Error::<T>::MaxOutRatioExceeded
+			);
+
+			let buy_price = weighted::calculate_in_given_out(
+				asset_sell_reserve,
+				weight_sell,
+				asset_buy_reserve,
+				weight_buy,
+				amount_buy_with_fee,
+			).ok_or(Error::<T>::BuyAssetAmountInvalid)?;
+
+			ensure!(
+				T::Currency::free_balance(asset_sell, &who) >= buy_price,
+				Error::<T>::InsufficientAssetBalance
+			);
+
+			ensure!(
+				max_sold >= buy_price,
+				Error::<T>::AssetBalanceLimitExceeded
+			);
+
+			Self::update_weighted_price_history(&pool_account, &[asset_sell, asset_buy]);
+
+			T::Currency::transfer(asset_sell, &who, &pool_account, buy_price)?;
+			T::Currency::transfer(asset_buy, &pool_account, &who, amount_buy)?;
+
+			Self::collect_protocol_fee(&pool_account, asset_buy, transfer_fee)?;
+
+			Self::deposit_event(RawEvent::WeightedBuy(who, pool_account, asset_buy, asset_sell, amount_buy, buy_price));
+
+			Ok(())
+		}
+
+		/// Provide liquidity to the concentrated liquidity pool of `asset_a` and `asset_b` in the price range
+		/// between `tick_lower` and `tick_upper`.
+		///
+		/// Liquidity is maximized with up to `amount_a` of `asset_a` and `amount_b` of `asset_b`. If the range
+		/// is entirely above or below the current price, only one of the assets is needed.
+		///
+		/// Ticks must be multiples of tick spacing of the pool.
+		#[weight =  <T as Config>::WeightInfo::mint_position()]
+		#[transactional]
+		pub fn mint_position(
+			origin,
+			asset_a: AssetId,
+			asset_b: AssetId,
+			tick_lower: i32,
+			tick_upper: i32,
+			amount_a: Balance,
+			amount_b: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			ensure!(
+				Self::exists(asset_a, asset_b),
+				Error::<T>::TokenPoolNotFound
+			);
+
+			let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+
+			let tick_spacing = match Self::pool_type(&pair_account) {
+				PoolType::Concentrated(tick_spacing) => tick_spacing as i32,
+				_ => return Err(Error::<T>::UnsupportedPoolType.into()),
+			};
+
+			ensure!(
+				tick_lower < tick_upper
+					&& tick_lower >= concentrated::MIN_TICK
+					&& tick_upper <= concentrated::MAX_TICK
+					&& tick_lower % tick_spacing == 0
+					&& tick_upper % tick_spacing == 0,
+				Error::<T>::InvalidTickRange
+			);
+
+			let (amount_0, amount_1) = if asset_a < asset_b { (amount_a, amount_b) } else { (amount_b, amount_a) };
+
+			Self::add_position(&who, &pair_account, tick_lower, tick_upper, amount_0, amount_1)?;
+
+			Ok(())
+		}
+
+		/// Remove `liquidity` from concentrated liquidity position of origin.
+		///
+		/// Uncollected fees of the position are paid out as well. Position is removed when all its liquidity is removed.
+		#[weight =  <T as Config>::WeightInfo::remove_position_liquidity()]
+		#[transactional]
+		pub fn remove_position_liquidity(
+			origin,
+			position_id: PositionId,
+			liquidity: Balance,
+			deadline: Option<T::BlockNumber>,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_not_expired(deadline)?;
+
+			ensure!(
+				!liquidity.is_zero(),
+				Error::<T>::CannotRemoveLiquidityWithZero
+			);
+
+			let mut position = Self::positions(&who, position_id).ok_or(Error::<T>::PositionNotFound)?;
+
+			ensure!(
+				position.liquidity >= liquidity,
+				Error::<T>::InvalidLiquidityAmount
+			);
+
+			let remaining_liquidity = position.liquidity - liquidity;
+			ensure!(
+				remaining_liquidity.is_zero() || remaining_liquidity >= concentrated::MIN_POSITION_LIQUIDITY,
+				Error::<T>::InsufficientPositionLiquidity
+			);
+
+			let pool_account = position.pool.clone();
+			let mut pool = Self::concentrated_pool(&pool_account);
+
+			Self::settle_position_fees(&pool, &mut position);
+
+			let (amount_0, amount_1) = concentrated::amounts_for_liquidity(
+				&pool,
+				position.tick_lower,
+				position.tick_upper,
+				liquidity,
+				false,
+			).ok_or(Error::<T>::RemoveAssetAmountInvalid)?;
+
+			let liquidity_delta = Self::liquidity_delta(liquidity)?.checked_neg().ok_or(Error::<T>::InvalidLiquidityAmount)?;
+
+			Self::update_tick(&pool_account, &pool, position.tick_lower, liquidity_delta, false)?;
+			Self::update_tick(&pool_account, &pool, position.tick_upper, liquidity_delta, true)?;
+
+			if pool.tick >= position.tick_lower && pool.tick < position.tick_upper {
+				pool.liquidity = pool.liquidity.checked_sub(liquidity).ok_or(Error::<T>::InvalidLiquidityAmount)?;
+			}
+
+			position.liquidity -= liquidity;
+
+			let (fees_0, fees_1) = (position.fees_owed_0, position.fees_owed_1);
+			position.fees_owed_0 = 0;
+			position.fees_owed_1 = 0;
+
+			let (token_0, token_1) = Self::concentrated_tokens(&pool_account);
+
+			T::Currency::transfer(token_0, &pool_account, &who, amount_0.checked_add(fees_0).ok_or(Error::<T>::RemoveAssetAmountInvalid)?)?;
+			T::Currency::transfer(token_1, &pool_account, &who, amount_1.checked_add(fees_1).ok_or(Error::<T>::RemoveAssetAmountInvalid)?)?;
+
+			if position.liquidity.is_zero() {
+				<Positions<T>>::remove(&who, position_id);
+			} else {
+				<Positions<T>>::insert(&who, position_id, position);
+			}
+
+			<ConcentratedPools<T>>::insert(&pool_account, pool);
+
+			Self::deposit_event(RawEvent::PositionLiquidityRemoved(who.clone(), position_id, liquidity, amount_0, amount_1));
+
+			if !fees_0.is_zero() || !fees_1.is_zero() {
+				Self::deposit_event(RawEvent::PositionFeesCollected(who, position_id, fees_0, fees_1));
+			}
+
+			Ok(())
+		}
+
+		/// Collect trading fees earned by concentrated liquidity position of origin.
+		#[weight =  <T as Config>::WeightInfo::collect_position_fees()]
+		#[transactional]
+		pub fn collect_position_fees(
+			origin,
+			position_id: PositionId,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let mut position = Self::positions(&who, position_id).ok_or(Error::<T>::PositionNotFound)?;
+
+			let pool_account = position.pool.clone();
+
+			Self::settle_position_fees(&Self::concentrated_pool(&pool_account), &mut position);
+
+			let (fees_0, fees_1) = (position.fees_owed_0, position.fees_owed_1);
+
+			ensure!(
+				!fees_0.is_zero() || !fees_1.is_zero(),
+				Error::<T>::NoFeesToClaim
+			);
+
+			position.fees_owed_0 = 0;
+			position.fees_owed_1 = 0;
+
+			let (token_0, token_1) = Self::concentrated_tokens(&pool_account);
+
+			T::Currency::transfer(token_0, &pool_account, &who, fees_0)?;
+			T::Currency::transfer(token_1, &pool_account, &who, fees_1)?;
+
+			<Positions<T>>::insert(&who, position_id, position);
+
+			Self::deposit_event(RawEvent::PositionFeesCollected(who, position_id, fees_0, fees_1));
+
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Ensure that the current block is not after `deadline`, if any.
+	fn ensure_not_expired(deadline: Option<T::BlockNumber>) -> DispatchResult {
+		if let Some(deadline) = deadline {
+			ensure!(
+				<system::Module<T>>::block_number() <= deadline,
+				Error::<T>::DeadlineExpired
+			);
+		}
+
+		Ok(())
+	}
+
+	pub fn get_spot_price(asset_a: AssetId, asset_b: AssetId, amount: Balance) -> Balance {
+		match Self::exists(asset_a, asset_b) {
+			true => Self::get_spot_price_unchecked(asset_a, asset_b, amount),
+			false => 0,
+		}
+	}
+
+	pub fn get_sell_price(asset_a: AssetId, asset_b: AssetId, amount: Balance) -> Balance {
+		match Self::exists(asset_a, asset_b) {
+			true => {
+				let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+
+				let asset_a_reserve = T::Currency::free_balance(asset_a, &pair_account);
+				let asset_b_reserve = T::Currency::free_balance(asset_b, &pair_account);
+
+				amount
+					.just_fee(Self::pool_fee(&pair_account))
+					.and_then(|transfer_fee| {
+						Self::calculate_sell_price(
+							&pair_account,
+							asset_a,
+							asset_a_reserve,
+							asset_b_reserve,
+							amount - transfer_fee,
+						)
+					})
+					.unwrap_or(0)
+			}
+			false => 0,
+		}
+	}
+
+	pub fn get_buy_price(asset_a: AssetId, asset_b: AssetId, amount: Balance) -> Balance {
+		match Self::exists(asset_a, asset_b) {
+			true => {
+				let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+
+				let asset_a_reserve = T::Currency::free_balance(asset_a, &pair_account);
+				let asset_b_reserve = T::Currency::free_balance(asset_b, &pair_account);
+
+				amount
+					.just_fee(Self::pool_fee(&pair_account))
+					.and_then(|transfer_fee| amount.checked_add(transfer_fee))
+					.and_then(|amount_with_fee| {
+						Self::calculate_buy_price(
+							&pair_account,
+							asset_b,
+							asset_b_reserve,
+							asset_a_reserve,
+							amount_with_fee,
+						)
+					})
+					.unwrap_or(0)
+			}
+			false => 0,
+		}
+	}
+
+	/// Find route with at most `max_hops` pools which yields the biggest amount of `asset_out`
+	/// when selling `amount` of `asset_in`.
+	///
+	/// Returns the route, including `asset_in` and `asset_out`, and the expected amount of `asset_out`.
+	pub fn get_best_sell_route(
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount: Balance,
+		max_hops: u32,
+	) -> Option<(Vec<AssetId>, Balance)> {
+		if asset_in == asset_out || amount.is_zero() {
+			return None;
+		}
+
+		let max_hops = sp_std::cmp::min(max_hops as usize, MAX_ROUTE_LENGTH - 1);
+
+		// Assets tradeable with each asset are collected once,
+		// so that each step of the search visits only pools of the current asset.
+		let mut pools: BTreeMap<AssetId, Vec<AssetId>> = BTreeMap::new();
+
+		for (_, (asset_a, asset_b)) in <PoolAssets<T>>::iter() {
+			pools.entry(asset_a).or_insert_with(Vec::new).push(asset_b);
+			pools.entry(asset_b).or_insert_with(Vec::new).push(asset_a);
+		}
+
+		let mut route = vec![asset_in];
+		let mut best_route = None;
+
+		Self::find_best_sell_route(&pools, asset_out, amount, max_hops, &mut route, &mut best_route);
+
+		best_route
+	}
+
+	/// Depth-first search of all routes from the last asset of `route` to `asset_out`.
+	/// `pools` maps each asset to the assets it has a pool with.
+	/// `best_route` is updated whenever a route with bigger amount out is found.
+	fn find_best_sell_route(
+		pools: &BTreeMap<AssetId, Vec<AssetId>>,
+		asset_out: AssetId,
+		amount: Balance,
+		hops_left: usize,
+		route: &mut Vec<AssetId>,
+		best_route: &mut Option<(Vec<AssetId>, Balance)>,
+	) {
+		if hops_left == 0 {
+			return;
+		}
+
+		let current = route[route.len() - 1];
+
+		let neighbours = match pools.get(&current) {
+			Some(neighbours) => neighbours,
+			None => return,
+		};
+
+		for &next in neighbours.iter() {
+			if route.contains(&next) {
+				continue;
+			}
+
+			let amount_out = match Self::calculate_sell_amount_with_fee(current, next, amount) {
+				Ok(x) if !x.is_zero() => x,
+				_ => continue,
+			};
+
+			route.push(next);
+
+			if next == asset_out {
+				if best_route
+					.as_ref()
+					.map_or(true, |(_, best_amount)| amount_out > *best_amount)
+				{
+					*best_route = Some((route.clone(), amount_out));
+				}
+			} else {
+				Self::find_best_sell_route(pools, asset_out, amount_out, hops_left - 1, route, best_route);
+			}
+
+			route.pop();
+		}
+	}
+
+	pub fn get_pool_balances(pool_address: T::AccountId) -> Option<Vec<(AssetId, Balance)>> {
+		let mut balances = Vec::new();
+
+		if let Some(assets) = Self::get_pool_assets(&pool_address) {
+			for item in &assets {
+				let reserve = T::Currency::free_balance(*item, &pool_address);
+				balances.push((*item, reserve));
+			}
+		}
+		Some(balances)
+	}
+
+	/// Check that route contains at least two distinct assets, no asset twice and that each hop has a pool.
+	fn validate_route(route: &[AssetId]) -> DispatchResult {
+		ensure!(route.len() >= 2, Error::<T>::InvalidRoute);
+		ensure!(route.len() <= MAX_ROUTE_LENGTH, Error::<T>::MaxRouteLengthExceeded);
+
+		for (idx, asset) in route.iter().enumerate() {
+			ensure!(!route[idx + 1..].contains(asset), Error::<T>::InvalidRoute);
+		}
+
+		for hop in route.windows(2) {
+			ensure!(Self::exists(hop[0], hop[1]), Error::<T>::TokenPoolNotFound);
+		}
+
+		Ok(())
+	}
+
+	/// Calculate amount of `asset_buy` received for `amount_sell` of `asset_sell`, after trading fee.
+	fn calculate_sell_amount_with_fee(
+		asset_sell: AssetId,
+		asset_buy: AssetId,
+		amount_sell: Balance,
+	) -> Result<Balance, DispatchError> {
+		let pair_account = Self::get_pair_id(&asset_sell, &asset_buy);
+
+		let asset_sell_reserve = T::Currency::free_balance(asset_sell, &pair_account);
+		let asset_buy_reserve = T::Currency::free_balance(asset_buy, &pair_account);
+
+		ensure!(
+			amount_sell <= asset_sell_reserve / MAX_IN_RATIO,
+			Error::<T>::MaxInRatioExceeded
+		);
+
+		let mut hdx_amount = 0;
+
+		let transfer_fee = Self::calculate_fees(&pair_account, amount_sell, false, &mut hdx_amount)?;
+
+		Self::calculate_sell_price(
+			&pair_account,
+			asset_sell,
+			asset_sell_reserve,
+			asset_buy_reserve,
+			amount_sell - transfer_fee,
+		)
+		.ok_or_else(|| Error::<T>::SellAssetAmountInvalid.into())
+	}
+
+	/// Remove `liquidity_amount` of shares of `who` from the pool of `asset_a` and `asset_b`.
+	///
+	/// Returns amounts of asset a and asset b transferred to `who`, including accrued trading fees.
+	fn do_remove_liquidity(
+		who: &T::AccountId,
+		asset_a: AssetId,
+		asset_b: AssetId,
+		liquidity_amount: Balance,
+		min_amount_a: Balance,
+		min_amount_b: Balance,
+	) -> Result<(Balance, Balance), DispatchError> {
+		ensure!(!liquidity_amount.is_zero(), Error::<T>::CannotRemoveLiquidityWithZero);
+
+		ensure!(Self::exists(asset_a, asset_b), Error::<T>::TokenPoolNotFound);
+
+		let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+
+		ensure!(!Self::is_concentrated(&pair_account), Error::<T>::UnsupportedPoolType);
+
+		let share_token = Self::share_token(&pair_account);
+
+		let total_shares = Self::total_liquidity(&pair_account);
+
+		ensure!(total_shares >= liquidity_amount, Error::<T>::InsufficientAssetBalance);
+
+		ensure!(
+			T::Currency::free_balance(share_token, who) >= liquidity_amount,
+			Error::<T>::InsufficientAssetBalance
+		);
+
+		ensure!(!total_shares.is_zero(), Error::<T>::CannotRemoveLiquidityWithZero);
+
+		Self::settle_fees(who, &pair_account, share_token);
+
+		// Unclaimed fees are owed to liquidity providers according to their accrued fees,
+		// so they are excluded from the reserves which are split proportionally to shares.
+		let asset_a_unclaimed_fees = Self::unclaimed_fees((share_token, asset_a));
+		let asset_b_unclaimed_fees = Self::unclaimed_fees((share_token, asset_b));
+
+		let asset_a_reserve = T::Currency::free_balance(asset_a, &pair_account).saturating_sub(asset_a_unclaimed_fees);
+		let asset_b_reserve = T::Currency::free_balance(asset_b, &pair_account).saturating_sub(asset_b_unclaimed_fees);
+
+		let liquidity_out =
+			hydra_dx_math::calculate_liquidity_out(asset_a_reserve, asset_b_reserve, liquidity_amount, total_shares)
+				.ok_or(Error::<T>::RemoveAssetAmountInvalid)?;
+
+		let share_balance = T::Currency::free_balance(share_token, who);
+
+		let fees_out_a = Self::take_accrued_fees(who, share_token, asset_a, liquidity_amount, share_balance)?;
+		let fees_out_b = Self::take_accrued_fees(who, share_token, asset_b, liquidity_amount, share_balance)?;
+
+		let remove_amount_a = liquidity_out
+			.0
+			.checked_add(fees_out_a)
+			.ok_or(Error::<T>::RemoveAssetAmountInvalid)?;
+		let remove_amount_b = liquidity_out
+			.1
+			.checked_add(fees_out_b)
+			.ok_or(Error::<T>::RemoveAssetAmountInvalid)?;
+
+		ensure!(
+			remove_amount_a >= min_amount_a && remove_amount_b >= min_amount_b,
+			Error::<T>::AssetBalanceLimitExceeded
+		);
+
+		ensure!(
+			T::Currency::free_balance(asset_a, &pair_account) >= remove_amount_a,
+			Error::<T>::InsufficientPoolAssetBalance
+		);
+		ensure!(
+			T::Currency::free_balance(asset_b, &pair_account) >= remove_amount_b,
+			Error::<T>::InsufficientPoolAssetBalance
+		);
+
+		// Note: this check is not really needed as we already check if amount to remove >= liquidity in pool
+		let liquidity_left = total_shares
+			.checked_sub(liquidity_amount)
+			.ok_or(Error::<T>::InvalidLiquidityAmount)?;
+
+		Self::update_price_history(&pair_account);
+
+		T::Currency::transfer(asset_a, &pair_account, who, remove_amount_a)?;
+		T::Currency::transfer(asset_b, &pair_account, who, remove_amount_b)?;
+
+		T::Currency::withdraw(share_token, who, liquidity_amount)?;
+
+		<TotalLiquidity<T>>::insert(&pair_account, liquidity_left);
+
+		Self::record_fee_shares(who, &pair_account, share_token);
+
+		Self::deposit_event(RawEvent::RemoveLiquidity(
+			who.clone(),
+			asset_a,
+			asset_b,
+			liquidity_amount,
+			remove_amount_a,
+			remove_amount_b,
+		));
+
+		if liquidity_left == 0 {
+			let (pool_asset_a, pool_asset_b) = Self::pool_assets(&pair_account);
+			Self::remove_price_history(&(pair_account.clone(), pool_asset_a, pool_asset_b));
+
+			<ShareToken<T>>::remove(&pair_account);
+			<PoolAssets<T>>::remove(&pair_account);
+			<PoolFee<T>>::remove(&pair_account);
+			<PoolTypes<T>>::remove(&pair_account);
+			Self::remove_fee_state(share_token, &[asset_a, asset_b]);
+
+			Self::deposit_event(RawEvent::PoolDestroyed(who.clone(), asset_a, asset_b));
+		}
+
+		Ok((remove_amount_a, remove_amount_b))
+	}
+
+	/// Calculate amount of `asset_in` which should be sold for `asset_out` when adding `amount` of `asset_in`
+	/// as liquidity, so that the rest of `amount` and the amount bought match the ratio of the pool after the sale.
+	fn calculate_single_asset_swap_amount(
+		asset_in: AssetId,
+		asset_out: AssetId,
+		amount: Balance,
+	) -> Result<Balance, DispatchError> {
+		let pair_account = Self::get_pair_id(&asset_in, &asset_out);
+
+		let asset_in_reserve = T::Currency::free_balance(asset_in, &pair_account);
+		let asset_out_reserve = T::Currency::free_balance(asset_out, &pair_account);
+
+		// Whether the rest of `amount` would still need more of asset out than bought when selling `amount_sold`
+		let needs_more = |amount_sold: Balance| -> Result<bool, DispatchError> {
+			let bought = Self::calculate_sell_amount_with_fee(asset_in, asset_out, amount_sold)?;
+
+			let remaining_in = U256::from(amount - amount_sold);
+			let remaining_out_reserve = U256::from(asset_out_reserve.saturating_sub(bought));
+
+			Ok(remaining_in.full_mul(remaining_out_reserve)
+				> U256::from(bought).full_mul(U256::from(asset_in_reserve) + U256::from(amount_sold)))
+		};
+
+		let max_amount = sp_std::cmp::min(amount, asset_in_reserve / MAX_IN_RATIO);
+
+		if needs_more(max_amount)? {
+			return Err(Error::<T>::MaxInRatioExceeded.into());
+		}
+
+		let (mut low, mut high) = (Balance::zero(), max_amount);
+
+		while high - low > 1 {
+			let middle = low + (high - low) / 2;
+			if needs_more(middle)? {
+				low = middle;
+			} else {
+				high = middle;
+			}
+		}
+
+		Ok(high)
+	}
+
+	/// Calculate amount of `asset_sell` needed to buy `amount_buy` of `asset_buy`, including trading fee.
+	fn calculate_buy_amount_with_fee(
+		asset_buy: AssetId,
+		asset_sell: AssetId,
+		amount_buy: Balance,
+		discount: bool,
+	) -> Result<Balance, DispatchError> {
+		let pair_account = Self::get_pair_id(&asset_buy, &asset_sell);
+
+		let asset_buy_reserve = T::Currency::free_balance(asset_buy, &pair_account);
+		let asset_sell_reserve = T::Currency::free_balance(asset_sell, &pair_account);
+
+		let mut hdx_amount = 0;
+
+		let transfer_fee = Self::calculate_fees(&pair_account, amount_buy, discount, &mut hdx_amount)?;
+
+		let amount_buy_with_fee = amount_buy
+			.checked_add(transfer_fee)
+			.ok_or(Error::<T>::BuyAssetAmountInvalid)?;
+
+		ensure!(
+			amount_buy_with_fee <= asset_buy_reserve,
+			Error::<T>::InsufficientPoolAssetBalance
+		);
+
+		Self::calculate_buy_price(
+			&pair_account,
+			asset_sell,
+			asset_sell_reserve,
+			asset_buy_reserve,
+			amount_buy_with_fee,
+		)
+		.ok_or_else(|| Error::<T>::BuyAssetAmountInvalid.into())
+	}
+
+	fn is_concentrated(pair_account: &T::AccountId) -> bool {
+		matches!(Self::pool_type(pair_account), PoolType::Concentrated(_))
+	}
+
+	/// Return assets of concentrated liquidity pool as token 0 and token 1, ordered by asset id.
+	fn concentrated_tokens(pair_account: &T::AccountId) -> (AssetId, AssetId) {
+		let (asset_a, asset_b) = Self::pool_assets(pair_account);
+
+		if asset_a < asset_b {
+			(asset_a, asset_b)
+		} else {
+			(asset_b, asset_a)
+		}
+	}
+
+	/// Set initial price of a new concentrated liquidity pool and provide liquidity of the creator in full range.
+	///
+	/// Returns liquidity of the created position.
+	fn initialize_concentrated_pool(
+		who: &T::AccountId,
+		pair_account: &T::AccountId,
+		amount_0: Balance,
+		amount_1: Balance,
+		tick_spacing: u32,
+	) -> Result<Balance, DispatchError> {
+		let sqrt_price = concentrated::sqrt_price_from_ratio(amount_1, amount_0).ok_or(Error::<T>::InvalidTickRange)?;
+		let tick = concentrated::tick_at_sqrt_price(sqrt_price).ok_or(Error::<T>::InvalidTickRange)?;
+
+		<ConcentratedPools<T>>::insert(
+			pair_account,
+			ConcentratedPool {
+				sqrt_price,
+				tick,
+				..Default::default()
+			},
+		);
+
+		let tick_spacing = tick_spacing as i32;
+		let tick_upper = concentrated::MAX_TICK - concentrated::MAX_TICK % tick_spacing;
+
+		let (_, liquidity) = Self::add_position(who, pair_account, -tick_upper, tick_upper, amount_0, amount_1)?;
+
+		Ok(liquidity)
+	}
+
+	/// Create position of `who` in concentrated liquidity pool with max liquidity backed by given amounts.
+	fn add_position(
+		who: &T::AccountId,
+		pool_account: &T::AccountId,
+		tick_lower: i32,
+		tick_upper: i32,
+		amount_0_max: Balance,
+		amount_1_max: Balance,
+	) -> Result<(PositionId, Balance), DispatchError> {
+		let mut pool = Self::concentrated_pool(pool_account);
+
+		let liquidity = concentrated::liquidity_for_amounts(&pool, tick_lower, tick_upper, amount_0_max, amount_1_max)
+			.ok_or(Error::<T>::InvalidLiquidityAmount)?;
+
+		ensure!(!liquidity.is_zero(), Error::<T>::InvalidMintedLiquidity);
+		ensure!(
+			liquidity >= concentrated::MIN_POSITION_LIQUIDITY,
+			Error::<T>::InsufficientPositionLiquidity
+		);
+
+		let liquidity_delta = Self::liquidity_delta(liquidity)?;
+
+		let (amount_0, amount_1) = concentrated::amounts_for_liquidity(&pool, tick_lower, tick_upper, liquidity, true)
+			.ok_or(Error::<T>::AddAssetAmountInvalid)?;
+
+		ensure!(
+			amount_0 <= amount_0_max && amount_1 <= amount_1_max,
+			Error::<T>::AssetBalanceLimitExceeded
+		);
+
+		let (token_0, token_1) = Self::concentrated_tokens(pool_account);
+
+		ensure!(
+			T::Currency::free_balance(token_0, who) >= amount_0,
+			Error::<T>::InsufficientAssetBalance
+		);
+		ensure!(
+			T::Currency::free_balance(token_1, who) >= amount_1,
+			Error::<T>::InsufficientAssetBalance
+		);
+
+		Self::update_tick(pool_account, &pool, tick_lower, liquidity_delta, false)?;
+		Self::update_tick(pool_account, &pool, tick_upper, liquidity_delta, true)?;
+
+		if pool.tick >= tick_lower && pool.tick < tick_upper {
+			pool.liquidity = pool
+				.liquidity
+				.checked_add(liquidity)
+				.ok_or(Error::<T>::InvalidLiquidityAmount)?;
+		}
+
+		let (fee_growth_inside_0, fee_growth_inside_1) = concentrated::fee_growth_inside(
+			&pool,
+			&Self::ticks(pool_account, tick_lower),
+			&Self::ticks(pool_account, tick_upper),
+			tick_lower,
+			tick_upper,
+		);
+
+		let position_id = Self::next_position_id();
+
+		NextPositionId::put(position_id.checked_add(1).ok_or(Error::<T>::InvalidLiquidityAmount)?);
+
+		T::Currency::transfer(token_0, who, pool_account, amount_0)?;
+		T::Currency::transfer(token_1, who, pool_account, amount_1)?;
+
+		<Positions<T>>::insert(
+			who,
+			position_id,
+			Position {
+				pool: pool_account.clone(),
+				tick_lower,
+				tick_upper,
+				liquidity,
+				fee_growth_inside_0,
+				fee_growth_inside_1,
+				fees_owed_0: 0,
+				fees_owed_1: 0,
+			},
+		);
+
+		<ConcentratedPools<T>>::insert(pool_account, pool);
+
+		Self::deposit_event(RawEvent::PositionCreated(
+			who.clone(),
+			position_id,
+			pool_account.clone(),
+			tick_lower,
+			tick_upper,
+		));
+		Self::deposit_event(RawEvent::PositionLiquidityAdded(
+			who.clone(),
+			position_id,
+			liquidity,
+			amount_0,
+			amount_1,
+		));
+
+		Ok((position_id, liquidity))
+	}
+
+	fn liquidity_delta(liquidity: Balance) -> Result<i128, DispatchError> {
+		ensure!(
+			liquidity <= i128::max_value() as u128,
+			Error::<T>::InvalidLiquidityAmount
+		);
+
+		Ok(liquidity as i128)
+	}
+
+	/// Update liquidity referencing `tick` as a lower or upper bound of a position.
+	///
+	/// Tick is initialized when first referenced and cleared when no longer referenced.
+	fn update_tick(
+		pool_account: &T::AccountId,
+		pool: &ConcentratedPool,
+		tick: i32,
+		liquidity_delta: i128,
+		upper: bool,
+	) -> DispatchResult {
+		let mut info = Self::ticks(pool_account, tick);
+
+		let liquidity_gross = concentrated::add_liquidity_delta(info.liquidity_gross, liquidity_delta)
+			.ok_or(Error::<T>::InvalidLiquidityAmount)?;
+
+		let net_delta = if upper {
+			liquidity_delta
+				.checked_neg()
+				.ok_or(Error::<T>::InvalidLiquidityAmount)?
+		} else {
+			liquidity_delta
+		};
+
+		let liquidity_net = info
+			.liquidity_net
+			.checked_add(net_delta)
+			.ok_or(Error::<T>::InvalidLiquidityAmount)?;
+
+		if liquidity_gross.is_zero() {
+			<Ticks<T>>::remove(pool_account, tick);
+			Self::flip_tick(pool_account, tick);
+			return Ok(());
+		}
+
+		if info.liquidity_gross.is_zero() {
+			// All fee growth before initialization is considered to have happened below the tick
+			if tick <= pool.tick {
+				info.fee_growth_outside_0 = pool.fee_growth_0;
+				info.fee_growth_outside_1 = pool.fee_growth_1;
+			}
+
+			Self::flip_tick(pool_account, tick);
+		}
+
+		info.liquidity_gross = liquidity_gross;
+		info.liquidity_net = liquidity_net;
+
+		<Ticks<T>>::insert(pool_account, tick, info);
+
+		Ok(())
+	}
+
+	/// Toggle `tick` in the tick bitmap of the pool. Empty words are removed.
+	fn flip_tick(pool_account: &T::AccountId, tick: i32) {
+		let (word, bit) = concentrated::tick_bitmap_position(tick, Self::tick_spacing(pool_account));
+
+		<TickBitmap<T>>::mutate_exists(pool_account, word, |value| {
+			let flipped = value.unwrap_or_default() ^ (1u128 << bit);
+			*value = if flipped == 0 { None } else { Some(flipped) };
+		});
+	}
+
+	fn tick_spacing(pool_account: &T::AccountId) -> i32 {
+		match Self::pool_type(pool_account) {
+			PoolType::Concentrated(tick_spacing) => tick_spacing as i32,
+			_ => 1,
+		}
+	}
+
+	/// Move fees earned by the position since last settlement to its owed fees.
+	fn settle_position_fees(pool: &ConcentratedPool, position: &mut Position<T::AccountId>) {
+		let (fee_growth_inside_0, fee_growth_inside_1) = concentrated::fee_growth_inside(
+			pool,
+			&Self::ticks(&position.pool, position.tick_lower),
+			&Self::ticks(&position.pool, position.tick_upper),
+			position.tick_lower,
+			position.tick_upper,
+		);
+
+		position.fees_owed_0 = position.fees_owed_0.saturating_add(concentrated::fees_earned(
+			fee_growth_inside_0,
+			position.fee_growth_inside_0,
+			position.liquidity,
+		));
+		position.fees_owed_1 = position.fees_owed_1.saturating_add(concentrated::fees_earned(
+			fee_growth_inside_1,
+			position.fee_growth_inside_1,
+			position.liquidity,
+		));
+
+		position.fee_growth_inside_0 = fee_growth_inside_0;
+		position.fee_growth_inside_1 = fee_growth_inside_1;
+	}
+
+	/// Simulate swap in concentrated liquidity pool.
+	///
+	/// `amount` is amount of `asset_in` if `exact_input`, amount of the other asset otherwise.
+	fn concentrated_swap(
+		pair_account: &T::AccountId,
+		asset_in: AssetId,
+		amount: Balance,
+		exact_input: bool,
+		fee: Balance,
+	) -> Option<concentrated::SwapResult> {
+		let (token_0, _) = Self::concentrated_tokens(pair_account);
+		let tick_spacing = Self::tick_spacing(pair_account);
+
+		concentrated::swap(
+			&Self::concentrated_pool(pair_account),
+			asset_in == token_0,
+			exact_input,
+			amount,
+			fee,
+			|tick, lte| {
+				concentrated::next_initialized_tick_within_word(tick, tick_spacing, lte, |word| {
+					Self::tick_bitmap(pair_account, word)
+				})
+			},
+			|tick| Self::ticks(pair_account, tick).liquidity_net,
+		)
+	}
+
+	/// Execute swap in concentrated liquidity pool - move the price, cross ticks and distribute `fee`
+	/// to liquidity in range.
+	fn apply_concentrated_swap(
+		pair_account: &T::AccountId,
+		asset_in: AssetId,
+		amount: Balance,
+		exact_input: bool,
+		fee: Balance,
+	) -> DispatchResult {
+		let result =
+			Self::concentrated_swap(pair_account, asset_in, amount, exact_input, fee).ok_or(if exact_input {
+				Error::<T>::SellAssetAmountInvalid
+			} else {
+				Error::<T>::BuyAssetAmountInvalid
+			})?;
+
+		for (tick, fee_growth_0, fee_growth_1) in result.crossed_ticks {
+			<Ticks<T>>::mutate(pair_account, tick, |info| {
+				info.fee_growth_outside_0 = fee_growth_0.wrapping_sub(info.fee_growth_outside_0);
+				info.fee_growth_outside_1 = fee_growth_1.wrapping_sub(info.fee_growth_outside_1);
+			});
+		}
+
+		<ConcentratedPools<T>>::insert(pair_account, result.pool);
+
+		Ok(())
+	}
+
+	/// Return concentrated liquidity positions of `who` with amounts of assets backing the liquidity
+	/// and uncollected fees.
+	pub fn get_positions(who: &T::AccountId) -> Vec<PositionDetails<T::AccountId>> {
+		<Positions<T>>::iter_prefix(who)
+			.map(|(position_id, mut position)| {
+				let pool = Self::concentrated_pool(&position.pool);
+				let (token_0, token_1) = Self::concentrated_tokens(&position.pool);
+
+				Self::settle_position_fees(&pool, &mut position);
+
+				let (amount_0, amount_1) = concentrated::amounts_for_liquidity(
+					&pool,
+					position.tick_lower,
+					position.tick_upper,
+					position.liquidity,
+					false,
+				)
+				.unwrap_or_default();
+
+				PositionDetails {
+					position_id,
+					amounts: vec![(token_0, amount_0), (token_1, amount_1)],
+					fees: vec![(token_0, position.fees_owed_0), (token_1, position.fees_owed_1)],
+					position,
+				}
+			})
+			.collect()
+	}
+
+	/// Return account of the weighted pool of given assets, in any order.
+	pub fn get_weighted_pool_id(assets: &[AssetId]) -> T::AccountId {
+		T::AssetPairAccountId::from_asset_set(assets)
+	}
+
+	/// Return account of the weighted pool of `pool_assets` and weight of `asset` in the pool.
+	fn get_weighted_pool_asset(
+		pool_assets: &[AssetId],
+		asset: AssetId,
+	) -> Result<(T::AccountId, Permill), DispatchError> {
+		let pool_account = Self::get_weighted_pool_id(pool_assets);
+
+		ensure!(
+			<WeightedPoolAssets<T>>::contains_key(&pool_account),
+			Error::<T>::TokenPoolNotFound
+		);
+
+		let weight = Self::weighted_pool_assets(&pool_account)
+			.into_iter()
+			.find(|&(pool_asset, _)| pool_asset == asset)
+			.map(|(_, weight)| weight)
+			.ok_or(Error::<T>::AssetNotInPool)?;
+
+		Ok((pool_account, weight))
+	}
+
+	/// Check assets of a new weighted pool, sorted by asset id.
+	fn validate_weighted_pool_assets(assets: &[(AssetId, Permill, Balance)]) -> DispatchResult {
+		ensure!(
+			assets.len() >= 2 && assets.len() <= weighted::MAX_WEIGHTED_POOL_ASSETS,
+			Error::<T>::InvalidPoolAssetCount
+		);
+
+		ensure!(
+			assets.windows(2).all(|pair| pair[0].0 != pair[1].0),
+			Error::<T>::CannotCreatePoolWithSameAssets
+		);
+
+		ensure!(
+			assets.iter().all(|&(_, _, amount)| !amount.is_zero()),
+			Error::<T>::CannotCreatePoolWithZeroLiquidity
+		);
+
+		ensure!(
+			assets.iter().all(|&(_, weight, _)| weight >= weighted::MIN_WEIGHT),
+			Error::<T>::InvalidPoolWeights
+		);
+
+		let total_weight: u32 = assets.iter().map(|&(_, weight, _)| weight.deconstruct()).sum();
+
+		ensure!(
+			total_weight == Permill::one().deconstruct(),
+			Error::<T>::InvalidPoolWeights
+		);
+
+		Ok(())
+	}
+
+	fn get_weighted_pool_token_name(assets: &[AssetId]) -> Vec<u8> {
+		let mut sorted = assets.to_vec();
+		sorted.sort_unstable();
+
+		let mut buf: Vec<u8> = Vec::new();
+		for asset in sorted {
+			buf.extend_from_slice(b"HDW");
+			buf.extend_from_slice(&asset.to_le_bytes());
+		}
+		buf
+	}
+
+	/// Return trading fees accrued to the liquidity provided by `who` to the pool, including fees not settled yet.
+	pub fn get_accrued_fees(who: &T::AccountId, pool_address: &T::AccountId) -> Vec<(AssetId, Balance)> {
+		let mut fees = Vec::new();
+
+		if let Some(assets) = Self::get_pool_assets(pool_address) {
+			let share_token = Self::share_token(pool_address);
+			let earning_shares = Self::earning_shares(who, share_token);
+
+			for asset in assets {
+				let pending = Self::accumulated_fee_per_share((share_token, asset))
+					.saturating_sub(Self::fee_checkpoint((share_token, asset), who))
+					.saturating_mul_int(earning_shares);
+
+				fees.push((
+					asset,
+					Self::accrued_fees((share_token, asset), who).saturating_add(pending),
+				));
+			}
+		}
+
+		fees
+	}
+
+	/// Calculate amount of out asset received for `amount_in` of in asset, according to the pool type.
+	fn calculate_sell_price(
+		pair_account: &T::AccountId,
+		asset_in: AssetId,
+		in_reserve: Balance,
+		out_reserve: Balance,
+		amount_in: Balance,
+	) -> Option<Balance> {
+		match Self::pool_type(pair_account) {
+			PoolType::ConstantProduct => hydra_dx_math::calculate_sell_price(in_reserve, out_reserve, amount_in),
+			PoolType::StableSwap(amplification) => {
+				stableswap::calculate_sell_price(in_reserve, out_reserve, amount_in, amplification)
+			}
+			PoolType::Concentrated(_) => {
+				Self::concentrated_swap(pair_account, asset_in, amount_in, true, 0).map(|result| result.amount_out)
+			}
+		}
+	}
+
+	/// Calculate amount of in asset needed to receive `amount_out` of out asset, according to the pool type.
+	fn calculate_buy_price(
+		pair_account: &T::AccountId,
+		asset_in: AssetId,
+		in_reserve: Balance,
+		out_reserve: Balance,
+		amount_out: Balance,
+	) -> Option<Balance> {
+		match Self::pool_type(pair_account) {
+			PoolType::ConstantProduct => hydra_dx_math::calculate_buy_price(in_reserve, out_reserve, amount_out),
+			PoolType::StableSwap(amplification) => {
+				stableswap::calculate_buy_price(in_reserve, out_reserve, amount_out, amplification)
+			}
+			PoolType::Concentrated(_) => {
+				Self::concentrated_swap(pair_account, asset_in, amount_out, false, 0).map(|result| result.amount_in)
+			}
+		}
+	}
+
+	/// Calculate value of `amount` of asset a in asset b at current spot price, according to the pool type.
+	fn calculate_spot_price(
+		pair_account: &T::AccountId,
+		asset_a: AssetId,
+		asset_a_reserve: Balance,
+		asset_b_reserve: Balance,
+		amount: Balance,
+	) -> Option<Balance> {
+		match Self::pool_type(pair_account) {
+			PoolType::ConstantProduct => hydra_dx_math::calculate_spot_price(asset_a_reserve, asset_b_reserve, amount),
+			PoolType::StableSwap(amplification) => {
+				stableswap::calculate_spot_price(asset_a_reserve, asset_b_reserve, amplification)?
+					.checked_mul_int(amount)
+			}
+			PoolType::Concentrated(_) => {
+				let (token_0, _) = Self::concentrated_tokens(pair_account);
+
+				concentrated::spot_price(Self::concentrated_pool(pair_account).sqrt_price, asset_a != token_0)?
+					.checked_mul_int(amount)
+			}
+		}
+	}
+
+	/// Return spot prices of pool assets - price of asset a in asset b, price of asset b in asset a.
+	fn pool_spot_prices(pair_account: &T::AccountId) -> (Price, Price) {
+		let (asset_a, asset_b) = Self::pool_assets(pair_account);
+
+		let asset_a_reserve = T::Currency::free_balance(asset_a, pair_account);
+		let asset_b_reserve = T::Currency::free_balance(asset_b, pair_account);
+
+		match Self::pool_type(pair_account) {
+			PoolType::ConstantProduct => (
+				Price::checked_from_rational(asset_b_reserve, asset_a_reserve).unwrap_or_else(Price::zero),
+				Price::checked_from_rational(asset_a_reserve, asset_b_reserve).unwrap_or_else(Price::zero),
+			),
+			PoolType::StableSwap(amplification) => (
+				stableswap::calculate_spot_price(asset_a_reserve, asset_b_reserve, amplification)
+					.unwrap_or_else(Price::zero),
+				stableswap::calculate_spot_price(asset_b_reserve, asset_a_reserve, amplification)
+					.unwrap_or_else(Price::zero),
+			),
+			PoolType::Concentrated(_) => {
+				let sqrt_price = Self::concentrated_pool(pair_account).sqrt_price;
+				let inverse = asset_a > asset_b;
+
+				(
+					concentrated::spot_price(sqrt_price, inverse).unwrap_or_else(Price::zero),
+					concentrated::spot_price(sqrt_price, !inverse).unwrap_or_else(Price::zero),
+				)
+			}
+		}
+	}
+
+	/// Accumulate prices of the pool which were valid since the last record.
+	///
+	/// Must be called before pool reserves change. Only the first change in a block is recorded,
+	/// so prices set within the current block do not affect cumulative prices until the next block.
+	fn update_price_history(pair_account: &T::AccountId) {
+		let (asset_a, asset_b) = Self::pool_assets(pair_account);
+
+		Self::record_cumulative_prices(&(pair_account.clone(), asset_a, asset_b), || {
+			Self::pool_spot_prices(pair_account)
+		});
+	}
+
+	/// Accumulate prices of each pair of weighted pool assets which contains any of `changed_assets`.
+	///
+	/// Must be called before reserves of `changed_assets` change.
+	fn update_weighted_price_history(pool_account: &T::AccountId, changed_assets: &[AssetId]) {
+		let assets = Self::weighted_pool_assets(pool_account);
+
+		for (idx, &asset_a) in assets.iter().enumerate() {
+			for &asset_b in assets[idx + 1..].iter() {
+				if changed_assets.contains(&asset_a.0) || changed_assets.contains(&asset_b.0) {
+					Self::record_cumulative_prices(&(pool_account.clone(), asset_a.0, asset_b.0), || {
+						Self::weighted_spot_prices(pool_account, asset_a, asset_b)
+					});
+				}
+			}
+		}
+	}
+
+	/// Return spot prices of two assets of weighted pool with their weights - price of asset a in asset b,
+	/// price of asset b in asset a.
+	fn weighted_spot_prices(
+		pool_account: &T::AccountId,
+		(asset_a, weight_a): (AssetId, Permill),
+		(asset_b, weight_b): (AssetId, Permill),
+	) -> (Price, Price) {
+		let asset_a_reserve = T::Currency::free_balance(asset_a, pool_account);
+		let asset_b_reserve = T::Currency::free_balance(asset_b, pool_account);
+
+		(
+			weighted::calculate_spot_price(asset_a_reserve, weight_a, asset_b_reserve, weight_b)
+				.unwrap_or_else(Price::zero),
+			weighted::calculate_spot_price(asset_b_reserve, weight_b, asset_a_reserve, weight_a)
+				.unwrap_or_else(Price::zero),
+		)
+	}
+
+	/// Return record of the price history with given index, if it is still kept.
+	fn price_record(key: &PriceHistoryKey<T::AccountId>, index: u32) -> Option<(T::BlockNumber, Price, Price)> {
+		let length = T::PriceHistoryLength::get().max(1);
+
+		Self::price_history(key, index % length)
+	}
+
+	/// Append cumulative prices to the price history, unless there already is a record for the current block.
+	///
+	/// `spot_prices` returns prices which are valid since the last record.
+	fn record_cumulative_prices(key: &PriceHistoryKey<T::AccountId>, spot_prices: impl FnOnce() -> (Price, Price)) {
+		let now = <system::Module<T>>::block_number();
+		let head = Self::price_history_head(key);
+
+		let last_record = head.checked_sub(1).and_then(|last| Self::price_record(key, last));
+
+		let record = match last_record {
+			Some((last_block, _, _)) if last_block >= now => return,
+			Some((last_block, cumulative_a, cumulative_b)) => {
+				let elapsed = Price::saturating_from_integer::<u128>((now - last_block).unique_saturated_into());
+				let (price_a, price_b) = spot_prices();
+
+				(
+					now,
+					cumulative_a.saturating_add(price_a.saturating_mul(elapsed)),
+					cumulative_b.saturating_add(price_b.saturating_mul(elapsed)),
+				)
+			}
+			None => (now, Price::zero(), Price::zero()),
+		};
+
+		<PriceHistory<T>>::insert(key, head % T::PriceHistoryLength::get().max(1), record);
+		<PriceHistoryHead<T>>::insert(key, head.saturating_add(1));
+	}
+
+	fn remove_price_history(key: &PriceHistoryKey<T::AccountId>) {
+		<PriceHistory<T>>::remove_prefix(key);
+		<PriceHistoryHead<T>>::remove(key);
+	}
+
+	/// Return cumulative prices at the given block.
+	///
+	/// `spot_prices` returns current prices of the pair. Returns `None` if the block is older than the price history.
+	fn cumulative_prices_at(
+		key: &PriceHistoryKey<T::AccountId>,
+		block: T::BlockNumber,
+		spot_prices: impl FnOnce() -> (Price, Price),
+	) -> Option<(Price, Price)> {
+		let head = Self::price_history_head(key);
+		let oldest = head.saturating_sub(T::PriceHistoryLength::get());
+
+		// Find the first record newer than `block`, records are ordered by block number
+		let (mut low, mut high) = (oldest, head);
+
+		while low < high {
+			let middle = low + (high - low) / 2;
+			if Self::price_record(key, middle)?.0 <= block {
+				low = middle + 1;
+			} else {
+				high = middle;
+			}
+		}
+
+		if low == oldest {
+			return None;
+		}
+
+		let (record_block, cumulative_a, cumulative_b) = Self::price_record(key, low - 1)?;
+
+		let (price_a, price_b) = match Some(low).filter(|&next| next < head) {
+			// Prices were constant between two records
+			Some(next) => {
+				let (next_block, next_a, next_b) = Self::price_record(key, next)?;
+				let period =
+					Price::saturating_from_integer::<u128>((next_block - record_block).unique_saturated_into());
+				(
+					next_a.saturating_sub(cumulative_a).checked_div(&period)?,
+					next_b.saturating_sub(cumulative_b).checked_div(&period)?,
+				)
+			}
+			None => spot_prices(),
+		};
+
+		let elapsed = Price::saturating_from_integer::<u128>((block - record_block).unique_saturated_into());
+
+		Some((
+			cumulative_a.saturating_add(price_a.saturating_mul(elapsed)),
+			cumulative_b.saturating_add(price_b.saturating_mul(elapsed)),
+		))
+	}
+
+	/// Return time weighted average price of asset a (if `asset_a`) or asset b of the price history
+	/// over last `window` blocks.
+	fn calculate_twap(
+		key: &PriceHistoryKey<T::AccountId>,
+		asset_a: bool,
+		window: T::BlockNumber,
+		spot_prices: impl Fn() -> (Price, Price),
+	) -> Option<Price> {
+		if window.is_zero() {
+			return None;
+		}
+
+		let now = <system::Module<T>>::block_number();
+		let start = now.checked_sub(&window)?;
+
+		let (start_a, start_b) = Self::cumulative_prices_at(key, start, &spot_prices)?;
+		let (end_a, end_b) = Self::cumulative_prices_at(key, now, &spot_prices)?;
+
+		let (start, end) = if asset_a { (start_a, end_a) } else { (start_b, end_b) };
+
+		end.saturating_sub(start)
+			.checked_div(&Price::saturating_from_integer::<u128>(window.unique_saturated_into()))
+	}
+
+	/// Return time weighted average price of `asset_a` denominated in `asset_b` over last `window` blocks.
+	///
+	/// Returns `None` if the pool does not exist or its price history does not cover the whole window.
+	pub fn get_twap(asset_a: AssetId, asset_b: AssetId, window: T::BlockNumber) -> Option<Price> {
+		if !Self::exists(asset_a, asset_b) {
+			return None;
+		}
+
+		let pair_account = Self::get_pair_id(&asset_a, &asset_b);
+		let (pool_asset_a, pool_asset_b) = Self::pool_assets(&pair_account);
+
+		Self::calculate_twap(
+			&(pair_account.clone(), pool_asset_a, pool_asset_b),
+			pool_asset_a == asset_a,
+			window,
+			|| Self::pool_spot_prices(&pair_account),
+		)
+	}
+
+	/// Return time weighted average price of `asset_a` denominated in `asset_b` in the weighted pool of `pool_assets`
+	/// over last `window` blocks.
+	///
+	/// Returns `None` if the pool does not exist or its price history does not cover the whole window.
+	pub fn get_weighted_twap(
+		pool_assets: &[AssetId],
+		asset_a: AssetId,
+		asset_b: AssetId,
+		window: T::BlockNumber,
+	) -> Option<Price> {
+		if asset_a == asset_b {
+			return None;
+		}
+
+		let (pool_account, weight_a) = Self::get_weighted_pool_asset(pool_assets, asset_a).ok()?;
+		let (_, weight_b) = Self::get_weighted_pool_asset(pool_assets, asset_b).ok()?;
+
+		// Price history of weighted pool assets is kept for pairs ordered by asset id
+		let (first, second) = if asset_a < asset_b {
+			((asset_a, weight_a), (asset_b, weight_b))
+		} else {
+			((asset_b, weight_b), (asset_a, weight_a))
+		};
+
+		Self::calculate_twap(
+			&(pool_account.clone(), first.0, second.0),
+			first.0 == asset_a,
+			window,
+			|| Self::weighted_spot_prices(&pool_account, first, second),
+		)
+	}
+
+	/// Transfer protocol share of the trading fee from the pool to `ProtocolFeeReceiver`.
+	///
+	/// Returns the amount taken by the protocol.
+	fn collect_protocol_fee(
+		pair_account: &T::AccountId,
+		asset: AssetId,
+		fee: Balance,
+	) -> Result<Balance, DispatchError> {
+		let protocol_fee = T::ProtocolFee::get() * fee;
+
+		if protocol_fee.is_zero() {
+			return Ok(Balance::zero());
+		}
+
+		let receiver = T::ProtocolFeeReceiver::get();
+
+		T::Currency::transfer(asset, pair_account, &receiver, protocol_fee)?;
+
+		ProtocolRevenue::mutate(asset, |revenue| *revenue = revenue.saturating_add(protocol_fee));
+
+		Self::deposit_event(RawEvent::ProtocolFeeCollected(
+			pair_account.clone(),
+			asset,
+			protocol_fee,
+			receiver,
+		));
+
+		Ok(protocol_fee)
+	}
+
+	/// Distribute trading fee between all pool shares.
+	///
+	/// Only shares registered in `FeeShares` are credited, the part of the fee which belongs to other shares
+	/// stays in the pool reserves.
+	fn accrue_fee(pair_account: &T::AccountId, asset: AssetId, amount: Balance) {
+		let share_token = Self::share_token(pair_account);
+		let fee_shares = Self::total_fee_shares(share_token);
+		let total_liquidity = sp_std::cmp::max(Self::total_liquidity(pair_account), fee_shares);
+
+		if amount.is_zero() || fee_shares.is_zero() {
+			return;
+		}
+
+		if let Some(fee_per_share) = FixedU128::checked_from_rational(amount, total_liquidity) {
+			let credited = fee_per_share.saturating_mul_int(fee_shares);
+
+			AccumulatedFeePerShare::mutate((share_token, asset), |acc| *acc = acc.saturating_add(fee_per_share));
+			UnclaimedFees::mutate((share_token, asset), |total| *total = total.saturating_add(credited));
+		}
+	}
+
+	/// Return registered shares of `who` which earned fees since the last settlement.
+	///
+	/// Share tokens can be moved by plain transfers, so only registered shares which are still held are taken
+	/// into account.
+	fn earning_shares(who: &T::AccountId, share_token: AssetId) -> Balance {
+		sp_std::cmp::min(
+			Self::fee_shares(share_token, who),
+			T::Currency::free_balance(share_token, who),
+		)
+	}
+
+	/// Move fees accumulated since last settlement to accrued fees of `who` and register its current shares.
+	///
+	/// Fees of registered shares which are no longer held by `who` are released to the pool reserves.
+	/// Must be called before the share balance of `who` changes, followed by `record_fee_shares`
+	/// once it has changed.
+	fn settle_fees(who: &T::AccountId, pair_account: &T::AccountId, share_token: AssetId) {
+		let fee_shares = Self::fee_shares(share_token, who);
+		let earning_shares = Self::earning_shares(who, share_token);
+
+		for asset in Self::get_pool_assets(pair_account).unwrap_or_default() {
+			let accumulated = Self::accumulated_fee_per_share((share_token, asset));
+			let fee_per_share = accumulated.saturating_sub(Self::fee_checkpoint((share_token, asset), who));
+
+			let pending = fee_per_share.saturating_mul_int(earning_shares);
+			let released = fee_per_share.saturating_mul_int(fee_shares).saturating_sub(pending);
+
+			if !pending.is_zero() {
+				<AccruedFees<T>>::mutate((share_token, asset), who, |fees| *fees = fees.saturating_add(pending));
+			}
+
+			if !released.is_zero() {
+				UnclaimedFees::mutate((share_token, asset), |total| *total = total.saturating_sub(released));
+			}
+
+			<FeeCheckpoint<T>>::insert((share_token, asset), who, accumulated);
+		}
+
+		Self::record_fee_shares(who, pair_account, share_token);
+	}
+
+	/// Register current share balance of `who` as the shares which earn fees from now on.
+	///
+	/// Shares are registered only up to the total liquidity of the pool, so shares still registered by the sender
+	/// of a transfer are not registered again by the receiver. Must be called after `TotalLiquidity` is updated.
+	fn record_fee_shares(who: &T::AccountId, pair_account: &T::AccountId, share_token: AssetId) {
+		let registered_by_others =
+			Self::total_fee_shares(share_token).saturating_sub(Self::fee_shares(share_token, who));
+		let unregistered = Self::total_liquidity(pair_account).saturating_sub(registered_by_others);
+
+		let fee_shares = sp_std::cmp::min(T::Currency::free_balance(share_token, who), unregistered);
+
+		if fee_shares.is_zero() {
+			<FeeShares<T>>::remove(share_token, who);
+		} else {
+			<FeeShares<T>>::insert(share_token, who, fee_shares);
+		}
+
+		TotalFeeShares::insert(share_token, registered_by_others.saturating_add(fee_shares));
+	}
+
+	/// Register shares of pools held by liquidity providers before fee shares were introduced.
+	///
+	/// `holders` are balances of all accounts, balances of assets other than pool share tokens are skipped.
+	/// Used by runtime upgrade, returns weight consumed.
+	pub fn migrate_fee_shares<I>(holders: I) -> Weight
+	where
+		I: IntoIterator<Item = (T::AccountId, AssetId, Balance)>,
+	{
+		// Fees of weighted pools stay in the reserves
+		let share_tokens: BTreeMap<AssetId, T::AccountId> = <ShareToken<T>>::iter()
+			.filter(|(pool, _)| !<WeightedPoolAssets<T>>::contains_key(pool))
+			.map(|(pool, share_token)| (share_token, pool))
+			.collect();
+
+		let mut reads = share_tokens.len() as Weight;
+		let mut writes: Weight = 0;
+
+		for (who, asset, balance) in holders {
+			reads += 1;
+
+			if balance.is_zero() {
+				continue;
+			}
+
+			if let Some(pair_account) = share_tokens.get(&asset) {
+				if Self::fee_shares(asset, &who).is_zero() {
+					Self::settle_fees(&who, pair_account, asset);
+
+					reads += 6;
+					writes += 4;
+				}
+			}
+		}
+
+		T::DbWeight::get().reads_writes(reads, writes)
+	}
+
+	/// Remove fee accounting of destroyed pool, so that it is not inherited by a new pool with the same share token.
+	fn remove_fee_state(share_token: AssetId, assets: &[AssetId]) {
+		for asset in assets {
+			AccumulatedFeePerShare::remove((share_token, *asset));
+			UnclaimedFees::remove((share_token, *asset));
+			<FeeCheckpoint<T>>::remove_prefix((share_token, *asset));
+			<AccruedFees<T>>::remove_prefix((share_token, *asset));
+		}
+
+		<FeeShares<T>>::remove_prefix(share_token);
+		TotalFeeShares::remove(share_token);
+	}
+
+	/// Take part of settled fees of `who` which belongs to `shares` out of `share_balance`.
+	fn take_accrued_fees(
+		who: &T::AccountId,
+		share_token: AssetId,
+		asset: AssetId,
+		shares: Balance,
+		share_balance: Balance,
+	) -> Result<Balance, DispatchError> {
+		let accrued = Self::accrued_fees((share_token, asset), who);
+
+		if accrued.is_zero() {
+			return Ok(Balance::zero());
+		}
+
+		// Fees settled before the shares were transferred away can still be claimed in full
+		let amount = if shares >= share_balance {
+			accrued
+		} else {
+			multiply_by_rational(accrued, shares, share_balance).map_err(|_| Error::<T>::FeeAmountInvalid)?
+		};
+
+		<AccruedFees<T>>::insert((share_token, asset), who, accrued - amount);
+		UnclaimedFees::mutate((share_token, asset), |total| *total = total.saturating_sub(amount));
+
+		Ok(amount)
+	}
+
+	/// Check that fee is a valid fraction which does not exceed `MaxExchangeFee`.
+	fn validate_fee(fee: fee::Fee) -> DispatchResult {
+		ensure!(
+			fee.denominator > 0 && fee.numerator <= fee.denominator,
+			Error::<T>::InvalidFee
+		);
+
+		let max_fee = T::MaxExchangeFee::get();
+
+		ensure!(
+			(fee.numerator as u64) * (max_fee.denominator as u64)
+				<= (max_fee.numerator as u64) * (fee.denominator as u64),
+			Error::<T>::MaxExchangeFeeExceeded
+		);
+
+		Ok(())
+	}
+
+	fn calculate_fees(
+		pair_account: &T::AccountId,
+		amount: Balance,
+		discount: bool,
+		hdx_fee: &mut Balance,
+	) -> Result<Balance, DispatchError> {
+		match discount {
+			true => {
+				let transfer_fee = amount
+					.discounted_fee()
+					.ok_or::<Error<T>>(Error::<T>::FeeAmountInvalid)?;
+				*hdx_fee = transfer_fee;
+				Ok(transfer_fee)
+			}
+			false => {
+				*hdx_fee = 0;
+				Ok(amount
+					.just_fee(Self::pool_fee(pair_account))
+					.ok_or::<Error<T>>(Error::<T>::FeeAmountInvalid)?)
+			}
+		}
+	}
+}
+
+impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
+	fn exists(asset_a: AssetId, asset_b: AssetId) -> bool {
+		let pair_account = T::AssetPairAccountId::from_assets(asset_a, asset_b);
+		<ShareToken<T>>::contains_key(&pair_account)
+	}
+
+	fn get_pair_id(asset_a: &AssetId, asset_b: &AssetId) -> T::AccountId {
+		T::AssetPairAccountId::from_assets(*asset_a, *asset_b)
+	}
+
+	fn get_pool_assets(pool_account_id: &T::AccountId) -> Option<Vec<AssetId>> {
+		match <PoolAssets<T>>::contains_key(pool_account_id) {
+			true => {
+				let assets = Self::pool_assets(pool_account_id);
+				Some(vec![assets.0, assets.1])
+			}
+			false => match <WeightedPoolAssets<T>>::contains_key(pool_account_id) {
+				true => Some(
+					Self::weighted_pool_assets(pool_account_id)
+						.into_iter()
+						.map(|(asset, _)| asset)
+						.collect(),
+				),
+				false => None,
+			},
+		}
+	}
 
 	fn get_spot_price_unchecked(asset_a: AssetId, asset_b: AssetId, amount: Balance) -> Balance {
 		let pair_account = Self::get_pair_id(&asset_a, &asset_b);
@@ -537,7 +3043,7 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 		let asset_a_reserve = T::Currency::free_balance(asset_a, &pair_account);
 		let asset_b_reserve = T::Currency::free_balance(asset_b, &pair_account);
 
-		hydra_dx_math::calculate_spot_price(asset_a_reserve, asset_b_reserve, amount)
+		Self::calculate_spot_price(&pair_account, asset_a, asset_a_reserve, asset_b_reserve, amount)
 			.or(Some(0))
 			.unwrap()
 	}
@@ -577,15 +3083,20 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 
 		let mut hdx_amount = 0;
 
-		let transfer_fee = Self::calculate_fees(amount_sell, discount, &mut hdx_amount)?;
+		let transfer_fee = Self::calculate_fees(&pair_account, amount_sell, discount, &mut hdx_amount)?;
 
-		let sale_price =
-			match hydra_dx_math::calculate_sell_price(asset_sell_total, asset_buy_total, amount_sell - transfer_fee) {
-				Some(x) => x,
-				None => {
-					return Err(Error::<T>::SellAssetAmountInvalid.into());
-				}
-			};
+		let sale_price = match Self::calculate_sell_price(
+			&pair_account,
+			asset_sell,
+			asset_sell_total,
+			asset_buy_total,
+			amount_sell - transfer_fee,
+		) {
+			Some(x) => x,
+			None => {
+				return Err(Error::<T>::SellAssetAmountInvalid.into());
+			}
+		};
 
 		ensure!(asset_buy_total >= sale_price, Error::<T>::InsufficientAssetBalance);
 
@@ -599,8 +3110,9 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 			let hdx_reserve = T::Currency::free_balance(hdx_asset, &hdx_pair_account);
 			let asset_reserve = T::Currency::free_balance(asset_sell, &hdx_pair_account);
 
-			let hdx_fee_spot_price = hydra_dx_math::calculate_spot_price(asset_reserve, hdx_reserve, hdx_amount)
-				.ok_or(Error::<T>::CannotApplyDiscount)?;
+			let hdx_fee_spot_price =
+				Self::calculate_spot_price(&hdx_pair_account, asset_sell, asset_reserve, hdx_reserve, hdx_amount)
+					.ok_or(Error::<T>::CannotApplyDiscount)?;
 
 			ensure!(
 				T::Currency::free_balance(hdx_asset, who) >= hdx_fee_spot_price,
@@ -620,6 +3132,7 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 			amount_out: sale_price,
 			discount,
 			discount_amount: discount_fee,
+			fee: (asset_sell, transfer_fee),
 		};
 
 		Ok(transfer)
@@ -633,9 +3146,25 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 			T::Currency::withdraw(hdx_asset, &transfer.origin, transfer.discount_amount)?;
 		}
 
+		Self::update_price_history(&pair_account);
+
 		T::Currency::transfer(transfer.asset_sell, &transfer.origin, &pair_account, transfer.amount)?;
 		T::Currency::transfer(transfer.asset_buy, &pair_account, &transfer.origin, transfer.amount_out)?;
 
+		let protocol_fee = Self::collect_protocol_fee(&pair_account, transfer.fee.0, transfer.fee.1)?;
+
+		if Self::is_concentrated(&pair_account) {
+			Self::apply_concentrated_swap(
+				&pair_account,
+				transfer.asset_sell,
+				transfer.amount - transfer.fee.1,
+				true,
+				transfer.fee.1 - protocol_fee,
+			)?;
+		} else {
+			Self::accrue_fee(&pair_account, transfer.fee.0, transfer.fee.1 - protocol_fee);
+		}
+
 		Self::deposit_event(Event::<T>::Sell(
 			transfer.origin.clone(),
 			transfer.asset_sell,
@@ -679,14 +3208,16 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 
 		let mut hdx_amount = 0;
 
-		let transfer_fee = Self::calculate_fees(amount_buy, discount, &mut hdx_amount)?;
+		let transfer_fee = Self::calculate_fees(&pair_account, amount_buy, discount, &mut hdx_amount)?;
 
 		ensure!(
 			amount_buy + transfer_fee <= asset_buy_reserve,
 			Error::<T>::InsufficientPoolAssetBalance
 		);
 
-		let buy_price = match hydra_dx_math::calculate_buy_price(
+		let buy_price = match Self::calculate_buy_price(
+			&pair_account,
+			asset_sell,
 			asset_sell_reserve,
 			asset_buy_reserve,
 			amount_buy + transfer_fee,
@@ -712,8 +3243,9 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 			let hdx_reserve = T::Currency::free_balance(hdx_asset, &hdx_pair_account);
 			let asset_reserve = T::Currency::free_balance(asset_buy, &hdx_pair_account);
 
-			let hdx_fee_spot_price = hydra_dx_math::calculate_spot_price(asset_reserve, hdx_reserve, hdx_amount)
-				.ok_or(Error::<T>::CannotApplyDiscount)?;
+			let hdx_fee_spot_price =
+				Self::calculate_spot_price(&hdx_pair_account, asset_buy, asset_reserve, hdx_reserve, hdx_amount)
+					.ok_or(Error::<T>::CannotApplyDiscount)?;
 
 			ensure!(
 				T::Currency::free_balance(hdx_asset, who) >= hdx_fee_spot_price,
@@ -732,6 +3264,7 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 			amount_out: buy_price,
 			discount,
 			discount_amount: discount_fee,
+			fee: (asset_buy, transfer_fee),
 		};
 
 		Ok(transfer)
@@ -745,6 +3278,8 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 			T::Currency::withdraw(hdx_asset, &transfer.origin, transfer.discount_amount)?;
 		}
 
+		Self::update_price_history(&pair_account);
+
 		T::Currency::transfer(transfer.asset_buy, &pair_account, &transfer.origin, transfer.amount)?;
 		T::Currency::transfer(
 			transfer.asset_sell,
@@ -753,6 +3288,20 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 			transfer.amount_out,
 		)?;
 
+		let protocol_fee = Self::collect_protocol_fee(&pair_account, transfer.fee.0, transfer.fee.1)?;
+
+		if Self::is_concentrated(&pair_account) {
+			Self::apply_concentrated_swap(
+				&pair_account,
+				transfer.asset_sell,
+				transfer.amount + transfer.fee.1,
+				false,
+				transfer.fee.1 - protocol_fee,
+			)?;
+		} else {
+			Self::accrue_fee(&pair_account, transfer.fee.0, transfer.fee.1 - protocol_fee);
+		}
+
 		Self::deposit_event(Event::<T>::Buy(
 			transfer.origin.clone(),
 			transfer.asset_buy,
@@ -764,3 +3313,102 @@ impl<T: Config> AMM<T::AccountId, AssetId, Balance> for Module<T> {
 		Ok(())
 	}
 }
+
+impl<T: Config> TWAPOracle<AssetId, T::BlockNumber> for Module<T> {
+	fn get_twap(asset_a: AssetId, asset_b: AssetId, window: T::BlockNumber) -> Option<Price> {
+		Self::get_twap(asset_a, asset_b, window)
+	}
+}
+
+impl<T: Config> Call<T> {
+	/// Return deadline of the call, if the call accepts one and it is set.
+	pub fn deadline(&self) -> Option<T::BlockNumber> {
+		match self {
+			Call::add_liquidity(.., deadline)
+			| Call::add_liquidity_single_asset(.., deadline)
+			| Call::remove_liquidity(.., deadline)
+			| Call::remove_liquidity_single_asset(.., deadline)
+			| Call::sell(.., deadline)
+			| Call::buy(.., deadline)
+			| Call::sell_route(.., deadline)
+			| Call::buy_route(.., deadline)
+			| Call::add_weighted_liquidity_single_asset(.., deadline)
+			| Call::remove_weighted_liquidity_single_asset(.., deadline)
+			| Call::remove_weighted_liquidity(.., deadline)
+			| Call::weighted_sell(.., deadline)
+			| Call::weighted_buy(.., deadline)
+			| Call::mint_position(.., deadline)
+			| Call::remove_position_liquidity(.., deadline) => *deadline,
+			_ => None,
+		}
+	}
+}
+
+/// Signed extension which rejects AMM calls with expired deadline already in transaction validation,
+/// so they are dropped from the transaction pool.
+///
+/// Valid calls with a deadline are given longevity which ends at the deadline.
+///
+/// Only top-level AMM calls are checked. Calls nested in other calls (e.g. batches or proxies) pass
+/// validation and are rejected by the deadline check of the call itself when dispatched.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckDeadline<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckDeadline<T> {
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckDeadline<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> sp_std::fmt::Debug for CheckDeadline<T> {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckDeadline")
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckDeadline<T>
+where
+	<T as system::Config>::Call: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckDeadline";
+	type AccountId = T::AccountId;
+	type Call = <T as system::Config>::Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		match call.is_sub_type().and_then(|call| call.deadline()) {
+			Some(deadline) => {
+				let current_block = <system::Module<T>>::block_number();
+
+				if current_block > deadline {
+					return InvalidTransaction::Stale.into();
+				}
+
+				let longevity: u64 = (deadline - current_block).unique_saturated_into();
+
+				Ok(ValidTransaction {
+					longevity: longevity.saturating_add(1),
+					..Default::default()
+				})
+			}
+			None => Ok(ValidTransaction::default()),
+		}
+	}
+}
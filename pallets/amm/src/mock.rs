@@ -2,26 +2,42 @@
 
 use super::*;
 use crate::{AssetPairAccountIdFor, Config, Module};
-use frame_support::{impl_outer_event, impl_outer_origin, parameter_types};
+use frame_support::{impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types};
 use frame_system as system;
 use orml_traits::parameter_type_with_key;
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup, Zero},
+	Permill,
 };
 
 use primitives::{fee, AssetId, Balance};
+use std::cell::RefCell;
 
 pub type AccountId = u64;
 
 pub const ALICE: AccountId = 1;
 pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
 
 pub const HDX: AssetId = 1000;
 pub const DOT: AssetId = 2000;
 pub const ACA: AssetId = 3000;
 
+pub const TREASURY: AccountId = 100;
+
+thread_local! {
+	static PROTOCOL_FEE: RefCell<Permill> = RefCell::new(Permill::zero());
+}
+
+pub struct ProtocolFeeRate;
+impl Get<Permill> for ProtocolFeeRate {
+	fn get() -> Permill {
+		PROTOCOL_FEE.with(|v| *v.borrow())
+	}
+}
+
 mod amm {
 	pub use super::super::*;
 }
@@ -38,6 +54,12 @@ impl_outer_origin! {
 	pub enum Origin for Test {}
 }
 
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		amm::AMM,
+	}
+}
+
 // For testing the pallet, we construct most of a mock runtime. This means
 // first constructing a configuration type (`Test`) which `impl`s each of the
 // configuration traits of pallets we want to use.
@@ -48,7 +70,10 @@ parameter_types! {
 
 	pub const HDXAssetId: AssetId = HDX;
 
-	pub ExchangeFeeRate: fee::Fee = fee::Fee::default();
+	pub MaxExchangeFeeRate: fee::Fee = fee::Fee { numerator: 1, denominator: 10 };
+	pub const PriceHistoryLength: u32 = 10;
+
+	pub const ProtocolFeeReceiverAccount: AccountId = TREASURY;
 }
 
 impl pallet_asset_registry::Config for Test {
@@ -60,7 +85,7 @@ impl system::Config for Test {
 	type BlockWeights = ();
 	type BlockLength = ();
 	type Origin = Origin;
-	type Call = ();
+	type Call = Call;
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -112,6 +137,14 @@ impl AssetPairAccountIdFor<AssetId, u64> for AssetPairAccountIdTest {
 		}
 		return (a * 1000 + b) as u64;
 	}
+
+	fn from_asset_set(assets: &[AssetId]) -> u64 {
+		let mut sorted = assets.to_vec();
+		sorted.sort_unstable();
+		sorted.iter().fold(0u64, |acc, asset| {
+			acc.wrapping_mul(1_000_003).wrapping_add(*asset as u64)
+		}) | (1 << 62)
+	}
 }
 
 impl Config for Test {
@@ -120,13 +153,18 @@ impl Config for Test {
 	type Currency = Currency;
 	type HDXAssetId = HDXAssetId;
 	type WeightInfo = ();
-	type GetExchangeFee = ExchangeFeeRate;
+	type MaxExchangeFee = MaxExchangeFeeRate;
+	type UpdatePoolFeeOrigin = frame_system::EnsureRoot<AccountId>;
+	type ProtocolFee = ProtocolFeeRate;
+	type ProtocolFeeReceiver = ProtocolFeeReceiverAccount;
+	type PriceHistoryLength = PriceHistoryLength;
 }
 pub type AMM = Module<Test>;
 pub type System = system::Module<Test>;
 
 pub struct ExtBuilder {
 	endowed_accounts: Vec<(AccountId, AssetId, Balance)>,
+	protocol_fee: Permill,
 }
 
 // Returns default values for genesis config
@@ -141,6 +179,7 @@ impl Default for ExtBuilder {
 				(ALICE, DOT, 1000_000_000_000_000u128),
 				(BOB, DOT, 1000_000_000_000_000u128),
 			],
+			protocol_fee: Permill::zero(),
 		}
 	}
 }
@@ -153,7 +192,17 @@ impl ExtBuilder {
 		self
 	}
 
+	pub fn with_protocol_fee(mut self, protocol_fee: Permill) -> Self {
+		self.protocol_fee = protocol_fee;
+		self
+	}
+
+	fn set_constants(&self) {
+		PROTOCOL_FEE.with(|v| *v.borrow_mut() = self.protocol_fee);
+	}
+
 	pub fn build(self) -> sp_io::TestExternalities {
+		self.set_constants();
 		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
 
 		orml_tokens::GenesisConfig::<Test> {
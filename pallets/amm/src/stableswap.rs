@@ -0,0 +1,165 @@
+//! Stable swap pricing for two asset pools.
+//!
+//! Pool keeps invariant `A * n^n * (x + y) + D = A * n^n * D + D^(n + 1) / (n^n * x * y)` where `n = 2`.
+//! Close to balanced reserves it behaves like a constant sum pool, when reserves diverge it approaches
+//! constant product.
+
+use frame_support::sp_runtime::FixedPointNumber;
+use primitive_types::U256;
+use primitives::{Balance, Price};
+
+const N_COINS: u128 = 2;
+const MAX_ITERATIONS: u8 = 255;
+
+/// Max allowed amplification of a stable swap pool.
+pub const MAX_AMPLIFICATION: u32 = 10_000;
+
+fn has_converged(previous: U256, current: U256) -> bool {
+	if current > previous {
+		current - previous <= U256::one()
+	} else {
+		previous - current <= U256::one()
+	}
+}
+
+fn to_balance(value: U256) -> Option<Balance> {
+	if value > U256::from(Balance::max_value()) {
+		None
+	} else {
+		Some(value.low_u128())
+	}
+}
+
+fn amplification_coefficient(amplification: u32) -> Option<U256> {
+	U256::from(amplification).checked_mul(U256::from(N_COINS * N_COINS))
+}
+
+/// Calculate invariant `D` of a pool with given reserves.
+pub fn calculate_d(reserve_a: Balance, reserve_b: Balance, amplification: u32) -> Option<Balance> {
+	let (reserve_a, reserve_b) = (U256::from(reserve_a), U256::from(reserve_b));
+
+	let sum = reserve_a.checked_add(reserve_b)?;
+
+	if sum.is_zero() {
+		return Some(0);
+	}
+
+	let ann = amplification_coefficient(amplification)?;
+	let n = U256::from(N_COINS);
+
+	let mut d = sum;
+
+	for _ in 0..MAX_ITERATIONS {
+		let d_p = d
+			.checked_mul(d)?
+			.checked_div(reserve_a.checked_mul(n)?)?
+			.checked_mul(d)?
+			.checked_div(reserve_b.checked_mul(n)?)?;
+
+		let d_previous = d;
+
+		let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(n)?)?.checked_mul(d)?;
+		let denominator = ann
+			.checked_sub(U256::one())?
+			.checked_mul(d)?
+			.checked_add(n.checked_add(U256::one())?.checked_mul(d_p)?)?;
+
+		d = numerator.checked_div(denominator)?;
+
+		if has_converged(d_previous, d) {
+			return to_balance(d);
+		}
+	}
+
+	None
+}
+
+/// Calculate reserve of the other asset which keeps invariant `d` when reserve of one asset is `reserve`.
+fn calculate_y(reserve: Balance, d: Balance, amplification: u32) -> Option<Balance> {
+	let (x, d) = (U256::from(reserve), U256::from(d));
+
+	let ann = amplification_coefficient(amplification)?;
+	let n = U256::from(N_COINS);
+
+	let c = d
+		.checked_mul(d)?
+		.checked_div(x.checked_mul(n)?)?
+		.checked_mul(d)?
+		.checked_div(ann.checked_mul(n)?)?;
+	let b = x.checked_add(d.checked_div(ann)?)?;
+
+	let mut y = d;
+
+	for _ in 0..MAX_ITERATIONS {
+		let y_previous = y;
+
+		let numerator = y.checked_mul(y)?.checked_add(c)?;
+		let denominator = y.checked_mul(n)?.checked_add(b)?.checked_sub(d)?;
+
+		y = numerator.checked_div(denominator)?;
+
+		if has_converged(y_previous, y) {
+			return to_balance(y);
+		}
+	}
+
+	None
+}
+
+/// Calculate amount of out asset received for `amount_in` of in asset.
+///
+/// Result is rounded down in favour of the pool.
+pub fn calculate_sell_price(
+	in_reserve: Balance,
+	out_reserve: Balance,
+	amount_in: Balance,
+	amplification: u32,
+) -> Option<Balance> {
+	let d = calculate_d(in_reserve, out_reserve, amplification)?;
+
+	let new_out_reserve = calculate_y(in_reserve.checked_add(amount_in)?, d, amplification)?;
+
+	out_reserve.checked_sub(new_out_reserve)?.checked_sub(1)
+}
+
+/// Calculate amount of in asset needed to receive `amount_out` of out asset.
+///
+/// Result is rounded up in favour of the pool.
+pub fn calculate_buy_price(
+	in_reserve: Balance,
+	out_reserve: Balance,
+	amount_out: Balance,
+	amplification: u32,
+) -> Option<Balance> {
+	let d = calculate_d(in_reserve, out_reserve, amplification)?;
+
+	let new_in_reserve = calculate_y(out_reserve.checked_sub(amount_out)?, d, amplification)?;
+
+	new_in_reserve.checked_sub(in_reserve)?.checked_add(1)
+}
+
+/// Calculate marginal price of in asset denominated in out asset.
+pub fn calculate_spot_price(in_reserve: Balance, out_reserve: Balance, amplification: u32) -> Option<Price> {
+	if in_reserve == 0 || out_reserve == 0 {
+		return None;
+	}
+
+	let d = U256::from(calculate_d(in_reserve, out_reserve, amplification)?);
+	let (x, y) = (U256::from(in_reserve), U256::from(out_reserve));
+
+	// Ratio of partial derivatives of the invariant:
+	// (4 * A * n^n * x * y + D^3 / x) / (4 * A * n^n * x * y + D^3 / y)
+	let common = amplification_coefficient(amplification)?
+		.checked_mul(U256::from(4))?
+		.checked_mul(x)?
+		.checked_mul(y)?;
+	let d_squared = d.checked_mul(d)?;
+
+	let numerator = common.checked_add(d_squared.checked_div(x)?.checked_mul(d)?)?;
+	let denominator = common.checked_add(d_squared.checked_div(y)?.checked_mul(d)?)?;
+
+	// Scale both down to fit into Balance
+	let scale = numerator.max(denominator) / U256::from(Balance::max_value()) + U256::one();
+
+	Price::checked_from_rational((numerator / scale).low_u128(), (denominator / scale).low_u128())
+}
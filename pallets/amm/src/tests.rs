@@ -1,6 +1,9 @@
 use super::*;
-pub use crate::mock::{Currency, ExtBuilder, Origin, System, Test, TestEvent, ACA, ALICE, AMM, BOB, DOT, HDX};
-use frame_support::{assert_noop, assert_ok};
+pub use crate::mock::{
+	AccountId, Currency, ExtBuilder, Origin, PriceHistoryLength, System, Test, TestEvent, ACA, ALICE, AMM, BOB,
+	CHARLIE, DOT, HDX, TREASURY,
+};
+use frame_support::{assert_noop, assert_ok, weights::GetDispatchInfo};
 use primitives::traits::AMM as AMMPool;
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
@@ -33,7 +36,9 @@ fn create_pool_should_work() {
 			asset_a,
 			asset_b,
 			100_000_000_000_000,
-			Price::from(10)
+			Price::from(10),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -64,10 +69,20 @@ fn create_same_pool_should_not_work() {
 			asset_b,
 			asset_a,
 			100,
-			Price::from(2)
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 		assert_noop!(
-			AMM::create_pool(Origin::signed(user), asset_b, asset_a, 100, Price::from(2)),
+			AMM::create_pool(
+				Origin::signed(user),
+				asset_b,
+				asset_a,
+				100,
+				Price::from(2),
+				fee::Fee::default(),
+				PoolType::ConstantProduct
+			),
 			Error::<Test>::TokenPoolAlreadyExists
 		);
 		expect_events(vec![RawEvent::CreatePool(ALICE, asset_b, asset_a, 200).into()]);
@@ -86,7 +101,9 @@ fn add_liquidity_should_work() {
 			asset_a,
 			asset_b,
 			100_000_000,
-			Price::from(10_000)
+			Price::from(10_000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		assert_ok!(AMM::add_liquidity(
@@ -94,7 +111,8 @@ fn add_liquidity_should_work() {
 			asset_a,
 			asset_b,
 			400_000,
-			1_000_000_000_000
+			1_000_000_000_000,
+			None
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_b, &asset_a);
@@ -124,14 +142,17 @@ fn add_liquidity_as_another_user_should_work() {
 			asset_b,
 			asset_a,
 			100_000_000,
-			Price::from(10_000)
+			Price::from(10_000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 		assert_ok!(AMM::add_liquidity(
 			Origin::signed(user),
 			asset_b,
 			asset_a,
 			400_000,
-			1_000_000_000_000
+			1_000_000_000_000,
+			None
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -148,7 +169,8 @@ fn add_liquidity_as_another_user_should_work() {
 			asset_b,
 			asset_a,
 			1_000_000,
-			1_000_000_000_000
+			1_000_000_000_000,
+			None
 		));
 
 		assert_eq!(Currency::free_balance(asset_a, &pair_account), 1014000000000);
@@ -179,7 +201,9 @@ fn remove_liquidity_should_work() {
 			asset_a,
 			asset_b,
 			100_000_000,
-			Price::from(10_000)
+			Price::from(10_000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -190,7 +214,15 @@ fn remove_liquidity_should_work() {
 		assert_eq!(Currency::free_balance(asset_a, &pair_account), 100000000);
 		assert_eq!(Currency::free_balance(asset_b, &pair_account), 1000000000000);
 
-		assert_ok!(AMM::remove_liquidity(Origin::signed(user), asset_a, asset_b, 355_000));
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(user),
+			asset_a,
+			asset_b,
+			355_000,
+			0,
+			0,
+			None
+		));
 
 		assert_eq!(Currency::free_balance(asset_b, &pair_account), 996450000000);
 		assert_eq!(Currency::free_balance(asset_a, &user), 999999900355000);
@@ -200,7 +232,7 @@ fn remove_liquidity_should_work() {
 
 		expect_events(vec![
 			RawEvent::CreatePool(ALICE, asset_a, asset_b, 100000000).into(),
-			RawEvent::RemoveLiquidity(ALICE, asset_a, asset_b, 355_000).into(),
+			RawEvent::RemoveLiquidity(ALICE, asset_a, asset_b, 355_000, 355_000, 3_550_000_000).into(),
 		]);
 	});
 }
@@ -213,13 +245,22 @@ fn add_liquidity_more_than_owner_should_not_work() {
 			HDX,
 			ACA,
 			200_000_000,
-			Price::from(3000000)
+			Price::from(3000000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		assert_eq!(Currency::free_balance(ACA, &ALICE), 400000000000000);
 
 		assert_noop!(
-			AMM::add_liquidity(Origin::signed(ALICE), HDX, ACA, 200_000_000_000_000_000, 600_000_000),
+			AMM::add_liquidity(
+				Origin::signed(ALICE),
+				HDX,
+				ACA,
+				200_000_000_000_000_000,
+				600_000_000,
+				None
+			),
 			Error::<Test>::InsufficientAssetBalance
 		);
 	});
@@ -228,15 +269,23 @@ fn add_liquidity_more_than_owner_should_not_work() {
 #[test]
 fn add_zero_liquidity_should_not_work() {
 	new_test_ext().execute_with(|| {
-		assert_ok!(AMM::create_pool(Origin::signed(ALICE), HDX, ACA, 100, Price::from(1)));
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			ACA,
+			100,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
 
 		assert_noop!(
-			AMM::add_liquidity(Origin::signed(ALICE), HDX, ACA, 0, 0),
+			AMM::add_liquidity(Origin::signed(ALICE), HDX, ACA, 0, 0, None),
 			Error::<Test>::CannotAddZeroLiquidity
 		);
 
 		assert_noop!(
-			AMM::add_liquidity(Origin::signed(ALICE), HDX, ACA, 100, 0),
+			AMM::add_liquidity(Origin::signed(ALICE), HDX, ACA, 100, 0, None),
 			Error::<Test>::CannotAddZeroLiquidity
 		);
 	});
@@ -246,7 +295,7 @@ fn add_zero_liquidity_should_not_work() {
 fn remove_zero_liquidity_should_not_work() {
 	new_test_ext().execute_with(|| {
 		assert_noop!(
-			AMM::remove_liquidity(Origin::signed(ALICE), HDX, ACA, 0),
+			AMM::remove_liquidity(Origin::signed(ALICE), HDX, ACA, 0, 0, 0, None),
 			Error::<Test>::CannotRemoveLiquidityWithZero
 		);
 	});
@@ -264,7 +313,9 @@ fn sell_test() {
 			asset_a,
 			asset_b,
 			200_000_000_000,
-			Price::from(3000)
+			Price::from(3000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -284,6 +335,7 @@ fn sell_test() {
 			456_444_678,
 			1000000000000,
 			false,
+			None,
 		));
 
 		assert_eq!(Currency::free_balance(asset_a, &user_1), 999799543555322);
@@ -320,12 +372,21 @@ fn work_flow_happy_path_should_work() {
 			asset_a,
 			asset_b,
 			350_000_000_000,
-			Price::from(40)
+			Price::from(40),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		// User 1 really tries!
 		assert_noop!(
-			AMM::add_liquidity(Origin::signed(user_1), asset_a, asset_b, 800_000_000_000_000_000, 100),
+			AMM::add_liquidity(
+				Origin::signed(user_1),
+				asset_a,
+				asset_b,
+				800_000_000_000_000_000,
+				100,
+				None
+			),
 			Error::<Test>::InsufficientAssetBalance
 		);
 
@@ -355,7 +416,8 @@ fn work_flow_happy_path_should_work() {
 			asset_a,
 			asset_b,
 			300_000_000_000,
-			current_b_balance
+			current_b_balance,
+			None
 		));
 
 		assert_eq!(AMM::total_liquidity(&pair_account), 650_000_000_000);
@@ -381,6 +443,7 @@ fn work_flow_happy_path_should_work() {
 			216_666_666_666,
 			100_000_000_000,
 			false,
+			None,
 		));
 
 		assert_eq!(Currency::free_balance(asset_a, &user_1), 999_650_000_000_000);
@@ -403,6 +466,7 @@ fn work_flow_happy_path_should_work() {
 			288_888_888_888,
 			100_000_000_000,
 			false,
+			None,
 		));
 
 		assert_eq!(Currency::free_balance(asset_a, &user_1), 999_361_111_111_112);
@@ -419,7 +483,15 @@ fn work_flow_happy_path_should_work() {
 
 		// User 2 removes liquidity
 
-		assert_ok!(AMM::remove_liquidity(Origin::signed(user_2), asset_a, asset_b, 10_000));
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(user_2),
+			asset_a,
+			asset_b,
+			10_000,
+			0,
+			0,
+			None
+		));
 
 		let user_2_remove_1_balance_1 = Currency::free_balance(asset_a, &user_2);
 		let user_2_remove_1_balance_2 = Currency::free_balance(asset_b, &user_2);
@@ -428,7 +500,15 @@ fn work_flow_happy_path_should_work() {
 		assert_eq!(user_2_remove_1_balance_2, 994_490_245_347_779);
 		assert_eq!(Currency::free_balance(share_token, &user_2), 299_999_990_000);
 
-		assert_ok!(AMM::remove_liquidity(Origin::signed(user_2), asset_b, asset_a, 10_000));
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(user_2),
+			asset_b,
+			asset_a,
+			10_000,
+			0,
+			0,
+			None
+		));
 
 		let user_2_remove_2_balance_1 = Currency::free_balance(asset_a, &user_2);
 		let user_2_remove_2_balance_2 = Currency::free_balance(asset_b, &user_2);
@@ -451,9 +531,20 @@ fn work_flow_happy_path_should_work() {
 
 		assert_eq!(AMM::total_liquidity(&pair_account), 649_999_980_000);
 
-		assert_ok!(AMM::remove_liquidity(Origin::signed(user_2), asset_a, asset_b, 18_000));
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(user_2),
+			asset_a,
+			asset_b,
+			18_000,
+			0,
+			0,
+			None
+		));
 		assert_eq!(Currency::free_balance(share_token, &user_2), 299_999_962_000);
 
+		let user_2_remove_3_amount_1 = Currency::free_balance(asset_a, &user_2) - user_2_remove_2_balance_1;
+		let user_2_remove_3_amount_2 = Currency::free_balance(asset_b, &user_2) - user_2_remove_2_balance_2;
+
 		assert_eq!(AMM::total_liquidity(&pair_account), 649_999_962_000);
 
 		expect_events(vec![
@@ -461,9 +552,17 @@ fn work_flow_happy_path_should_work() {
 			RawEvent::AddLiquidity(user_2, asset_a, asset_b, 300_000_000_000, 12_000_000_000_000).into(),
 			RawEvent::Sell(user_2, asset_a, asset_b, 216_666_666_666, 6_490_245_122_554).into(),
 			RawEvent::Sell(ALICE, asset_a, asset_b, 288_888_888_888, 4_870_118_901_375).into(),
-			RawEvent::RemoveLiquidity(user_2, asset_a, asset_b, 10_000).into(),
-			RawEvent::RemoveLiquidity(user_2, asset_b, asset_a, 10_000).into(),
-			RawEvent::RemoveLiquidity(user_2, asset_a, asset_b, 18_000).into(),
+			RawEvent::RemoveLiquidity(user_2, asset_a, asset_b, 10_000, 17_777, 225_225).into(),
+			RawEvent::RemoveLiquidity(user_2, asset_b, asset_a, 10_000, 225_225, 17_777).into(),
+			RawEvent::RemoveLiquidity(
+				user_2,
+				asset_a,
+				asset_b,
+				18_000,
+				user_2_remove_3_amount_1,
+				user_2_remove_3_amount_2,
+			)
+			.into(),
 		]);
 	});
 }
@@ -499,7 +598,9 @@ fn sell_with_correct_fees_should_work() {
 			asset_a,
 			asset_b,
 			10_000_000,
-			Price::from(200)
+			Price::from(200),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -520,6 +621,7 @@ fn sell_with_correct_fees_should_work() {
 			100_000,
 			1_000_000,
 			false,
+			None,
 		));
 
 		assert_eq!(Currency::free_balance(asset_a, &pair_account), 10100000);
@@ -556,14 +658,18 @@ fn discount_sell_fees_should_work() {
 			asset_a,
 			HDX,
 			5_000,
-			Price::from(2)
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 		assert_ok!(AMM::create_pool(
 			Origin::signed(user_1),
 			asset_a,
 			asset_b,
 			30_000,
-			Price::from(2)
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -578,7 +684,15 @@ fn discount_sell_fees_should_work() {
 		assert_eq!(Currency::free_balance(asset_b, &user_1), 940_000);
 		assert_eq!(Currency::free_balance(HDX, &user_1), 990_000);
 
-		assert_ok!(AMM::sell(Origin::signed(user_1), asset_a, asset_b, 10_000, 1_500, true,));
+		assert_ok!(AMM::sell(
+			Origin::signed(user_1),
+			asset_a,
+			asset_b,
+			10_000,
+			1_500,
+			true,
+			None,
+		));
 
 		assert_eq!(Currency::free_balance(asset_a, &pair_account), 40_000);
 		assert_eq!(Currency::free_balance(asset_b, &pair_account), 45_007);
@@ -609,7 +723,9 @@ fn single_buy_should_work() {
 			asset_a,
 			asset_b,
 			200_000_000,
-			Price::from(3200)
+			Price::from(3200),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -629,6 +745,7 @@ fn single_buy_should_work() {
 			66_666_666,
 			1_000_000_000_000,
 			false,
+			None,
 		));
 
 		assert_eq!(Currency::free_balance(asset_a, &user_1), 999_999_866_666_666);
@@ -656,7 +773,9 @@ fn single_buy_with_discount_should_work() {
 			asset_a,
 			asset_b,
 			200_000_000,
-			Price::from(3200)
+			Price::from(3200),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		assert_ok!(AMM::create_pool(
@@ -664,7 +783,9 @@ fn single_buy_with_discount_should_work() {
 			asset_a,
 			HDX,
 			50_000_000_000,
-			Price::from(2)
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -687,6 +808,7 @@ fn single_buy_with_discount_should_work() {
 			66_666_666,
 			1_000_000_000_000,
 			true,
+			None,
 		));
 
 		assert_eq!(Currency::free_balance(asset_a, &user_1), 999_949_866_666_666);
@@ -710,12 +832,28 @@ fn single_buy_with_discount_should_work() {
 fn create_pool_with_zero_liquidity_should_not_work() {
 	new_test_ext().execute_with(|| {
 		assert_noop!(
-			AMM::create_pool(Origin::signed(ALICE), ACA, HDX, 0, Price::from(3200)),
+			AMM::create_pool(
+				Origin::signed(ALICE),
+				ACA,
+				HDX,
+				0,
+				Price::from(3200),
+				fee::Fee::default(),
+				PoolType::ConstantProduct
+			),
 			Error::<Test>::CannotCreatePoolWithZeroLiquidity
 		);
 
 		assert_noop!(
-			AMM::create_pool(Origin::signed(ALICE), ACA, HDX, 10, Price::from(0)),
+			AMM::create_pool(
+				Origin::signed(ALICE),
+				ACA,
+				HDX,
+				10,
+				Price::from(0),
+				fee::Fee::default(),
+				PoolType::ConstantProduct
+			),
 			Error::<Test>::CannotCreatePoolWithZeroInitialPrice
 		);
 	});
@@ -725,7 +863,14 @@ fn create_pool_with_zero_liquidity_should_not_work() {
 fn add_liquidity_to_non_existing_pool_should_not_work() {
 	new_test_ext().execute_with(|| {
 		assert_noop!(
-			AMM::add_liquidity(Origin::signed(ALICE), HDX, ACA, 200_000_000_000_000_000, 600_000_000),
+			AMM::add_liquidity(
+				Origin::signed(ALICE),
+				HDX,
+				ACA,
+				200_000_000_000_000_000,
+				600_000_000,
+				None
+			),
 			Error::<Test>::TokenPoolNotFound
 		);
 	});
@@ -735,7 +880,7 @@ fn add_liquidity_to_non_existing_pool_should_not_work() {
 fn remove_zero_liquidity_from_non_existing_pool_should_not_work() {
 	new_test_ext().execute_with(|| {
 		assert_noop!(
-			AMM::remove_liquidity(Origin::signed(ALICE), HDX, ACA, 100),
+			AMM::remove_liquidity(Origin::signed(ALICE), HDX, ACA, 100, 0, 0, None),
 			Error::<Test>::TokenPoolNotFound
 		);
 	});
@@ -745,7 +890,7 @@ fn remove_zero_liquidity_from_non_existing_pool_should_not_work() {
 fn sell_with_non_existing_pool_should_not_work() {
 	new_test_ext().execute_with(|| {
 		assert_noop!(
-			AMM::sell(Origin::signed(ALICE), HDX, DOT, 456_444_678, 1_000_000, false),
+			AMM::sell(Origin::signed(ALICE), HDX, DOT, 456_444_678, 1_000_000, false, None),
 			Error::<Test>::TokenPoolNotFound
 		);
 	});
@@ -759,11 +904,13 @@ fn discount_sell_with_no_hdx_pool_should_not_work() {
 			ACA,
 			DOT,
 			100,
-			Price::from(3200)
+			Price::from(3200),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		assert_noop!(
-			AMM::sell(Origin::signed(ALICE), ACA, DOT, 456_444_678, 1_000_000, true),
+			AMM::sell(Origin::signed(ALICE), ACA, DOT, 456_444_678, 1_000_000, true, None),
 			Error::<Test>::CannotApplyDiscount
 		);
 	});
@@ -773,7 +920,7 @@ fn discount_sell_with_no_hdx_pool_should_not_work() {
 fn buy_with_non_existing_pool_should_not_work() {
 	new_test_ext().execute_with(|| {
 		assert_noop!(
-			AMM::buy(Origin::signed(ALICE), HDX, DOT, 456_444_678, 1_000_000_000, false),
+			AMM::buy(Origin::signed(ALICE), HDX, DOT, 456_444_678, 1_000_000_000, false, None),
 			Error::<Test>::TokenPoolNotFound
 		);
 	});
@@ -787,11 +934,13 @@ fn discount_buy_with_no_hdx_pool_should_not_work() {
 			ACA,
 			DOT,
 			100,
-			Price::from(3200)
+			Price::from(3200),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		assert_noop!(
-			AMM::buy(Origin::signed(ALICE), ACA, DOT, 10, 1_000_000_000, true),
+			AMM::buy(Origin::signed(ALICE), ACA, DOT, 10, 1_000_000_000, true, None),
 			Error::<Test>::CannotApplyDiscount
 		);
 	});
@@ -807,7 +956,9 @@ fn create_pool_small_fixed_point_amount_should_work() {
 			asset_a,
 			asset_b,
 			100_000_000_000_000,
-			Price::from_fraction(0.00001)
+			Price::from_fraction(0.00001),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -836,7 +987,9 @@ fn create_pool_fixed_point_amount_should_work() {
 			asset_a,
 			asset_b,
 			100_000_000_000,
-			Price::from_fraction(4560.234543)
+			Price::from_fraction(4560.234543),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -865,7 +1018,9 @@ fn destry_pool_on_remove_liquidity_and_recreate_should_work() {
 			asset_a,
 			asset_b,
 			100_000_000,
-			Price::from(10_000)
+			Price::from(10_000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -875,7 +1030,10 @@ fn destry_pool_on_remove_liquidity_and_recreate_should_work() {
 			Origin::signed(user),
 			asset_a,
 			asset_b,
-			100_000_000
+			100_000_000,
+			0,
+			0,
+			None
 		));
 
 		assert_eq!(AMM::total_liquidity(&pair_account), 0);
@@ -889,12 +1047,14 @@ fn destry_pool_on_remove_liquidity_and_recreate_should_work() {
 			asset_a,
 			asset_b,
 			100_000_000,
-			Price::from(10_000)
+			Price::from(10_000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		expect_events(vec![
 			RawEvent::CreatePool(user, asset_a, asset_b, 100_000_000).into(),
-			RawEvent::RemoveLiquidity(user, asset_a, asset_b, 100_000_000).into(),
+			RawEvent::RemoveLiquidity(user, asset_a, asset_b, 100_000_000, 100_000_000, 1_000_000_000_000).into(),
 			RawEvent::PoolDestroyed(user, asset_a, asset_b).into(),
 			RawEvent::CreatePool(user, asset_a, asset_b, 100_000_000).into(),
 		]);
@@ -908,7 +1068,15 @@ fn create_pool_with_same_assets_should_not_be_allowed() {
 		let asset_a = HDX;
 
 		assert_noop!(
-			AMM::create_pool(Origin::signed(user), asset_a, asset_a, 100_000_000, Price::from(10_000)),
+			AMM::create_pool(
+				Origin::signed(user),
+				asset_a,
+				asset_a,
+				100_000_000,
+				Price::from(10_000),
+				fee::Fee::default(),
+				PoolType::ConstantProduct
+			),
 			Error::<Test>::CannotCreatePoolWithSameAssets
 		);
 	})
@@ -926,7 +1094,9 @@ fn sell_test_exceeding_max_limit() {
 			asset_a,
 			asset_b,
 			200_000_000_000,
-			Price::from(3000)
+			Price::from(3000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -947,6 +1117,7 @@ fn sell_test_exceeding_max_limit() {
 				456_444_678,
 				1_000_000_000_000_000,
 				false,
+				None,
 			),
 			Error::<Test>::AssetBalanceLimitExceeded
 		);
@@ -971,7 +1142,9 @@ fn buy_test_exceeding_max_limit() {
 			asset_a,
 			asset_b,
 			200_000_000_000,
-			Price::from(3000)
+			Price::from(3000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -992,6 +1165,7 @@ fn buy_test_exceeding_max_limit() {
 				456_444_678,
 				1_000_000_000,
 				false,
+				None,
 			),
 			Error::<Test>::AssetBalanceLimitExceeded
 		);
@@ -1016,7 +1190,9 @@ fn single_buy_more_than_ratio_out_should_not_work() {
 			asset_a,
 			asset_b,
 			200_000_000,
-			Price::from(3200)
+			Price::from(3200),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -1037,6 +1213,7 @@ fn single_buy_more_than_ratio_out_should_not_work() {
 				66_666_667,
 				1_000_000_000_000,
 				false,
+				None,
 			),
 			Error::<Test>::MaxOutRatioExceeded
 		);
@@ -1055,7 +1232,9 @@ fn single_sell_more_than_ratio_in_should_not_work() {
 			asset_a,
 			asset_b,
 			200_000_000_000,
-			Price::from(3000)
+			Price::from(3000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
 		));
 
 		let pair_account = AMM::get_pair_id(&asset_a, &asset_b);
@@ -1076,6 +1255,7 @@ fn single_sell_more_than_ratio_in_should_not_work() {
 				66_666_666_667,
 				10_000_000,
 				false,
+				None,
 			),
 			Error::<Test>::MaxInRatioExceeded
 		);
@@ -1125,3 +1305,2546 @@ fn test_calculate_buy_price() {
 		assert_eq!(result, Some(1111111111112));
 	});
 }
+
+fn create_route_pools() {
+	assert_ok!(AMM::create_pool(
+		Origin::signed(ALICE),
+		ACA,
+		HDX,
+		100_000_000_000_000,
+		Price::from(2),
+		fee::Fee::default(),
+		PoolType::ConstantProduct
+	));
+	assert_ok!(AMM::create_pool(
+		Origin::signed(ALICE),
+		HDX,
+		DOT,
+		100_000_000_000_000,
+		Price::from(3),
+		fee::Fee::default(),
+		PoolType::ConstantProduct
+	));
+}
+
+#[test]
+fn sell_route_should_work() {
+	let amount = 1_000_000_000;
+
+	// Expected result is the same as if each hop was traded as separate sell.
+	let (expected_hdx, expected_dot) = new_test_ext().execute_with(|| {
+		create_route_pools();
+
+		let dot_before = Currency::free_balance(DOT, &BOB);
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+		assert_ok!(AMM::sell(Origin::signed(BOB), ACA, HDX, amount, 0, false, None));
+		let hdx_bought = Currency::free_balance(HDX, &BOB) - hdx_before;
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, hdx_bought, 0, false, None));
+
+		(hdx_bought, Currency::free_balance(DOT, &BOB) - dot_before)
+	});
+
+	new_test_ext().execute_with(|| {
+		create_route_pools();
+
+		let aca_before = Currency::free_balance(ACA, &BOB);
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_ok!(AMM::sell_route(
+			Origin::signed(BOB),
+			vec![ACA, HDX, DOT],
+			amount,
+			expected_dot,
+			false,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(ACA, &BOB), aca_before - amount);
+		assert_eq!(Currency::free_balance(HDX, &BOB), hdx_before);
+		assert_eq!(Currency::free_balance(DOT, &BOB), dot_before + expected_dot);
+
+		expect_events(vec![
+			RawEvent::Sell(BOB, ACA, HDX, amount, expected_hdx).into(),
+			RawEvent::Sell(BOB, HDX, DOT, expected_hdx, expected_dot).into(),
+			RawEvent::RouteSell(BOB, vec![ACA, HDX, DOT], amount, expected_dot).into(),
+		]);
+	});
+}
+
+#[test]
+fn sell_route_exceeding_min_bought_should_not_work() {
+	new_test_ext().execute_with(|| {
+		create_route_pools();
+
+		let aca_before = Currency::free_balance(ACA, &BOB);
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+
+		assert_noop!(
+			AMM::sell_route(
+				Origin::signed(BOB),
+				vec![ACA, HDX, DOT],
+				1_000_000_000,
+				1_000_000_000_000,
+				false,
+				None
+			),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+
+		assert_eq!(Currency::free_balance(ACA, &BOB), aca_before);
+		assert_eq!(Currency::free_balance(HDX, &BOB), hdx_before);
+	});
+}
+
+#[test]
+fn buy_route_should_work() {
+	let amount = 1_000_000_000;
+
+	new_test_ext().execute_with(|| {
+		create_route_pools();
+
+		let aca_before = Currency::free_balance(ACA, &BOB);
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_ok!(AMM::buy_route(
+			Origin::signed(BOB),
+			vec![ACA, HDX, DOT],
+			amount,
+			1_000_000_000_000,
+			false,
+			None
+		));
+
+		let aca_sold = aca_before - Currency::free_balance(ACA, &BOB);
+
+		assert!(aca_sold > 0);
+		assert_eq!(Currency::free_balance(HDX, &BOB), hdx_before);
+		assert_eq!(Currency::free_balance(DOT, &BOB), dot_before + amount);
+
+		expect_events(vec![
+			RawEvent::RouteBuy(BOB, vec![ACA, HDX, DOT], amount, aca_sold).into()
+		]);
+	});
+}
+
+#[test]
+fn buy_route_exceeding_max_sold_should_not_work() {
+	new_test_ext().execute_with(|| {
+		create_route_pools();
+
+		assert_noop!(
+			AMM::buy_route(
+				Origin::signed(BOB),
+				vec![ACA, HDX, DOT],
+				1_000_000_000,
+				1_000,
+				false,
+				None
+			),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+	});
+}
+
+#[test]
+fn invalid_route_should_not_work() {
+	new_test_ext().execute_with(|| {
+		create_route_pools();
+
+		assert_noop!(
+			AMM::sell_route(Origin::signed(BOB), vec![ACA], 1_000, 0, false, None),
+			Error::<Test>::InvalidRoute
+		);
+		assert_noop!(
+			AMM::sell_route(Origin::signed(BOB), vec![ACA, HDX, ACA], 1_000, 0, false, None),
+			Error::<Test>::InvalidRoute
+		);
+		assert_noop!(
+			AMM::sell_route(Origin::signed(BOB), vec![ACA, DOT], 1_000, 0, false, None),
+			Error::<Test>::TokenPoolNotFound
+		);
+		assert_noop!(
+			AMM::buy_route(
+				Origin::signed(BOB),
+				vec![ACA, HDX, DOT, ACA, HDX, DOT],
+				1_000,
+				0,
+				false,
+				None
+			),
+			Error::<Test>::MaxRouteLengthExceeded
+		);
+	});
+}
+
+#[test]
+fn route_trade_weight_should_be_charged_per_hop() {
+	let hop = <() as WeightInfo>::sell().saturating_add(concentrated_swap_weight::<Test>());
+
+	let weight = |route: Vec<AssetId>| {
+		Call::<Test>::sell_route(route, 1_000, 0, false, None)
+			.get_dispatch_info()
+			.weight
+	};
+
+	assert_eq!(weight(vec![ACA, HDX]), hop);
+	assert_eq!(weight(vec![ACA, HDX, DOT]), 2 * hop);
+
+	// Length of a route is bounded by MAX_ROUTE_LENGTH before it is validated.
+	assert_eq!(weight(vec![ACA; 100]), (MAX_ROUTE_LENGTH as Weight - 1) * hop);
+}
+
+#[test]
+fn get_best_sell_route_should_work() {
+	new_test_ext().execute_with(|| {
+		create_route_pools();
+
+		// Direct pool with much worse price than the route through HDX
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			ACA,
+			DOT,
+			100_000_000_000_000,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let amount = 1_000_000_000;
+
+		let (route, amount_out) = AMM::get_best_sell_route(ACA, DOT, amount, 3).unwrap();
+		assert_eq!(route, vec![ACA, HDX, DOT]);
+
+		let dot_before = Currency::free_balance(DOT, &BOB);
+		assert_ok!(AMM::sell_route(Origin::signed(BOB), route, amount, 0, false, None));
+		assert_eq!(Currency::free_balance(DOT, &BOB), dot_before + amount_out);
+	});
+}
+
+#[test]
+fn get_best_sell_route_with_max_hops_should_work() {
+	new_test_ext().execute_with(|| {
+		create_route_pools();
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			ACA,
+			DOT,
+			100_000_000_000_000,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let amount = 1_000_000_000;
+
+		let (route, amount_out) = AMM::get_best_sell_route(ACA, DOT, amount, 1).unwrap();
+		assert_eq!(route, vec![ACA, DOT]);
+		assert_eq!(
+			amount_out,
+			AMM::calculate_sell_amount_with_fee(ACA, DOT, amount).unwrap()
+		);
+	});
+}
+
+#[test]
+fn get_best_sell_route_without_pools_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(AMM::get_best_sell_route(ACA, DOT, 1_000_000_000, 3), None);
+
+		create_route_pools();
+
+		assert_eq!(AMM::get_best_sell_route(ACA, DOT, 1_000_000_000, 1), None);
+		assert_eq!(AMM::get_best_sell_route(ACA, ACA, 1_000_000_000, 3), None);
+	});
+}
+
+#[test]
+fn create_pool_with_fee_exceeding_max_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AMM::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				DOT,
+				100_000_000,
+				Price::from(2),
+				fee::Fee {
+					numerator: 2,
+					denominator: 10
+				},
+				PoolType::ConstantProduct
+			),
+			Error::<Test>::MaxExchangeFeeExceeded
+		);
+		assert_noop!(
+			AMM::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				DOT,
+				100_000_000,
+				Price::from(2),
+				fee::Fee {
+					numerator: 1,
+					denominator: 0
+				},
+				PoolType::ConstantProduct
+			),
+			Error::<Test>::InvalidFee
+		);
+	});
+}
+
+#[test]
+fn sell_with_pool_fee_should_work() {
+	new_test_ext().execute_with(|| {
+		let pool_fee = fee::Fee {
+			numerator: 1,
+			denominator: 100,
+		};
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			pool_fee,
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		assert_eq!(AMM::pool_fee(&pair_account), pool_fee);
+
+		let amount = 1_000_000_000;
+		let expected = hydra_dx_math::calculate_sell_price(
+			Currency::free_balance(HDX, &pair_account),
+			Currency::free_balance(DOT, &pair_account),
+			amount - 10_000_000,
+		)
+		.unwrap();
+
+		assert_eq!(AMM::get_sell_price(HDX, DOT, amount), expected);
+
+		let dot_before = Currency::free_balance(DOT, &BOB);
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, amount, 0, false, None));
+		assert_eq!(Currency::free_balance(DOT, &BOB), dot_before + expected);
+	});
+}
+
+#[test]
+fn set_pool_fee_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let new_fee = fee::Fee {
+			numerator: 5,
+			denominator: 1000,
+		};
+
+		assert_noop!(
+			AMM::set_pool_fee(Origin::signed(ALICE), HDX, DOT, new_fee),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			AMM::set_pool_fee(Origin::root(), HDX, ACA, new_fee),
+			Error::<Test>::TokenPoolNotFound
+		);
+
+		assert_ok!(AMM::set_pool_fee(Origin::root(), DOT, HDX, new_fee));
+
+		assert_eq!(AMM::pool_fee(AMM::get_pair_id(&HDX, &DOT)), new_fee);
+
+		expect_events(vec![RawEvent::PoolFeeUpdated(DOT, HDX, new_fee).into()]);
+	});
+}
+
+#[test]
+fn pool_fee_should_be_removed_when_pool_is_destroyed() {
+	new_test_ext().execute_with(|| {
+		let pool_fee = fee::Fee {
+			numerator: 1,
+			denominator: 100,
+		};
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000,
+			Price::from(2),
+			pool_fee,
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+		let shares = Currency::free_balance(share_token, &ALICE);
+
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			shares,
+			0,
+			0,
+			None
+		));
+
+		assert!(!PoolFee::<Test>::contains_key(&pair_account));
+	});
+}
+
+#[test]
+fn claim_fees_should_work() {
+	new_test_ext().execute_with(|| {
+		let pool_fee = fee::Fee {
+			numerator: 1,
+			denominator: 100,
+		};
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			pool_fee,
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+
+		assert_eq!(
+			AMM::get_accrued_fees(&ALICE, &pair_account),
+			vec![(HDX, 10_000_000), (DOT, 0)]
+		);
+		assert_eq!(AMM::get_accrued_fees(&BOB, &pair_account), vec![(HDX, 0), (DOT, 0)]);
+
+		let hdx_before = Currency::free_balance(HDX, &ALICE);
+		let pool_hdx_before = Currency::free_balance(HDX, &pair_account);
+
+		assert_ok!(AMM::claim_fees(Origin::signed(ALICE), HDX, DOT));
+
+		assert_eq!(Currency::free_balance(HDX, &ALICE), hdx_before + 10_000_000);
+		assert_eq!(Currency::free_balance(HDX, &pair_account), pool_hdx_before - 10_000_000);
+		assert_eq!(AMM::get_accrued_fees(&ALICE, &pair_account), vec![(HDX, 0), (DOT, 0)]);
+
+		let share_token = AMM::share_token(&pair_account);
+		assert_eq!(AMM::unclaimed_fees((share_token, HDX)), 0);
+
+		expect_events(vec![RawEvent::FeesClaimed(ALICE, HDX, DOT, 10_000_000, 0).into()]);
+
+		assert_noop!(
+			AMM::claim_fees(Origin::signed(ALICE), HDX, DOT),
+			Error::<Test>::NoFeesToClaim
+		);
+		assert_noop!(
+			AMM::claim_fees(Origin::signed(ALICE), HDX, ACA),
+			Error::<Test>::TokenPoolNotFound
+		);
+	});
+}
+
+#[test]
+fn transferred_shares_should_not_earn_past_fees() {
+	new_test_ext().execute_with(|| {
+		let pool_fee = fee::Fee {
+			numerator: 1,
+			denominator: 100,
+		};
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			pool_fee,
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+
+		assert_ok!(<Currency as MultiCurrency<AccountId>>::transfer(
+			share_token,
+			&ALICE,
+			&CHARLIE,
+			50_000_000_000_000
+		));
+
+		assert_eq!(AMM::get_accrued_fees(&CHARLIE, &pair_account), vec![(HDX, 0), (DOT, 0)]);
+		assert_noop!(
+			AMM::claim_fees(Origin::signed(CHARLIE), HDX, DOT),
+			Error::<Test>::NoFeesToClaim
+		);
+
+		// Shares which were transferred away stop earning, shares still held keep their fees
+		assert_eq!(
+			AMM::get_accrued_fees(&ALICE, &pair_account),
+			vec![(HDX, 5_000_000), (DOT, 0)]
+		);
+
+		// Shares are still registered by the sender, so they cannot be registered by the receiver yet
+		assert_ok!(AMM::update_fee_shares(Origin::signed(CHARLIE), CHARLIE, HDX, DOT));
+		assert_eq!(AMM::fee_shares(share_token, CHARLIE), 0);
+		expect_events(vec![RawEvent::FeeSharesUpdated(CHARLIE, HDX, DOT, 0).into()]);
+
+		// Settling the sender releases fees of the transferred shares to the pool reserves
+		assert_ok!(AMM::update_fee_shares(Origin::signed(CHARLIE), ALICE, HDX, DOT));
+		assert_eq!(AMM::fee_shares(share_token, ALICE), 50_000_000_000_000);
+		assert_eq!(AMM::unclaimed_fees((share_token, HDX)), 5_000_000);
+
+		assert_ok!(AMM::update_fee_shares(Origin::signed(CHARLIE), CHARLIE, HDX, DOT));
+		assert_eq!(AMM::fee_shares(share_token, CHARLIE), 50_000_000_000_000);
+		assert_eq!(AMM::total_fee_shares(share_token), 100_000_000_000_000);
+		expect_events(vec![
+			RawEvent::FeeSharesUpdated(CHARLIE, HDX, DOT, 50_000_000_000_000).into()
+		]);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+
+		assert_eq!(
+			AMM::get_accrued_fees(&CHARLIE, &pair_account),
+			vec![(HDX, 5_000_000), (DOT, 0)]
+		);
+		assert_eq!(
+			AMM::get_accrued_fees(&ALICE, &pair_account),
+			vec![(HDX, 10_000_000), (DOT, 0)]
+		);
+
+		let pool_hdx_before = Currency::free_balance(HDX, &pair_account);
+
+		assert_ok!(AMM::claim_fees(Origin::signed(ALICE), HDX, DOT));
+		assert_ok!(AMM::claim_fees(Origin::signed(CHARLIE), HDX, DOT));
+
+		assert_eq!(Currency::free_balance(HDX, &pair_account), pool_hdx_before - 15_000_000);
+		assert_eq!(Currency::free_balance(HDX, &CHARLIE), 5_000_000);
+		assert_eq!(AMM::unclaimed_fees((share_token, HDX)), 0);
+	});
+}
+
+#[test]
+fn fees_of_unregistered_shares_should_stay_in_reserves() {
+	new_test_ext().execute_with(|| {
+		let pool_fee = fee::Fee {
+			numerator: 1,
+			denominator: 100,
+		};
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			pool_fee,
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+
+		// Shares held before fee shares were introduced are not registered
+		FeeShares::<Test>::remove(share_token, ALICE);
+		TotalFeeShares::remove(share_token);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+
+		assert_eq!(AMM::unclaimed_fees((share_token, HDX)), 0);
+		assert_eq!(AMM::get_accrued_fees(&ALICE, &pair_account), vec![(HDX, 0), (DOT, 0)]);
+
+		AMM::migrate_fee_shares(
+			orml_tokens::Accounts::<Test>::iter().map(|(who, asset, account)| (who, asset, account.free)),
+		);
+
+		assert_eq!(AMM::fee_shares(share_token, ALICE), 100_000_000_000_000);
+		assert_eq!(AMM::total_fee_shares(share_token), 100_000_000_000_000);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+
+		assert_eq!(
+			AMM::get_accrued_fees(&ALICE, &pair_account),
+			vec![(HDX, 10_000_000), (DOT, 0)]
+		);
+
+		// Migration can run again without registering the shares twice
+		AMM::migrate_fee_shares(
+			orml_tokens::Accounts::<Test>::iter().map(|(who, asset, account)| (who, asset, account.free)),
+		);
+
+		assert_eq!(AMM::total_fee_shares(share_token), 100_000_000_000_000);
+	});
+}
+
+#[test]
+fn fee_state_should_be_removed_when_pool_is_destroyed() {
+	new_test_ext().execute_with(|| {
+		let pool_fee = fee::Fee {
+			numerator: 1,
+			denominator: 100,
+		};
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			pool_fee,
+			PoolType::ConstantProduct
+		));
+		assert_ok!(AMM::add_liquidity(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			300_000_000_000_000,
+			None
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+
+		// Bob keeps settled fees after transferring all his shares away
+		assert_ok!(AMM::update_fee_shares(Origin::signed(BOB), BOB, HDX, DOT));
+
+		let bob_shares = Currency::free_balance(share_token, &BOB);
+
+		assert_ok!(<Currency as MultiCurrency<AccountId>>::transfer(
+			share_token,
+			&BOB,
+			&ALICE,
+			bob_shares
+		));
+
+		assert_eq!(AMM::accrued_fees((share_token, HDX), BOB), 5_000_000);
+
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			AMM::total_liquidity(&pair_account),
+			0,
+			0,
+			None
+		));
+
+		assert!(!AMM::exists(HDX, DOT));
+
+		for asset in vec![HDX, DOT] {
+			assert_eq!(AMM::accumulated_fee_per_share((share_token, asset)), FixedU128::zero());
+			assert_eq!(AMM::unclaimed_fees((share_token, asset)), 0);
+			assert_eq!(AMM::accrued_fees((share_token, asset), BOB), 0);
+			assert_eq!(AMM::fee_checkpoint((share_token, asset), ALICE), FixedU128::zero());
+		}
+
+		assert_eq!(AMM::fee_shares(share_token, ALICE), 0);
+		assert_eq!(AMM::fee_shares(share_token, BOB), 0);
+		assert_eq!(AMM::total_fee_shares(share_token), 0);
+	});
+}
+
+#[test]
+fn fees_should_be_shared_between_liquidity_providers() {
+	new_test_ext().execute_with(|| {
+		let pool_fee = fee::Fee {
+			numerator: 1,
+			denominator: 100,
+		};
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			pool_fee,
+			PoolType::ConstantProduct
+		));
+		assert_ok!(AMM::add_liquidity(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			300_000_000_000_000,
+			None
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+		assert_ok!(AMM::buy(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			1_000_000_000,
+			10_000_000_000,
+			false,
+			None
+		));
+
+		let alice_fees = AMM::get_accrued_fees(&ALICE, &pair_account);
+		assert_eq!(alice_fees, AMM::get_accrued_fees(&BOB, &pair_account));
+		assert_eq!(alice_fees, vec![(HDX, 10_000_000), (DOT, 0)]);
+
+		// Removing liquidity pays out the proportional part of accrued fees on top of the reserves
+		let total_shares = AMM::total_liquidity(&pair_account);
+		let (principal_hdx, principal_dot) = hydra_dx_math::calculate_liquidity_out(
+			Currency::free_balance(HDX, &pair_account) - AMM::unclaimed_fees((share_token, HDX)),
+			Currency::free_balance(DOT, &pair_account) - AMM::unclaimed_fees((share_token, DOT)),
+			50_000_000_000_000,
+			total_shares,
+		)
+		.unwrap();
+
+		let hdx_before = Currency::free_balance(HDX, &ALICE);
+		let dot_before = Currency::free_balance(DOT, &ALICE);
+
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			50_000_000_000_000,
+			0,
+			0,
+			None
+		));
+
+		assert_eq!(
+			Currency::free_balance(HDX, &ALICE),
+			hdx_before + principal_hdx + 5_000_000
+		);
+		assert_eq!(Currency::free_balance(DOT, &ALICE), dot_before + principal_dot);
+		assert_eq!(
+			AMM::get_accrued_fees(&ALICE, &pair_account),
+			vec![(HDX, 5_000_000), (DOT, 0)]
+		);
+
+		let bob_hdx_before = Currency::free_balance(HDX, &BOB);
+		assert_ok!(AMM::claim_fees(Origin::signed(BOB), HDX, DOT));
+		assert_eq!(Currency::free_balance(HDX, &BOB), bob_hdx_before + 10_000_000);
+	});
+}
+
+#[test]
+fn protocol_fee_should_be_sent_to_receiver() {
+	let mut ext = ExtBuilder::default()
+		.with_protocol_fee(Permill::from_percent(20))
+		.build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		let pool_fee = fee::Fee {
+			numerator: 1,
+			denominator: 100,
+		};
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			pool_fee,
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+
+		assert_eq!(Currency::free_balance(HDX, &TREASURY), 2_000_000);
+		assert_eq!(AMM::protocol_revenue(HDX), 2_000_000);
+		assert_eq!(
+			AMM::get_accrued_fees(&ALICE, &pair_account),
+			vec![(HDX, 8_000_000), (DOT, 0)]
+		);
+		assert_eq!(
+			last_events(2)[0],
+			RawEvent::ProtocolFeeCollected(pair_account, HDX, 2_000_000, TREASURY).into()
+		);
+
+		assert_ok!(AMM::buy(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			1_000_000_000,
+			10_000_000_000,
+			false,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(HDX, &TREASURY), 4_000_000);
+		assert_eq!(AMM::protocol_revenue(HDX), 4_000_000);
+		assert_eq!(AMM::protocol_revenue(DOT), 0);
+		assert_eq!(
+			AMM::get_accrued_fees(&ALICE, &pair_account),
+			vec![(HDX, 16_000_000), (DOT, 0)]
+		);
+	});
+}
+
+#[test]
+fn get_twap_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+
+		System::set_block_number(11);
+
+		assert_eq!(AMM::get_twap(HDX, DOT, 10), Some(Price::from(2)));
+		assert_eq!(AMM::get_twap(DOT, HDX, 10), Price::checked_from_rational(1, 2));
+
+		assert_ok!(AMM::sell(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			10_000_000_000_000,
+			0,
+			false,
+			None
+		));
+
+		// Price change is not reflected within the same block
+		assert_eq!(AMM::get_twap(HDX, DOT, 10), Some(Price::from(2)));
+
+		let new_price = Price::checked_from_rational(
+			Currency::free_balance(DOT, &pair_account),
+			Currency::free_balance(HDX, &pair_account),
+		)
+		.unwrap();
+
+		System::set_block_number(21);
+
+		let cumulative_price = Price::from(20).saturating_add(new_price.saturating_mul(Price::from(10)));
+
+		assert_eq!(
+			AMM::get_twap(HDX, DOT, 20),
+			cumulative_price.checked_div(&Price::from(20))
+		);
+		assert_eq!(
+			AMM::get_twap(HDX, DOT, 10),
+			cumulative_price
+				.saturating_sub(Price::from(20))
+				.checked_div(&Price::from(10))
+		);
+
+		// Trade in current block does not change the average price
+		let twap = AMM::get_twap(HDX, DOT, 20);
+		assert_ok!(AMM::sell(
+			Origin::signed(BOB),
+			DOT,
+			HDX,
+			10_000_000_000_000,
+			0,
+			false,
+			None
+		));
+		assert_eq!(AMM::get_twap(HDX, DOT, 20), twap);
+	});
+}
+
+#[test]
+fn get_twap_without_price_history_should_not_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(5);
+
+		assert_eq!(AMM::get_twap(HDX, DOT, 1), None);
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		System::set_block_number(10);
+
+		assert_eq!(AMM::get_twap(HDX, DOT, 0), None);
+		assert_eq!(AMM::get_twap(HDX, DOT, 6), None);
+		assert_eq!(AMM::get_twap(HDX, DOT, 20), None);
+		assert_eq!(AMM::get_twap(HDX, DOT, 5), Some(Price::from(2)));
+	});
+}
+
+#[test]
+fn price_history_should_be_limited() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+
+		for block in 2..=20 {
+			System::set_block_number(block);
+			assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, None));
+		}
+
+		let key = (pair_account, HDX, DOT);
+		let length = PriceHistoryLength::get();
+
+		assert_eq!(AMM::price_history_head(&key), 20);
+		assert_eq!(AMM::price_history(&key, length), None);
+
+		// Records of blocks 1 to 10 were overwritten
+		let blocks: Vec<u64> = (0..length)
+			.filter_map(|index| AMM::price_history(&key, index).map(|record| record.0))
+			.collect();
+		assert_eq!(blocks, (11..=20).collect::<Vec<u64>>());
+
+		assert!(AMM::get_twap(HDX, DOT, 9).is_some());
+		assert_eq!(AMM::get_twap(HDX, DOT, 10), None);
+	});
+}
+
+#[test]
+fn weighted_pool_twap_should_work() {
+	new_test_ext().execute_with(|| {
+		let pool_assets = create_weighted_pool();
+		let pool_account = AMM::get_weighted_pool_id(&pool_assets);
+
+		let spot_price = weighted::calculate_spot_price(
+			100_000_000_000_000,
+			Permill::from_percent(50),
+			100_000_000_000_000,
+			Permill::from_percent(30),
+		)
+		.unwrap();
+
+		System::set_block_number(11);
+
+		assert_eq!(AMM::get_weighted_twap(&pool_assets, HDX, DOT, 10), Some(spot_price));
+		assert_eq!(AMM::get_weighted_twap(&pool_assets, HDX, HDX, 10), None);
+		assert_eq!(AMM::get_weighted_twap(&pool_assets, HDX, DOT, 11), None);
+
+		assert_ok!(AMM::weighted_sell(
+			Origin::signed(BOB),
+			pool_assets.clone(),
+			ACA,
+			HDX,
+			1_000_000_000_000,
+			0,
+			None
+		));
+
+		// Price change is not reflected within the same block
+		assert_eq!(AMM::get_weighted_twap(&pool_assets, HDX, DOT, 10), Some(spot_price));
+		assert_eq!(AMM::price_history_head(&(pool_account, HDX, DOT)), 2);
+		assert_eq!(AMM::price_history_head(&(pool_account, DOT, ACA)), 2);
+
+		let new_price = weighted::calculate_spot_price(
+			Currency::free_balance(HDX, &pool_account),
+			Permill::from_percent(50),
+			Currency::free_balance(DOT, &pool_account),
+			Permill::from_percent(30),
+		)
+		.unwrap();
+
+		System::set_block_number(21);
+
+		let cumulative_price = spot_price
+			.saturating_mul(Price::from(10))
+			.saturating_add(new_price.saturating_mul(Price::from(10)));
+
+		assert_eq!(
+			AMM::get_weighted_twap(&pool_assets, HDX, DOT, 20),
+			cumulative_price.checked_div(&Price::from(20))
+		);
+		assert!(AMM::get_weighted_twap(&pool_assets, DOT, HDX, 20).is_some());
+	});
+}
+
+#[test]
+fn create_stable_swap_pool_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::StableSwap(100)
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+
+		assert_eq!(AMM::pool_type(&pair_account), PoolType::StableSwap(100));
+
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			0,
+			0,
+			None
+		));
+
+		assert_eq!(AMM::pool_type(&pair_account), PoolType::ConstantProduct);
+		assert!(!<PoolTypes<Test>>::contains_key(&pair_account));
+	});
+}
+
+#[test]
+fn create_stable_swap_pool_with_invalid_amplification_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AMM::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				DOT,
+				100_000_000_000_000,
+				Price::from(1),
+				fee::Fee::default(),
+				PoolType::StableSwap(0)
+			),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			AMM::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				DOT,
+				100_000_000_000_000,
+				Price::from(1),
+				fee::Fee::default(),
+				PoolType::StableSwap(stableswap::MAX_AMPLIFICATION + 1)
+			),
+			Error::<Test>::InvalidAmplification
+		);
+	});
+}
+
+#[test]
+fn sell_from_stable_swap_pool_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::StableSwap(100)
+		));
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			ACA,
+			100_000_000_000_000,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+
+		let amount: Balance = 1_000_000_000_000;
+		let transfer_fee = amount.just_fee(fee::Fee::default()).unwrap();
+
+		let expected = stableswap::calculate_sell_price(
+			Currency::free_balance(HDX, &pair_account),
+			Currency::free_balance(DOT, &pair_account),
+			amount - transfer_fee,
+			100,
+		)
+		.unwrap();
+
+		assert_eq!(AMM::get_sell_price(HDX, DOT, amount), expected);
+		assert_eq!(AMM::get_spot_price(HDX, DOT, amount), amount);
+
+		// Stable swap pool gives better price for pegged assets than constant product pool of the same size
+		assert!(expected > AMM::get_sell_price(HDX, ACA, amount));
+
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_ok!(AMM::sell(Origin::signed(BOB), HDX, DOT, amount, expected, false, None));
+
+		assert_eq!(Currency::free_balance(DOT, &BOB), dot_before + expected);
+		assert_eq!(Currency::free_balance(HDX, &pair_account), 100_000_000_000_000 + amount);
+		assert_eq!(
+			Currency::free_balance(DOT, &pair_account),
+			100_000_000_000_000 - expected
+		);
+	});
+}
+
+#[test]
+fn buy_from_stable_swap_pool_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::StableSwap(100)
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+
+		let amount: Balance = 1_000_000_000_000;
+		let transfer_fee = amount.just_fee(fee::Fee::default()).unwrap();
+
+		let expected = stableswap::calculate_buy_price(
+			Currency::free_balance(HDX, &pair_account),
+			Currency::free_balance(DOT, &pair_account),
+			amount + transfer_fee,
+			100,
+		)
+		.unwrap();
+
+		assert_eq!(AMM::get_buy_price(DOT, HDX, amount), expected);
+
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_noop!(
+			AMM::buy(Origin::signed(BOB), DOT, HDX, amount, expected - 1, false, None),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+		assert_ok!(AMM::buy(Origin::signed(BOB), DOT, HDX, amount, expected, false, None));
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), hdx_before - expected);
+		assert_eq!(Currency::free_balance(DOT, &BOB), dot_before + amount);
+	});
+}
+
+#[test]
+fn stable_swap_invariant_of_balanced_pool_should_be_sum_of_reserves() {
+	assert_eq!(stableswap::calculate_d(1_000_000, 1_000_000, 100), Some(2_000_000));
+	assert_eq!(stableswap::calculate_d(0, 0, 100), Some(0));
+}
+
+#[test]
+fn stable_swap_buy_should_cost_at_least_sell_price() {
+	let reserve = 1_000_000_000_000_000;
+	let amount = 1_000_000_000_000;
+
+	let amount_in = stableswap::calculate_buy_price(reserve, reserve, amount, 100).unwrap();
+
+	assert!(amount_in > amount);
+	assert!(stableswap::calculate_sell_price(reserve, reserve, amount_in, 100).unwrap() >= amount - 1);
+}
+
+#[test]
+fn stable_swap_spot_price_should_work() {
+	assert_eq!(
+		stableswap::calculate_spot_price(1_000_000_000_000, 1_000_000_000_000, 100),
+		Some(Price::from(1))
+	);
+	assert_eq!(stableswap::calculate_spot_price(0, 1_000_000_000_000, 100), None);
+
+	let price = stableswap::calculate_spot_price(2_000_000_000_000, 1_000_000_000_000, 100).unwrap();
+
+	assert!(price < Price::from(1));
+	// Amplified pool keeps the price closer to the peg than constant product
+	assert!(price > Price::saturating_from_rational(1, 2));
+}
+
+fn create_weighted_pool() -> Vec<AssetId> {
+	assert_ok!(AMM::create_weighted_pool(
+		Origin::signed(ALICE),
+		vec![
+			(HDX, Permill::from_percent(50), 100_000_000_000_000),
+			(DOT, Permill::from_percent(30), 100_000_000_000_000),
+			(ACA, Permill::from_percent(20), 100_000_000_000_000),
+		],
+		fee::Fee::default()
+	));
+
+	vec![HDX, DOT, ACA]
+}
+
+#[test]
+fn create_weighted_pool_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_weighted_pool(
+			Origin::signed(ALICE),
+			vec![
+				(ACA, Permill::from_percent(20), 300_000_000_000_000),
+				(HDX, Permill::from_percent(50), 100_000_000_000_000),
+				(DOT, Permill::from_percent(30), 200_000_000_000_000),
+			],
+			fee::Fee::default()
+		));
+
+		let pool_account = AMM::get_weighted_pool_id(&[HDX, DOT, ACA]);
+		let share_token = AMM::share_token(&pool_account);
+
+		assert_eq!(pool_account, AMM::get_weighted_pool_id(&[ACA, DOT, HDX]));
+		assert_ne!(pool_account, AMM::get_pair_id(&HDX, &DOT));
+
+		assert_eq!(
+			AMM::weighted_pool_assets(&pool_account),
+			vec![
+				(HDX, Permill::from_percent(50)),
+				(DOT, Permill::from_percent(30)),
+				(ACA, Permill::from_percent(20))
+			]
+		);
+		assert_eq!(AMM::get_pool_assets(&pool_account), Some(vec![HDX, DOT, ACA]));
+
+		assert_eq!(Currency::free_balance(HDX, &pool_account), 100_000_000_000_000);
+		assert_eq!(Currency::free_balance(DOT, &pool_account), 200_000_000_000_000);
+		assert_eq!(Currency::free_balance(ACA, &pool_account), 300_000_000_000_000);
+
+		assert_eq!(Currency::free_balance(share_token, &ALICE), 100_000_000_000_000);
+		assert_eq!(AMM::total_liquidity(&pool_account), 100_000_000_000_000);
+
+		expect_events(vec![RawEvent::CreateWeightedPool(
+			ALICE,
+			vec![HDX, DOT, ACA],
+			100_000_000_000_000,
+		)
+		.into()]);
+	});
+}
+
+#[test]
+fn create_weighted_pool_with_invalid_assets_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AMM::create_weighted_pool(
+				Origin::signed(ALICE),
+				vec![(HDX, Permill::from_percent(100), 100_000_000_000_000)],
+				fee::Fee::default()
+			),
+			Error::<Test>::InvalidPoolAssetCount
+		);
+
+		assert_noop!(
+			AMM::create_weighted_pool(
+				Origin::signed(ALICE),
+				vec![
+					(HDX, Permill::from_percent(50), 100_000_000_000_000),
+					(HDX, Permill::from_percent(50), 100_000_000_000_000)
+				],
+				fee::Fee::default()
+			),
+			Error::<Test>::CannotCreatePoolWithSameAssets
+		);
+
+		assert_noop!(
+			AMM::create_weighted_pool(
+				Origin::signed(ALICE),
+				vec![
+					(HDX, Permill::from_percent(50), 100_000_000_000_000),
+					(DOT, Permill::from_percent(40), 100_000_000_000_000)
+				],
+				fee::Fee::default()
+			),
+			Error::<Test>::InvalidPoolWeights
+		);
+
+		assert_noop!(
+			AMM::create_weighted_pool(
+				Origin::signed(ALICE),
+				vec![
+					(HDX, Permill::from_percent(99), 100_000_000_000_000),
+					(DOT, Permill::from_percent(1), 100_000_000_000_000)
+				],
+				fee::Fee::default()
+			),
+			Error::<Test>::InvalidPoolWeights
+		);
+
+		assert_noop!(
+			AMM::create_weighted_pool(
+				Origin::signed(ALICE),
+				vec![
+					(HDX, Permill::from_percent(50), 100_000_000_000_000),
+					(DOT, Permill::from_percent(50), 0)
+				],
+				fee::Fee::default()
+			),
+			Error::<Test>::CannotCreatePoolWithZeroLiquidity
+		);
+
+		create_weighted_pool();
+
+		assert_noop!(
+			AMM::create_weighted_pool(
+				Origin::signed(BOB),
+				vec![
+					(ACA, Permill::from_percent(20), 100_000_000_000_000),
+					(DOT, Permill::from_percent(30), 100_000_000_000_000),
+					(HDX, Permill::from_percent(50), 100_000_000_000_000)
+				],
+				fee::Fee::default()
+			),
+			Error::<Test>::TokenPoolAlreadyExists
+		);
+	});
+}
+
+#[test]
+fn weighted_sell_should_work() {
+	new_test_ext().execute_with(|| {
+		let pool_assets = create_weighted_pool();
+		let pool_account = AMM::get_weighted_pool_id(&pool_assets);
+
+		let amount: Balance = 1_000_000_000_000;
+		let transfer_fee = amount.just_fee(fee::Fee::default()).unwrap();
+
+		let expected = weighted::calculate_out_given_in(
+			100_000_000_000_000,
+			Permill::from_percent(20),
+			100_000_000_000_000,
+			Permill::from_percent(50),
+			amount - transfer_fee,
+		)
+		.unwrap();
+
+		// Lighter asset is cheaper in terms of the heavier one
+		assert!(expected < amount * 2 / 5);
+		assert!(expected > amount * 39 / 100);
+
+		assert_noop!(
+			AMM::weighted_sell(
+				Origin::signed(BOB),
+				pool_assets.clone(),
+				ACA,
+				HDX,
+				amount,
+				expected + 1,
+				None
+			),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+
+		assert_ok!(AMM::weighted_sell(
+			Origin::signed(BOB),
+			pool_assets.clone(),
+			ACA,
+			HDX,
+			amount,
+			expected,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), hdx_before + expected);
+		assert_eq!(Currency::free_balance(ACA, &pool_account), 100_000_000_000_000 + amount);
+		assert_eq!(
+			Currency::free_balance(HDX, &pool_account),
+			100_000_000_000_000 - expected
+		);
+
+		expect_events(vec![RawEvent::WeightedSell(
+			BOB,
+			pool_account,
+			ACA,
+			HDX,
+			amount,
+			expected,
+		)
+		.into()]);
+	});
+}
+
+#[test]
+fn weighted_buy_should_work() {
+	new_test_ext().execute_with(|| {
+		let pool_assets = create_weighted_pool();
+		let pool_account = AMM::get_weighted_pool_id(&pool_assets);
+
+		let amount: Balance = 1_000_000_000_000;
+		let transfer_fee = amount.just_fee(fee::Fee::default()).unwrap();
+
+		let expected = weighted::calculate_in_given_out(
+			100_000_000_000_000,
+			Permill::from_percent(50),
+			100_000_000_000_000,
+			Permill::from_percent(30),
+			amount + transfer_fee,
+		)
+		.unwrap();
+
+		assert!(expected > amount * 3 / 5);
+
+		assert_noop!(
+			AMM::weighted_buy(
+				Origin::signed(BOB),
+				pool_assets.clone(),
+				DOT,
+				HDX,
+				amount,
+				expected - 1,
+				None
+			),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_ok!(AMM::weighted_buy(
+			Origin::signed(BOB),
+			pool_assets.clone(),
+			DOT,
+			HDX,
+			amount,
+			expected,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(DOT, &BOB), dot_before + amount);
+		assert_eq!(Currency::free_balance(HDX, &BOB), hdx_before - expected);
+		assert_eq!(Currency::free_balance(DOT, &pool_account), 100_000_000_000_000 - amount);
+
+		expect_events(vec![RawEvent::WeightedBuy(
+			BOB,
+			pool_account,
+			DOT,
+			HDX,
+			amount,
+			expected,
+		)
+		.into()]);
+	});
+}
+
+#[test]
+fn weighted_trade_with_asset_not_in_pool_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_weighted_pool(
+			Origin::signed(ALICE),
+			vec![
+				(HDX, Permill::from_percent(50), 100_000_000_000_000),
+				(DOT, Permill::from_percent(50), 100_000_000_000_000),
+			],
+			fee::Fee::default()
+		));
+
+		assert_noop!(
+			AMM::weighted_sell(Origin::signed(BOB), vec![HDX, DOT], ACA, HDX, 1_000, 0, None),
+			Error::<Test>::AssetNotInPool
+		);
+		assert_noop!(
+			AMM::weighted_sell(Origin::signed(BOB), vec![HDX, ACA], ACA, HDX, 1_000, 0, None),
+			Error::<Test>::TokenPoolNotFound
+		);
+	});
+}
+
+#[test]
+fn weighted_pool_fees_should_work() {
+	let mut ext = ExtBuilder::default()
+		.with_protocol_fee(Permill::from_percent(20))
+		.build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		let pool_assets = create_weighted_pool();
+		let pool_account = AMM::get_weighted_pool_id(&pool_assets);
+		let share_token = AMM::share_token(&pool_account);
+
+		assert_noop!(
+			AMM::weighted_sell(Origin::signed(BOB), pool_assets.clone(), HDX, HDX, 1_000, 0, None),
+			Error::<Test>::CannotTradeSameAsset
+		);
+		assert_noop!(
+			AMM::weighted_buy(
+				Origin::signed(BOB),
+				pool_assets.clone(),
+				HDX,
+				HDX,
+				1_000,
+				1_000_000,
+				None
+			),
+			Error::<Test>::CannotTradeSameAsset
+		);
+
+		let amount: Balance = 1_000_000_000_000;
+		let transfer_fee = amount.just_fee(fee::Fee::default()).unwrap();
+		let protocol_fee = Permill::from_percent(20) * transfer_fee;
+
+		assert_ok!(AMM::weighted_sell(
+			Origin::signed(BOB),
+			pool_assets.clone(),
+			ACA,
+			HDX,
+			amount,
+			0,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(ACA, &TREASURY), protocol_fee);
+		assert_eq!(AMM::protocol_revenue(ACA), protocol_fee);
+		assert_eq!(
+			Currency::free_balance(ACA, &pool_account),
+			100_000_000_000_000 + amount - protocol_fee
+		);
+
+		// Liquidity provider part of the fee stays in the reserves and is not tracked for claims
+		assert_eq!(AMM::accumulated_fee_per_share((share_token, ACA)), FixedU128::zero());
+		assert_eq!(
+			AMM::get_accrued_fees(&ALICE, &pool_account),
+			vec![(HDX, 0), (DOT, 0), (ACA, 0)]
+		);
+
+		let deposit_fee = (amount - Permill::from_percent(30) * amount)
+			.just_fee(fee::Fee::default())
+			.unwrap();
+		let dot_reserve = Currency::free_balance(DOT, &pool_account);
+
+		assert_ok!(AMM::add_weighted_liquidity_single_asset(
+			Origin::signed(BOB),
+			pool_assets.clone(),
+			DOT,
+			amount,
+			0,
+			None
+		));
+
+		let deposit_protocol_fee = Permill::from_percent(20) * deposit_fee;
+
+		assert_eq!(Currency::free_balance(DOT, &TREASURY), deposit_protocol_fee);
+		assert_eq!(
+			Currency::free_balance(DOT, &pool_account),
+			dot_reserve + amount - deposit_protocol_fee
+		);
+	});
+}
+
+#[test]
+fn weighted_single_asset_liquidity_should_work() {
+	new_test_ext().execute_with(|| {
+		let pool_assets = create_weighted_pool();
+		let pool_account = AMM::get_weighted_pool_id(&pool_assets);
+		let share_token = AMM::share_token(&pool_account);
+
+		let amount: Balance = 1_000_000_000_000;
+
+		// Fee is charged on the part which is not backed by the weight of the asset
+		let transfer_fee = (amount - Permill::from_percent(30) * amount)
+			.just_fee(fee::Fee::default())
+			.unwrap();
+
+		let expected_shares = weighted::calculate_shares_for_deposit(
+			100_000_000_000_000,
+			Permill::from_percent(30),
+			100_000_000_000_000,
+			amount - transfer_fee,
+		)
+		.unwrap();
+
+		assert_noop!(
+			AMM::add_weighted_liquidity_single_asset(
+				Origin::signed(BOB),
+				pool_assets.clone(),
+				DOT,
+				amount,
+				expected_shares + 1,
+				None
+			),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+
+		assert_ok!(AMM::add_weighted_liquidity_single_asset(
+			Origin::signed(BOB),
+			pool_assets.clone(),
+			DOT,
+			amount,
+			expected_shares,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(share_token, &BOB), expected_shares);
+		assert_eq!(
+			AMM::total_liquidity(&pool_account),
+			100_000_000_000_000 + expected_shares
+		);
+		assert_eq!(Currency::free_balance(DOT, &pool_account), 100_000_000_000_000 + amount);
+
+		expect_events(vec![RawEvent::WeightedLiquidityAdded(
+			BOB,
+			pool_account,
+			DOT,
+			amount,
+			expected_shares,
+		)
+		.into()]);
+
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_ok!(AMM::remove_weighted_liquidity_single_asset(
+			Origin::signed(BOB),
+			pool_assets.clone(),
+			DOT,
+			expected_shares,
+			0,
+			None
+		));
+
+		let received = Currency::free_balance(DOT, &BOB) - dot_before;
+
+		// Fees are charged both ways
+		assert!(received < amount);
+		assert!(received > amount - 2 * transfer_fee - 1_000_000);
+
+		assert_eq!(Currency::free_balance(share_token, &BOB), 0);
+		assert_eq!(AMM::total_liquidity(&pool_account), 100_000_000_000_000);
+
+		expect_events(vec![RawEvent::WeightedLiquidityRemoved(
+			BOB,
+			pool_account,
+			DOT,
+			received,
+			expected_shares,
+		)
+		.into()]);
+	});
+}
+
+#[test]
+fn remove_weighted_liquidity_should_work() {
+	new_test_ext().execute_with(|| {
+		let pool_assets = create_weighted_pool();
+		let pool_account = AMM::get_weighted_pool_id(&pool_assets);
+		let share_token = AMM::share_token(&pool_account);
+
+		let hdx_before = Currency::free_balance(HDX, &ALICE);
+
+		assert_ok!(AMM::remove_weighted_liquidity(
+			Origin::signed(ALICE),
+			pool_assets.clone(),
+			25_000_000_000_000,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(HDX, &ALICE), hdx_before + 25_000_000_000_000);
+		assert_eq!(Currency::free_balance(DOT, &pool_account), 75_000_000_000_000);
+		assert_eq!(Currency::free_balance(ACA, &pool_account), 75_000_000_000_000);
+		assert_eq!(AMM::total_liquidity(&pool_account), 75_000_000_000_000);
+
+		expect_events(vec![RawEvent::WeightedPoolLiquidityRemoved(
+			ALICE,
+			pool_account,
+			25_000_000_000_000,
+			vec![
+				(HDX, 25_000_000_000_000),
+				(DOT, 25_000_000_000_000),
+				(ACA, 25_000_000_000_000),
+			],
+		)
+		.into()]);
+
+		assert_ok!(AMM::remove_weighted_liquidity(
+			Origin::signed(ALICE),
+			pool_assets.clone(),
+			75_000_000_000_000,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(HDX, &pool_account), 0);
+		assert_eq!(Currency::free_balance(share_token, &ALICE), 0);
+		assert!(!WeightedPoolAssets::<Test>::contains_key(&pool_account));
+		assert_eq!(AMM::get_pool_assets(&pool_account), None);
+		assert_eq!(AMM::total_liquidity(&pool_account), 0);
+
+		expect_events(vec![RawEvent::WeightedPoolDestroyed(ALICE, pool_account).into()]);
+
+		assert_noop!(
+			AMM::weighted_sell(Origin::signed(BOB), pool_assets, HDX, DOT, 1_000, 0, None),
+			Error::<Test>::TokenPoolNotFound
+		);
+	});
+}
+
+#[test]
+fn weighted_pow_should_work() {
+	assert_eq!(weighted::pow(Price::from(2), Price::from(3)), Some(Price::from(8)));
+	assert_eq!(weighted::pow(Price::from(5), Price::zero()), Some(Price::from(1)));
+
+	let tolerance = Price::from_inner(1_000_000_000);
+
+	let root = weighted::pow(
+		Price::saturating_from_rational(121, 100),
+		Price::saturating_from_rational(1, 2),
+	)
+	.unwrap();
+	assert!(root > Price::saturating_from_rational(11, 10) - tolerance);
+	assert!(root < Price::saturating_from_rational(11, 10) + tolerance);
+
+	let cube = weighted::pow(
+		Price::saturating_from_rational(1, 2),
+		Price::saturating_from_rational(3, 2),
+	)
+	.unwrap();
+	let expected = Price::from_inner(353_553_390_593_273_762);
+	assert!(cube > expected - tolerance);
+	assert!(cube < expected + tolerance);
+}
+
+fn create_concentrated_pool() -> AccountId {
+	create_concentrated_pool_of(HDX, DOT)
+}
+
+fn create_concentrated_pool_of(asset_a: AssetId, asset_b: AssetId) -> AccountId {
+	assert_ok!(AMM::create_pool(
+		Origin::signed(ALICE),
+		asset_a,
+		asset_b,
+		100_000_000_000_000,
+		Price::from(1),
+		fee::Fee::default(),
+		PoolType::Concentrated(10)
+	));
+
+	AMM::get_pair_id(&asset_a, &asset_b)
+}
+
+#[test]
+fn concentrated_math_should_work() {
+	assert_eq!(concentrated::sqrt_price_at_tick(0), Some(1 << 64));
+	assert_eq!(concentrated::sqrt_price_at_tick(concentrated::MAX_TICK + 1), None);
+	assert_eq!(concentrated::sqrt_price_at_tick(concentrated::MIN_TICK - 1), None);
+
+	for &tick in &[
+		concentrated::MIN_TICK,
+		-23_028,
+		-1,
+		0,
+		1,
+		100,
+		23_027,
+		concentrated::MAX_TICK - 1,
+	] {
+		let sqrt_price = concentrated::sqrt_price_at_tick(tick).unwrap();
+		assert_eq!(concentrated::tick_at_sqrt_price(sqrt_price), Some(tick));
+		assert_eq!(concentrated::tick_at_sqrt_price(sqrt_price + 1), Some(tick));
+	}
+
+	assert_eq!(concentrated::spot_price(1 << 64, false), Some(Price::from(1)));
+	assert_eq!(concentrated::sqrt_price_from_ratio(4_000_000, 1_000_000), Some(2 << 64));
+	assert_eq!(
+		concentrated::spot_price(2 << 64, true),
+		Some(Price::saturating_from_rational(1, 4))
+	);
+}
+
+#[test]
+fn create_concentrated_pool_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::Concentrated(10)
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+
+		assert_eq!(AMM::pool_type(&pair_account), PoolType::Concentrated(10));
+		assert_eq!(Currency::free_balance(HDX, &pair_account), 99_999_999_854_109);
+		assert_eq!(Currency::free_balance(DOT, &pair_account), 200_000_000_000_000);
+		assert_eq!(Currency::free_balance(share_token, &ALICE), 0);
+
+		let pool = AMM::concentrated_pool(&pair_account);
+		assert_eq!(pool.tick, 6_931);
+		assert_eq!(pool.liquidity, 141_421_356_443_631);
+
+		let position = AMM::positions(&ALICE, 0).unwrap();
+		assert_eq!(position.tick_lower, -400_000);
+		assert_eq!(position.tick_upper, 400_000);
+		assert_eq!(position.liquidity, 141_421_356_443_631);
+
+		assert_eq!(AMM::get_spot_price(HDX, DOT, 1_000_000), 1_999_999);
+		assert_eq!(AMM::get_spot_price(DOT, HDX, 1_000_000), 500_000);
+
+		expect_events(vec![
+			RawEvent::PositionCreated(ALICE, 0, pair_account, -400_000, 400_000).into(),
+			RawEvent::PositionLiquidityAdded(ALICE, 0, 141_421_356_443_631, 99_999_999_854_109, 200_000_000_000_000)
+				.into(),
+			RawEvent::CreatePool(ALICE, HDX, DOT, 141_421_356_443_631).into(),
+		]);
+
+		assert_noop!(
+			AMM::add_liquidity(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 10_000_000_000, None),
+			Error::<Test>::UnsupportedPoolType
+		);
+		assert_noop!(
+			AMM::mint_position(
+				Origin::signed(BOB),
+				HDX,
+				DOT,
+				-105,
+				100,
+				1_000_000_000,
+				1_000_000_000,
+				None
+			),
+			Error::<Test>::InvalidTickRange
+		);
+		assert_noop!(
+			AMM::mint_position(
+				Origin::signed(BOB),
+				HDX,
+				DOT,
+				100,
+				100,
+				1_000_000_000,
+				1_000_000_000,
+				None
+			),
+			Error::<Test>::InvalidTickRange
+		);
+		assert_noop!(
+			AMM::mint_position(
+				Origin::signed(BOB),
+				HDX,
+				ACA,
+				-100,
+				100,
+				1_000_000_000,
+				1_000_000_000,
+				None
+			),
+			Error::<Test>::TokenPoolNotFound
+		);
+	});
+}
+
+#[test]
+fn create_concentrated_pool_with_invalid_tick_spacing_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AMM::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				DOT,
+				100_000_000_000_000,
+				Price::from(1),
+				fee::Fee::default(),
+				PoolType::Concentrated(0)
+			),
+			Error::<Test>::InvalidTickSpacing
+		);
+		assert_noop!(
+			AMM::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				DOT,
+				100_000_000_000_000,
+				Price::from(1),
+				fee::Fee::default(),
+				PoolType::Concentrated(concentrated::MAX_TICK_SPACING + 1)
+			),
+			Error::<Test>::InvalidTickSpacing
+		);
+	});
+}
+
+#[test]
+fn sell_in_concentrated_position_range_should_work() {
+	new_test_ext().execute_with(|| {
+		let pair_account = create_concentrated_pool();
+
+		assert_ok!(AMM::mint_position(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			-100,
+			100,
+			10_000_000_000_000,
+			10_000_000_000_000,
+			None
+		));
+
+		expect_events(vec![
+			RawEvent::PositionCreated(BOB, 1, pair_account, -100, 100).into(),
+			RawEvent::PositionLiquidityAdded(BOB, 1, 2_005_104_164_790_030, 10_000_000_000_000, 10_000_000_000_000)
+				.into(),
+		]);
+
+		assert_eq!(AMM::concentrated_pool(&pair_account).liquidity, 2_105_104_164_996_351);
+
+		let expected = AMM::get_sell_price(HDX, DOT, 1_000_000_000_000);
+		assert_eq!(expected, 997_527_086_569);
+
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_ok!(AMM::sell(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			1_000_000_000_000,
+			expected,
+			false,
+			None
+		));
+
+		assert_eq!(Currency::free_balance(DOT, &BOB), dot_before + expected);
+		assert_eq!(AMM::concentrated_pool(&pair_account).tick, -10);
+		assert_eq!(AMM::concentrated_pool(&pair_account).liquidity, 2_105_104_164_996_351);
+	});
+}
+
+#[test]
+fn sell_crossing_concentrated_position_range_should_work() {
+	new_test_ext().execute_with(|| {
+		let pair_account = create_concentrated_pool();
+
+		assert_ok!(AMM::mint_position(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			-100,
+			100,
+			10_000_000_000_000,
+			10_000_000_000_000,
+			None
+		));
+
+		assert_ok!(AMM::sell(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			15_000_000_000_000,
+			14_689_175_617_314,
+			false,
+			None
+		));
+
+		let pool = AMM::concentrated_pool(&pair_account);
+		assert_eq!(pool.tick, -961);
+		assert_eq!(pool.liquidity, AMM::positions(&ALICE, 0).unwrap().liquidity);
+
+		// Position below the price is backed only by the sold asset
+		let positions = AMM::get_positions(&BOB);
+		assert_eq!(positions.len(), 1);
+		assert_eq!(positions[0].amounts, vec![(HDX, 20_050_122_696_230), (DOT, 0)]);
+		assert_eq!(positions[0].fees, vec![(HDX, 20_140_526_444), (DOT, 0)]);
+	});
+}
+
+#[test]
+fn concentrated_fees_should_go_to_positions_in_range() {
+	new_test_ext().execute_with(|| {
+		let pair_account = create_concentrated_pool();
+
+		assert_ok!(AMM::mint_position(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			-100,
+			100,
+			10_000_000_000_000,
+			10_000_000_000_000,
+			None
+		));
+		assert_ok!(AMM::mint_position(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			200,
+			300,
+			1_000_000_000_000,
+			0,
+			None
+		));
+
+		assert_ok!(AMM::sell(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			1_000_000_000_000,
+			0,
+			false,
+			None
+		));
+
+		let alice_positions = AMM::get_positions(&ALICE);
+		assert_eq!(alice_positions[0].fees, vec![(HDX, 95_007_175), (DOT, 0)]);
+
+		let bob_positions = AMM::get_positions(&BOB);
+		assert_eq!(bob_positions.len(), 2);
+
+		let in_range = bob_positions.iter().find(|p| p.position_id == 1).unwrap();
+		let out_of_range = bob_positions.iter().find(|p| p.position_id == 2).unwrap();
+		assert_eq!(in_range.fees, vec![(HDX, 1_904_992_824), (DOT, 0)]);
+		assert_eq!(out_of_range.fees, vec![(HDX, 0), (DOT, 0)]);
+		assert_eq!(out_of_range.amounts, vec![(HDX, 999_999_999_999), (DOT, 0)]);
+
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+
+		assert_ok!(AMM::collect_position_fees(Origin::signed(BOB), 1));
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), hdx_before + 1_904_992_824);
+
+		expect_events(vec![RawEvent::PositionFeesCollected(BOB, 1, 1_904_992_824, 0).into()]);
+
+		assert_noop!(
+			AMM::collect_position_fees(Origin::signed(BOB), 1),
+			Error::<Test>::NoFeesToClaim
+		);
+		assert_noop!(
+			AMM::collect_position_fees(Origin::signed(BOB), 2),
+			Error::<Test>::NoFeesToClaim
+		);
+		assert_noop!(
+			AMM::collect_position_fees(Origin::signed(ALICE), 1),
+			Error::<Test>::PositionNotFound
+		);
+	});
+}
+
+#[test]
+fn remove_position_liquidity_should_work() {
+	new_test_ext().execute_with(|| {
+		let pair_account = create_concentrated_pool();
+
+		assert_ok!(AMM::mint_position(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			-100,
+			100,
+			10_000_000_000_000,
+			10_000_000_000_000,
+			None
+		));
+
+		let liquidity = AMM::positions(&BOB, 1).unwrap().liquidity;
+
+		assert_noop!(
+			AMM::remove_position_liquidity(Origin::signed(BOB), 1, liquidity + 1, None),
+			Error::<Test>::InvalidLiquidityAmount
+		);
+		assert_noop!(
+			AMM::remove_position_liquidity(Origin::signed(BOB), 2, liquidity, None),
+			Error::<Test>::PositionNotFound
+		);
+
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_ok!(AMM::remove_position_liquidity(
+			Origin::signed(BOB),
+			1,
+			liquidity / 2,
+			None
+		));
+
+		assert_eq!(AMM::positions(&BOB, 1).unwrap().liquidity, liquidity - liquidity / 2);
+		assert_eq!(
+			AMM::concentrated_pool(&pair_account).liquidity,
+			2_105_104_164_996_351 - liquidity / 2
+		);
+
+		assert_ok!(AMM::remove_position_liquidity(
+			Origin::signed(BOB),
+			1,
+			liquidity - liquidity / 2,
+			None
+		));
+
+		// Amounts are rounded down on removal
+		assert!(Currency::free_balance(HDX, &BOB) <= hdx_before + 10_000_000_000_000);
+		assert!(Currency::free_balance(HDX, &BOB) >= hdx_before + 10_000_000_000_000 - 2);
+		assert!(Currency::free_balance(DOT, &BOB) <= dot_before + 10_000_000_000_000);
+		assert!(Currency::free_balance(DOT, &BOB) >= dot_before + 10_000_000_000_000 - 2);
+
+		assert!(AMM::positions(&BOB, 1).is_none());
+		assert!(AMM::get_positions(&BOB).is_empty());
+		for &tick in &[-100, 100] {
+			let (word, bit) = concentrated::tick_bitmap_position(tick, 10);
+			assert_eq!(AMM::tick_bitmap(&pair_account, word) & (1 << bit), 0);
+		}
+		for &tick in &[-400_000, 400_000] {
+			let (word, bit) = concentrated::tick_bitmap_position(tick, 10);
+			assert_eq!(AMM::tick_bitmap(&pair_account, word), 1 << bit);
+		}
+		assert_eq!(
+			AMM::concentrated_pool(&pair_account).liquidity,
+			AMM::positions(&ALICE, 0).unwrap().liquidity
+		);
+	});
+}
+
+#[test]
+fn position_with_insufficient_liquidity_should_not_work() {
+	new_test_ext().execute_with(|| {
+		create_concentrated_pool();
+
+		assert_noop!(
+			AMM::mint_position(Origin::signed(BOB), HDX, DOT, -100, 100, 1_000, 1_000, None),
+			Error::<Test>::InsufficientPositionLiquidity
+		);
+
+		assert_ok!(AMM::mint_position(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			-100,
+			100,
+			10_000_000_000_000,
+			10_000_000_000_000,
+			None
+		));
+
+		let liquidity = AMM::positions(&BOB, 1).unwrap().liquidity;
+
+		assert_noop!(
+			AMM::remove_position_liquidity(Origin::signed(BOB), 1, liquidity - 1, None),
+			Error::<Test>::InsufficientPositionLiquidity
+		);
+		assert_ok!(AMM::remove_position_liquidity(Origin::signed(BOB), 1, liquidity, None));
+	});
+}
+
+fn last_single_asset_liquidity_added() -> (AccountId, AssetId, AssetId, Balance, Balance, Balance, Balance, Balance) {
+	match last_events(1).pop() {
+		Some(TestEvent::amm(RawEvent::SingleAssetLiquidityAdded(
+			who,
+			asset_a,
+			asset_b,
+			amount_swapped,
+			amount_bought,
+			amount_a_added,
+			amount_b_added,
+			shares,
+		))) => (
+			who,
+			asset_a,
+			asset_b,
+			amount_swapped,
+			amount_bought,
+			amount_a_added,
+			amount_b_added,
+			shares,
+		),
+		_ => panic!("SingleAssetLiquidityAdded event expected"),
+	}
+}
+
+#[test]
+fn add_liquidity_single_asset_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+
+		let hdx_before = Currency::free_balance(HDX, &BOB);
+		let dot_before = Currency::free_balance(DOT, &BOB);
+
+		assert_ok!(AMM::add_liquidity_single_asset(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			10_000_000_000_000,
+			5_000_000_000_000,
+			None
+		));
+
+		let (who, asset_a, asset_b, amount_swapped, amount_bought, amount_a_added, amount_b_added, shares) =
+			last_single_asset_liquidity_added();
+
+		assert_eq!((who, asset_a, asset_b), (BOB, HDX, DOT));
+		assert!(amount_swapped > 4_880_000_000_000 && amount_swapped < 4_890_000_000_000);
+		assert!(amount_bought - amount_b_added <= 2);
+		assert!(10_000_000_000_000 - amount_swapped - amount_a_added <= 2);
+
+		// Shares are denominated in the asset with lower id
+		assert_eq!(shares, amount_a_added);
+		assert_eq!(Currency::free_balance(share_token, &BOB), shares);
+		assert_eq!(AMM::total_liquidity(&pair_account), 100_000_000_000_000 + shares);
+
+		assert_eq!(
+			Currency::free_balance(HDX, &BOB),
+			hdx_before - amount_swapped - amount_a_added
+		);
+		assert_eq!(
+			Currency::free_balance(DOT, &BOB),
+			dot_before + amount_bought - amount_b_added
+		);
+		assert_eq!(
+			Currency::free_balance(HDX, &pair_account),
+			100_000_000_000_000 + amount_swapped + amount_a_added
+		);
+		assert_eq!(
+			Currency::free_balance(DOT, &pair_account),
+			200_000_000_000_000 - amount_bought + amount_b_added
+		);
+	});
+}
+
+#[test]
+fn add_liquidity_single_asset_with_asset_of_higher_id_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+
+		assert_ok!(AMM::add_liquidity_single_asset(
+			Origin::signed(BOB),
+			DOT,
+			HDX,
+			20_000_000_000_000,
+			0,
+			None
+		));
+
+		let (_, asset_a, asset_b, amount_swapped, _, amount_a_added, amount_b_added, shares) =
+			last_single_asset_liquidity_added();
+
+		assert_eq!((asset_a, asset_b), (DOT, HDX));
+		assert!(20_000_000_000_000 - amount_swapped - amount_a_added <= 4);
+		assert_eq!(shares, amount_b_added);
+		assert!(shares > 4_600_000_000_000 && shares < 4_700_000_000_000);
+		assert_eq!(Currency::free_balance(share_token, &BOB), shares);
+	});
+}
+
+#[test]
+fn add_liquidity_single_asset_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AMM::add_liquidity_single_asset(Origin::signed(BOB), HDX, DOT, 10_000_000_000_000, 0, None),
+			Error::<Test>::TokenPoolNotFound
+		);
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		assert_noop!(
+			AMM::add_liquidity_single_asset(Origin::signed(BOB), HDX, DOT, 0, 0, None),
+			Error::<Test>::CannotAddZeroLiquidity
+		);
+		assert_noop!(
+			AMM::add_liquidity_single_asset(
+				Origin::signed(BOB),
+				HDX,
+				DOT,
+				10_000_000_000_000,
+				5_200_000_000_000,
+				None
+			),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+		assert_noop!(
+			AMM::add_liquidity_single_asset(Origin::signed(BOB), HDX, DOT, 1_000_000_000_000_000, 0, None),
+			Error::<Test>::MaxInRatioExceeded
+		);
+
+		let pair_account = create_concentrated_pool_of(HDX, ACA);
+		assert_eq!(AMM::pool_type(&pair_account), PoolType::Concentrated(10));
+
+		assert_noop!(
+			AMM::add_liquidity_single_asset(Origin::signed(BOB), HDX, ACA, 10_000_000_000_000, 0, None),
+			Error::<Test>::UnsupportedPoolType
+		);
+	});
+}
+
+#[test]
+fn remove_liquidity_with_min_amounts_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000,
+			Price::from(10_000),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		assert_noop!(
+			AMM::remove_liquidity(Origin::signed(ALICE), HDX, DOT, 355_000, 355_001, 0, None),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+		assert_noop!(
+			AMM::remove_liquidity(Origin::signed(ALICE), HDX, DOT, 355_000, 0, 3_550_000_001, None),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+
+		assert_ok!(AMM::remove_liquidity(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			355_000,
+			355_000,
+			3_550_000_000,
+			None
+		));
+
+		expect_events(vec![RawEvent::RemoveLiquidity(
+			ALICE,
+			HDX,
+			DOT,
+			355_000,
+			355_000,
+			3_550_000_000,
+		)
+		.into()]);
+	});
+}
+
+#[test]
+fn remove_liquidity_single_asset_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		let pair_account = AMM::get_pair_id(&HDX, &DOT);
+		let share_token = AMM::share_token(&pair_account);
+
+		let hdx_before = Currency::free_balance(HDX, &ALICE);
+		let dot_before = Currency::free_balance(DOT, &ALICE);
+
+		assert_ok!(AMM::remove_liquidity_single_asset(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			10_000_000_000_000,
+			18_900_000_000_000,
+			None
+		));
+
+		let hdx_received = Currency::free_balance(HDX, &ALICE) - hdx_before;
+
+		assert!(hdx_received > 18_900_000_000_000 && hdx_received < 19_000_000_000_000);
+		assert_eq!(Currency::free_balance(DOT, &ALICE), dot_before);
+		assert_eq!(Currency::free_balance(share_token, &ALICE), 90_000_000_000_000);
+		assert_eq!(AMM::total_liquidity(&pair_account), 90_000_000_000_000);
+		assert_eq!(
+			Currency::free_balance(HDX, &pair_account),
+			100_000_000_000_000 - hdx_received
+		);
+		assert_eq!(Currency::free_balance(DOT, &pair_account), 200_000_000_000_000);
+
+		expect_events(vec![
+			RawEvent::RemoveLiquidity(
+				ALICE,
+				HDX,
+				DOT,
+				10_000_000_000_000,
+				10_000_000_000_000,
+				20_000_000_000_000,
+			)
+			.into(),
+			RawEvent::Sell(ALICE, DOT, HDX, 20_000_000_000_000, hdx_received - 10_000_000_000_000).into(),
+			RawEvent::SingleAssetLiquidityRemoved(
+				ALICE,
+				HDX,
+				DOT,
+				10_000_000_000_000,
+				20_000_000_000_000,
+				hdx_received,
+			)
+			.into(),
+		]);
+	});
+}
+
+#[test]
+fn remove_liquidity_single_asset_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AMM::remove_liquidity_single_asset(Origin::signed(ALICE), HDX, DOT, 10_000_000_000_000, 0, None),
+			Error::<Test>::TokenPoolNotFound
+		);
+
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(2),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		assert_noop!(
+			AMM::remove_liquidity_single_asset(
+				Origin::signed(ALICE),
+				HDX,
+				DOT,
+				10_000_000_000_000,
+				19_000_000_000_000,
+				None
+			),
+			Error::<Test>::AssetBalanceLimitExceeded
+		);
+		assert_noop!(
+			AMM::remove_liquidity_single_asset(Origin::signed(ALICE), HDX, DOT, 100_000_000_000_000, 0, None),
+			Error::<Test>::CannotRemoveAllLiquidityInSingleAsset
+		);
+		assert_noop!(
+			AMM::remove_liquidity_single_asset(Origin::signed(BOB), HDX, DOT, 10_000_000_000_000, 0, None),
+			Error::<Test>::InsufficientAssetBalance
+		);
+	});
+}
+
+#[test]
+fn sell_with_expired_deadline_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AMM::create_pool(
+			Origin::signed(ALICE),
+			HDX,
+			DOT,
+			100_000_000_000_000,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::ConstantProduct
+		));
+
+		System::set_block_number(10);
+
+		assert_noop!(
+			AMM::sell(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 0, false, Some(9)),
+			Error::<Test>::DeadlineExpired
+		);
+		assert_noop!(
+			AMM::buy(
+				Origin::signed(BOB),
+				DOT,
+				HDX,
+				1_000_000_000,
+				2_000_000_000,
+				false,
+				Some(9)
+			),
+			Error::<Test>::DeadlineExpired
+		);
+		assert_noop!(
+			AMM::add_liquidity(Origin::signed(BOB), HDX, DOT, 1_000_000_000, 2_000_000_000, Some(9)),
+			Error::<Test>::DeadlineExpired
+		);
+
+		assert_ok!(AMM::sell(
+			Origin::signed(BOB),
+			HDX,
+			DOT,
+			1_000_000_000,
+			0,
+			false,
+			Some(10)
+		));
+		assert_ok!(AMM::buy(
+			Origin::signed(BOB),
+			DOT,
+			HDX,
+			1_000_000_000,
+			2_000_000_000,
+			false,
+			Some(20)
+		));
+	});
+}
+
+#[test]
+fn check_deadline_should_validate_transactions() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(10);
+
+		let check = CheckDeadline::<Test>::new();
+		let info = frame_support::weights::DispatchInfo::default();
+		let sell = |deadline| crate::mock::Call::AMM(Call::sell(HDX, DOT, 1_000_000_000, 0, false, deadline));
+
+		assert_eq!(
+			check.validate(&BOB, &sell(Some(9)), &info, 0),
+			Err(InvalidTransaction::Stale.into())
+		);
+		assert_eq!(
+			check.validate(&BOB, &sell(Some(14)), &info, 0).map(|v| v.longevity),
+			Ok(5)
+		);
+		assert_eq!(
+			check.validate(&BOB, &sell(None), &info, 0),
+			Ok(ValidTransaction::default())
+		);
+		let create_pool = crate::mock::Call::AMM(Call::create_pool(
+			HDX,
+			DOT,
+			1_000_000_000,
+			Price::from(1),
+			fee::Fee::default(),
+			PoolType::ConstantProduct,
+		));
+		assert_eq!(
+			check.validate(&BOB, &create_pool, &info, 0),
+			Ok(ValidTransaction::default())
+		);
+	});
+}
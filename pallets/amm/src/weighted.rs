@@ -0,0 +1,193 @@
+//! Pricing of weighted pools.
+//!
+//! Pool keeps invariant `V = B_1^W_1 * B_2^W_2 * ... * B_n^W_n` where `B_i` is reserve of asset `i`
+//! and `W_i` its normalized weight.
+
+use frame_support::sp_runtime::{
+	traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero},
+	FixedPointNumber, Permill,
+};
+use primitives::{Balance, Price};
+
+/// Max number of assets in a weighted pool.
+pub const MAX_WEIGHTED_POOL_ASSETS: usize = 8;
+
+/// Min normalized weight of an asset in a weighted pool.
+pub const MIN_WEIGHT: Permill = Permill::from_percent(2);
+
+/// Precision of the approximation of fractional powers.
+const POW_PRECISION: u128 = 100_000_000;
+const MAX_POW_ITERATIONS: u32 = 128;
+
+fn weight_to_price(weight: Permill) -> Option<Price> {
+	Price::checked_from_rational(weight.deconstruct(), Permill::one().deconstruct())
+}
+
+/// Calculate `base^exponent` for integer exponent.
+fn pow_int(base: Price, mut exponent: u128) -> Option<Price> {
+	let mut result = Price::one();
+	let mut base = base;
+
+	while exponent > 0 {
+		if exponent % 2 == 1 {
+			result = result.checked_mul(&base)?;
+		}
+		exponent /= 2;
+		if exponent > 0 {
+			base = base.checked_mul(&base)?;
+		}
+	}
+
+	Some(result)
+}
+
+/// Approximate `base^exponent` for `0 <= exponent < 1` by binomial series.
+///
+/// Converges for `0 < base < 2`.
+fn pow_frac(base: Price, exponent: Price) -> Option<Price> {
+	let one = Price::one();
+	let precision = Price::from_inner(POW_PRECISION);
+
+	let (x, x_negative) = if base >= one {
+		(base - one, false)
+	} else {
+		(one - base, true)
+	};
+
+	let mut term = one;
+	let mut sum = one;
+	let mut negative = false;
+
+	for k in 1..=MAX_POW_ITERATIONS {
+		let big_k = Price::saturating_from_integer(k);
+		let previous = big_k - one;
+
+		let (c, c_negative) = if exponent >= previous {
+			(exponent - previous, false)
+		} else {
+			(previous - exponent, true)
+		};
+
+		term = term.checked_mul(&c.checked_mul(&x)?)?.checked_div(&big_k)?;
+
+		if term.is_zero() {
+			break;
+		}
+
+		if x_negative {
+			negative = !negative;
+		}
+		if c_negative {
+			negative = !negative;
+		}
+
+		sum = if negative {
+			sum.checked_sub(&term)?
+		} else {
+			sum.checked_add(&term)?
+		};
+
+		if term < precision {
+			break;
+		}
+	}
+
+	Some(sum)
+}
+
+/// Calculate `base^exponent`.
+///
+/// Base must be in `(0, 2)` range unless the exponent is an integer.
+pub fn pow(base: Price, exponent: Price) -> Option<Price> {
+	let accuracy = Price::accuracy();
+
+	let whole = exponent.into_inner() / accuracy;
+	let remain = Price::from_inner(exponent.into_inner() % accuracy);
+
+	let whole_pow = pow_int(base, whole)?;
+
+	if remain.is_zero() {
+		return Some(whole_pow);
+	}
+
+	if base.is_zero() {
+		return Some(Price::zero());
+	}
+
+	whole_pow.checked_mul(&pow_frac(base, remain)?)
+}
+
+/// Calculate amount of out asset received for `amount_in` of in asset.
+pub fn calculate_out_given_in(
+	in_reserve: Balance,
+	in_weight: Permill,
+	out_reserve: Balance,
+	out_weight: Permill,
+	amount_in: Balance,
+) -> Option<Balance> {
+	let base = Price::checked_from_rational(in_reserve, in_reserve.checked_add(amount_in)?)?;
+	let exponent = Price::checked_from_rational(in_weight.deconstruct(), out_weight.deconstruct())?;
+
+	Price::one()
+		.checked_sub(&pow(base, exponent)?)?
+		.checked_mul_int(out_reserve)
+}
+
+/// Calculate amount of in asset needed to receive `amount_out` of out asset.
+pub fn calculate_in_given_out(
+	in_reserve: Balance,
+	in_weight: Permill,
+	out_reserve: Balance,
+	out_weight: Permill,
+	amount_out: Balance,
+) -> Option<Balance> {
+	let base = Price::checked_from_rational(out_reserve, out_reserve.checked_sub(amount_out)?)?;
+	let exponent = Price::checked_from_rational(out_weight.deconstruct(), in_weight.deconstruct())?;
+
+	pow(base, exponent)?
+		.checked_sub(&Price::one())?
+		.checked_mul_int(in_reserve)?
+		.checked_add(1)
+}
+
+/// Calculate amount of pool shares minted for single asset deposit of `amount_in`.
+pub fn calculate_shares_for_deposit(
+	reserve: Balance,
+	weight: Permill,
+	total_shares: Balance,
+	amount_in: Balance,
+) -> Option<Balance> {
+	let base = Price::checked_from_rational(reserve.checked_add(amount_in)?, reserve)?;
+
+	pow(base, weight_to_price(weight)?)?
+		.checked_sub(&Price::one())?
+		.checked_mul_int(total_shares)
+}
+
+/// Calculate amount of asset paid out for `shares_in` pool shares in single asset withdrawal.
+pub fn calculate_withdrawal(
+	reserve: Balance,
+	weight: Permill,
+	total_shares: Balance,
+	shares_in: Balance,
+) -> Option<Balance> {
+	let base = Price::checked_from_rational(total_shares.checked_sub(shares_in)?, total_shares)?;
+	let exponent = Price::checked_from_rational(Permill::one().deconstruct(), weight.deconstruct())?;
+
+	Price::one()
+		.checked_sub(&pow(base, exponent)?)?
+		.checked_mul_int(reserve)
+}
+
+/// Calculate spot price of in asset denominated in out asset.
+pub fn calculate_spot_price(
+	in_reserve: Balance,
+	in_weight: Permill,
+	out_reserve: Balance,
+	out_weight: Permill,
+) -> Option<Price> {
+	let in_ratio = Price::checked_from_rational(in_reserve, in_weight.deconstruct())?;
+	let out_ratio = Price::checked_from_rational(out_reserve, out_weight.deconstruct())?;
+
+	out_ratio.checked_div(&in_ratio)
+}
@@ -11,7 +11,7 @@ use frame_benchmarking::{account, benchmarks};
 use frame_support::traits::OnFinalize;
 use frame_system::RawOrigin;
 use orml_traits::{MultiCurrency, MultiCurrencyExtended};
-use primitives::{AssetId, Balance, Price};
+use primitives::{fee::Fee, AssetId, Balance, FillPolicy, Price};
 use sp_runtime::DispatchError;
 
 use pallet_amm as ammpool;
@@ -52,6 +52,8 @@ fn initialize_pool<T: Config>(
 		asset_b,
 		amount,
 		price,
+		Fee::default(),
+		ammpool::PoolType::ConstantProduct,
 	)?;
 
 	Ok(())
@@ -72,6 +74,8 @@ fn feed_intentions<T: Config>(asset_a: AssetId, asset_b: AssetId, number: u32) -
 			SELL_INTENTION_AMOUNT,
 			SELL_INTENTION_LIMIT,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		)?;
 	}
 
@@ -84,6 +88,8 @@ fn feed_intentions<T: Config>(asset_a: AssetId, asset_b: AssetId, number: u32) -
 			BUY_INTENTION_AMOUNT,
 			BUY_INTENTION_LIMIT,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		)?;
 	}
 
@@ -131,7 +137,7 @@ benchmarks! {
 
 		assert_eq!(pallet_exchange::Module::<T>::get_intentions_count((asset_a, asset_b)), 0);
 
-	}: {  Exchange::<T>::sell(RawOrigin::Signed(caller.clone()).into(), asset_a, asset_b, amount ,limit, false)? }
+	}: {  Exchange::<T>::sell(RawOrigin::Signed(caller.clone()).into(), asset_a, asset_b, amount ,limit, false, FillPolicy::PartialFill, None)? }
 	verify{
 		assert_eq!(pallet_exchange::Module::<T>::get_intentions_count((asset_a, asset_b)), 1);
 	}
@@ -148,7 +154,7 @@ benchmarks! {
 
 		assert_eq!(pallet_exchange::Module::<T>::get_intentions_count((asset_a, asset_b)), 0);
 
-	}: {  Exchange::<T>::buy(RawOrigin::Signed(caller.clone()).into(), asset_a, asset_b, amount / 10 ,limit, false)? }
+	}: {  Exchange::<T>::buy(RawOrigin::Signed(caller.clone()).into(), asset_a, asset_b, amount / 10 ,limit, false, FillPolicy::PartialFill, None)? }
 	verify{
 		assert_eq!(pallet_exchange::Module::<T>::get_intentions_count((asset_a, asset_b)), 1);
 	}
@@ -192,6 +198,8 @@ benchmarks! {
 				BUY_INTENTION_AMOUNT,
 				BUY_INTENTION_LIMIT,
 				false,
+				FillPolicy::PartialFill,
+				None,
 			)?;
 		}
 
@@ -225,6 +233,8 @@ benchmarks! {
 				SELL_INTENTION_AMOUNT,
 				SELL_INTENTION_LIMIT,
 				false,
+				FillPolicy::PartialFill,
+				None,
 			)?;
 		}
 
@@ -251,7 +261,7 @@ benchmarks! {
 
 		initialize_pool::<T>(creator, asset_a, asset_b, amount, Price::from(1))?;
 
-	}: { ammpool::Module::<T>::sell(RawOrigin::Signed(seller.clone()).into(), asset_a, asset_b, 1_000_000_000, min_bought, false)?; }
+	}: { ammpool::Module::<T>::sell(RawOrigin::Signed(seller.clone()).into(), asset_a, asset_b, 1_000_000_000, min_bought, false, None)?; }
 	verify {
 		assert_eq!(<T as ammpool::Config>::Currency::free_balance(asset_a, &seller), 999_999_000_000_000);
 		assert_eq!(<T as ammpool::Config>::Currency::free_balance(asset_b, &seller), 1000000907437716);
@@ -275,6 +285,8 @@ benchmarks! {
 			SELL_INTENTION_AMOUNT,
 			SELL_INTENTION_LIMIT,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		)?;
 
 		assert_eq!(pallet_exchange::Module::<T>::get_intentions_count((asset_a, asset_b)), 1);
@@ -298,7 +310,7 @@ benchmarks! {
 
 		initialize_pool::<T>(creator, asset_a, asset_b, amount, Price::from(1))?;
 
-	}: { ammpool::Module::<T>::buy(RawOrigin::Signed(buyer.clone()).into(), asset_a, asset_b, 1_000_000_000, max_sold, false)?; }
+	}: { ammpool::Module::<T>::buy(RawOrigin::Signed(buyer.clone()).into(), asset_a, asset_b, 1_000_000_000, max_sold, false, None)?; }
 	verify {
 		assert_eq!(<T as ammpool::Config>::Currency::free_balance(asset_a, &buyer), 1000001000000000);
 		assert_eq!(<T as ammpool::Config>::Currency::free_balance(asset_b, &buyer), 999998886419204);
@@ -325,6 +337,8 @@ benchmarks! {
 			1_000_000_000,
 			max_sold,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		)?;
 
 		assert_eq!(pallet_exchange::Module::<T>::get_intentions_count((asset_a, asset_b)), 1);
@@ -335,6 +349,81 @@ benchmarks! {
 		assert_eq!(<T as ammpool::Config>::Currency::free_balance(asset_a, &buyer), 1000001000000000);
 		assert_eq!(<T as ammpool::Config>::Currency::free_balance(asset_b, &buyer), 999998886419204);
 	}
+
+	cancel_intention {
+		let q in 1 .. 100; // Queued intention component
+		let caller = funded_account::<T>("caller", 1);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+
+		initialize_pool::<T>(caller, asset_a, asset_b, 100_000_000_000_000, Price::from(2))?;
+
+		// Trade limit cannot be satisfied, so all-or-none intentions are queued for next blocks.
+		for idx in 0 .. q {
+			let user = funded_account::<T>("user", idx + 100);
+			Exchange::<T>::sell(RawOrigin::Signed(user).into(), asset_a, asset_b, SELL_INTENTION_AMOUNT, 4 * SELL_INTENTION_AMOUNT, false, FillPolicy::AllOrNone, None)?;
+		}
+
+		Exchange::<T>::on_finalize(1u32.into());
+
+		assert_eq!(pallet_exchange::Module::<T>::queue_depth(), q);
+
+		// Last queued intention is the worst case - the whole queue is searched.
+		let intention = pallet_exchange::Module::<T>::intention_queue()[q as usize - 1].clone();
+
+	}: {  Exchange::<T>::cancel_intention(RawOrigin::Signed(intention.who.clone()).into(), intention.intention_id)? }
+	verify {
+		assert_eq!(pallet_exchange::Module::<T>::queue_depth(), q - 1);
+	}
+
+	place_limit_order {
+		let caller = funded_account::<T>("caller", 1);
+		let seller = funded_account::<T>("seller", 2);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+
+		initialize_pool::<T>(caller, asset_a, asset_b, 100_000_000_000_000, Price::from(2))?;
+
+	}: {  Exchange::<T>::place_limit_order(RawOrigin::Signed(seller.clone()).into(), asset_a, asset_b, 1_000_000_000_000, Price::from(3), 10u32.into())? }
+	verify {
+		assert_eq!(pallet_exchange::Module::<T>::limit_order_count(), 1);
+	}
+
+	cancel_limit_order {
+		let caller = funded_account::<T>("caller", 1);
+		let seller = funded_account::<T>("seller", 2);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+
+		initialize_pool::<T>(caller, asset_a, asset_b, 100_000_000_000_000, Price::from(2))?;
+
+		Exchange::<T>::place_limit_order(RawOrigin::Signed(seller.clone()).into(), asset_a, asset_b, 1_000_000_000_000, Price::from(3), 10u32.into())?;
+
+	}: {  Exchange::<T>::cancel_limit_order(RawOrigin::Signed(seller.clone()).into(), 0)? }
+	verify {
+		assert_eq!(pallet_exchange::Module::<T>::limit_order_count(), 0);
+	}
+
+	resolve_limit_order {
+		let t: u32 = 5;
+		let caller = funded_account::<T>("caller", 1);
+		let seller = funded_account::<T>("seller", 2);
+
+		let asset_a: AssetId = 1;
+		let asset_b: AssetId = 2;
+
+		initialize_pool::<T>(caller, asset_a, asset_b, 100_000_000_000_000, Price::from(2))?;
+
+		Exchange::<T>::place_limit_order(RawOrigin::Signed(seller.clone()).into(), asset_a, asset_b, 1_000_000_000_000, Price::from(1), 10u32.into())?;
+
+	}: {  Exchange::<T>::on_finalize(t.into()); }
+	verify {
+		assert_eq!(pallet_exchange::Module::<T>::limit_order_count(), 0);
+		assert_eq!(<T as ammpool::Config>::Currency::free_balance(asset_a, &seller), INITIAL_ASSET_BALANCE - 1_000_000_000_000);
+	}
 }
 
 #[cfg(test)]
@@ -356,6 +445,10 @@ mod tests {
 			assert_ok!(test_benchmark_on_finalize_for_one_sell_extrinsic::<Test>());
 			assert_ok!(test_benchmark_buy_extrinsic::<Test>());
 			assert_ok!(test_benchmark_on_finalize_for_one_buy_extrinsic::<Test>());
+			assert_ok!(test_benchmark_cancel_intention::<Test>());
+			assert_ok!(test_benchmark_place_limit_order::<Test>());
+			assert_ok!(test_benchmark_cancel_limit_order::<Test>());
+			assert_ok!(test_benchmark_resolve_limit_order::<Test>());
 		});
 	}
 }
@@ -8,6 +8,7 @@ use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup, Zero},
+	ModuleId, Permill,
 };
 
 use pallet_amm::AssetPairAccountIdFor;
@@ -42,7 +43,22 @@ parameter_types! {
 
 	pub const HDXAssetId: AssetId = HDX;
 
-	pub ExchangeFeeRate: fee::Fee = fee::Fee::default();
+	pub MaxExchangeFeeRate: fee::Fee = fee::Fee { numerator: 1, denominator: 10 };
+	pub const PriceHistoryLength: u32 = 10;
+	pub const ProtocolFeeRate: Permill = Permill::from_percent(0);
+	pub const ProtocolFeeReceiverAccount: AccountId = 100;
+	pub const ExchangeModuleId: ModuleId = ModuleId(*b"hdx/xchg");
+	pub const MaxIntentionsPerBlock: u32 = 1_000;
+	pub const MaxQueuedIntentions: u32 = 1_000;
+	pub const MaxIntentionRequeues: u32 = 10;
+	pub DirectTradeFee: fee::Fee = fee::Fee::default();
+	pub DiscountedDirectTradeFee: fee::Fee = fee::Fee::default();
+	pub const DirectTradeFeeShare: Permill = Permill::from_percent(0);
+	pub const DirectTradeFeeReceiverAccount: AccountId = 101;
+	pub const MaxLimitOrders: u32 = 1_000;
+	pub const MaxLimitOrdersPerBlock: u32 = 16;
+	pub const MinLimitOrderAmount: Balance = 1_000;
+	pub const MaxLimitOrderExpiry: u64 = 100;
 }
 impl system::Config for Test {
 	type BaseCallFilter = ();
@@ -99,6 +115,14 @@ impl AssetPairAccountIdFor<AssetId, u64> for AssetPairAccountIdTest {
 		}
 		return (a * 1000 + b) as u64;
 	}
+
+	fn from_asset_set(assets: &[AssetId]) -> u64 {
+		let mut sorted = assets.to_vec();
+		sorted.sort_unstable();
+		sorted.iter().fold(0u64, |acc, asset| {
+			acc.wrapping_mul(1_000_003).wrapping_add(*asset as u64)
+		}) | (1 << 62)
+	}
 }
 
 impl pallet_asset_registry::Config for Test {
@@ -111,7 +135,11 @@ impl pallet_amm::Config for Test {
 	type Currency = Currency;
 	type HDXAssetId = HDXAssetId;
 	type WeightInfo = ();
-	type GetExchangeFee = ExchangeFeeRate;
+	type MaxExchangeFee = MaxExchangeFeeRate;
+	type UpdatePoolFeeOrigin = frame_system::EnsureRoot<AccountId>;
+	type ProtocolFee = ProtocolFeeRate;
+	type ProtocolFeeReceiver = ProtocolFeeReceiverAccount;
+	type PriceHistoryLength = PriceHistoryLength;
 }
 
 pub type AMMModule = pallet_amm::Module<Test>;
@@ -123,6 +151,19 @@ impl pallet_exchange::Config for Test {
 	type Currency = Currency;
 	type Resolver = pallet_exchange::Module<Test>;
 	type WeightInfo = ();
+	type ModuleId = ExchangeModuleId;
+	type MatchingStrategy = pallet_exchange::GreedyBySize;
+	type MaxIntentionsPerBlock = MaxIntentionsPerBlock;
+	type MaxQueuedIntentions = MaxQueuedIntentions;
+	type MaxIntentionRequeues = MaxIntentionRequeues;
+	type DirectTradeFee = DirectTradeFee;
+	type DiscountedDirectTradeFee = DiscountedDirectTradeFee;
+	type DirectTradeFeeShare = DirectTradeFeeShare;
+	type DirectTradeFeeReceiver = DirectTradeFeeReceiverAccount;
+	type MaxLimitOrders = MaxLimitOrders;
+	type MaxLimitOrdersPerBlock = MaxLimitOrdersPerBlock;
+	type MinLimitOrderAmount = MinLimitOrderAmount;
+	type MaxLimitOrderExpiry = MaxLimitOrderExpiry;
 }
 
 pub struct ExtBuilder {
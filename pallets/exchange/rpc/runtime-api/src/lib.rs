@@ -0,0 +1,124 @@
+//! Runtime API definition for exchange module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::{Codec, Decode, Encode};
+use primitives::{ExchangeIntention, ExchangeTrade, FillPolicy, IntentionType};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sp_runtime::traits::{Block as BlockT, MaybeDisplay, MaybeFromStr};
+use sp_runtime::DispatchError;
+use sp_std::prelude::*;
+
+#[derive(Eq, PartialEq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct IntentionInfo<AccountId, AssetId, Balance, IntentionId> {
+	pub intention_id: IntentionId,
+
+	pub who: AccountId,
+
+	pub asset_sell: AssetId,
+
+	pub asset_buy: AssetId,
+
+	#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+	#[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+	#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+	#[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+	pub amount_sell: Balance,
+
+	#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+	#[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+	#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+	#[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+	pub amount_buy: Balance,
+
+	#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+	#[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+	#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+	#[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+	pub trade_limit: Balance,
+
+	pub discount: bool,
+
+	pub sell_or_buy: IntentionType,
+
+	pub fill_policy: FillPolicy,
+}
+
+impl<AccountId, AssetId, Balance, IntentionId, BlockNumber>
+	From<ExchangeIntention<AccountId, AssetId, Balance, IntentionId, BlockNumber>>
+	for IntentionInfo<AccountId, AssetId, Balance, IntentionId>
+{
+	fn from(intention: ExchangeIntention<AccountId, AssetId, Balance, IntentionId, BlockNumber>) -> Self {
+		IntentionInfo {
+			intention_id: intention.intention_id,
+			who: intention.who,
+			asset_sell: intention.asset_sell,
+			asset_buy: intention.asset_buy,
+			amount_sell: intention.amount_sell,
+			amount_buy: intention.amount_buy,
+			trade_limit: intention.trade_limit,
+			discount: intention.discount,
+			sell_or_buy: intention.sell_or_buy,
+			fill_policy: intention.fill_policy,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+fn serialize_as_string<S: Serializer, T: std::fmt::Display>(t: &T, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.serialize_str(&t.to_string())
+}
+
+#[cfg(feature = "std")]
+fn deserialize_from_string<'de, D: Deserializer<'de>, T: std::str::FromStr>(deserializer: D) -> Result<T, D::Error> {
+	let s = String::deserialize(deserializer)?;
+	s.parse::<T>()
+		.map_err(|_| serde::de::Error::custom("Parse from string failed"))
+}
+
+sp_api::decl_runtime_apis! {
+	pub trait ExchangeApi<AccountId, AssetId, Balance, IntentionId> where
+		AccountId: Codec,
+		AssetId: Codec,
+		Balance: Codec + MaybeDisplay + MaybeFromStr,
+		IntentionId: Codec,
+	{
+		/// Number of intentions queued to be resolved in next blocks.
+		fn get_queue_depth() -> u32;
+
+		/// Intentions of an asset pair waiting to be resolved - queued intentions and intentions of `pending` extrinsics.
+		///
+		/// Intentions registered in a block are resolved when it is finalized, so intentions not resolved yet
+		/// are taken from extrinsics which are not included in a block, eg. extrinsics in the transaction pool.
+		fn get_pending_intentions(
+			asset_a: AssetId,
+			asset_b: AssetId,
+			pending: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Vec<IntentionInfo<AccountId, AssetId, Balance, IntentionId>>;
+
+		/// Intentions of an account waiting to be resolved - queued intentions and intentions of `pending` extrinsics.
+		fn get_intentions_of(
+			who: AccountId,
+			pending: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Vec<IntentionInfo<AccountId, AssetId, Balance, IntentionId>>;
+	}
+
+	pub trait ExchangeSimulationApi<AccountId, AssetId, Balance, IntentionId, ExchangeCall> where
+		AccountId: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+		IntentionId: Codec,
+		ExchangeCall: Codec,
+	{
+		/// Predict trades of queued intentions and intentions of `pending` extrinsics ( eg. extrinsics in the transaction pool ),
+		/// including intention of an exchange call dispatched by the account. Nothing is written to storage.
+		fn simulate_resolution(
+			pending: Vec<<Block as BlockT>::Extrinsic>,
+			call: Option<(AccountId, ExchangeCall)>,
+		) -> Result<Vec<ExchangeTrade<AccountId, AssetId, Balance, IntentionId>>, DispatchError>;
+	}
+}
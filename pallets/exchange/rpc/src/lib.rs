@@ -0,0 +1,184 @@
+use codec::{Codec, Decode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use module_exchange_rpc_runtime_api::IntentionInfo;
+use primitives::ExchangeTrade;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, MaybeDisplay, MaybeFromStr},
+};
+use sp_transaction_pool::{InPoolTransaction, TransactionPool};
+use std::sync::Arc;
+
+pub use self::gen_client::Client as ExchangeClient;
+pub use module_exchange_rpc_runtime_api::ExchangeApi as ExchangeRuntimeApi;
+pub use module_exchange_rpc_runtime_api::ExchangeSimulationApi as ExchangeSimulationRuntimeApi;
+
+/// Intentions registered in a block are resolved when the block is finalized,
+/// so intentions waiting to be resolved are read from the queue and from extrinsics ready in the transaction pool.
+/// `ExchangeCall` is the call of the exchange pallet, passed SCALE encoded.
+#[rpc]
+pub trait ExchangeApi<BlockHash, AccountId, AssetId, ExchangeCall, ResponseType, TradeResponseType> {
+	#[rpc(name = "exchange_pendingIntentions")]
+	fn pending_intentions(
+		&self,
+		asset_a: AssetId,
+		asset_b: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<Vec<ResponseType>>;
+
+	#[rpc(name = "exchange_intentionsOf")]
+	fn intentions_of(&self, who: AccountId, at: Option<BlockHash>) -> Result<Vec<ResponseType>>;
+
+	/// Predict trades of intentions waiting to be resolved, including optional SCALE encoded exchange call
+	/// dispatched by the account.
+	#[rpc(name = "exchange_simulateResolution")]
+	fn simulate_resolution(
+		&self,
+		call: Option<(AccountId, Bytes)>,
+		at: Option<BlockHash>,
+	) -> Result<Vec<TradeResponseType>>;
+}
+
+/// A struct that implements the [`ExchangeApi`].
+pub struct Exchange<C, P, B> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, P, B> Exchange<C, P, B> {
+	/// Create new `Exchange` with the given reference to the client and the transaction pool.
+	pub fn new(client: Arc<C>, pool: Arc<P>) -> Self {
+		Exchange {
+			client,
+			pool,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, P, Block> Exchange<C, P, Block>
+where
+	Block: BlockT,
+	P: TransactionPool<Block = Block>,
+{
+	/// Extrinsics ready to be included in the next block.
+	fn pending_extrinsics(&self) -> Vec<<Block as BlockT>::Extrinsic> {
+		self.pool.ready().map(|tx| tx.data().clone()).collect()
+	}
+}
+
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// The exchange call could not be decoded.
+	DecodeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::DecodeError => 2,
+		}
+	}
+}
+
+impl<C, P, Block, AccountId, AssetId, Balance, IntentionId, ExchangeCall>
+	ExchangeApi<
+		<Block as BlockT>::Hash,
+		AccountId,
+		AssetId,
+		ExchangeCall,
+		IntentionInfo<AccountId, AssetId, Balance, IntentionId>,
+		ExchangeTrade<AccountId, AssetId, Balance, IntentionId>,
+	> for Exchange<C, P, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: ExchangeRuntimeApi<Block, AccountId, AssetId, Balance, IntentionId>,
+	C::Api: ExchangeSimulationRuntimeApi<Block, AccountId, AssetId, Balance, IntentionId, ExchangeCall>,
+	P: TransactionPool<Block = Block> + 'static,
+	AccountId: Codec,
+	AssetId: Codec,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+	IntentionId: Codec,
+	ExchangeCall: Codec,
+{
+	fn pending_intentions(
+		&self,
+		asset_a: AssetId,
+		asset_b: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<IntentionInfo<AccountId, AssetId, Balance, IntentionId>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.get_pending_intentions(&at, asset_a, asset_b, self.pending_extrinsics())
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to retrieve pending intentions.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+
+	fn intentions_of(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<IntentionInfo<AccountId, AssetId, Balance, IntentionId>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		api.get_intentions_of(&at, who, self.pending_extrinsics())
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to retrieve intentions of account.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+
+	fn simulate_resolution(
+		&self,
+		call: Option<(AccountId, Bytes)>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<ExchangeTrade<AccountId, AssetId, Balance, IntentionId>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		let call = match call {
+			Some((who, encoded)) => {
+				let call = ExchangeCall::decode(&mut &encoded[..]).map_err(|e| RpcError {
+					code: ErrorCode::ServerError(Error::DecodeError.into()),
+					message: "Unable to decode exchange call.".into(),
+					data: Some(format!("{:?}", e).into()),
+				})?;
+
+				Some((who, call))
+			}
+			None => None,
+		};
+
+		api.simulate_resolution(&at, self.pending_extrinsics(), call)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to simulate resolution.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Simulated resolution failed.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+}
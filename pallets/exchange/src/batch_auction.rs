@@ -0,0 +1,499 @@
+use super::*;
+use frame_support::sp_runtime::traits::AccountIdConversion;
+use frame_support::sp_runtime::{DispatchError, FixedPointNumber, TransactionOutcome};
+use frame_support::storage::with_transaction;
+use primitive_types::U256;
+
+/// Maximum number of binary search steps when looking for the clearing price.
+pub const MAX_CLEARING_PRICE_ITERATIONS: u32 = 64;
+
+/// Reads of validating an AMM sell - pool existence, pool type, pool fee and balances.
+const AMM_VALIDATE_SELL_READS: Weight = 6;
+
+/// Participant of a batch auction.
+/// ```budget``` is the amount of sold asset transferred to the settlement account - the maximum participant can pay.
+struct Bid<'a, T: Config> {
+	intention: &'a Intention<T>,
+	budget: Balance,
+}
+
+/// Result of clearing a batch auction.
+/// Price is amount of ```asset_out``` per one unit of ```asset_in```. Imbalance of ```asset_in``` is sold via AMM.
+struct Clearing {
+	asset_in: AssetId,
+	asset_out: AssetId,
+	price: Price,
+	amm_amount_in: Balance,
+	amm_min_amount_out: Balance,
+	totals: Totals,
+}
+
+/// Totals of amounts paid and received by both sides of an auction at given price.
+#[derive(Default)]
+struct Totals {
+	in_paid: Balance,
+	out_received: Balance,
+	out_paid: Balance,
+	in_received: Balance,
+}
+
+fn mul_price(amount: Balance, price: Price, round_up: bool) -> Option<Balance> {
+	div_rational(
+		U256::from(amount) * U256::from(price.into_inner()),
+		Price::accuracy(),
+		round_up,
+	)
+}
+
+fn div_price(amount: Balance, price: Price, round_up: bool) -> Option<Balance> {
+	div_rational(
+		U256::from(amount) * U256::from(Price::accuracy()),
+		price.into_inner(),
+		round_up,
+	)
+}
+
+fn div_rational(numerator: U256, denominator: Balance, round_up: bool) -> Option<Balance> {
+	if denominator == 0 {
+		return None;
+	}
+
+	let denominator = U256::from(denominator);
+	let mut result = numerator / denominator;
+
+	if round_up && !(numerator % denominator).is_zero() {
+		result += U256::one();
+	}
+
+	if result > U256::from(Balance::max_value()) {
+		None
+	} else {
+		Some(result.low_u128())
+	}
+}
+
+/// Batch auction implementation
+impl<T: Config> Module<T> {
+	/// Account which holds funds of batch auction participants during settlement.
+	pub fn settlement_account() -> T::AccountId {
+		T::ModuleId::get().into_account()
+	}
+
+	/// Resolve intentions of an asset pair in uniform clearing price batch auction.
+	///
+	/// 1. Sold amounts are transferred to the settlement account.
+	/// 2. Single clearing price is found at which opposite intentions net against each other and the imbalance
+	///    can be sold via AMM at the same price.
+	/// 3. Intentions whose limits are not satisfied at the clearing price are refunded and the price is found again.
+	/// 4. The imbalance is sold via AMM and every participant receives the amount at the clearing price.
+	///    Direct trade fee is paid from the received amount for the part matched with the other side.
+	///
+	/// If the auction cannot be cleared or settled, intentions are refunded and resolved by ```process_exchange_intentions```.
+	pub(crate) fn process_batch_auction(
+		pair_account: &T::AccountId,
+		asset_1: AssetId,
+		asset_2: AssetId,
+		sell_1_intentions: &[Intention<T>],
+		sell_2_intentions: &[Intention<T>],
+	) {
+		let settlement = Self::settlement_account();
+
+		let mut bids_1 = Self::collect_bids(&settlement, sell_1_intentions);
+		let mut bids_2 = Self::collect_bids(&settlement, sell_2_intentions);
+
+		loop {
+			if bids_1.is_empty() && bids_2.is_empty() {
+				break;
+			}
+
+			let clearing = Self::clear_batch_auction(&settlement, asset_1, asset_2, &bids_1, &bids_2);
+
+			let (price, in_side_is_1) = match clearing {
+				Some((asset_in, price)) => (price, asset_in == asset_1),
+				None => {
+					let (remaining_1, remaining_2) = Self::refund_bids(&settlement, bids_1, bids_2);
+					Self::process_exchange_intentions(pair_account, &remaining_2, &remaining_1);
+					break;
+				}
+			};
+
+			let violated = |bid: &Bid<T>, is_in_side: bool| match Self::bid_amounts(bid, price, is_in_side) {
+				Some((pay_in, pay_out)) => {
+					pay_in > bid.budget
+						|| (bid.intention.sell_or_buy == IntentionType::SELL && pay_out < bid.intention.trade_limit)
+				}
+				None => true,
+			};
+
+			let (keep_1, drop_1): (Vec<_>, Vec<_>) = bids_1.into_iter().partition(|bid| !violated(bid, in_side_is_1));
+			let (keep_2, drop_2): (Vec<_>, Vec<_>) = bids_2.into_iter().partition(|bid| !violated(bid, !in_side_is_1));
+
+			bids_1 = keep_1;
+			bids_2 = keep_2;
+
+			for bid in drop_1.iter().chain(drop_2.iter()) {
+				Self::refund_bid(&settlement, bid);
+				Self::send_intention_error_event(bid.intention, Error::<T>::AssetBalanceLimitExceeded.into());
+			}
+
+			if !drop_1.is_empty() || !drop_2.is_empty() {
+				continue;
+			}
+
+			let (asset_in, asset_out, in_bids, out_bids) = if in_side_is_1 {
+				(asset_1, asset_2, bids_1, bids_2)
+			} else {
+				(asset_2, asset_1, bids_2, bids_1)
+			};
+
+			let clearing = Self::totals(&in_bids, &out_bids, price).and_then(|totals| {
+				let (amm_amount_in, amm_min_amount_out) =
+					Self::settle_amounts(&settlement, asset_in, asset_out, &totals)?;
+
+				Some(Clearing {
+					asset_in,
+					asset_out,
+					price,
+					amm_amount_in,
+					amm_min_amount_out,
+					totals,
+				})
+			});
+
+			// Whole settlement is reverted if any of its transfers fails.
+			let settled = match clearing {
+				Some(clearing) => with_transaction(|| {
+					match Self::settle_batch_auction(&settlement, pair_account, &clearing, &in_bids, &out_bids) {
+						Ok(_) => TransactionOutcome::Commit(true),
+						Err(_) => TransactionOutcome::Rollback(false),
+					}
+				}),
+				None => false,
+			};
+
+			if !settled {
+				let (remaining_in, remaining_out) = Self::refund_bids(&settlement, in_bids, out_bids);
+				let (remaining_1, remaining_2) = if in_side_is_1 {
+					(remaining_in, remaining_out)
+				} else {
+					(remaining_out, remaining_in)
+				};
+				Self::process_exchange_intentions(pair_account, &remaining_2, &remaining_1);
+			}
+
+			break;
+		}
+	}
+
+	/// Worst case weight of batch auction clearing for one intention.
+	///
+	/// Each intention dropped from the auction causes another search of the clearing price,
+	/// each step of the search validates an AMM sell.
+	pub(crate) fn batch_auction_weight() -> Weight {
+		T::DbWeight::get()
+			.reads(AMM_VALIDATE_SELL_READS)
+			.saturating_mul(MAX_CLEARING_PRICE_ITERATIONS.saturating_add(2) as Weight)
+	}
+
+	/// Transfer budget of each intention to the settlement account.
+	/// Sell intentions pay the sold amount, buy intentions pay at most their trade limit.
+	fn collect_bids<'a>(settlement: &T::AccountId, intentions: &'a [Intention<T>]) -> Vec<Bid<'a, T>> {
+		let mut bids = Vec::<Bid<T>>::new();
+
+		for intention in intentions.iter() {
+			let free_balance = T::Currency::free_balance(intention.asset_sell, &intention.who);
+
+			let budget = match intention.sell_or_buy {
+				IntentionType::SELL => intention.amount_sell,
+				IntentionType::BUY => cmp::min(intention.trade_limit, free_balance),
+			};
+
+			if budget == 0
+				|| free_balance < budget
+				|| T::Currency::transfer(intention.asset_sell, &intention.who, settlement, budget).is_err()
+			{
+				Self::deposit_event(RawEvent::InsufficientAssetBalanceEvent(
+					intention.who.clone(),
+					intention.asset_sell,
+					intention.sell_or_buy.clone(),
+					intention.intention_id,
+					Error::<T>::InsufficientAssetBalance.into(),
+				));
+				continue;
+			}
+
+			bids.push(Bid { intention, budget });
+		}
+
+		bids
+	}
+
+	fn refund_bid(settlement: &T::AccountId, bid: &Bid<T>) {
+		let _ = T::Currency::transfer(bid.intention.asset_sell, settlement, &bid.intention.who, bid.budget);
+	}
+
+	/// Refund all bids and return their intentions.
+	fn refund_bids(
+		settlement: &T::AccountId,
+		bids_1: Vec<Bid<T>>,
+		bids_2: Vec<Bid<T>>,
+	) -> (Vec<Intention<T>>, Vec<Intention<T>>) {
+		let refund = |bids: Vec<Bid<T>>| {
+			bids.into_iter()
+				.map(|bid| {
+					Self::refund_bid(settlement, &bid);
+					bid.intention.clone()
+				})
+				.collect::<Vec<_>>()
+		};
+
+		(refund(bids_1), refund(bids_2))
+	}
+
+	/// Amount paid and amount received by a bid at given price.
+	/// ```price``` is amount of asset_out per one unit of asset_in, ```is_in_side``` is true if the bid sells asset_in.
+	/// Paid amounts are rounded up, received amounts are rounded down.
+	fn bid_amounts(bid: &Bid<T>, price: Price, is_in_side: bool) -> Option<(Balance, Balance)> {
+		let intention = bid.intention;
+		match (&intention.sell_or_buy, is_in_side) {
+			(IntentionType::SELL, true) => {
+				Some((intention.amount_sell, mul_price(intention.amount_sell, price, false)?))
+			}
+			(IntentionType::SELL, false) => {
+				Some((intention.amount_sell, div_price(intention.amount_sell, price, false)?))
+			}
+			(IntentionType::BUY, true) => Some((div_price(intention.amount_buy, price, true)?, intention.amount_buy)),
+			(IntentionType::BUY, false) => Some((mul_price(intention.amount_buy, price, true)?, intention.amount_buy)),
+		}
+	}
+
+	fn totals(in_bids: &[Bid<T>], out_bids: &[Bid<T>], price: Price) -> Option<Totals> {
+		let mut totals = Totals::default();
+
+		for bid in in_bids.iter() {
+			let (pay_in, pay_out) = Self::bid_amounts(bid, price, true)?;
+			totals.in_paid = totals.in_paid.checked_add(pay_in)?;
+			totals.out_received = totals.out_received.checked_add(pay_out)?;
+		}
+
+		for bid in out_bids.iter() {
+			let (pay_in, pay_out) = Self::bid_amounts(bid, price, false)?;
+			totals.out_paid = totals.out_paid.checked_add(pay_in)?;
+			totals.in_received = totals.in_received.checked_add(pay_out)?;
+		}
+
+		Some(totals)
+	}
+
+	/// Check if auction can be settled at given price.
+	/// Returns amount of ```asset_in``` sold via AMM and minimum amount of ```asset_out``` needed from AMM.
+	fn settle_amounts(
+		settlement: &T::AccountId,
+		asset_in: AssetId,
+		asset_out: AssetId,
+		totals: &Totals,
+	) -> Option<(Balance, Balance)> {
+		let amm_amount_in = totals.in_paid.checked_sub(totals.in_received)?;
+		let amm_min_amount_out = totals.out_received.saturating_sub(totals.out_paid);
+
+		if amm_amount_in == 0 {
+			return if amm_min_amount_out == 0 { Some((0, 0)) } else { None };
+		}
+
+		T::AMMPool::validate_sell(
+			settlement,
+			asset_in,
+			asset_out,
+			amm_amount_in,
+			amm_min_amount_out,
+			false,
+		)
+		.ok()?;
+
+		Some((amm_amount_in, amm_min_amount_out))
+	}
+
+	/// Find clearing price of the auction.
+	///
+	/// Side with excess at current spot price sells the imbalance via AMM, so the clearing price is at or below the spot price
+	/// of its asset. The highest such price at which the auction can be settled is found by binary search,
+	/// limited to ```MAX_CLEARING_PRICE_ITERATIONS``` steps.
+	/// Returns the asset sold via AMM and the clearing price.
+	fn clear_batch_auction(
+		settlement: &T::AccountId,
+		asset_1: AssetId,
+		asset_2: AssetId,
+		bids_1: &[Bid<T>],
+		bids_2: &[Bid<T>],
+	) -> Option<(AssetId, Price)> {
+		let spot_price = |asset_in: AssetId, asset_out: AssetId| {
+			let unit = Price::accuracy();
+			Price::checked_from_rational(T::AMMPool::get_spot_price_unchecked(asset_in, asset_out, unit), unit)
+				.filter(|price| !price.is_zero())
+		};
+
+		let spot_1 = spot_price(asset_1, asset_2)?;
+		let totals = Self::totals(bids_1, bids_2, spot_1)?;
+
+		let (asset_in, asset_out, in_bids, out_bids, spot) = if totals.in_paid >= totals.in_received {
+			(asset_1, asset_2, bids_1, bids_2, spot_1)
+		} else {
+			(asset_2, asset_1, bids_2, bids_1, spot_price(asset_2, asset_1)?)
+		};
+
+		// Price is too high if there is an excess of asset_in which cannot be sold via AMM at this price.
+		let too_high = |price: Price| match Self::totals(in_bids, out_bids, price) {
+			Some(totals) => {
+				totals.in_paid >= totals.in_received
+					&& Self::settle_amounts(settlement, asset_in, asset_out, &totals).is_none()
+			}
+			None => false,
+		};
+
+		if !too_high(spot) {
+			return Some((asset_in, spot));
+		}
+
+		let mut low: u128 = 1;
+		let mut high = spot.into_inner();
+
+		if too_high(Price::from_inner(low)) {
+			return None;
+		}
+
+		let mut iterations: u32 = 0;
+
+		while high - low > 1 && iterations < MAX_CLEARING_PRICE_ITERATIONS {
+			iterations += 1;
+
+			let mid = low + (high - low) / 2;
+			if too_high(Price::from_inner(mid)) {
+				high = mid;
+			} else {
+				low = mid;
+			}
+		}
+
+		Some((asset_in, Price::from_inner(low)))
+	}
+
+	/// Sell the imbalance via AMM and pay out all participants at the clearing price.
+	///
+	/// Each participant pays direct trade fee from the received amount, for the part matched with the other side -
+	/// whole amount of the side without excess, the part not sold via AMM of the side with excess.
+	/// Rounding leftovers go to the pool. Transfers made before an error have to be reverted by the caller.
+	fn settle_batch_auction(
+		settlement: &T::AccountId,
+		pair_account: &T::AccountId,
+		clearing: &Clearing,
+		in_bids: &[Bid<T>],
+		out_bids: &[Bid<T>],
+	) -> Result<(), DispatchError> {
+		let amm_amount_out = if clearing.amm_amount_in > 0 {
+			let transfer = T::AMMPool::validate_sell(
+				settlement,
+				clearing.asset_in,
+				clearing.asset_out,
+				clearing.amm_amount_in,
+				clearing.amm_min_amount_out,
+				false,
+			)?;
+
+			T::AMMPool::execute_sell(&transfer)?;
+
+			Self::deposit_event(RawEvent::BatchAuctionCleared(
+				clearing.asset_in,
+				clearing.asset_out,
+				clearing.price,
+				transfer.amount,
+				transfer.amount_out,
+			));
+
+			transfer.amount_out
+		} else {
+			Self::deposit_event(RawEvent::BatchAuctionCleared(
+				clearing.asset_in,
+				clearing.asset_out,
+				clearing.price,
+				0,
+				0,
+			));
+
+			0
+		};
+
+		let totals = &clearing.totals;
+
+		for (bid, is_in_side) in in_bids
+			.iter()
+			.map(|bid| (bid, true))
+			.chain(out_bids.iter().map(|bid| (bid, false)))
+		{
+			let intention = bid.intention;
+
+			let (pay_in, pay_out) =
+				Self::bid_amounts(bid, clearing.price, is_in_side).ok_or(Error::<T>::AssetBalanceLimitExceeded)?;
+
+			let refund = bid.budget.saturating_sub(pay_in);
+
+			T::Currency::transfer(intention.asset_sell, settlement, &intention.who, refund)?;
+			T::Currency::transfer(intention.asset_buy, settlement, &intention.who, pay_out)?;
+
+			Self::deposit_event(RawEvent::IntentionResolvedBatchTrade(
+				intention.who.clone(),
+				intention.sell_or_buy.clone(),
+				intention.intention_id,
+				pay_in,
+				pay_out,
+			));
+
+			let matched = if is_in_side {
+				div_rational(
+					U256::from(pay_out) * U256::from(totals.in_received),
+					totals.in_paid,
+					false,
+				)
+			} else {
+				Some(pay_out)
+			};
+
+			let fee_rate = Self::direct_trade_fee_rate(intention.discount);
+			let fee = matched
+				.and_then(|matched| matched.just_fee(fee_rate))
+				.ok_or(Error::<T>::AssetBalanceLimitExceeded)?;
+
+			if !fee.is_zero() {
+				T::Currency::reserve(intention.asset_buy, &intention.who, fee)?;
+				Self::pay_direct_trade_fee(&intention.who, pair_account, intention.asset_buy, fee, fee_rate);
+			}
+
+			Self::record_fill(
+				intention.intention_id,
+				intention.asset_sell,
+				pay_in,
+				pay_out,
+				(intention.asset_buy, fee),
+			);
+		}
+
+		// Rounding leftovers are given to the pool.
+		let dust_in = totals
+			.in_paid
+			.checked_sub(clearing.amm_amount_in)
+			.and_then(|amount| amount.checked_sub(totals.in_received))
+			.ok_or(Error::<T>::AssetBalanceLimitExceeded)?;
+		let dust_out = totals
+			.out_paid
+			.checked_add(amm_amount_out)
+			.and_then(|amount| amount.checked_sub(totals.out_received))
+			.ok_or(Error::<T>::AssetBalanceLimitExceeded)?;
+
+		for (asset, dust) in [(clearing.asset_in, dust_in), (clearing.asset_out, dust_out)].iter() {
+			if !dust.is_zero() {
+				T::Currency::transfer(*asset, settlement, pair_account, *dust)?;
+			}
+		}
+
+		Ok(())
+	}
+}
@@ -1,7 +1,7 @@
 use super::*;
 use frame_support::traits::BalanceStatus;
 
-use primitives::fee::{Fee, WithFee};
+use primitives::fee::WithFee;
 
 /// Hold info about each transfer which has to be made to resolve a direct trade.
 pub struct Transfer<'a, T: Config> {
@@ -9,7 +9,8 @@ pub struct Transfer<'a, T: Config> {
 	pub to: &'a T::AccountId,
 	pub asset: AssetId,
 	pub amount: Balance,
-	pub fee_transfer: bool,
+	/// Direct trade fee rate, if the transfer pays a direct trade fee
+	pub fee_rate: Option<Fee>,
 }
 
 /// Hold info about a direct trade between two intentions.
@@ -50,7 +51,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 			to: &self.intention_b.who,
 			asset: self.intention_a.asset_sell,
 			amount: self.amount_from_a,
-			fee_transfer: false,
+			fee_rate: None,
 		};
 		self.transfers.push(transfer);
 		let transfer = Transfer::<T> {
@@ -58,14 +59,24 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 			to: &self.intention_a.who,
 			asset: self.intention_a.asset_buy,
 			amount: self.amount_from_b,
-			fee_transfer: false,
+			fee_rate: None,
 		};
 		self.transfers.push(transfer);
 
 		// Let's handle the fees now for registered transfers.
 
-		let fee_a = self.amount_from_a.just_fee(Fee::default());
-		let fee_b = self.amount_from_b.just_fee(Fee::default());
+		// Each intention pays the fee at its own rate.
+		// Fee of amount sold by A is paid by B only if both intentions sell.
+		let fee_rate_a = Module::<T>::direct_trade_fee_rate(self.intention_a.discount);
+		let fee_rate_b = Module::<T>::direct_trade_fee_rate(self.intention_b.discount);
+
+		let (rate_from_a, rate_from_b) = match (&self.intention_a.sell_or_buy, &self.intention_b.sell_or_buy) {
+			(IntentionType::SELL, IntentionType::SELL) => (fee_rate_b, fee_rate_a),
+			_ => (fee_rate_a, fee_rate_b),
+		};
+
+		let fee_a = self.amount_from_a.just_fee(rate_from_a);
+		let fee_b = self.amount_from_b.just_fee(rate_from_b);
 
 		if fee_a.is_none() || fee_b.is_none() {
 			return false;
@@ -95,7 +106,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 					to: pool_account,
 					asset: self.intention_a.asset_buy,
 					amount: transfer_b_fee,
-					fee_transfer: true,
+					fee_rate: Some(fee_rate_a),
 				};
 				self.transfers.push(transfer);
 
@@ -104,7 +115,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 					to: pool_account,
 					asset: self.intention_b.asset_buy,
 					amount: transfer_a_fee,
-					fee_transfer: true,
+					fee_rate: Some(fee_rate_b),
 				};
 				self.transfers.push(transfer);
 			}
@@ -121,7 +132,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 					to: pool_account,
 					asset: self.intention_a.asset_sell,
 					amount: transfer_a_fee,
-					fee_transfer: true,
+					fee_rate: Some(fee_rate_a),
 				};
 				self.transfers.push(transfer);
 
@@ -130,7 +141,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 					to: pool_account,
 					asset: self.intention_b.asset_sell,
 					amount: transfer_b_fee,
-					fee_transfer: true,
+					fee_rate: Some(fee_rate_b),
 				};
 				self.transfers.push(transfer);
 			}
@@ -147,7 +158,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 					to: pool_account,
 					asset: self.intention_a.asset_sell,
 					amount: transfer_a_fee,
-					fee_transfer: true,
+					fee_rate: Some(fee_rate_a),
 				};
 				self.transfers.push(transfer);
 
@@ -156,7 +167,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 					to: pool_account,
 					asset: self.intention_b.asset_buy,
 					amount: transfer_b_fee,
-					fee_transfer: true,
+					fee_rate: Some(fee_rate_b),
 				};
 				self.transfers.push(transfer);
 			}
@@ -173,7 +184,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 					to: pool_account,
 					asset: self.intention_a.asset_buy,
 					amount: transfer_a_fee,
-					fee_transfer: true,
+					fee_rate: Some(fee_rate_a),
 				};
 				self.transfers.push(transfer);
 
@@ -182,7 +193,7 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 					to: pool_account,
 					asset: self.intention_b.asset_sell,
 					amount: transfer_b_fee,
-					fee_transfer: true,
+					fee_rate: Some(fee_rate_b),
 				};
 				self.transfers.push(transfer);
 			}
@@ -196,6 +207,16 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 	pub fn execute(&self) -> bool {
 		self.send_direct_trade_resolve_event();
 		for transfer in &self.transfers {
+			if let Some(fee_rate) = transfer.fee_rate {
+				Module::<T>::pay_direct_trade_fee(
+					transfer.from,
+					transfer.to,
+					transfer.asset,
+					transfer.amount,
+					fee_rate,
+				);
+				continue;
+			}
 			T::Currency::repatriate_reserved(
 				transfer.asset,
 				transfer.from,
@@ -204,13 +225,36 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 				BalanceStatus::Free,
 			)
 			.expect("Cannot fail. Checks should have been done prior to this.");
-			if transfer.fee_transfer {
-				Self::send_trade_fee_event(transfer.from, transfer.to, transfer.asset, transfer.amount);
-			}
 		}
+		self.record_fills();
 		true
 	}
 
+	/// Add the trade to fills of both intentions.
+	/// Fee transfers are registered for intention A first, then for intention B.
+	fn record_fills(&self) {
+		let mut fees = self
+			.transfers
+			.iter()
+			.filter(|transfer| transfer.fee_rate.is_some())
+			.map(|transfer| (transfer.asset, transfer.amount));
+
+		Module::<T>::record_fill(
+			self.intention_a.intention_id,
+			self.intention_a.asset_sell,
+			self.amount_from_a,
+			self.amount_from_b,
+			fees.next().unwrap_or_default(),
+		);
+		Module::<T>::record_fill(
+			self.intention_b.intention_id,
+			self.intention_b.asset_sell,
+			self.amount_from_b,
+			self.amount_from_a,
+			fees.next().unwrap_or_default(),
+		);
+	}
+
 	/// Revert all reserverd amounts.
 	/// This does NOT revert transfers, only reserved amounts. So it can be only called if a preparation fails.
 	pub fn revert(&mut self) {
@@ -230,16 +274,6 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 		));
 	}
 
-	/// Send pallet event after a free is transferred.
-	fn send_trade_fee_event(from: &T::AccountId, to: &T::AccountId, asset: AssetId, amount: Balance) {
-		Module::<T>::deposit_event(RawEvent::IntentionResolvedDirectTradeFees(
-			from.clone(),
-			to.clone(),
-			asset,
-			amount,
-		));
-	}
-
 	/// Send event after successful direct trade.
 	fn send_direct_trade_resolve_event(&self) {
 		Module::<T>::deposit_event(RawEvent::IntentionResolvedDirectTrade(
@@ -257,3 +291,65 @@ impl<'a, T: Config> DirectTradeData<'a, T> {
 		T::Currency::reserve(asset, who, amount).is_ok()
 	}
 }
+
+/// Direct trade fee implementation
+impl<T: Config> Module<T> {
+	/// Direct trade fee rate of an intention.
+	///
+	/// Discounted intentions pay `DiscountedDirectTradeFee` if it is lower than `DirectTradeFee`.
+	pub(crate) fn direct_trade_fee_rate(discount: bool) -> Fee {
+		let fee = T::DirectTradeFee::get();
+		let discounted = T::DiscountedDirectTradeFee::get();
+
+		let is_lower = (discounted.numerator as u64) * (fee.denominator as u64)
+			< (fee.numerator as u64) * (discounted.denominator as u64);
+
+		if discount && is_lower {
+			discounted
+		} else {
+			fee
+		}
+	}
+
+	/// Pay direct trade fee reserved on `who`, charged at `fee_rate`.
+	///
+	/// `DirectTradeFeeShare` of the fee goes to `DirectTradeFeeReceiver`, the rest to the pool account of the traded asset pair.
+	pub(crate) fn pay_direct_trade_fee(
+		who: &T::AccountId,
+		pool_account: &T::AccountId,
+		asset: AssetId,
+		amount: Balance,
+		fee_rate: Fee,
+	) {
+		let receiver = T::DirectTradeFeeReceiver::get();
+		let receiver_fee = T::DirectTradeFeeShare::get() * amount;
+
+		for (to, fee) in [
+			(&receiver, receiver_fee),
+			(pool_account, amount.saturating_sub(receiver_fee)),
+		]
+		.iter()
+		{
+			if fee.is_zero() {
+				continue;
+			}
+
+			T::Currency::repatriate_reserved(asset, who, to, *fee, BalanceStatus::Free)
+				.expect("Cannot fail. Fee has been reserved.");
+
+			Self::deposit_event(RawEvent::IntentionResolvedDirectTradeFees(
+				who.clone(),
+				(*to).clone(),
+				asset,
+				*fee,
+			));
+			Self::deposit_event(RawEvent::DirectTradeFeePaid(
+				who.clone(),
+				(*to).clone(),
+				asset,
+				*fee,
+				fee_rate,
+			));
+		}
+	}
+}
@@ -0,0 +1,144 @@
+use super::*;
+use frame_support::sp_runtime::{FixedPointNumber, RuntimeDebug, TransactionOutcome};
+use frame_support::storage::with_transaction;
+use primitive_types::U256;
+
+/// Amounts traded by an intention in current block - sum of all its direct and AMM trades.
+#[derive(Encode, Decode, Default, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct IntentionFill {
+	pub amount_sold: Balance,
+	pub amount_bought: Balance,
+	/// Fees paid in sold asset
+	pub fee_sold: Balance,
+	/// Fees paid in bought asset
+	pub fee_bought: Balance,
+}
+
+/// Fill policy implementation
+impl<T: Config> Module<T> {
+	/// Add a trade to the fill of an intention.
+	/// Fee is counted in sold or bought asset of the intention according to fee asset.
+	pub(crate) fn record_fill(
+		intention_id: IntentionId<T>,
+		asset_sell: AssetId,
+		amount_sold: Balance,
+		amount_bought: Balance,
+		fee: (AssetId, Balance),
+	) {
+		<IntentionFills<T>>::mutate(intention_id, |fill| {
+			let fill = fill.get_or_insert_with(IntentionFill::default);
+
+			fill.amount_sold = fill.amount_sold.saturating_add(amount_sold);
+			fill.amount_bought = fill.amount_bought.saturating_add(amount_bought);
+
+			if fee.0 == asset_sell {
+				fill.fee_sold = fill.fee_sold.saturating_add(fee.1);
+			} else {
+				fill.fee_bought = fill.fee_bought.saturating_add(fee.1);
+			}
+		});
+	}
+
+	/// Amount of an intention filled so far in current block - sold amount of SELL, bought amount of BUY intention.
+	pub(crate) fn filled_amount(intention: &Intention<T>) -> Balance {
+		match Self::intention_fills(intention.intention_id) {
+			Some(fill) => match intention.sell_or_buy {
+				IntentionType::SELL => fill.amount_sold,
+				IntentionType::BUY => fill.amount_bought,
+			},
+			None => Balance::zero(),
+		}
+	}
+
+	/// Check if trade limit of an intention is satisfied by a partial trade.
+	/// Trade limit is applied proportionally to the traded part of the intention.
+	pub(crate) fn is_within_partial_limit(intention: &Intention<T>, sold: Balance, bought: Balance) -> bool {
+		match intention.sell_or_buy {
+			IntentionType::SELL => {
+				U256::from(bought) * U256::from(intention.amount_sell)
+					>= U256::from(intention.trade_limit) * U256::from(sold)
+			}
+			IntentionType::BUY => {
+				U256::from(sold) * U256::from(intention.amount_buy)
+					<= U256::from(intention.trade_limit) * U256::from(bought)
+			}
+		}
+	}
+
+	/// Resolve main intention, which cannot be partially filled, and its matched intentions.
+	///
+	/// All trades are reverted if the main intention is not completely filled. Matched intentions are then resolved via AMM.
+	pub(crate) fn resolve_matched_intentions_completely(
+		pair_account: &T::AccountId,
+		intention: &Intention<T>,
+		matched: &[Intention<T>],
+	) {
+		let required = Self::filled_amount(intention).saturating_add(match intention.sell_or_buy {
+			IntentionType::SELL => intention.amount_sell,
+			IntentionType::BUY => intention.amount_buy,
+		});
+
+		let filled = with_transaction(|| {
+			T::Resolver::resolve_matched_intentions(pair_account, intention, matched);
+
+			if Self::filled_amount(intention) >= required {
+				TransactionOutcome::Commit(true)
+			} else {
+				TransactionOutcome::Rollback(false)
+			}
+		});
+
+		if !filled {
+			Self::send_intention_error_event(intention, Error::<T>::IntentionNotFilled.into());
+
+			for matched_intention in matched.iter() {
+				T::Resolver::resolve_single_intention(matched_intention);
+			}
+		}
+	}
+
+	/// Report fills of resolved intentions.
+	///
+	/// ```IntentionFilled``` event is sent for each intention traded in current block.
+	/// All-or-none intention which has not been traded at all is queued for next blocks again,
+	/// at most ```MaxIntentionRequeues``` times - then it is cancelled.
+	pub(crate) fn report_fills(resolved: &[Intention<T>]) {
+		for intention in resolved.iter() {
+			let requeues = <IntentionRequeues<T>>::take(intention.intention_id);
+
+			match <IntentionFills<T>>::take(intention.intention_id) {
+				Some(fill) => {
+					let average_price =
+						Price::checked_from_rational(fill.amount_bought, fill.amount_sold).unwrap_or_else(Price::zero);
+
+					Self::deposit_event(RawEvent::IntentionFilled(
+						intention.who.clone(),
+						intention.sell_or_buy.clone(),
+						intention.intention_id,
+						fill.amount_sold,
+						fill.amount_bought,
+						average_price,
+						fill.fee_sold,
+						fill.fee_bought,
+					));
+				}
+				None if intention.fill_policy == FillPolicy::AllOrNone => {
+					if requeues >= T::MaxIntentionRequeues::get() {
+						Self::cancel_unresolved_intention(intention);
+						continue;
+					}
+
+					match Self::reserve_intention_amount(intention) {
+						Ok(_) => {
+							if Self::queue_intention(intention) {
+								<IntentionRequeues<T>>::insert(intention.intention_id, requeues.saturating_add(1));
+							}
+						}
+						Err(error) => Self::send_intention_error_event(intention, error),
+					}
+				}
+				None => {}
+			}
+		}
+	}
+}
@@ -1,15 +1,20 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::comparison_chain)]
 
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, storage::IterableStorageMap};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, dispatch, ensure,
+	storage::IterableStorageMap,
+	traits::{Get, IsSubType},
+};
 use frame_system::{self as system, ensure_signed};
 
-use codec::Encode;
-use sp_std::vec::Vec;
+use codec::{Decode, Encode};
+use sp_std::{marker::PhantomData, vec::Vec};
 
 use primitives::{
+	fee::{Fee, WithFee},
 	traits::{Resolver, AMM},
-	AssetId, Balance, ExchangeIntention, IntentionType,
+	AssetId, Balance, ExchangeIntention, FillPolicy, IntentionType, Price,
 };
 use sp_std::borrow::ToOwned;
 use sp_std::cmp;
@@ -21,7 +26,11 @@ use frame_support::weights::Weight;
 use primitives::traits::AMMTransfer;
 
 use frame_support::sp_runtime::offchain::storage_lock::BlockNumberProvider;
-use frame_support::sp_runtime::traits::Hash;
+use frame_support::sp_runtime::traits::{DispatchInfoOf, Hash, Saturating, SignedExtension, UniqueSaturatedInto, Zero};
+use frame_support::sp_runtime::transaction_validity::{
+	InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+};
+use frame_support::sp_runtime::{ModuleId, Permill};
 
 #[cfg(test)]
 mod mock;
@@ -30,13 +39,32 @@ pub mod weights;
 
 use weights::WeightInfo;
 
+mod batch_auction;
 mod direct;
+mod fill;
+mod limit_order;
+mod matching;
+mod ring;
+mod simulation;
 #[cfg(test)]
 mod tests;
 
+pub use fill::IntentionFill;
+pub use limit_order::{LimitOrder, LimitOrderOf, OrderId};
+pub use matching::{BatchAuction, GreedyBySize, MatchingStrategy, PriceTimePriority, ProRata};
+use ring::IntentionGroups;
+pub use ring::RingTradeLeg;
+pub use simulation::SimulatedTrade;
+
 /// Intention alias
 type IntentionId<T> = <T as system::Config>::Hash;
-pub type Intention<T> = ExchangeIntention<<T as system::Config>::AccountId, AssetId, Balance, IntentionId<T>>;
+pub type Intention<T> = ExchangeIntention<
+	<T as system::Config>::AccountId,
+	AssetId,
+	Balance,
+	IntentionId<T>,
+	<T as system::Config>::BlockNumber,
+>;
 
 /// The pallet's configuration trait.
 pub trait Config: system::Config {
@@ -54,6 +82,46 @@ pub trait Config: system::Config {
 
 	/// Weight information for the extrinsics.
 	type WeightInfo: WeightInfo;
+
+	/// Exchange module id - its account holds funds of batch auction participants during settlement
+	type ModuleId: Get<ModuleId>;
+
+	/// Strategy of matching intentions of each asset pair
+	type MatchingStrategy: MatchingStrategy<Self>;
+
+	/// Maximum number of intentions resolved in one block - the rest is queued for next blocks.
+	/// Limit orders and ring trades processed in a block count against this limit too.
+	type MaxIntentionsPerBlock: Get<u32>;
+
+	/// Maximum number of intentions queued for next blocks
+	type MaxQueuedIntentions: Get<u32>;
+
+	/// Maximum number of times an all-or-none intention which was not traded is queued again
+	type MaxIntentionRequeues: Get<u32>;
+
+	/// Fee rate paid by each side of a direct trade, apart from AMM trading fee
+	type DirectTradeFee: Get<Fee>;
+
+	/// Fee rate paid instead of `DirectTradeFee` by intentions with `discount`, if it is lower
+	type DiscountedDirectTradeFee: Get<Fee>;
+
+	/// Share of direct trade fees which goes to `DirectTradeFeeReceiver` - the rest goes to the pool (liquidity providers)
+	type DirectTradeFeeShare: Get<Permill>;
+
+	/// Account which receives its share of direct trade fees (e.g. treasury)
+	type DirectTradeFeeReceiver: Get<Self::AccountId>;
+
+	/// Maximum number of resting limit orders
+	type MaxLimitOrders: Get<u32>;
+
+	/// Maximum number of resting limit orders processed in one block - the rest is processed in next blocks
+	type MaxLimitOrdersPerBlock: Get<u32>;
+
+	/// Minimum amount sold by a limit order
+	type MinLimitOrderAmount: Get<Balance>;
+
+	/// Maximum number of blocks a limit order can rest for
+	type MaxLimitOrderExpiry: Get<Self::BlockNumber>;
 }
 
 // This pallet's storage items.
@@ -63,9 +131,51 @@ decl_storage! {
 		/// Current intention count for current block
 		ExchangeAssetsIntentionCount get(fn get_intentions_count): map hasher(blake2_128_concat) (AssetId, AssetId) => u32;
 
+		/// Number of intentions registered in current block, of all asset pairs
+		BlockIntentionCount get(fn block_intention_count): u32;
+
 		/// Registered intentions for current block
 		/// Always stored for ( asset_a, asset_b ) combination where asset_a < asset_B
 		ExchangeAssetsIntentions get(fn get_intentions): map hasher(blake2_128_concat) (AssetId, AssetId) => Vec<Intention<T>>;
+
+		/// Number of intentions registered for asset pair in current block, including cancelled ones.
+		/// Used to generate unique intention ids.
+		ExchangeAssetsIntentionNonce get(fn get_intentions_nonce): map hasher(blake2_128_concat) (AssetId, AssetId) => u32;
+
+		/// ( asset_sell, asset_buy ) key in ```ExchangeAssetsIntentions``` of registered intentions for current block
+		ExchangeIntentionKeys get(fn get_intention_key): map hasher(blake2_128_concat) IntentionId<T> => Option<(AssetId, AssetId)>;
+
+		/// Resting limit orders
+		LimitOrders get(fn limit_orders): map hasher(twox_64_concat) OrderId => Option<LimitOrderOf<T>>;
+
+		/// Limit orders of an account
+		AccountLimitOrders get(fn account_limit_orders): map hasher(blake2_128_concat) T::AccountId => Vec<OrderId>;
+
+		/// Number of resting limit orders
+		LimitOrderCount get(fn limit_order_count): u32;
+
+		/// Identifiers of resting limit orders in ascending order
+		LimitOrderIds get(fn limit_order_ids): Vec<OrderId>;
+
+		/// Identifier of the first limit order processed in next block
+		NextLimitOrderToProcess get(fn next_limit_order_to_process): OrderId;
+
+		/// Identifier of the next limit order
+		NextLimitOrderId get(fn next_limit_order_id): OrderId;
+
+		/// Intentions which were not resolved in their block because of the per block limit, oldest first.
+		/// Amounts of queued intentions stay reserved. Bounded by ```MaxQueuedIntentions```.
+		IntentionQueue get(fn intention_queue): Vec<Intention<T>>;
+
+		/// Number of times an all-or-none intention has been queued again because it was not traded
+		IntentionRequeues get(fn intention_requeues): map hasher(blake2_128_concat) IntentionId<T> => u32;
+
+		/// Amounts reserved for intentions waiting to be resolved.
+		/// Tracked per intention, so releasing them never touches amounts reserved by limit orders.
+		IntentionReserves get(fn intention_reserve): map hasher(blake2_128_concat) IntentionId<T> => Balance;
+
+		/// Amounts traded by intentions in current block
+		IntentionFills get(fn intention_fills): map hasher(blake2_128_concat) IntentionId<T> => Option<IntentionFill>;
 	}
 }
 
@@ -74,6 +184,7 @@ decl_event!(
 	pub enum Event<T>
 	where
 		AccountId = <T as system::Config>::AccountId,
+		BlockNumber = <T as system::Config>::BlockNumber,
 		IntentionID = IntentionId<T>,
 	{
 		/// Intention registered event
@@ -85,8 +196,15 @@ decl_event!(
 		IntentionResolvedAMMTrade(AccountId, IntentionType, IntentionID, Balance, Balance),
 
 		IntentionResolvedDirectTrade(AccountId, AccountId, IntentionID, IntentionID, Balance, Balance),
+
+		/// Direct trade fee transferred, emitted together with ```DirectTradeFeePaid```
+		/// who, destination, asset, amount
 		IntentionResolvedDirectTradeFees(AccountId, AccountId, AssetId, Balance),
 
+		/// Direct trade fee paid
+		/// who, destination, asset, amount, fee rate
+		DirectTradeFeePaid(AccountId, AccountId, AssetId, Balance, Fee),
+
 		InsufficientAssetBalanceEvent(AccountId, AssetId, IntentionType, IntentionID, dispatch::DispatchError),
 
 		//Note: This event can be used instead of AMMSellErrorEvent, AMMBuyErrorEvent
@@ -115,6 +233,63 @@ decl_event!(
 			IntentionID,
 			dispatch::DispatchError,
 		),
+
+		/// Intention cancelled
+		/// who, asset sell, asset buy, intention type, intention id
+		IntentionCancelled(AccountId, AssetId, AssetId, IntentionType, IntentionID),
+
+		/// Limit order placed
+		/// who, order id, asset sell, asset buy, amount, limit price, expiry
+		LimitOrderPlaced(AccountId, OrderId, AssetId, AssetId, Balance, Price, BlockNumber),
+
+		/// Limit order partially or completely filled
+		/// who, order id, amount sold, amount bought, amount remaining
+		LimitOrderFilled(AccountId, OrderId, Balance, Balance, Balance),
+
+		/// Limit order cancelled
+		/// who, order id, amount remaining
+		LimitOrderCancelled(AccountId, OrderId, Balance),
+
+		/// Limit order expired
+		/// who, order id, amount remaining
+		LimitOrderExpired(AccountId, OrderId, Balance),
+
+		/// Intention resolved as direct trade with a limit order
+		/// who, order owner, intention id, order id, amount sold, amount bought
+		IntentionResolvedLimitOrderTrade(AccountId, AccountId, IntentionID, OrderId, Balance, Balance),
+
+		/// Intention resolved in batch auction at uniform clearing price
+		/// who, intention type, intention id, amount sold, amount bought
+		IntentionResolvedBatchTrade(AccountId, IntentionType, IntentionID, Balance, Balance),
+
+		/// Batch auction of an asset pair cleared
+		/// asset sold via AMM, asset bought via AMM, clearing price, amount sold via AMM, amount bought via AMM
+		BatchAuctionCleared(AssetId, AssetId, Price, Balance, Balance),
+
+		/// Ring of intentions across asset pairs resolved as direct trade
+		/// legs of the ring - who, intention id, asset sold, asset bought, amount sold, amount bought, fee
+		IntentionsResolvedRingTrade(Vec<RingTradeLeg<AccountId, IntentionID>>),
+
+		/// Intention queued to be resolved in next blocks
+		/// who, intention id
+		IntentionQueued(AccountId, IntentionID),
+
+		/// Queued intention expired before it was resolved, its reserved amount is released
+		/// who, intention id
+		IntentionExpired(AccountId, IntentionID),
+
+		/// Intention filled in current block - sum of all its direct and AMM trades
+		/// who, intention type, intention id, amount sold, amount bought, average price ( bought per one unit sold ), fee paid in sold asset, fee paid in bought asset
+		IntentionFilled(
+			AccountId,
+			IntentionType,
+			IntentionID,
+			Balance,
+			Balance,
+			Price,
+			Balance,
+			Balance,
+		),
 	}
 );
 
@@ -135,6 +310,42 @@ decl_error! {
 
 		/// Limit exceeded
 		AssetBalanceLimitExceeded,
+
+		/// Deadline of the call has passed
+		DeadlineExpired,
+
+		/// Intention is not registered in current block
+		IntentionNotFound,
+
+		/// Intention belongs to another account
+		NotIntentionOwner,
+
+		/// Limit order amount is zero
+		ZeroLimitOrderAmount,
+
+		/// Limit price is zero
+		ZeroLimitPrice,
+
+		/// Expiry of the limit order is in the past or too far in the future
+		InvalidLimitOrderExpiry,
+
+		/// Limit order amount is below the minimum
+		InsufficientLimitOrderAmount,
+
+		/// Maximum number of resting limit orders reached
+		TooManyLimitOrders,
+
+		/// Limit order does not exist
+		LimitOrderNotFound,
+
+		/// Limit order belongs to another account
+		NotLimitOrderOwner,
+
+		/// Intention cannot be completely filled
+		IntentionNotFilled,
+
+		/// Maximum number of intentions waiting to be resolved reached
+		TooManyIntentions,
 	}
 }
 
@@ -148,7 +359,8 @@ decl_module! {
 
 		/// Create sell intention
 		/// Calculate current spot price, create an intention and store in ```ExchangeAssetsIntentions```
-		#[weight =  <T as Config>::WeightInfo::sell_intention() + <T as Config>::WeightInfo::on_finalize_for_one_sell_extrinsic() -  <T as Config>::WeightInfo::known_overhead_for_on_finalize()]
+		#[weight =  <T as Config>::WeightInfo::sell_intention() + <T as Config>::WeightInfo::on_finalize_for_one_sell_extrinsic() -  <T as Config>::WeightInfo::known_overhead_for_on_finalize()
+			+ <T::MatchingStrategy as MatchingStrategy<T>>::intention_weight()]
 		pub fn sell(
 			origin,
 			asset_sell: AssetId,
@@ -156,27 +368,27 @@ decl_module! {
 			amount_sell: Balance,
 			min_bought: Balance,
 			discount: bool,
+			fill_policy: FillPolicy,
+			deadline: Option<T::BlockNumber>,
 		)  -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 
+			Self::ensure_not_expired(deadline)?;
+			Self::ensure_intention_capacity()?;
+
 			ensure!(
 				T::AMMPool::exists(asset_sell, asset_buy),
 				Error::<T>::TokenPoolNotFound
 			);
 
-			ensure!(
-				T::Currency::free_balance(asset_sell, &who) >= amount_sell,
-				Error::<T>::InsufficientAssetBalance
-			);
-
 			let amount_buy = T::AMMPool::get_spot_price_unchecked(asset_sell, asset_buy, amount_sell);
 
 			let asset_1 = cmp::min(asset_sell, asset_buy);
 			let asset_2 = cmp::max(asset_sell, asset_buy);
 
-			let intention_count = ExchangeAssetsIntentionCount::get((asset_1, asset_2));
+			let intention_nonce = ExchangeAssetsIntentionNonce::get((asset_1, asset_2));
 
-			let intention_id = Self::generate_intention_id(&who, intention_count, asset_1, asset_2);
+			let intention_id = Self::generate_intention_id(&who, intention_nonce, asset_1, asset_2);
 
 			let intention = Intention::<T> {
 					who: who.clone(),
@@ -187,15 +399,22 @@ decl_module! {
 					discount,
 					sell_or_buy : IntentionType::SELL,
 					intention_id,
-					trade_limit: min_bought
+					trade_limit: min_bought,
+					fill_policy,
+					deadline,
 			};
 
+			Self::reserve_intention_amount(&intention)?;
+
 			<ExchangeAssetsIntentions<T>>::append((intention.asset_sell, intention.asset_buy), intention.clone());
+			<ExchangeIntentionKeys<T>>::insert(intention.intention_id, (intention.asset_sell, intention.asset_buy));
 
 			let asset_1 = cmp::min(intention.asset_sell, intention.asset_buy);
 			let asset_2 = cmp::max(intention.asset_sell, intention.asset_buy);
 
 			ExchangeAssetsIntentionCount::mutate((asset_1,asset_2), |total| *total += 1u32);
+			ExchangeAssetsIntentionNonce::mutate((asset_1,asset_2), |nonce| *nonce += 1u32);
+			BlockIntentionCount::mutate(|total| *total += 1u32);
 
 			Self::deposit_event(RawEvent::IntentionRegistered(who, asset_sell, asset_buy, amount_sell, IntentionType::SELL, intention.intention_id));
 
@@ -204,7 +423,8 @@ decl_module! {
 
 		/// Create buy intention
 		/// Calculate current spot price, create an intention and store in ```ExchangeAssetsIntentions```
-		#[weight =  <T as Config>::WeightInfo::buy_intention() + <T as Config>::WeightInfo::on_finalize_for_one_buy_extrinsic() -  <T as Config>::WeightInfo::known_overhead_for_on_finalize()]
+		#[weight =  <T as Config>::WeightInfo::buy_intention() + <T as Config>::WeightInfo::on_finalize_for_one_buy_extrinsic() -  <T as Config>::WeightInfo::known_overhead_for_on_finalize()
+			+ <T::MatchingStrategy as MatchingStrategy<T>>::intention_weight()]
 		pub fn buy(
 			origin,
 			asset_buy: AssetId,
@@ -212,9 +432,14 @@ decl_module! {
 			amount_buy: Balance,
 			max_sold: Balance,
 			discount: bool,
+			fill_policy: FillPolicy,
+			deadline: Option<T::BlockNumber>,
 		)  -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 
+			Self::ensure_not_expired(deadline)?;
+			Self::ensure_intention_capacity()?;
+
 			ensure!(
 				T::AMMPool::exists(asset_sell, asset_buy),
 				Error::<T>::TokenPoolNotFound
@@ -222,17 +447,12 @@ decl_module! {
 
 			let amount_sell = T::AMMPool::get_spot_price_unchecked(asset_buy, asset_sell, amount_buy);
 
-			ensure!(
-				T::Currency::free_balance(asset_sell, &who) >= amount_sell,
-				Error::<T>::InsufficientAssetBalance
-			);
-
 			let asset_1 = cmp::min(asset_sell, asset_buy);
 			let asset_2 = cmp::max(asset_sell, asset_buy);
 
-			let intention_count = ExchangeAssetsIntentionCount::get((asset_1, asset_2));
+			let intention_nonce = ExchangeAssetsIntentionNonce::get((asset_1, asset_2));
 
-			let intention_id = Self::generate_intention_id(&who, intention_count, asset_1, asset_2);
+			let intention_id = Self::generate_intention_id(&who, intention_nonce, asset_1, asset_2);
 
 			let intention = Intention::<T> {
 					who: who.clone(),
@@ -243,50 +463,413 @@ decl_module! {
 					sell_or_buy: IntentionType::BUY,
 					discount,
 					intention_id,
-					trade_limit: max_sold
+					trade_limit: max_sold,
+					fill_policy,
+					deadline,
 			};
 
+			Self::reserve_intention_amount(&intention)?;
+
 			<ExchangeAssetsIntentions<T>>::append((intention.asset_sell, intention.asset_buy), intention.clone());
+			<ExchangeIntentionKeys<T>>::insert(intention.intention_id, (intention.asset_sell, intention.asset_buy));
 
 			ExchangeAssetsIntentionCount::mutate((asset_1,asset_2), |total| *total += 1u32);
+			ExchangeAssetsIntentionNonce::mutate((asset_1,asset_2), |nonce| *nonce += 1u32);
+			BlockIntentionCount::mutate(|total| *total += 1u32);
 
 			Self::deposit_event(RawEvent::IntentionRegistered(who, asset_buy, asset_sell, amount_buy, IntentionType::BUY, intention.intention_id));
 
 			Ok(())
 		}
 
+		/// Cancel intention registered in current block or queued from previous blocks
+		/// Remove the intention from ```ExchangeAssetsIntentions``` or ```IntentionQueue``` so it is not resolved in ```on_finalize```.
+		/// Both are decoded and encoded whole, so the weight is charged for the longer of them.
+		#[weight = <T as Config>::WeightInfo::cancel_intention(cmp::max(T::MaxQueuedIntentions::get(), T::MaxIntentionsPerBlock::get()))]
+		pub fn cancel_intention(origin, intention_id: IntentionId<T>) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let intention = match Self::get_intention_key(intention_id) {
+				Some((asset_sell, asset_buy)) => {
+					let mut intentions = Self::get_intentions((asset_sell, asset_buy));
+
+					let idx = intentions
+						.iter()
+						.position(|intention| intention.intention_id == intention_id)
+						.ok_or(Error::<T>::IntentionNotFound)?;
+
+					ensure!(intentions[idx].who == who, Error::<T>::NotIntentionOwner);
+
+					let intention = intentions.remove(idx);
+
+					<ExchangeAssetsIntentions<T>>::insert((asset_sell, asset_buy), intentions);
+					<ExchangeIntentionKeys<T>>::remove(intention_id);
+
+					let asset_1 = cmp::min(asset_sell, asset_buy);
+					let asset_2 = cmp::max(asset_sell, asset_buy);
+
+					ExchangeAssetsIntentionCount::mutate((asset_1,asset_2), |total| *total = total.saturating_sub(1u32));
+					BlockIntentionCount::mutate(|total| *total = total.saturating_sub(1u32));
+
+					intention
+				}
+				None => {
+					let mut queue = Self::intention_queue();
+
+					let idx = queue
+						.iter()
+						.position(|intention| intention.intention_id == intention_id)
+						.ok_or(Error::<T>::IntentionNotFound)?;
+
+					ensure!(queue[idx].who == who, Error::<T>::NotIntentionOwner);
+
+					let intention = queue.remove(idx);
+
+					<IntentionQueue<T>>::put(queue);
+					<IntentionRequeues<T>>::remove(intention_id);
+
+					intention
+				}
+			};
+
+			Self::release_intention_amount(&intention);
+
+			Self::deposit_event(RawEvent::IntentionCancelled(who, intention.asset_sell, intention.asset_buy, intention.sell_or_buy, intention_id));
+
+			Ok(())
+		}
+
+		/// Place limit order
+		/// Reserve ```amount_sell``` and keep the order across blocks until it is filled, expires or is cancelled.
+		/// ```limit_price``` is the minimum amount of ```asset_buy``` received for one unit of ```asset_sell```.
+		#[weight = <T as Config>::WeightInfo::place_limit_order()]
+		pub fn place_limit_order(
+			origin,
+			asset_sell: AssetId,
+			asset_buy: AssetId,
+			amount_sell: Balance,
+			limit_price: Price,
+			expiry: T::BlockNumber,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			ensure!(amount_sell > 0, Error::<T>::ZeroLimitOrderAmount);
+			ensure!(amount_sell >= T::MinLimitOrderAmount::get(), Error::<T>::InsufficientLimitOrderAmount);
+			ensure!(!limit_price.is_zero(), Error::<T>::ZeroLimitPrice);
+
+			let now = <system::Module<T>>::block_number();
+			ensure!(
+				now <= expiry && expiry <= now.saturating_add(T::MaxLimitOrderExpiry::get()),
+				Error::<T>::InvalidLimitOrderExpiry
+			);
+
+			ensure!(
+				Self::limit_order_count() < T::MaxLimitOrders::get(),
+				Error::<T>::TooManyLimitOrders
+			);
+
+			ensure!(
+				T::AMMPool::exists(asset_sell, asset_buy),
+				Error::<T>::TokenPoolNotFound
+			);
+
+			let order_id = Self::next_limit_order_id();
+			let next_order_id = order_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
+
+			T::Currency::reserve(asset_sell, &who, amount_sell).map_err(|_| Error::<T>::InsufficientAssetBalance)?;
+
+			let order = LimitOrderOf::<T> {
+				who: who.clone(),
+				asset_sell,
+				asset_buy,
+				amount_sell,
+				limit_price,
+				expiry,
+			};
+
+			<LimitOrders<T>>::insert(order_id, order);
+			<AccountLimitOrders<T>>::append(&who, order_id);
+			LimitOrderIds::append(order_id);
+			LimitOrderCount::mutate(|count| *count += 1);
+			NextLimitOrderId::put(next_order_id);
+
+			Self::deposit_event(RawEvent::LimitOrderPlaced(who, order_id, asset_sell, asset_buy, amount_sell, limit_price, expiry));
+
+			Ok(())
+		}
+
+		/// Cancel limit order
+		/// Remove the order and release its remaining reserved amount.
+		#[weight = <T as Config>::WeightInfo::cancel_limit_order()]
+		pub fn cancel_limit_order(origin, order_id: OrderId) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let order = Self::limit_orders(order_id).ok_or(Error::<T>::LimitOrderNotFound)?;
+
+			ensure!(order.who == who, Error::<T>::NotLimitOrderOwner);
+
+			Self::remove_limit_order(order_id, &order);
+
+			Self::deposit_event(RawEvent::LimitOrderCancelled(who, order_id, order.amount_sell));
+
+			Ok(())
+		}
+
 		fn on_initialize() -> Weight {
+			// Resolution of intentions registered in this block is paid by their calls, queued intentions are paid here.
+			let queued = cmp::min(
+				<IntentionQueue<T>>::decode_len().unwrap_or(0) as Weight,
+				T::MaxIntentionsPerBlock::get() as Weight,
+			);
+
+			let resolve_intention = T::WeightInfo::on_finalize_for_one_sell_extrinsic()
+				.saturating_sub(T::WeightInfo::known_overhead_for_on_finalize())
+				.saturating_add(T::MatchingStrategy::intention_weight());
+
+			let limit_orders = cmp::min(LimitOrderCount::get(), T::MaxLimitOrdersPerBlock::get()) as Weight;
+
 			T::WeightInfo::known_overhead_for_on_finalize()
+				.saturating_add(Self::ring_matching_weight())
+				.saturating_add(T::WeightInfo::resolve_limit_order().saturating_mul(limit_orders))
+				.saturating_add(resolve_intention.saturating_mul(queued))
 		}
 
 		/// Finalize and resolve all registered intentions.
 		/// Group/match intentions which can be directly traded.
+		/// Rings of intentions across asset pairs are traded directly first.
+		/// Intentions are then matched with resting limit orders, limit orders left are filled via AMM.
+		/// At most ```MaxLimitOrdersPerBlock``` limit orders are processed in a block, in turns.
+		/// Remaining intentions of each pair are matched by ```MatchingStrategy``` configured in runtime.
+		/// Limit orders, intentions and ring trades together are limited by ```MaxIntentionsPerBlock```.
 		fn on_finalize(){
+			let now = <system::Module<T>>::block_number();
+
+			let max_intentions = T::MaxIntentionsPerBlock::get() as usize;
+
+			let mut limit_orders = Self::load_limit_orders(now, max_intentions);
+
+			// Queued intentions are resolved first, intentions over the per block limit are queued for next blocks.
+			let mut to_resolve = Self::take_queued_intentions(now);
 
-			for ((asset_1,asset_2), count) in ExchangeAssetsIntentionCount::iter() {
+			for ((asset_1, asset_2), count) in ExchangeAssetsIntentionCount::iter() {
 				// If no intention registered for asset1/2, move onto next one
 				if count == 0u32 {
 					continue;
 				}
 
+				to_resolve.extend(<ExchangeAssetsIntentions<T>>::get((asset_2, asset_1)));
+				to_resolve.extend(<ExchangeAssetsIntentions<T>>::get((asset_1, asset_2)));
+			}
+
+			let max_intentions = max_intentions.saturating_sub(limit_orders.len());
+
+			if to_resolve.len() > max_intentions {
+				let queue = to_resolve.split_off(max_intentions);
+
+				for intention in queue.iter() {
+					Self::queue_intention(intention);
+				}
+			}
+
+			let mut pairs = Vec::<(AssetId, AssetId)>::new();
+			let mut intentions = IntentionGroups::<T>::new();
+
+			for intention in to_resolve.iter() {
+				let pair = (
+					cmp::min(intention.asset_sell, intention.asset_buy),
+					cmp::max(intention.asset_sell, intention.asset_buy),
+				);
+
+				if !pairs.contains(&pair) {
+					pairs.push(pair);
+				}
+
+				// Amounts reserved at registration are released right before the intentions are resolved.
+				// Nothing else can be executed in between, so resolution can rely on the funds being available.
+				Self::release_intention_amount(intention);
+
+				intentions
+					.entry((intention.asset_sell, intention.asset_buy))
+					.or_insert_with(Vec::new)
+					.push(intention.clone());
+			}
+
+			Self::match_rings(&mut intentions, max_intentions.saturating_sub(to_resolve.len()));
+
+			for (asset_1, asset_2) in pairs {
 				let pair_account = T::AMMPool::get_pair_id(&asset_1, &asset_2);
 
-				let asset_a_sells = <ExchangeAssetsIntentions<T>>::get((asset_2, asset_1));
-				let asset_b_sells = <ExchangeAssetsIntentions<T>>::get((asset_1, asset_2));
+				let mut asset_a_sells = intentions.remove(&(asset_2, asset_1)).unwrap_or_default();
+				let mut asset_b_sells = intentions.remove(&(asset_1, asset_2)).unwrap_or_default();
 
-				//TODO: we can short circuit here if nothing in asset_b_sells and just resolve asset a sells.
+				Self::match_limit_orders(&pair_account, &mut limit_orders, &mut asset_a_sells);
+				Self::match_limit_orders(&pair_account, &mut limit_orders, &mut asset_b_sells);
 
-				Self::process_exchange_intentions(&pair_account, &asset_a_sells, &asset_b_sells);
+				T::MatchingStrategy::match_intentions(&pair_account, asset_1, asset_2, &asset_b_sells, &asset_a_sells);
 			}
 
+			Self::report_fills(&to_resolve);
+
+			Self::resolve_limit_orders(limit_orders, now);
+
 			ExchangeAssetsIntentionCount::remove_all();
+			BlockIntentionCount::kill();
+			ExchangeAssetsIntentionNonce::remove_all();
 			ExchangeAssetsIntentions::<T>::remove_all();
+			ExchangeIntentionKeys::<T>::remove_all();
+			IntentionFills::<T>::remove_all();
 		}
 	}
 }
 
 // "Internal" functions, callable by code.
 impl<T: Config> Module<T> {
+	/// Ensure that the current block is not after `deadline`, if any.
+	fn ensure_not_expired(deadline: Option<T::BlockNumber>) -> dispatch::DispatchResult {
+		if let Some(deadline) = deadline {
+			ensure!(
+				<system::Module<T>>::block_number() <= deadline,
+				Error::<T>::DeadlineExpired
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Number of intentions queued to be resolved in next blocks.
+	pub fn queue_depth() -> u32 {
+		<IntentionQueue<T>>::decode_len().unwrap_or(0) as u32
+	}
+
+	/// Ensure that a new intention fits into current block or the queue.
+	fn ensure_intention_capacity() -> dispatch::DispatchResult {
+		let pending = Self::block_intention_count().saturating_add(Self::queue_depth());
+		let capacity = T::MaxIntentionsPerBlock::get().saturating_add(T::MaxQueuedIntentions::get());
+
+		ensure!(pending < capacity, Error::<T>::TooManyIntentions);
+
+		Ok(())
+	}
+
+	/// Take intentions queued from previous blocks.
+	/// Intentions whose deadline has passed are dropped and their reserved amounts released.
+	fn take_queued_intentions(now: T::BlockNumber) -> Vec<Intention<T>> {
+		let mut queue = <IntentionQueue<T>>::take();
+
+		queue.retain(|intention| match intention.deadline {
+			Some(deadline) if now > deadline => {
+				Self::release_intention_amount(intention);
+				<IntentionRequeues<T>>::remove(intention.intention_id);
+				Self::deposit_event(RawEvent::IntentionExpired(
+					intention.who.clone(),
+					intention.intention_id,
+				));
+				false
+			}
+			_ => true,
+		});
+
+		queue
+	}
+
+	/// Queue intention to be resolved in next blocks. Its amount has to be reserved.
+	/// If the queue is full, the intention is cancelled and its reserved amount released.
+	/// Returns true if the intention has been queued.
+	pub(crate) fn queue_intention(intention: &Intention<T>) -> bool {
+		if Self::queue_depth() < T::MaxQueuedIntentions::get() {
+			<IntentionQueue<T>>::append(intention);
+
+			Self::deposit_event(RawEvent::IntentionQueued(intention.who.clone(), intention.intention_id));
+
+			true
+		} else {
+			Self::cancel_unresolved_intention(intention);
+
+			false
+		}
+	}
+
+	/// Cancel intention which cannot be resolved and release its reserved amount.
+	pub(crate) fn cancel_unresolved_intention(intention: &Intention<T>) {
+		Self::release_intention_amount(intention);
+
+		Self::deposit_event(RawEvent::IntentionCancelled(
+			intention.who.clone(),
+			intention.asset_sell,
+			intention.asset_buy,
+			intention.sell_or_buy.clone(),
+			intention.intention_id,
+		));
+	}
+
+	/// Intentions of an asset pair, in both directions, waiting to be resolved.
+	/// Queued intentions go first, then intentions registered in current block.
+	/// Intentions of extrinsics not included in a block yet are added by `with_pending_calls`.
+	pub fn pending_intentions(asset_a: AssetId, asset_b: AssetId) -> Vec<Intention<T>> {
+		let mut intentions: Vec<Intention<T>> = <IntentionQueue<T>>::get()
+			.into_iter()
+			.filter(|intention| {
+				(intention.asset_sell, intention.asset_buy) == (asset_a, asset_b)
+					|| (intention.asset_sell, intention.asset_buy) == (asset_b, asset_a)
+			})
+			.collect();
+
+		intentions.extend(<ExchangeAssetsIntentions<T>>::get((asset_a, asset_b)));
+		intentions.extend(<ExchangeAssetsIntentions<T>>::get((asset_b, asset_a)));
+
+		intentions
+	}
+
+	/// Intentions of an account waiting to be resolved.
+	/// Queued intentions go first, then intentions registered in current block.
+	/// Intentions of extrinsics not included in a block yet are added by `with_pending_calls`.
+	pub fn intentions_of(who: &T::AccountId) -> Vec<Intention<T>> {
+		let mut intentions: Vec<Intention<T>> = <IntentionQueue<T>>::get()
+			.into_iter()
+			.filter(|intention| &intention.who == who)
+			.collect();
+
+		intentions.extend(
+			<ExchangeAssetsIntentions<T>>::iter()
+				.flat_map(|(_, pair_intentions)| pair_intentions.into_iter())
+				.filter(|intention| &intention.who == who),
+		);
+
+		intentions
+	}
+
+	/// Amount reserved for an intention from its registration until it is resolved - max sold amount plus fee headroom.
+	/// Max sold amount is the sold amount of SELL intention and the trade limit of BUY intention.
+	fn intention_reserve_amount(intention: &Intention<T>) -> Option<Balance> {
+		let amount = match intention.sell_or_buy {
+			IntentionType::SELL => intention.amount_sell,
+			IntentionType::BUY => intention.trade_limit,
+		};
+
+		amount.checked_add(amount.just_fee(T::DirectTradeFee::get())?)
+	}
+
+	/// Reserve max sold amount of an intention.
+	fn reserve_intention_amount(intention: &Intention<T>) -> dispatch::DispatchResult {
+		let amount = Self::intention_reserve_amount(intention).ok_or(Error::<T>::InsufficientAssetBalance)?;
+
+		T::Currency::reserve(intention.asset_sell, &intention.who, amount)
+			.map_err(|_| Error::<T>::InsufficientAssetBalance)?;
+
+		<IntentionReserves<T>>::insert(intention.intention_id, amount);
+
+		Ok(())
+	}
+
+	/// Release amount reserved for an intention.
+	fn release_intention_amount(intention: &Intention<T>) {
+		let amount = <IntentionReserves<T>>::take(intention.intention_id);
+
+		if !amount.is_zero() {
+			T::Currency::unreserve(intention.asset_sell, &intention.who, amount);
+		}
+	}
+
 	/// Process intentions and attempt to match them so they can be direct traded.
 	/// ```sell_a_intentions``` are considered 'main' intentions.
 	///
@@ -294,7 +877,7 @@ impl<T: Config> Module<T> {
 	/// satisfying  that sum( sell_b_intentions.amount_sell ) <= sell_a_intention.amount_sell
 	///
 	/// Intention A must be valid - that means that it is verified first by validating if it was possible to do AMM trade.
-	fn process_exchange_intentions(
+	pub(crate) fn process_exchange_intentions(
 		pair_account: &T::AccountId,
 		sell_a_intentions: &[Intention<T>],
 		sell_b_intentions: &[Intention<T>],
@@ -305,7 +888,19 @@ impl<T: Config> Module<T> {
 		b_copy.sort_by(|a, b| b.amount_sell.cmp(&a.amount_sell));
 		a_copy.sort_by(|a, b| b.amount_sell.cmp(&a.amount_sell));
 
-		for intention in a_copy {
+		Self::match_intentions_in_order(pair_account, a_copy, b_copy);
+	}
+
+	/// Match each of ```sell_a_intentions``` with ```sell_b_intentions``` in given order and resolve them.
+	///
+	/// Intentions from ```sell_b_intentions``` are matched with an intention A until their amount covers the amount of intention A.
+	/// Whatever is left in ```sell_b_intentions``` is resolved via AMM.
+	pub(crate) fn match_intentions_in_order(
+		pair_account: &T::AccountId,
+		sell_a_intentions: Vec<Intention<T>>,
+		mut sell_b_intentions: Vec<Intention<T>>,
+	) {
+		for intention in sell_a_intentions {
 			if !Self::verify_intention(&intention) {
 				continue;
 			}
@@ -314,10 +909,10 @@ impl<T: Config> Module<T> {
 			let mut total = 0;
 			let mut idx: usize = 0;
 
-			while let Some(matched) = b_copy.get(idx) {
+			while let Some(matched) = sell_b_intentions.get(idx) {
 				bvec.push(matched.clone());
 				total += matched.amount_sell;
-				b_copy.remove(idx);
+				sell_b_intentions.remove(idx);
 				idx += 1;
 
 				if total >= intention.amount_sell {
@@ -325,11 +920,14 @@ impl<T: Config> Module<T> {
 				}
 			}
 
-			T::Resolver::resolve_matched_intentions(pair_account, &intention, &bvec);
+			match intention.fill_policy {
+				FillPolicy::PartialFill => T::Resolver::resolve_matched_intentions(pair_account, &intention, &bvec),
+				_ => Self::resolve_matched_intentions_completely(pair_account, &intention, &bvec),
+			}
 		}
 
 		// If something left in sell_b_intentions, just run it throught AMM.
-		while let Some(b_intention) = b_copy.pop() {
+		while let Some(b_intention) = sell_b_intentions.pop() {
 			T::Resolver::resolve_single_intention(&b_intention);
 		}
 	}
@@ -353,6 +951,14 @@ impl<T: Config> Module<T> {
 					transfer.amount,
 					transfer.amount_out,
 				));
+
+				Self::record_fill(
+					intention_id,
+					transfer.asset_sell,
+					transfer.amount,
+					transfer.amount_out,
+					transfer.fee,
+				);
 			}
 			IntentionType::BUY => {
 				T::AMMPool::execute_buy(transfer)?;
@@ -364,6 +970,14 @@ impl<T: Config> Module<T> {
 					transfer.amount,
 					transfer.amount_out,
 				));
+
+				Self::record_fill(
+					intention_id,
+					transfer.asset_sell,
+					transfer.amount_out,
+					transfer.amount,
+					transfer.fee,
+				);
 			}
 		};
 
@@ -596,6 +1210,19 @@ impl<T: Config> Resolver<T::AccountId, Intention<T>, Error<T>> for Module<T> {
 					Ok(x) => x,
 					Err(error) => {
 						Self::send_intention_error_event(&matched_intention, error);
+
+						// Matched intention which allows partial fill is at least traded directly.
+						if matched_intention.fill_policy == FillPolicy::PartialFill
+							&& Self::is_within_partial_limit(&matched_intention, dt.amount_from_b, dt.amount_from_a)
+						{
+							match dt.prepare(pair_account) {
+								true => {
+									dt.execute();
+									intention_copy.amount_sell = 0;
+								}
+								false => dt.revert(),
+							}
+						}
 						continue;
 					}
 				};
@@ -714,3 +1341,82 @@ impl<T: Config> Resolver<T::AccountId, Intention<T>, Error<T>> for Module<T> {
 		}
 	}
 }
+
+impl<T: Config> Call<T> {
+	/// Return deadline of the call, if the call accepts one and it is set.
+	pub fn deadline(&self) -> Option<T::BlockNumber> {
+		match self {
+			Call::sell(.., deadline) | Call::buy(.., deadline) => *deadline,
+			_ => None,
+		}
+	}
+}
+
+/// Signed extension which rejects exchange intentions with expired deadline already in transaction validation,
+/// so they are dropped from the transaction pool.
+///
+/// Valid calls with a deadline are given longevity which ends at the deadline.
+///
+/// Only top-level exchange calls are checked. Calls nested in other calls (e.g. batches or proxies) pass
+/// validation and are rejected by the deadline check of the call itself when dispatched.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckDeadline<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckDeadline<T> {
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckDeadline<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> sp_std::fmt::Debug for CheckDeadline<T> {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckDeadline")
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckDeadline<T>
+where
+	<T as system::Config>::Call: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckExchangeDeadline";
+	type AccountId = T::AccountId;
+	type Call = <T as system::Config>::Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		match call.is_sub_type().and_then(|call| call.deadline()) {
+			Some(deadline) => {
+				let current_block = <system::Module<T>>::block_number();
+
+				if current_block > deadline {
+					return InvalidTransaction::Stale.into();
+				}
+
+				let longevity: u64 = (deadline - current_block).unique_saturated_into();
+
+				Ok(ValidTransaction {
+					longevity: longevity.saturating_add(1),
+					..Default::default()
+				})
+			}
+			None => Ok(ValidTransaction::default()),
+		}
+	}
+}
@@ -0,0 +1,313 @@
+use super::*;
+use frame_support::sp_runtime::{DispatchError, FixedPointNumber, RuntimeDebug, TransactionOutcome};
+use frame_support::storage::with_transaction;
+use frame_support::traits::BalanceStatus;
+
+use primitives::fee::WithFee;
+
+/// Limit order identifier
+pub type OrderId = u64;
+
+/// Resting limit order which is kept across blocks until it is filled, expires or is cancelled.
+/// Remaining ```amount_sell``` is reserved on owner's account.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct LimitOrder<AccountId, BlockNumber> {
+	pub who: AccountId,
+	pub asset_sell: AssetId,
+	pub asset_buy: AssetId,
+	/// Amount of ```asset_sell``` which is still to be sold
+	pub amount_sell: Balance,
+	/// Minimum amount of ```asset_buy``` received for one unit of ```asset_sell```
+	pub limit_price: Price,
+	/// Last block in which the order can be filled
+	pub expiry: BlockNumber,
+}
+
+impl<AccountId, BlockNumber> LimitOrder<AccountId, BlockNumber> {
+	/// Minimum amount of ```asset_buy``` which has to be received for given amount of ```asset_sell```.
+	pub fn min_amount_buy(&self, amount_sell: Balance) -> Option<Balance> {
+		self.limit_price.checked_mul_int(amount_sell)
+	}
+}
+
+pub type LimitOrderOf<T> = LimitOrder<<T as system::Config>::AccountId, <T as system::Config>::BlockNumber>;
+
+/// Limit order book implementation
+impl<T: Config> Module<T> {
+	/// Load resting limit orders processed in current block, oldest first.
+	///
+	/// At most ```MaxLimitOrdersPerBlock``` orders and not more than ```max_orders``` are loaded, starting where the previous
+	/// block stopped. Orders which expired while waiting for their turn are removed.
+	pub(crate) fn load_limit_orders(now: T::BlockNumber, max_orders: usize) -> Vec<(OrderId, LimitOrderOf<T>)> {
+		let order_ids = Self::limit_order_ids();
+
+		let count = cmp::min(
+			order_ids.len(),
+			cmp::min(T::MaxLimitOrdersPerBlock::get() as usize, max_orders),
+		);
+		let start = order_ids
+			.iter()
+			.position(|order_id| *order_id >= Self::next_limit_order_to_process())
+			.unwrap_or(0);
+
+		let mut to_process = order_ids
+			.iter()
+			.cycle()
+			.skip(start)
+			.take(count)
+			.copied()
+			.collect::<Vec<_>>();
+
+		if let Some(last) = to_process.last() {
+			NextLimitOrderToProcess::put(last.saturating_add(1));
+		}
+
+		to_process.sort_unstable();
+
+		let mut orders = Vec::with_capacity(to_process.len());
+
+		for order_id in to_process {
+			let order = match Self::limit_orders(order_id) {
+				Some(order) => order,
+				None => continue,
+			};
+
+			if now > order.expiry {
+				Self::remove_limit_order(order_id, &order);
+				Self::deposit_event(RawEvent::LimitOrderExpired(
+					order.who.clone(),
+					order_id,
+					order.amount_sell,
+				));
+			} else {
+				orders.push((order_id, order));
+			}
+		}
+
+		orders
+	}
+
+	/// Remove limit order from the order book and release its remaining reserved amount.
+	pub(crate) fn remove_limit_order(order_id: OrderId, order: &LimitOrderOf<T>) {
+		T::Currency::unreserve(order.asset_sell, &order.who, order.amount_sell);
+
+		<LimitOrders<T>>::remove(order_id);
+		<AccountLimitOrders<T>>::mutate(&order.who, |orders| orders.retain(|id| *id != order_id));
+		LimitOrderIds::mutate(|orders| orders.retain(|id| *id != order_id));
+		LimitOrderCount::mutate(|count| *count = count.saturating_sub(1));
+	}
+
+	/// Match resting limit orders with opposite intentions registered in current block.
+	///
+	/// An intention is matched only if it can be completely filled by a single order at the intention's registered rate
+	/// and this rate satisfies both the order's limit price and the intention's trade limit.
+	/// Matched intentions are removed from ```intentions```. A trade which fails is reverted and the intention is kept.
+	pub(crate) fn match_limit_orders(
+		pair_account: &T::AccountId,
+		orders: &mut [(OrderId, LimitOrderOf<T>)],
+		intentions: &mut Vec<Intention<T>>,
+	) {
+		intentions.retain(|intention| {
+			for (order_id, order) in orders.iter_mut() {
+				let result = with_transaction(|| {
+					match Self::fill_intention_from_limit_order(pair_account, *order_id, order, intention) {
+						Ok(filled) => TransactionOutcome::Commit(Ok(filled)),
+						Err(error) => TransactionOutcome::Rollback(Err(error)),
+					}
+				});
+
+				match result {
+					Ok(true) => return false,
+					Ok(false) => {}
+					Err(error) => {
+						Self::send_intention_error_event(intention, error);
+						return true;
+					}
+				}
+			}
+
+			true
+		});
+	}
+
+	/// Directly trade an intention against a limit order.
+	///
+	/// Order owner receives ```amount_sell``` of the intention and pays ```amount_buy``` from the reserved balance.
+	/// Intention owner pays direct trade fee from the received amount.
+	///
+	/// Returns whether the intention has been filled. Order is updated only if the whole trade succeeds,
+	/// transfers made before an error have to be reverted by the caller.
+	fn fill_intention_from_limit_order(
+		pair_account: &T::AccountId,
+		order_id: OrderId,
+		order: &mut LimitOrderOf<T>,
+		intention: &Intention<T>,
+	) -> Result<bool, DispatchError> {
+		if order.asset_sell != intention.asset_buy
+			|| order.asset_buy != intention.asset_sell
+			|| intention.amount_buy == 0
+			|| order.amount_sell < intention.amount_buy
+		{
+			return Ok(false);
+		}
+
+		let fee_rate = Self::direct_trade_fee_rate(intention.discount);
+		let fee = match intention.amount_buy.just_fee(fee_rate) {
+			Some(fee) => fee,
+			None => return Ok(false),
+		};
+
+		let within_trade_limit = match intention.sell_or_buy {
+			IntentionType::SELL => intention.amount_buy.saturating_sub(fee) >= intention.trade_limit,
+			IntentionType::BUY => intention.amount_sell <= intention.trade_limit,
+		};
+
+		let within_limit_price = match order.min_amount_buy(intention.amount_buy) {
+			Some(min_amount_buy) => intention.amount_sell >= min_amount_buy,
+			None => false,
+		};
+
+		if !within_trade_limit || !within_limit_price {
+			return Ok(false);
+		}
+
+		let amount_remaining = order.amount_sell - intention.amount_buy;
+
+		T::Currency::transfer(intention.asset_sell, &intention.who, &order.who, intention.amount_sell)?;
+
+		let not_repatriated = T::Currency::repatriate_reserved(
+			order.asset_sell,
+			&order.who,
+			&intention.who,
+			intention.amount_buy,
+			BalanceStatus::Free,
+		)?;
+
+		ensure!(not_repatriated.is_zero(), Error::<T>::InsufficientAssetBalance);
+
+		Self::deposit_event(RawEvent::IntentionResolvedLimitOrderTrade(
+			intention.who.clone(),
+			order.who.clone(),
+			intention.intention_id,
+			order_id,
+			intention.amount_sell,
+			intention.amount_buy,
+		));
+
+		T::Currency::reserve(order.asset_sell, &intention.who, fee)?;
+		Self::pay_direct_trade_fee(&intention.who, pair_account, order.asset_sell, fee, fee_rate);
+
+		Self::record_fill(
+			intention.intention_id,
+			intention.asset_sell,
+			intention.amount_sell,
+			intention.amount_buy,
+			(order.asset_sell, fee),
+		);
+
+		Self::deposit_event(RawEvent::LimitOrderFilled(
+			order.who.clone(),
+			order_id,
+			intention.amount_buy,
+			intention.amount_sell,
+			amount_remaining,
+		));
+
+		order.amount_sell = amount_remaining;
+
+		Ok(true)
+	}
+
+	/// Resolve limit orders left after matching with intentions.
+	///
+	/// Each order is filled via AMM as much as its limit price allows.
+	/// Filled orders are removed, expired orders are removed and their remaining amount unreserved.
+	pub(crate) fn resolve_limit_orders(orders: Vec<(OrderId, LimitOrderOf<T>)>, now: T::BlockNumber) {
+		for (order_id, mut order) in orders {
+			if order.amount_sell > 0 {
+				Self::fill_limit_order_via_amm(order_id, &mut order);
+			}
+
+			if order.amount_sell == 0 {
+				Self::remove_limit_order(order_id, &order);
+			} else if now >= order.expiry {
+				Self::remove_limit_order(order_id, &order);
+				Self::deposit_event(RawEvent::LimitOrderExpired(
+					order.who.clone(),
+					order_id,
+					order.amount_sell,
+				));
+			} else {
+				<LimitOrders<T>>::insert(order_id, order);
+			}
+		}
+	}
+
+	/// Sell as much of the remaining order amount via AMM as the limit price allows.
+	/// Order is tried only if the current spot price reaches the limit price.
+	fn fill_limit_order_via_amm(order_id: OrderId, order: &mut LimitOrderOf<T>) {
+		if !T::AMMPool::exists(order.asset_sell, order.asset_buy) {
+			return;
+		}
+
+		let spot_amount_buy =
+			T::AMMPool::get_spot_price_unchecked(order.asset_sell, order.asset_buy, order.amount_sell);
+
+		match order.min_amount_buy(order.amount_sell) {
+			Some(min_amount_buy) if spot_amount_buy >= min_amount_buy => {}
+			_ => return,
+		}
+
+		// AMM trades from free balance - release reserved amount for the trade and reserve the rest again after.
+		T::Currency::unreserve(order.asset_sell, &order.who, order.amount_sell);
+
+		if let Some(transfer) = Self::limit_order_amm_transfer(order) {
+			if T::AMMPool::execute_sell(&transfer).is_ok() {
+				order.amount_sell -= transfer.amount;
+
+				Self::deposit_event(RawEvent::LimitOrderFilled(
+					order.who.clone(),
+					order_id,
+					transfer.amount,
+					transfer.amount_out,
+					order.amount_sell,
+				));
+			}
+		}
+
+		// Cannot fail - the remaining amount has just been unreserved.
+		let _ = T::Currency::reserve(order.asset_sell, &order.who, order.amount_sell);
+	}
+
+	/// Find the largest AMM sell of the remaining order amount which satisfies the limit price.
+	///
+	/// Average price of an AMM sell decreases with the amount sold, so binary search is used.
+	fn limit_order_amm_transfer(order: &LimitOrderOf<T>) -> Option<AMMTransfer<T::AccountId, AssetId, Balance>> {
+		let validate = |amount: Balance| {
+			let min_bought = order.min_amount_buy(amount)?;
+			T::AMMPool::validate_sell(&order.who, order.asset_sell, order.asset_buy, amount, min_bought, false).ok()
+		};
+
+		if let Some(transfer) = validate(order.amount_sell) {
+			return Some(transfer);
+		}
+
+		let mut best = None;
+		let mut low: Balance = 0;
+		let mut high = order.amount_sell;
+
+		while high - low > 1 {
+			let mid = low + (high - low) / 2;
+
+			match validate(mid) {
+				Some(transfer) => {
+					low = mid;
+					best = Some(transfer);
+				}
+				None => high = mid,
+			}
+		}
+
+		best
+	}
+}
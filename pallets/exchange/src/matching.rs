@@ -0,0 +1,199 @@
+use super::*;
+use frame_support::sp_runtime::traits::Bounded;
+use frame_support::sp_runtime::FixedPointNumber;
+use primitive_types::U256;
+
+/// Strategy of matching intentions of an asset pair registered in current block.
+pub trait MatchingStrategy<T: Config> {
+	/// Match intentions of an asset pair and resolve them via `Config::Resolver`.
+	/// `sell_1_intentions` sell `asset_1`, `sell_2_intentions` sell `asset_2`. Both are in registration order.
+	fn match_intentions(
+		pair_account: &T::AccountId,
+		asset_1: AssetId,
+		asset_2: AssetId,
+		sell_1_intentions: &[Intention<T>],
+		sell_2_intentions: &[Intention<T>],
+	);
+
+	/// Weight of matching one intention on top of resolving it.
+	fn intention_weight() -> Weight {
+		0
+	}
+}
+
+/// Largest intentions first.
+///
+/// Each intention selling asset 2 is matched with the largest intentions selling asset 1 until its amount is covered.
+pub struct GreedyBySize;
+
+impl<T: Config> MatchingStrategy<T> for GreedyBySize {
+	fn match_intentions(
+		pair_account: &T::AccountId,
+		_asset_1: AssetId,
+		_asset_2: AssetId,
+		sell_1_intentions: &[Intention<T>],
+		sell_2_intentions: &[Intention<T>],
+	) {
+		Module::<T>::process_exchange_intentions(pair_account, sell_2_intentions, sell_1_intentions);
+	}
+}
+
+/// Best price first, earlier registered first on the same price.
+///
+/// Price of an intention is the minimum amount it accepts for one unit sold - the lower the better.
+pub struct PriceTimePriority;
+
+impl<T: Config> MatchingStrategy<T> for PriceTimePriority {
+	fn match_intentions(
+		pair_account: &T::AccountId,
+		_asset_1: AssetId,
+		_asset_2: AssetId,
+		sell_1_intentions: &[Intention<T>],
+		sell_2_intentions: &[Intention<T>],
+	) {
+		let mut sell_1 = sell_1_intentions.to_owned();
+		let mut sell_2 = sell_2_intentions.to_owned();
+
+		// Sort is stable - registration order is kept for the same price.
+		sell_1.sort_by_key(Module::<T>::limit_price);
+		sell_2.sort_by_key(Module::<T>::limit_price);
+
+		Module::<T>::match_intentions_in_order(pair_account, sell_2, sell_1);
+	}
+}
+
+/// Pro-rata allocation.
+///
+/// Side with smaller volume at spot price is traded directly completely.
+/// Volume of the other side is allocated proportionally to intention amounts, the rest of each intention is traded via AMM.
+pub struct ProRata;
+
+impl<T: Config> MatchingStrategy<T> for ProRata {
+	fn match_intentions(
+		pair_account: &T::AccountId,
+		_asset_1: AssetId,
+		_asset_2: AssetId,
+		sell_1_intentions: &[Intention<T>],
+		sell_2_intentions: &[Intention<T>],
+	) {
+		Module::<T>::process_pro_rata(pair_account, sell_2_intentions, sell_1_intentions);
+	}
+}
+
+/// Uniform clearing price batch auction.
+///
+/// If the auction cannot be cleared, intentions are matched by `GreedyBySize`.
+pub struct BatchAuction;
+
+impl<T: Config> MatchingStrategy<T> for BatchAuction {
+	fn match_intentions(
+		pair_account: &T::AccountId,
+		asset_1: AssetId,
+		asset_2: AssetId,
+		sell_1_intentions: &[Intention<T>],
+		sell_2_intentions: &[Intention<T>],
+	) {
+		Module::<T>::process_batch_auction(pair_account, asset_1, asset_2, sell_1_intentions, sell_2_intentions);
+	}
+
+	fn intention_weight() -> Weight {
+		Module::<T>::batch_auction_weight()
+	}
+}
+
+/// Matching strategies implementation
+impl<T: Config> Module<T> {
+	/// Minimum amount of bought asset accepted for one unit of sold asset.
+	fn limit_price(intention: &Intention<T>) -> Price {
+		match intention.sell_or_buy {
+			IntentionType::SELL => Price::checked_from_rational(intention.trade_limit, intention.amount_sell),
+			IntentionType::BUY => Price::checked_from_rational(intention.amount_buy, intention.trade_limit),
+		}
+		.unwrap_or_else(Price::max_value)
+	}
+
+	/// Copy of an intention with amounts and trade limit scaled by `numerator / denominator`.
+	fn scale_intention(intention: &Intention<T>, numerator: Balance, denominator: Balance) -> Intention<T> {
+		let scale = |amount: Balance| -> Balance {
+			if denominator.is_zero() {
+				return Balance::zero();
+			}
+			(U256::from(amount) * U256::from(numerator) / U256::from(denominator)).low_u128()
+		};
+
+		let mut scaled = intention.clone();
+		scaled.amount_sell = scale(intention.amount_sell);
+		scaled.amount_buy = scale(intention.amount_buy);
+		scaled.trade_limit = scale(intention.trade_limit);
+		scaled
+	}
+
+	/// Match intentions of one side with intentions of the other side pro rata.
+	///
+	/// Intentions which cannot be partially filled are traded via AMM only.
+	fn process_pro_rata(
+		pair_account: &T::AccountId,
+		sell_a_intentions: &[Intention<T>],
+		sell_b_intentions: &[Intention<T>],
+	) {
+		let (sell_a, amm_a): (Vec<_>, Vec<_>) = sell_a_intentions
+			.iter()
+			.cloned()
+			.partition(|intention| intention.fill_policy == FillPolicy::PartialFill);
+		let (sell_b, amm_b): (Vec<_>, Vec<_>) = sell_b_intentions
+			.iter()
+			.cloned()
+			.partition(|intention| intention.fill_policy == FillPolicy::PartialFill);
+
+		let total_a_sell: Balance = sell_a.iter().fold(0, |total, x| total.saturating_add(x.amount_sell));
+		let total_b_buy: Balance = sell_b.iter().fold(0, |total, x| total.saturating_add(x.amount_buy));
+
+		if total_a_sell <= total_b_buy {
+			Self::resolve_pro_rata(pair_account, sell_a, sell_b);
+		} else {
+			Self::resolve_pro_rata(pair_account, sell_b, sell_a);
+		}
+
+		for intention in amm_a.iter().chain(amm_b.iter()) {
+			T::Resolver::resolve_single_intention(intention);
+		}
+	}
+
+	/// Trade each of `smaller_side` intentions directly with a proportional part of each of `larger_side` intentions.
+	/// What is left of `larger_side` intentions is traded via AMM.
+	fn resolve_pro_rata(pair_account: &T::AccountId, smaller_side: Vec<Intention<T>>, larger_side: Vec<Intention<T>>) {
+		let smaller_side: Vec<Intention<T>> = smaller_side.into_iter().filter(Self::verify_intention).collect();
+
+		// Amounts are compared at spot price - larger side is never allocated more than its volume.
+		let total_sell: Balance = smaller_side
+			.iter()
+			.fold(0, |total, x| total.saturating_add(x.amount_sell));
+		let total_buy: Balance = larger_side
+			.iter()
+			.fold(0, |total, x| total.saturating_add(x.amount_buy));
+		let total = cmp::max(total_sell, total_buy);
+
+		let filled_before: Vec<Balance> = larger_side.iter().map(Self::filled_amount).collect();
+
+		for intention in smaller_side.iter() {
+			let matched: Vec<Intention<T>> = larger_side
+				.iter()
+				.map(|x| Self::scale_intention(x, intention.amount_sell, total))
+				.collect();
+
+			T::Resolver::resolve_matched_intentions(pair_account, intention, &matched);
+		}
+
+		for (intention, filled_before) in larger_side.iter().zip(filled_before) {
+			let amount = match intention.sell_or_buy {
+				IntentionType::SELL => intention.amount_sell,
+				IntentionType::BUY => intention.amount_buy,
+			};
+			let rest = amount.saturating_sub(Self::filled_amount(intention).saturating_sub(filled_before));
+
+			if !rest.is_zero() {
+				T::Resolver::resolve_single_intention(&Self::scale_intention(intention, rest, amount));
+			}
+		}
+	}
+}
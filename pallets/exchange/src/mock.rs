@@ -1,13 +1,14 @@
 // Creating mock runtime here
 
-use crate::{Config, Module};
-use frame_support::{impl_outer_event, impl_outer_origin, parameter_types};
+use crate::{BatchAuction, Config, GreedyBySize, Intention, MatchingStrategy, Module, PriceTimePriority, ProRata};
+use frame_support::{impl_outer_event, impl_outer_origin, parameter_types, traits::Get, weights::Weight};
 use frame_system as system;
 use orml_traits::parameter_type_with_key;
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup, Zero},
+	ModuleId, Permill,
 };
 
 use pallet_amm as amm;
@@ -15,6 +16,8 @@ use pallet_amm as amm;
 use pallet_amm::AssetPairAccountIdFor;
 use primitives::{fee, AssetId, Balance};
 
+use std::cell::RefCell;
+
 pub type Amount = i128;
 pub type AccountId = u64;
 
@@ -29,6 +32,101 @@ pub const HDX: AssetId = 1000;
 pub const DOT: AssetId = 2000;
 pub const ETH: AssetId = 3000;
 
+thread_local! {
+	static MATCHING: RefCell<Matching> = RefCell::new(Matching::GreedyBySize);
+	static MAX_INTENTIONS_PER_BLOCK: RefCell<u32> = RefCell::new(1_000);
+	static DIRECT_TRADE_FEE: RefCell<fee::Fee> = RefCell::new(fee::Fee::default());
+	static DISCOUNTED_DIRECT_TRADE_FEE: RefCell<fee::Fee> = RefCell::new(fee::Fee::default());
+	static DIRECT_TRADE_FEE_SHARE: RefCell<Permill> = RefCell::new(Permill::from_percent(0));
+}
+
+#[derive(Clone, Copy)]
+pub enum Matching {
+	GreedyBySize,
+	PriceTimePriority,
+	ProRata,
+	BatchAuction,
+}
+
+pub struct TestMatchingStrategy;
+impl MatchingStrategy<Test> for TestMatchingStrategy {
+	fn match_intentions(
+		pair_account: &AccountId,
+		asset_1: AssetId,
+		asset_2: AssetId,
+		sell_1_intentions: &[Intention<Test>],
+		sell_2_intentions: &[Intention<Test>],
+	) {
+		match MATCHING.with(|v| *v.borrow()) {
+			Matching::GreedyBySize => <GreedyBySize as MatchingStrategy<Test>>::match_intentions(
+				pair_account,
+				asset_1,
+				asset_2,
+				sell_1_intentions,
+				sell_2_intentions,
+			),
+			Matching::PriceTimePriority => <PriceTimePriority as MatchingStrategy<Test>>::match_intentions(
+				pair_account,
+				asset_1,
+				asset_2,
+				sell_1_intentions,
+				sell_2_intentions,
+			),
+			Matching::ProRata => <ProRata as MatchingStrategy<Test>>::match_intentions(
+				pair_account,
+				asset_1,
+				asset_2,
+				sell_1_intentions,
+				sell_2_intentions,
+			),
+			Matching::BatchAuction => <BatchAuction as MatchingStrategy<Test>>::match_intentions(
+				pair_account,
+				asset_1,
+				asset_2,
+				sell_1_intentions,
+				sell_2_intentions,
+			),
+		}
+	}
+
+	fn intention_weight() -> Weight {
+		match MATCHING.with(|v| *v.borrow()) {
+			Matching::GreedyBySize => <GreedyBySize as MatchingStrategy<Test>>::intention_weight(),
+			Matching::PriceTimePriority => <PriceTimePriority as MatchingStrategy<Test>>::intention_weight(),
+			Matching::ProRata => <ProRata as MatchingStrategy<Test>>::intention_weight(),
+			Matching::BatchAuction => <BatchAuction as MatchingStrategy<Test>>::intention_weight(),
+		}
+	}
+}
+
+pub struct MaxIntentionsPerBlock;
+impl Get<u32> for MaxIntentionsPerBlock {
+	fn get() -> u32 {
+		MAX_INTENTIONS_PER_BLOCK.with(|v| *v.borrow())
+	}
+}
+
+pub struct DirectTradeFee;
+impl Get<fee::Fee> for DirectTradeFee {
+	fn get() -> fee::Fee {
+		DIRECT_TRADE_FEE.with(|v| *v.borrow())
+	}
+}
+
+pub struct DiscountedDirectTradeFee;
+impl Get<fee::Fee> for DiscountedDirectTradeFee {
+	fn get() -> fee::Fee {
+		DISCOUNTED_DIRECT_TRADE_FEE.with(|v| *v.borrow())
+	}
+}
+
+pub struct DirectTradeFeeShare;
+impl Get<Permill> for DirectTradeFeeShare {
+	fn get() -> Permill {
+		DIRECT_TRADE_FEE_SHARE.with(|v| *v.borrow())
+	}
+}
+
 mod exchange {
 	pub use super::super::*;
 }
@@ -56,7 +154,18 @@ parameter_types! {
 
 	pub const HDXAssetId: AssetId = HDX;
 
-	pub ExchangeFeeRate: fee::Fee = fee::Fee::default();
+	pub MaxExchangeFeeRate: fee::Fee = fee::Fee { numerator: 1, denominator: 10 };
+	pub const PriceHistoryLength: u32 = 10;
+	pub const ProtocolFeeRate: Permill = Permill::from_percent(0);
+	pub const ProtocolFeeReceiverAccount: AccountId = 100;
+	pub const ExchangeModuleId: ModuleId = ModuleId(*b"hdx/xchg");
+	pub const DirectTradeFeeReceiverAccount: AccountId = 101;
+	pub const MaxQueuedIntentions: u32 = 2;
+	pub const MaxIntentionRequeues: u32 = 2;
+	pub const MaxLimitOrders: u32 = 3;
+	pub const MaxLimitOrdersPerBlock: u32 = 2;
+	pub const MinLimitOrderAmount: Balance = 1_000;
+	pub const MaxLimitOrderExpiry: u64 = 100;
 }
 impl system::Config for Test {
 	type BaseCallFilter = ();
@@ -117,6 +226,14 @@ impl AssetPairAccountIdFor<AssetId, u64> for AssetPairAccountIdTest {
 		}
 		return (a * 1000 + b) as u64;
 	}
+
+	fn from_asset_set(assets: &[AssetId]) -> u64 {
+		let mut sorted = assets.to_vec();
+		sorted.sort_unstable();
+		sorted.iter().fold(0u64, |acc, asset| {
+			acc.wrapping_mul(1_000_003).wrapping_add(*asset as u64)
+		}) | (1 << 62)
+	}
 }
 
 impl amm::Config for Test {
@@ -125,7 +242,11 @@ impl amm::Config for Test {
 	type Currency = Currency;
 	type HDXAssetId = HDXAssetId;
 	type WeightInfo = ();
-	type GetExchangeFee = ExchangeFeeRate;
+	type MaxExchangeFee = MaxExchangeFeeRate;
+	type UpdatePoolFeeOrigin = frame_system::EnsureRoot<AccountId>;
+	type ProtocolFee = ProtocolFeeRate;
+	type ProtocolFeeReceiver = ProtocolFeeReceiverAccount;
+	type PriceHistoryLength = PriceHistoryLength;
 }
 
 pub type AMMModule = amm::Module<Test>;
@@ -137,11 +258,29 @@ impl Config for Test {
 	type Currency = Currency;
 	type Resolver = exchange::Module<Test>;
 	type WeightInfo = ();
+	type ModuleId = ExchangeModuleId;
+	type MatchingStrategy = TestMatchingStrategy;
+	type MaxIntentionsPerBlock = MaxIntentionsPerBlock;
+	type MaxQueuedIntentions = MaxQueuedIntentions;
+	type MaxIntentionRequeues = MaxIntentionRequeues;
+	type DirectTradeFee = DirectTradeFee;
+	type DiscountedDirectTradeFee = DiscountedDirectTradeFee;
+	type DirectTradeFeeShare = DirectTradeFeeShare;
+	type DirectTradeFeeReceiver = DirectTradeFeeReceiverAccount;
+	type MaxLimitOrders = MaxLimitOrders;
+	type MaxLimitOrdersPerBlock = MaxLimitOrdersPerBlock;
+	type MinLimitOrderAmount = MinLimitOrderAmount;
+	type MaxLimitOrderExpiry = MaxLimitOrderExpiry;
 }
 pub type Exchange = Module<Test>;
 
 pub struct ExtBuilder {
 	endowed_accounts: Vec<(AccountId, AssetId, Balance)>,
+	matching: Matching,
+	max_intentions_per_block: u32,
+	direct_trade_fee: fee::Fee,
+	discounted_direct_trade_fee: fee::Fee,
+	direct_trade_fee_share: Permill,
 }
 
 impl Default for ExtBuilder {
@@ -167,6 +306,11 @@ impl Default for ExtBuilder {
 				(FERDIE, DOT, 1000_000_000_000_000u128),
 				(GEORGE, DOT, 1000_000_000_000_000u128),
 			],
+			matching: Matching::GreedyBySize,
+			max_intentions_per_block: 1_000,
+			direct_trade_fee: fee::Fee::default(),
+			discounted_direct_trade_fee: fee::Fee::default(),
+			direct_trade_fee_share: Permill::from_percent(0),
 		}
 	}
 }
@@ -174,7 +318,45 @@ impl Default for ExtBuilder {
 impl ExtBuilder {
 	// builds genesis config
 
+	pub fn with_batch_auction(self) -> Self {
+		self.with_matching(Matching::BatchAuction)
+	}
+
+	pub fn with_matching(mut self, matching: Matching) -> Self {
+		self.matching = matching;
+		self
+	}
+
+	pub fn with_max_intentions_per_block(mut self, max_intentions: u32) -> Self {
+		self.max_intentions_per_block = max_intentions;
+		self
+	}
+
+	pub fn with_direct_trade_fee(mut self, fee: fee::Fee) -> Self {
+		self.direct_trade_fee = fee;
+		self
+	}
+
+	pub fn with_discounted_direct_trade_fee(mut self, fee: fee::Fee) -> Self {
+		self.discounted_direct_trade_fee = fee;
+		self
+	}
+
+	pub fn with_direct_trade_fee_share(mut self, share: Permill) -> Self {
+		self.direct_trade_fee_share = share;
+		self
+	}
+
+	fn set_constants(&self) {
+		MATCHING.with(|v| *v.borrow_mut() = self.matching);
+		MAX_INTENTIONS_PER_BLOCK.with(|v| *v.borrow_mut() = self.max_intentions_per_block);
+		DIRECT_TRADE_FEE.with(|v| *v.borrow_mut() = self.direct_trade_fee);
+		DISCOUNTED_DIRECT_TRADE_FEE.with(|v| *v.borrow_mut() = self.discounted_direct_trade_fee);
+		DIRECT_TRADE_FEE_SHARE.with(|v| *v.borrow_mut() = self.direct_trade_fee_share);
+	}
+
 	pub fn build(self) -> sp_io::TestExternalities {
+		self.set_constants();
 		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
 
 		orml_tokens::GenesisConfig::<Test> {
@@ -0,0 +1,262 @@
+use super::*;
+use frame_support::traits::BalanceStatus;
+use primitive_types::U256;
+use sp_std::collections::btree_map::BTreeMap;
+
+/// Registered intentions grouped by (asset sell, asset buy) - same as in ```ExchangeAssetsIntentions```.
+pub type IntentionGroups<T> = BTreeMap<(AssetId, AssetId), Vec<Intention<T>>>;
+
+/// One leg of a ring trade.
+/// who, intention id, asset sold, asset bought, amount sold, amount bought, fee
+pub type RingTradeLeg<AccountId, IntentionID> = (AccountId, IntentionID, AssetId, AssetId, Balance, Balance, Balance);
+
+/// Number of intentions in a ring - A -> B, B -> C, C -> A
+const RING_SIZE: usize = 3;
+
+/// Maximum number of intentions of each asset pair considered for rings - earlier registered first.
+pub const MAX_RING_CANDIDATES: usize = 8;
+
+/// Maximum number of candidate rings checked in a block.
+pub const MAX_RING_ATTEMPTS: u32 = 256;
+
+/// Maximum number of rings traded in a block.
+pub const MAX_RING_TRADES: u32 = 8;
+
+/// Weight of checking amounts and limits of one candidate ring.
+const RING_ATTEMPT_WEIGHT: Weight = 5_000_000;
+
+fn mul_div(amount: Balance, numerator: Balance, denominator: Balance) -> Option<Balance> {
+	if denominator == 0 {
+		return None;
+	}
+
+	let result = U256::from(amount) * U256::from(numerator) / U256::from(denominator);
+
+	if result > U256::from(Balance::max_value()) {
+		None
+	} else {
+		Some(result.low_u128())
+	}
+}
+
+/// Ring (coincidence of wants) matching implementation
+impl<T: Config> Module<T> {
+	/// Find rings of sell intentions across asset pairs and trade them directly.
+	///
+	/// Ring is formed by three intentions A -> B, B -> C and C -> A. Each intention sells to the previous one in the ring
+	/// at its registered rate, so the last intention receives what the first one sells and its trade limit has to be
+	/// satisfied by that amount. Intentions in a ring may be partially filled - rest amounts stay in ```intentions```
+	/// and are resolved as usual.
+	///
+	/// Search is bounded - only first ```MAX_RING_CANDIDATES``` intentions of each pair are considered, at most
+	/// ```MAX_RING_ATTEMPTS``` rings are checked and ```MAX_RING_TRADES``` rings, not more than ```max_trades```, traded in a block.
+	pub(crate) fn match_rings(intentions: &mut IntentionGroups<T>, max_trades: usize) {
+		let keys = intentions.keys().cloned().collect::<Vec<_>>();
+		let candidates = |group: &Vec<Intention<T>>| cmp::min(group.len(), MAX_RING_CANDIDATES);
+
+		let max_trades = cmp::min(MAX_RING_TRADES as usize, max_trades);
+
+		let mut attempts: u32 = 0;
+		let mut trades: usize = 0;
+
+		'search: for &(asset_a, asset_b) in keys.iter() {
+			for i in 0..candidates(&intentions[&(asset_a, asset_b)]) {
+				'ring: for &(_, asset_c) in keys.iter().filter(|(sell, buy)| *sell == asset_b && *buy != asset_a) {
+					let (len_j, len_k) =
+						match (intentions.get(&(asset_b, asset_c)), intentions.get(&(asset_c, asset_a))) {
+							(Some(b_sells), Some(c_sells)) => (candidates(b_sells), candidates(c_sells)),
+							_ => continue,
+						};
+
+					for j in 0..len_j {
+						for k in 0..len_k {
+							if attempts >= MAX_RING_ATTEMPTS || trades >= max_trades {
+								break 'search;
+							}
+							attempts += 1;
+
+							let positions = [
+								((asset_a, asset_b), i),
+								((asset_b, asset_c), j),
+								((asset_c, asset_a), k),
+							];
+
+							let ring = positions
+								.iter()
+								.map(|(key, idx)| intentions[key][*idx].clone())
+								.collect::<Vec<_>>();
+
+							if let Some(legs) = Self::resolve_ring(&ring) {
+								trades += 1;
+
+								for ((key, idx), leg) in positions.iter().zip(legs.iter()) {
+									if let Some(intention) =
+										intentions.get_mut(key).and_then(|group| group.get_mut(*idx))
+									{
+										intention.amount_sell = intention.amount_sell.saturating_sub(leg.4);
+										intention.amount_buy = intention.amount_buy.saturating_sub(leg.5);
+										intention.trade_limit = intention.trade_limit.saturating_sub(leg.5);
+									}
+								}
+							}
+
+							if intentions[&(asset_a, asset_b)][i].amount_sell == 0 {
+								break 'ring;
+							}
+						}
+					}
+				}
+			}
+		}
+
+		for group in intentions.values_mut() {
+			group.retain(|intention| intention.amount_sell > 0);
+		}
+	}
+
+	/// Worst case weight of ring matching in a block.
+	///
+	/// Each traded ring reserves and repatriates two amounts of each of its intentions and records their fills.
+	pub(crate) fn ring_matching_weight() -> Weight {
+		let trade = T::DbWeight::get().reads_writes(4 * RING_SIZE as Weight, 5 * RING_SIZE as Weight);
+
+		RING_ATTEMPT_WEIGHT
+			.saturating_mul(MAX_RING_ATTEMPTS as Weight)
+			.saturating_add(trade.saturating_mul(MAX_RING_TRADES as Weight))
+	}
+
+	/// Work out amounts of a ring and trade them directly.
+	///
+	/// All amounts and fees are reserved first, the ring is traded only if all reservations succeed.
+	/// Returns traded legs or None if the ring cannot be traded.
+	fn resolve_ring(ring: &[Intention<T>]) -> Option<Vec<RingTradeLeg<T::AccountId, IntentionId<T>>>> {
+		if ring.len() != RING_SIZE
+			|| ring
+				.iter()
+				.any(|intention| intention.sell_or_buy != IntentionType::SELL || intention.amount_sell == 0)
+		{
+			return None;
+		}
+
+		let sold = Self::ring_amounts(ring)?;
+
+		let mut legs = Vec::with_capacity(RING_SIZE);
+
+		for (idx, intention) in ring.iter().enumerate() {
+			let amount_sold = sold[idx];
+			let amount_bought = sold[(idx + 1) % RING_SIZE];
+			let fee = amount_bought.just_fee(Self::direct_trade_fee_rate(intention.discount))?;
+
+			// Intention which cannot be partially filled takes part only if the ring fills it completely.
+			if intention.fill_policy != FillPolicy::PartialFill && amount_sold < intention.amount_sell {
+				return None;
+			}
+
+			// Trade limit of partially filled intention is applied proportionally.
+			let received = U256::from(amount_bought.checked_sub(fee)?) * U256::from(intention.amount_sell);
+			let required = U256::from(intention.trade_limit) * U256::from(amount_sold);
+
+			if received < required {
+				return None;
+			}
+
+			legs.push((
+				intention.who.clone(),
+				intention.intention_id,
+				intention.asset_sell,
+				intention.asset_buy,
+				amount_sold,
+				amount_bought,
+				fee,
+			));
+		}
+
+		let pair_accounts = ring
+			.iter()
+			.map(|intention| T::AMMPool::get_pair_id(&intention.asset_sell, &intention.asset_buy))
+			.collect::<Vec<_>>();
+
+		let mut transfers = Vec::<Transfer<T>>::new();
+
+		for (idx, intention) in ring.iter().enumerate() {
+			// Sold amount goes to the previous intention in the ring - it buys this asset.
+			transfers.push(Transfer::<T> {
+				from: &intention.who,
+				to: &ring[(idx + RING_SIZE - 1) % RING_SIZE].who,
+				asset: intention.asset_sell,
+				amount: legs[idx].4,
+				fee_rate: None,
+			});
+			transfers.push(Transfer::<T> {
+				from: &intention.who,
+				to: &pair_accounts[idx],
+				asset: intention.asset_buy,
+				amount: legs[idx].6,
+				fee_rate: Some(Self::direct_trade_fee_rate(intention.discount)),
+			});
+		}
+
+		for (idx, transfer) in transfers.iter().enumerate() {
+			if T::Currency::reserve(transfer.asset, transfer.from, transfer.amount).is_err() {
+				for reserved in transfers.iter().take(idx) {
+					T::Currency::unreserve(reserved.asset, reserved.from, reserved.amount);
+				}
+				return None;
+			}
+		}
+
+		for transfer in transfers.iter() {
+			if let Some(fee_rate) = transfer.fee_rate {
+				Self::pay_direct_trade_fee(transfer.from, transfer.to, transfer.asset, transfer.amount, fee_rate);
+				continue;
+			}
+			T::Currency::repatriate_reserved(
+				transfer.asset,
+				transfer.from,
+				transfer.to,
+				transfer.amount,
+				BalanceStatus::Free,
+			)
+			.expect("Cannot fail. Amounts have been reserved.");
+		}
+
+		for leg in legs.iter() {
+			Self::record_fill(leg.1, leg.2, leg.4, leg.5, (leg.3, leg.6));
+		}
+
+		Self::deposit_event(RawEvent::IntentionsResolvedRingTrade(legs.clone()));
+
+		Some(legs)
+	}
+
+	/// Amounts sold by each intention of a ring.
+	///
+	/// First intention sells as much as all intentions in the ring can take, each next intention sells what the previous
+	/// one buys at its registered rate.
+	fn ring_amounts(ring: &[Intention<T>]) -> Option<Vec<Balance>> {
+		let mut amount = ring[0].amount_sell;
+
+		// Amount sold by the first intention is limited by amount sold by each next intention converted back along the ring.
+		for (n, intention) in ring.iter().enumerate().skip(1) {
+			let mut limit = intention.amount_sell;
+			for previous in ring[..n].iter().rev() {
+				limit = mul_div(limit, previous.amount_sell, previous.amount_buy)?;
+			}
+			amount = cmp::min(amount, limit);
+		}
+
+		let mut sold = Vec::with_capacity(RING_SIZE);
+		sold.push(amount);
+
+		for previous in ring.iter().take(RING_SIZE - 1) {
+			amount = mul_div(amount, previous.amount_buy, previous.amount_sell)?;
+			sold.push(amount);
+		}
+
+		if sold.iter().any(|amount| *amount == 0) {
+			return None;
+		}
+
+		Some(sold)
+	}
+}
@@ -0,0 +1,127 @@
+use super::*;
+use frame_support::dispatch::UnfilteredDispatchable;
+use frame_support::sp_runtime::{DispatchError, TransactionOutcome};
+use frame_support::storage::with_transaction;
+use frame_support::traits::OnFinalize;
+use primitives::{ExchangeTrade, TradeKind};
+use sp_std::convert::TryInto;
+
+/// Trade predicted by a dry run of block resolution.
+pub type SimulatedTrade<T> = ExchangeTrade<<T as system::Config>::AccountId, AssetId, Balance, IntentionId<T>>;
+
+/// Intentions not registered yet
+impl<T: Config> Module<T> {
+	/// Run `f` with intentions of `pending` exchange calls registered, eg. calls of extrinsics waiting in the transaction pool.
+	///
+	/// Intentions registered in a block are resolved in its `on_finalize`, so state of a finalized block contains only
+	/// queued intentions. Each call is dispatched by its account, calls which fail are skipped.
+	/// All changes are reverted afterwards.
+	pub fn with_pending_calls<R>(pending: Vec<(T::AccountId, Call<T>)>, f: impl FnOnce() -> R) -> R {
+		with_transaction(|| {
+			Self::dispatch_pending_calls(pending);
+
+			TransactionOutcome::Rollback(f())
+		})
+	}
+
+	fn dispatch_pending_calls(pending: Vec<(T::AccountId, Call<T>)>) {
+		for (who, call) in pending {
+			let _ = call.dispatch_bypass_filter(system::RawOrigin::Signed(who).into());
+		}
+	}
+}
+
+/// Dry run of block resolution
+impl<T: Config> Module<T>
+where
+	<T as system::Config>::Event: TryInto<Event<T>>,
+{
+	/// Predict trades of intentions waiting to be resolved in current block.
+	///
+	/// Intentions of `pending` exchange calls ( eg. calls of extrinsics waiting in the transaction pool ) are registered
+	/// first, see `with_pending_calls`. Optional exchange call ( eg. `sell` or `buy` ) is dispatched by `who` next,
+	/// so its intention is resolved together with the others.
+	/// Intentions are resolved as in `on_finalize` and all changes are reverted afterwards.
+	pub fn simulate_resolution(
+		pending: Vec<(T::AccountId, Call<T>)>,
+		call: Option<(T::AccountId, Call<T>)>,
+	) -> Result<Vec<SimulatedTrade<T>>, DispatchError> {
+		with_transaction(|| {
+			Self::dispatch_pending_calls(pending);
+
+			TransactionOutcome::Rollback(Self::resolve_and_collect_trades(call))
+		})
+	}
+
+	fn resolve_and_collect_trades(
+		call: Option<(T::AccountId, Call<T>)>,
+	) -> Result<Vec<SimulatedTrade<T>>, DispatchError> {
+		if let Some((who, call)) = call {
+			call.dispatch_bypass_filter(system::RawOrigin::Signed(who).into())
+				.map_err(|e| e.error)?;
+		}
+
+		let intentions: Vec<Intention<T>> = Self::intention_queue()
+			.into_iter()
+			.chain(<ExchangeAssetsIntentions<T>>::iter().flat_map(|(_, pair_intentions)| pair_intentions.into_iter()))
+			.collect();
+
+		let first_event = <system::Module<T>>::events().len();
+
+		<Self as OnFinalize<T::BlockNumber>>::on_finalize(<system::Module<T>>::block_number());
+
+		let mut trades = Vec::<SimulatedTrade<T>>::new();
+
+		for record in <system::Module<T>>::events().into_iter().skip(first_event) {
+			let event: Result<Event<T>, _> = record.event.try_into();
+
+			if let Ok(event) = event {
+				Self::collect_trades(&mut trades, &intentions, event);
+			}
+		}
+
+		Ok(trades)
+	}
+
+	/// Convert a trade event to trades of the intentions involved.
+	fn collect_trades(trades: &mut Vec<SimulatedTrade<T>>, intentions: &[Intention<T>], event: Event<T>) {
+		let mut push = |kind: TradeKind, intention_id: IntentionId<T>, amount_sold: Balance, amount_bought: Balance| {
+			if let Some(intention) = intentions.iter().find(|x| x.intention_id == intention_id) {
+				trades.push(ExchangeTrade {
+					kind,
+					who: intention.who.clone(),
+					intention_id,
+					asset_sell: intention.asset_sell,
+					asset_buy: intention.asset_buy,
+					amount_sold,
+					amount_bought,
+				});
+			}
+		};
+
+		match event {
+			RawEvent::IntentionResolvedAMMTrade(_, IntentionType::SELL, intention_id, amount, amount_out) => {
+				push(TradeKind::AMM, intention_id, amount, amount_out)
+			}
+			RawEvent::IntentionResolvedAMMTrade(_, IntentionType::BUY, intention_id, amount, amount_out) => {
+				push(TradeKind::AMM, intention_id, amount_out, amount)
+			}
+			RawEvent::IntentionResolvedDirectTrade(_, _, intention_a, intention_b, amount_from_a, amount_from_b) => {
+				push(TradeKind::Direct, intention_a, amount_from_a, amount_from_b);
+				push(TradeKind::Direct, intention_b, amount_from_b, amount_from_a);
+			}
+			RawEvent::IntentionResolvedLimitOrderTrade(_, _, intention_id, _, amount_sold, amount_bought) => {
+				push(TradeKind::LimitOrder, intention_id, amount_sold, amount_bought)
+			}
+			RawEvent::IntentionResolvedBatchTrade(_, _, intention_id, amount_sold, amount_bought) => {
+				push(TradeKind::BatchAuction, intention_id, amount_sold, amount_bought)
+			}
+			RawEvent::IntentionsResolvedRingTrade(legs) => {
+				for (_, intention_id, _, _, amount_sold, amount_bought, _) in legs {
+					push(TradeKind::Ring, intention_id, amount_sold, amount_bought);
+				}
+			}
+			_ => {}
+		}
+	}
+}
@@ -4,8 +4,8 @@ use frame_support::sp_runtime::traits::Hash;
 use frame_support::traits::OnFinalize;
 use frame_support::{assert_noop, assert_ok};
 use frame_system::InitKind;
-use primitives::Price;
-use sp_runtime::{DispatchError, FixedPointNumber};
+use primitives::{fee::Fee, ExchangeTrade, Price, TradeKind};
+use sp_runtime::{DispatchError, FixedPointNumber, Permill};
 
 use pallet_amm as amm;
 
@@ -17,6 +17,12 @@ fn new_test_ext() -> sp_io::TestExternalities {
 	ext
 }
 
+fn new_batch_auction_test_ext() -> sp_io::TestExternalities {
+	let mut ext = ExtBuilder::default().with_batch_auction().build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
 fn last_event() -> TestEvent {
 	system::Module::<Test>::events().pop().expect("Event expected").event
 }
@@ -39,6 +45,11 @@ fn expect_events(e: Vec<TestEvent>) {
 	assert_eq!(last_events(e.len()), e);
 }
 
+fn event_emitted<E: Into<TestEvent>>(e: E) -> bool {
+	let e = e.into();
+	system::Module::<Test>::events().iter().any(|record| record.event == e)
+}
+
 fn generate_intention_id(account: &<Test as system::Config>::AccountId, c: u32) -> crate::IntentionId<Test> {
 	let b = <system::Module<Test>>::current_block_number();
 	(c, &account, b, DOT, ETH).using_encoded(<Test as system::Config>::Hashing::hash)
@@ -51,7 +62,9 @@ fn initialize_pool(asset_a: u32, asset_b: u32, user: u64, amount: u128, price: P
 		asset_a,
 		asset_b,
 		amount,
-		price
+		price,
+		Fee::default(),
+		amm::PoolType::ConstantProduct
 	));
 
 	let shares = if asset_a <= asset_b {
@@ -112,6 +125,8 @@ fn sell_test_pool_finalization_states() {
 			2_000_000_000_000,
 			20000000000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
@@ -123,18 +138,22 @@ fn sell_test_pool_finalization_states() {
 			1_000_000_000_000,
 			4_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
 
 		assert_eq!(Exchange::get_intentions_count((asset_b, asset_a)), 2);
 
-		// Balance should not change yet
-		assert_eq!(Currency::free_balance(asset_a, &user_2), 1000_000_000_000_000u128);
+		// Max sold amounts plus fee headroom are reserved until the block is finalized
+		assert_eq!(Currency::free_balance(asset_a, &user_2), 999_997_996_000_000_000u128);
+		assert_eq!(Currency::reserved_balance(asset_a, &user_2), 2_004_000_000_000u128);
 		assert_eq!(Currency::free_balance(asset_b, &user_2), 1000_000_000_000_000u128);
 
 		assert_eq!(Currency::free_balance(asset_a, &user_3), 1000_000_000_000_000u128);
-		assert_eq!(Currency::free_balance(asset_b, &user_3), 1000_000_000_000_000u128);
+		assert_eq!(Currency::free_balance(asset_b, &user_3), 999_995_992_000_000_000u128);
+		assert_eq!(Currency::reserved_balance(asset_b, &user_3), 4_008_000_000_000u128);
 
 		assert_eq!(Currency::free_balance(asset_a, &pair_account), 100_000_000_000_000);
 
@@ -170,7 +189,9 @@ fn sell_test_pool_finalization_states() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 2000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 2000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_b, 4000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_b, 4000000000, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(user_2, 3000, 2000, 1000000000000, 1976336046259)),
 			RawEvent::IntentionResolvedAMMTrade(
 				user_2,
@@ -180,6 +201,28 @@ fn sell_test_pool_finalization_states() {
 				1976336046259,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				2000000000000,
+				3976336046259,
+				Price::from_inner(1988168023129500000),
+				2000000000,
+				2000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::BUY,
+				user_3_sell_intention_id,
+				2000000000000,
+				1000000000000,
+				Price::from_inner(500000000000000000),
+				4000000000,
+				0,
+			)
+			.into(),
 		]);
 
 		// Check final account balances
@@ -220,6 +263,8 @@ fn sell_test_standard() {
 			2_000_000_000_000,
 			300_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 
@@ -230,6 +275,8 @@ fn sell_test_standard() {
 			1_000_000_000_000,
 			4_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -283,7 +330,9 @@ fn sell_test_standard() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 2000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 2000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_b, 4000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_b, 4000000000, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(user_2, 3000, 2000, 1000000000000, 1976336046259)),
 			RawEvent::IntentionResolvedAMMTrade(
 				user_2,
@@ -293,6 +342,28 @@ fn sell_test_standard() {
 				1976336046259,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				2000000000000,
+				3976336046259,
+				Price::from_inner(1988168023129500000),
+				2000000000,
+				2000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::BUY,
+				user_3_sell_intention_id,
+				2000000000000,
+				1000000000000,
+				Price::from_inner(500000000000000000),
+				4000000000,
+				0,
+			)
+			.into(),
 		]);
 	});
 }
@@ -319,6 +390,8 @@ fn sell_test_inverse_standard() {
 			1_000_000_000_000,
 			100_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
@@ -330,6 +403,8 @@ fn sell_test_inverse_standard() {
 			4_000_000_000_000,
 			1_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -393,7 +468,31 @@ fn sell_test_inverse_standard() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 4000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 4000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 2000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 2000000000, Fee::default()).into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				1000000000000,
+				2000000000000,
+				Price::from_inner(2000000000000000000),
+				0,
+				4000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				4000000000000,
+				1988138378978,
+				Price::from_inner(497034594744500000),
+				4000000000,
+				2000000000,
+			)
+			.into(),
 		]);
 	});
 }
@@ -420,6 +519,8 @@ fn sell_test_exact_match() {
 			1_000_000_000_000,
 			1_500_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
@@ -431,6 +532,8 @@ fn sell_test_exact_match() {
 			2_000_000_000_000,
 			200_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -482,7 +585,31 @@ fn sell_test_exact_match() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 4000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 4000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 2000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 2000000000, Fee::default()).into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				1000000000000,
+				2000000000000,
+				Price::from_inner(2000000000000000000),
+				0,
+				4000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				2000000000000,
+				1000000000000,
+				Price::from_inner(500000000000000000),
+				0,
+				2000000000,
+			)
+			.into(),
 		]);
 	});
 }
@@ -509,6 +636,8 @@ fn sell_test_single_eth_sells() {
 			1_000_000_000_000,
 			100_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		assert_ok!(Exchange::sell(
@@ -518,6 +647,8 @@ fn sell_test_single_eth_sells() {
 			2_000_000_000_000,
 			200_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -589,6 +720,28 @@ fn sell_test_single_eth_sells() {
 				1899978143094,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				1000000000000,
+				1899978143094,
+				Price::from_inner(1899978143094000000),
+				2000000000,
+				0,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				2000000000000,
+				3913878975647,
+				Price::from_inner(1956939487823500000),
+				4000000000,
+				0,
+			)
+			.into(),
 		]);
 	});
 }
@@ -615,6 +768,8 @@ fn sell_test_single_dot_sells() {
 			1_000_000_000_000,
 			100_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
@@ -625,6 +780,8 @@ fn sell_test_single_dot_sells() {
 			2_000_000_000_000,
 			200_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -695,6 +852,28 @@ fn sell_test_single_dot_sells() {
 				978388447963,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				1000000000000,
+				496522353457,
+				Price::from_inner(496522353457000000),
+				2000000000,
+				0,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				2000000000000,
+				978388447963,
+				Price::from_inner(489194223981500000),
+				4000000000,
+				0,
+			)
+			.into(),
 		]);
 	});
 }
@@ -725,6 +904,8 @@ fn sell_test_single_multiple_sells() {
 			1_000_000_000_000,
 			100_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		assert_ok!(Exchange::sell(
@@ -734,6 +915,8 @@ fn sell_test_single_multiple_sells() {
 			1_000_000_000_000,
 			100_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
 		assert_ok!(Exchange::sell(
@@ -743,6 +926,8 @@ fn sell_test_single_multiple_sells() {
 			1_000_000_000_000,
 			100_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_4_sell_intention_id = generate_intention_id(&user_4, 2);
 		assert_ok!(Exchange::sell(
@@ -752,6 +937,8 @@ fn sell_test_single_multiple_sells() {
 			1_000_000_000_000,
 			100_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_5_sell_intention_id = generate_intention_id(&user_5, 3);
 		assert_ok!(Exchange::sell(
@@ -761,6 +948,8 @@ fn sell_test_single_multiple_sells() {
 			2_000_000_000_000,
 			200_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_6_sell_intention_id = generate_intention_id(&user_6, 4);
 
@@ -841,7 +1030,9 @@ fn sell_test_single_multiple_sells() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 4000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 4000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_6, pair_account, asset_a, 2000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_6, pair_account, asset_a, 2000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTrade(
 				user_4,
 				user_3,
@@ -852,7 +1043,9 @@ fn sell_test_single_multiple_sells() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_4, pair_account, asset_b, 2000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_4, pair_account, asset_b, 2000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 1000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 1000000000, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(
 				user_4,
 				asset_a,
@@ -883,6 +1076,61 @@ fn sell_test_single_multiple_sells() {
 				501482500933,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				1000000000000,
+				2000000000000,
+				Price::from_inner(2000000000000000000),
+				0,
+				4000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_4,
+				IntentionType::SELL,
+				user_4_sell_intention_id,
+				1000000000000,
+				1993044854829,
+				Price::from_inner(1993044854829000000),
+				1000000000,
+				2000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				1000000000000,
+				500000000000,
+				Price::from_inner(500000000000000000),
+				0,
+				1000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_5,
+				IntentionType::SELL,
+				user_5_sell_intention_id,
+				1000000000000,
+				501482500933,
+				Price::from_inner(501482500933000000),
+				2000000000,
+				0,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_6,
+				IntentionType::SELL,
+				user_6_sell_intention_id,
+				2000000000000,
+				1000000000000,
+				Price::from_inner(500000000000000000),
+				0,
+				2000000000,
+			)
+			.into(),
 		]);
 	});
 }
@@ -911,6 +1159,8 @@ fn sell_test_group_sells() {
 			5_000_000_000_000,
 			200_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		assert_ok!(Exchange::sell(
@@ -920,6 +1170,8 @@ fn sell_test_group_sells() {
 			3_000_000_000_000,
 			200_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
 		assert_ok!(Exchange::sell(
@@ -929,6 +1181,8 @@ fn sell_test_group_sells() {
 			10_000_000_000_000,
 			200_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_4_sell_intention_id = generate_intention_id(&user_4, 2);
 
@@ -991,7 +1245,9 @@ fn sell_test_group_sells() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_4, pair_account, asset_b, 10000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_4, pair_account, asset_b, 10000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_a, 5000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_a, 5000000000, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(
 				user_4,
 				asset_a,
@@ -1022,6 +1278,39 @@ fn sell_test_group_sells() {
 				1702327336909,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_4,
+				IntentionType::SELL,
+				user_4_sell_intention_id,
+				10000000000000,
+				18927573262630,
+				Price::from_inner(1892757326263000000),
+				15000000000,
+				10000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				5000000000000,
+				2500000000000,
+				Price::from_inner(500000000000000000),
+				0,
+				5000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				3000000000000,
+				1702327336909,
+				Price::from_inner(567442445636333333),
+				6000000000,
+				0,
+			)
+			.into(),
 		]);
 	});
 }
@@ -1029,12 +1318,69 @@ fn sell_test_group_sells() {
 fn sell_without_pool_should_not_work() {
 	new_test_ext().execute_with(|| {
 		assert_noop!(
-			Exchange::sell(Origin::signed(ALICE), HDX, ETH, 100, 200, false),
+			Exchange::sell(
+				Origin::signed(ALICE),
+				HDX,
+				ETH,
+				100,
+				200,
+				false,
+				FillPolicy::PartialFill,
+				None
+			),
 			Error::<Test>::TokenPoolNotFound
 		);
 	});
 }
 
+#[test]
+fn sell_and_buy_with_expired_deadline_should_not_work() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 200_000_000_000_000, Price::from(2));
+
+		System::set_block_number(10);
+
+		assert_noop!(
+			Exchange::sell(
+				Origin::signed(BOB),
+				HDX,
+				ETH,
+				1_000_000_000,
+				1,
+				false,
+				FillPolicy::PartialFill,
+				Some(9)
+			),
+			Error::<Test>::DeadlineExpired
+		);
+		assert_noop!(
+			Exchange::buy(
+				Origin::signed(BOB),
+				ETH,
+				HDX,
+				1_000_000_000,
+				3_000_000_000,
+				false,
+				FillPolicy::PartialFill,
+				Some(9)
+			),
+			Error::<Test>::DeadlineExpired
+		);
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000,
+			1,
+			false,
+			FillPolicy::PartialFill,
+			Some(10)
+		));
+		assert_eq!(Exchange::get_intentions_count((HDX, ETH)), 1);
+	});
+}
+
 #[test]
 fn sell_more_than_owner_should_not_work() {
 	new_test_ext().execute_with(|| {
@@ -1043,11 +1389,22 @@ fn sell_more_than_owner_should_not_work() {
 			HDX,
 			ETH,
 			200_000,
-			Price::from(2)
+			Price::from(2),
+			Fee::default(),
+			amm::PoolType::ConstantProduct
 		));
 
 		assert_noop!(
-			Exchange::sell(Origin::signed(ALICE), HDX, ETH, 1000_000_000_000_000u128, 1, false),
+			Exchange::sell(
+				Origin::signed(ALICE),
+				HDX,
+				ETH,
+				1000_000_000_000_000u128,
+				1,
+				false,
+				FillPolicy::PartialFill,
+				None
+			),
 			Error::<Test>::InsufficientAssetBalance
 		);
 	});
@@ -1077,6 +1434,8 @@ fn sell_test_mixed_buy_sells() {
 			5_000_000_000_000,
 			20_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		assert_ok!(Exchange::sell(
@@ -1086,6 +1445,8 @@ fn sell_test_mixed_buy_sells() {
 			3_000_000_000_000,
 			1400_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
 		assert_ok!(Exchange::sell(
@@ -1095,6 +1456,8 @@ fn sell_test_mixed_buy_sells() {
 			10_000_000_000_000,
 			2000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_4_sell_intention_id = generate_intention_id(&user_4, 2);
 
@@ -1157,7 +1520,9 @@ fn sell_test_mixed_buy_sells() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_4, pair_account, asset_b, 6000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_4, pair_account, asset_b, 6000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 3000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 3000000000, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(
 				user_4,
 				asset_a,
@@ -1188,6 +1553,39 @@ fn sell_test_mixed_buy_sells() {
 				3030832926719,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::BUY,
+				user_2_sell_intention_id,
+				3030832926719,
+				5000000000000,
+				Price::from_inner(1649711521846472581),
+				0,
+				10000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_4,
+				IntentionType::SELL,
+				user_4_sell_intention_id,
+				10000000000000,
+				18639353446528,
+				Price::from_inner(1863935344652800000),
+				17000000000,
+				6000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				3000000000000,
+				1500000000000,
+				Price::from_inner(500000000000000000),
+				0,
+				3000000000,
+			)
+			.into(),
 		]);
 	});
 }
@@ -1216,6 +1614,8 @@ fn discount_tests_no_discount() {
 			5_000_000_000_000,
 			20_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		assert_ok!(Exchange::sell(
@@ -1225,6 +1625,8 @@ fn discount_tests_no_discount() {
 			3_000_000_000_000,
 			1400_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
 		assert_ok!(Exchange::sell(
@@ -1234,6 +1636,8 @@ fn discount_tests_no_discount() {
 			10_000_000_000_000,
 			2000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_4_sell_intention_id = generate_intention_id(&user_4, 2);
 
@@ -1296,7 +1700,9 @@ fn discount_tests_no_discount() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_4, pair_account, asset_b, 6000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_4, pair_account, asset_b, 6000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 3000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 3000000000, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(
 				user_4,
 				asset_a,
@@ -1327,6 +1733,39 @@ fn discount_tests_no_discount() {
 				3030832926719,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::BUY,
+				user_2_sell_intention_id,
+				3030832926719,
+				5000000000000,
+				Price::from_inner(1649711521846472581),
+				0,
+				10000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_4,
+				IntentionType::SELL,
+				user_4_sell_intention_id,
+				10000000000000,
+				18639353446528,
+				Price::from_inner(1863935344652800000),
+				17000000000,
+				6000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				3000000000000,
+				1500000000000,
+				Price::from_inner(500000000000000000),
+				0,
+				3000000000,
+			)
+			.into(),
 		]);
 	});
 }
@@ -1357,6 +1796,8 @@ fn discount_tests_with_discount() {
 			5_000_000_000_000,
 			20_000_000_000_000,
 			true,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		assert_ok!(Exchange::sell(
@@ -1366,6 +1807,8 @@ fn discount_tests_with_discount() {
 			3_000_000_000_000,
 			1400_000_000_000,
 			true,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
 		assert_ok!(Exchange::sell(
@@ -1375,6 +1818,8 @@ fn discount_tests_with_discount() {
 			10_000_000_000_000,
 			2000_000_000_000,
 			true,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_4_sell_intention_id = generate_intention_id(&user_4, 2);
 
@@ -1441,7 +1886,9 @@ fn discount_tests_with_discount() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_4, pair_account, asset_b, 6000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_4, pair_account, asset_b, 6000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 3000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 3000000000, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(
 				user_4,
 				asset_a,
@@ -1472,6 +1919,39 @@ fn discount_tests_with_discount() {
 				3027107914884,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::BUY,
+				user_2_sell_intention_id,
+				3027107914884,
+				5000000000000,
+				Price::from_inner(1651741576643329553),
+				0,
+				3500000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_4,
+				IntentionType::SELL,
+				user_4_sell_intention_id,
+				10000000000000,
+				18658130468064,
+				Price::from_inner(1865813046806400000),
+				5950000000,
+				6000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				3000000000000,
+				1500000000000,
+				Price::from_inner(500000000000000000),
+				0,
+				3000000000,
+			)
+			.into(),
 		]);
 	});
 }
@@ -1498,6 +1978,8 @@ fn buy_test_exact_match() {
 			1_000_000_000_000,
 			4_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		assert_ok!(Exchange::buy(
@@ -1507,6 +1989,8 @@ fn buy_test_exact_match() {
 			2_000_000_000_000,
 			4_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
 
@@ -1557,7 +2041,31 @@ fn buy_test_exact_match() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 2000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 2000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 4000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 4000000000, Fee::default()).into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::BUY,
+				user_3_sell_intention_id,
+				1000000000000,
+				2000000000000,
+				Price::from_inner(2000000000000000000),
+				2000000000,
+				0,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::BUY,
+				user_2_sell_intention_id,
+				2000000000000,
+				1000000000000,
+				Price::from_inner(500000000000000000),
+				4000000000,
+				0,
+			)
+			.into(),
 		]);
 	});
 }
@@ -1586,6 +2094,8 @@ fn buy_test_group_buys() {
 			5_000_000_000_000,
 			20_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		assert_ok!(Exchange::buy(
@@ -1595,6 +2105,8 @@ fn buy_test_group_buys() {
 			3_000_000_000_000,
 			20_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
 		assert_ok!(Exchange::buy(
@@ -1604,6 +2116,8 @@ fn buy_test_group_buys() {
 			10_000_000_000_000,
 			22_000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_4_sell_intention_id = generate_intention_id(&user_4, 2);
 
@@ -1681,7 +2195,9 @@ fn buy_test_group_buys() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_a, 5000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_a, 5000000000, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_4, pair_account, asset_b, 10000000000).into(),
+			RawEvent::DirectTradeFeePaid(user_4, pair_account, asset_b, 10000000000, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Buy(
 				user_3,
 				asset_b,
@@ -1697,6 +2213,39 @@ fn buy_test_group_buys() {
 				1303930316730,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::BUY,
+				user_2_sell_intention_id,
+				2500000000000,
+				5000000000000,
+				Price::from_inner(2000000000000000000),
+				5000000000,
+				0,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::BUY,
+				user_3_sell_intention_id,
+				1303930316730,
+				3000000000000,
+				Price::from_inner(2300736443894799663),
+				0,
+				6000000000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_4,
+				IntentionType::BUY,
+				user_4_sell_intention_id,
+				21251283991999,
+				10000000000000,
+				Price::from_inner(470559802587220093),
+				10000000000,
+				15000000000,
+			)
+			.into(),
 		]);
 	});
 }
@@ -1725,6 +2274,8 @@ fn discount_tests_with_error() {
 			5_000_000_000_000,
 			20_000_000_000_000,
 			true,
+			FillPolicy::PartialFill,
+			None,
 		));
 		assert_ok!(Exchange::sell(
 			Origin::signed(user_3),
@@ -1733,6 +2284,8 @@ fn discount_tests_with_error() {
 			3_000_000_000_000,
 			20_000_000_000_000,
 			true,
+			FillPolicy::PartialFill,
+			None,
 		));
 		assert_ok!(Exchange::sell(
 			Origin::signed(user_4),
@@ -1741,6 +2294,8 @@ fn discount_tests_with_error() {
 			10_000_000_000_000,
 			20_000_000_000_000,
 			true,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -1864,6 +2419,8 @@ fn simple_sell_sell() {
 			2_000,
 			400,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		assert_ok!(Exchange::sell(
 			Origin::signed(user_3),
@@ -1872,6 +2429,8 @@ fn simple_sell_sell() {
 			1_000,
 			400,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -1919,20 +2478,44 @@ fn simple_sell_sell() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 2).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 2, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 1).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 1, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(2, 3000, 2000, 1500, 2994)),
 			RawEvent::IntentionResolvedAMMTrade(user_2, IntentionType::SELL, user_2_sell_intention_id, 1500, 2994)
 				.into(),
-		]);
-	});
-}
-
-#[test]
-fn simple_buy_buy() {
-	new_test_ext().execute_with(|| {
-		let user_1 = ALICE;
-		let user_2 = BOB;
-		let user_3 = CHARLIE;
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				2000,
+				3994,
+				Price::from_inner(1997000000000000000),
+				3,
+				2,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				1000,
+				500,
+				Price::from_inner(500000000000000000),
+				0,
+				1,
+			)
+			.into(),
+		]);
+	});
+}
+
+#[test]
+fn simple_buy_buy() {
+	new_test_ext().execute_with(|| {
+		let user_1 = ALICE;
+		let user_2 = BOB;
+		let user_3 = CHARLIE;
 		let asset_a = ETH;
 		let asset_b = DOT;
 		let pool_amount = 100_000_000;
@@ -1949,6 +2532,8 @@ fn simple_buy_buy() {
 			2_000,
 			5000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		assert_ok!(Exchange::buy(
 			Origin::signed(user_3),
@@ -1957,6 +2542,8 @@ fn simple_buy_buy() {
 			1_000,
 			5000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -2007,7 +2594,31 @@ fn simple_buy_buy() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_a, 1).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_a, 1, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 2).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 2, Fee::default()).into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::BUY,
+				user_3_sell_intention_id,
+				500,
+				1000,
+				Price::from_inner(2000000000000000000),
+				1,
+				0,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::BUY,
+				user_2_sell_intention_id,
+				4007,
+				2000,
+				Price::from_inner(499126528574993760),
+				2,
+				3,
+			)
+			.into(),
 		]);
 	});
 }
@@ -2034,6 +2645,8 @@ fn simple_sell_buy() {
 			2_000,
 			400,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		assert_ok!(Exchange::buy(
 			Origin::signed(user_3),
@@ -2042,6 +2655,8 @@ fn simple_sell_buy() {
 			1_000,
 			2_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
@@ -2090,10 +2705,34 @@ fn simple_sell_buy() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 2).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 2, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_b, 4).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_b, 4, Fee::default()).into(),
 			TestEvent::amm(amm::RawEvent::Sell(2, 3000, 2000, 1000, 1996)),
 			RawEvent::IntentionResolvedAMMTrade(user_2, IntentionType::SELL, user_2_sell_intention_id, 1000, 1996)
 				.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				2000,
+				3996,
+				Price::from_inner(1998000000000000000),
+				2,
+				2,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::BUY,
+				user_3_sell_intention_id,
+				2000,
+				1000,
+				Price::from_inner(500000000000000000),
+				4,
+				0,
+			)
+			.into(),
 		]);
 	});
 }
@@ -2120,6 +2759,8 @@ fn simple_buy_sell() {
 			2_000,
 			5000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		assert_ok!(Exchange::sell(
 			Origin::signed(user_3),
@@ -2128,6 +2769,8 @@ fn simple_buy_sell() {
 			1_000,
 			1500,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
@@ -2179,7 +2822,31 @@ fn simple_buy_sell() {
 			)
 			.into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_3, pair_account, asset_b, 2).into(),
+			RawEvent::DirectTradeFeePaid(user_3, pair_account, asset_b, 2, Fee::default()).into(),
 			RawEvent::IntentionResolvedDirectTradeFees(user_2, pair_account, asset_b, 4).into(),
+			RawEvent::DirectTradeFeePaid(user_2, pair_account, asset_b, 4, Fee::default()).into(),
+			RawEvent::IntentionFilled(
+				user_3,
+				IntentionType::SELL,
+				user_3_sell_intention_id,
+				1000,
+				2000,
+				Price::from_inner(2000000000000000000),
+				0,
+				2,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::BUY,
+				user_2_sell_intention_id,
+				4005,
+				2000,
+				Price::from_inner(499375780274656679),
+				4,
+				2,
+			)
+			.into(),
 		]);
 	});
 }
@@ -2205,6 +2872,8 @@ fn single_sell_intention_test() {
 			2_000_000_000_000,
 			400_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
 
@@ -2242,6 +2911,17 @@ fn single_sell_intention_test() {
 				3913878975647,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::SELL,
+				user_2_sell_intention_id,
+				2000000000000,
+				3913878975647,
+				Price::from_inner(1956939487823500000),
+				4000000000,
+				0,
+			)
+			.into(),
 		]);
 	});
 }
@@ -2267,6 +2947,8 @@ fn single_buy_intention_test() {
 			2_000_000_000_000,
 			15000_000_000_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
@@ -2305,6 +2987,17 @@ fn single_buy_intention_test() {
 				4089962855627,
 			)
 			.into(),
+			RawEvent::IntentionFilled(
+				user_2,
+				IntentionType::BUY,
+				user_2_sell_intention_id,
+				4089962855627,
+				2000000000000,
+				Price::from_inner(489001996007955370),
+				0,
+				4000000000,
+			)
+			.into(),
 		]);
 	});
 }
@@ -2331,6 +3024,8 @@ fn simple_sell_sell_with_error_should_not_pass() {
 			2_000,
 			5_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_2_sell_intention_id = generate_intention_id(&user_2, 0);
@@ -2342,6 +3037,8 @@ fn simple_sell_sell_with_error_should_not_pass() {
 			1_000,
 			5_000,
 			false,
+			FillPolicy::PartialFill,
+			None,
 		));
 
 		let user_3_sell_intention_id = generate_intention_id(&user_3, 1);
@@ -2408,3 +3105,2332 @@ fn simple_sell_sell_with_error_should_not_pass() {
 		]);
 	});
 }
+
+#[test]
+fn place_limit_order_should_work() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::place_limit_order(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			Price::from(3),
+			10
+		));
+
+		expect_event(RawEvent::LimitOrderPlaced(
+			BOB,
+			0,
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			Price::from(3),
+			10,
+		));
+
+		assert_eq!(
+			Exchange::limit_orders(0),
+			Some(LimitOrder {
+				who: BOB,
+				asset_sell: HDX,
+				asset_buy: ETH,
+				amount_sell: 1_000_000_000_000,
+				limit_price: Price::from(3),
+				expiry: 10,
+			})
+		);
+		assert_eq!(Exchange::account_limit_orders(BOB), vec![0]);
+		assert_eq!(Exchange::limit_order_count(), 1);
+		assert_eq!(Exchange::next_limit_order_id(), 1);
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 1_000_000_000_000);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 1_000_000_000_000);
+
+		// AMM price is below the limit price - order is kept for next blocks
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Exchange::limit_orders(0).unwrap().amount_sell, 1_000_000_000_000);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 1_000_000_000_000);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 1_000_000_000_000);
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT);
+	});
+}
+
+#[test]
+fn place_limit_order_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Exchange::place_limit_order(Origin::signed(BOB), HDX, ETH, 1_000, Price::from(1), 10),
+			Error::<Test>::TokenPoolNotFound
+		);
+
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_noop!(
+			Exchange::place_limit_order(Origin::signed(BOB), HDX, ETH, 0, Price::from(1), 10),
+			Error::<Test>::ZeroLimitOrderAmount
+		);
+		assert_noop!(
+			Exchange::place_limit_order(Origin::signed(BOB), HDX, ETH, 1_000, Price::from(0), 10),
+			Error::<Test>::ZeroLimitPrice
+		);
+		assert_noop!(
+			Exchange::place_limit_order(Origin::signed(BOB), HDX, ETH, 1_000, Price::from(1), 0),
+			Error::<Test>::InvalidLimitOrderExpiry
+		);
+		assert_noop!(
+			Exchange::place_limit_order(Origin::signed(BOB), HDX, ETH, ENDOWED_AMOUNT + 1, Price::from(1), 10),
+			Error::<Test>::InsufficientAssetBalance
+		);
+		assert_noop!(
+			Exchange::place_limit_order(Origin::signed(BOB), HDX, ETH, 999, Price::from(1), 10),
+			Error::<Test>::InsufficientLimitOrderAmount
+		);
+		assert_noop!(
+			Exchange::place_limit_order(Origin::signed(BOB), HDX, ETH, 1_000, Price::from(1), 102),
+			Error::<Test>::InvalidLimitOrderExpiry
+		);
+
+		for _ in 0..3 {
+			assert_ok!(Exchange::place_limit_order(
+				Origin::signed(BOB),
+				HDX,
+				ETH,
+				1_000,
+				Price::from(1),
+				101
+			));
+		}
+
+		assert_noop!(
+			Exchange::place_limit_order(Origin::signed(BOB), HDX, ETH, 1_000, Price::from(1), 10),
+			Error::<Test>::TooManyLimitOrders
+		);
+	});
+}
+
+#[test]
+fn limit_orders_should_be_processed_in_turns() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		for _ in 0..3 {
+			assert_ok!(Exchange::place_limit_order(
+				Origin::signed(BOB),
+				HDX,
+				ETH,
+				1_000_000_000_000,
+				Price::from(1),
+				10
+			));
+		}
+
+		// Only two orders are processed in a block
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Exchange::limit_orders(0), None);
+		assert_eq!(Exchange::limit_orders(1), None);
+		assert_eq!(Exchange::limit_orders(2).unwrap().amount_sell, 1_000_000_000_000);
+		assert_eq!(Exchange::limit_order_ids(), vec![2]);
+		assert_eq!(Exchange::next_limit_order_to_process(), 2);
+
+		System::set_block_number(2);
+		<Exchange as OnFinalize<u64>>::on_finalize(2);
+
+		assert_eq!(Exchange::limit_orders(2), None);
+		assert_eq!(Exchange::limit_order_count(), 0);
+		assert!(Exchange::limit_order_ids().is_empty());
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 0);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 3_000_000_000_000);
+	});
+}
+
+#[test]
+fn limit_order_expired_while_waiting_for_its_turn_should_be_removed() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		for _ in 0..3 {
+			assert_ok!(Exchange::place_limit_order(
+				Origin::signed(BOB),
+				HDX,
+				ETH,
+				1_000_000_000_000,
+				Price::from(3),
+				1
+			));
+		}
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Exchange::limit_order_ids(), vec![2]);
+
+		System::set_block_number(2);
+		<Exchange as OnFinalize<u64>>::on_finalize(2);
+
+		expect_event(RawEvent::LimitOrderExpired(BOB, 2, 1_000_000_000_000));
+
+		assert_eq!(Exchange::limit_order_count(), 0);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 0);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT);
+	});
+}
+
+#[test]
+fn limit_order_should_be_filled_via_amm() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::place_limit_order(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			Price::from(1),
+			10
+		));
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		let bought = match last_event() {
+			TestEvent::exchange(RawEvent::LimitOrderFilled(BOB, 0, 1_000_000_000_000, bought, 0)) => bought,
+			event => panic!("Unexpected event {:?}", event),
+		};
+		assert!(bought >= 1_000_000_000_000);
+
+		assert_eq!(Exchange::limit_orders(0), None);
+		assert_eq!(Exchange::account_limit_orders(BOB), vec![]);
+		assert_eq!(Exchange::limit_order_count(), 0);
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 1_000_000_000_000);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 0);
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT + bought);
+	});
+}
+
+#[test]
+fn limit_order_should_be_partially_filled_via_amm() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let limit_price = Price::saturating_from_rational(19, 10);
+
+		assert_ok!(Exchange::place_limit_order(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			10_000_000_000_000,
+			limit_price,
+			10
+		));
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		let (sold, bought, remaining) = match last_event() {
+			TestEvent::exchange(RawEvent::LimitOrderFilled(BOB, 0, sold, bought, remaining)) => {
+				(sold, bought, remaining)
+			}
+			event => panic!("Unexpected event {:?}", event),
+		};
+
+		// Only ~5 * 10^12 can be sold before the average price drops below the limit price
+		assert!(sold > 5_000_000_000_000 && sold < 5_100_000_000_000);
+		assert!(bought >= limit_price.checked_mul_int(sold).unwrap());
+		assert_eq!(sold + remaining, 10_000_000_000_000);
+
+		assert_eq!(Exchange::limit_orders(0).unwrap().amount_sell, remaining);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), remaining);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 10_000_000_000_000);
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT + bought);
+
+		// Price has moved to the limit - nothing more is filled in next block
+		System::set_block_number(2);
+		<Exchange as OnFinalize<u64>>::on_finalize(2);
+
+		assert_eq!(Exchange::limit_orders(0).unwrap().amount_sell, remaining);
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT + bought);
+	});
+}
+
+#[test]
+fn limit_order_should_be_matched_with_intention() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let pair_account = AMMModule::get_pair_id(&HDX, &ETH);
+
+		// Limit price equals the spot price - it cannot be filled via AMM because of the fee
+		assert_ok!(Exchange::place_limit_order(
+			Origin::signed(BOB),
+			ETH,
+			HDX,
+			5_000_000_000_000,
+			Price::saturating_from_rational(1, 2),
+			10
+		));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let intention_id = Exchange::get_intentions((HDX, ETH))[0].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		expect_events(vec![
+			RawEvent::IntentionResolvedLimitOrderTrade(
+				CHARLIE,
+				BOB,
+				intention_id,
+				0,
+				1_000_000_000_000,
+				2_000_000_000_000,
+			)
+			.into(),
+			RawEvent::IntentionResolvedDirectTradeFees(CHARLIE, pair_account, ETH, 4_000_000_000).into(),
+			RawEvent::DirectTradeFeePaid(CHARLIE, pair_account, ETH, 4_000_000_000, Fee::default()).into(),
+			RawEvent::LimitOrderFilled(BOB, 0, 2_000_000_000_000, 1_000_000_000_000, 3_000_000_000_000).into(),
+			RawEvent::IntentionFilled(
+				CHARLIE,
+				IntentionType::SELL,
+				intention_id,
+				1000000000000,
+				2000000000000,
+				Price::from_inner(2000000000000000000),
+				0,
+				4000000000,
+			)
+			.into(),
+		]);
+
+		assert_eq!(
+			Currency::free_balance(HDX, &CHARLIE),
+			ENDOWED_AMOUNT - 1_000_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &CHARLIE),
+			ENDOWED_AMOUNT + 2_000_000_000_000 - 4_000_000_000
+		);
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT + 1_000_000_000_000);
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT - 5_000_000_000_000);
+		assert_eq!(Currency::reserved_balance(ETH, &BOB), 3_000_000_000_000);
+
+		assert_eq!(Exchange::limit_orders(0).unwrap().amount_sell, 3_000_000_000_000);
+		assert_eq!(Exchange::get_intentions_count((HDX, ETH)), 0);
+	});
+}
+
+#[test]
+fn cancel_limit_order_should_work() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::place_limit_order(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			Price::from(3),
+			10
+		));
+
+		assert_noop!(
+			Exchange::cancel_limit_order(Origin::signed(CHARLIE), 0),
+			Error::<Test>::NotLimitOrderOwner
+		);
+
+		assert_ok!(Exchange::cancel_limit_order(Origin::signed(BOB), 0));
+
+		expect_event(RawEvent::LimitOrderCancelled(BOB, 0, 1_000_000_000_000));
+
+		assert_eq!(Exchange::limit_orders(0), None);
+		assert_eq!(Exchange::account_limit_orders(BOB), vec![]);
+		assert_eq!(Exchange::limit_order_count(), 0);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 0);
+
+		assert_noop!(
+			Exchange::cancel_limit_order(Origin::signed(BOB), 0),
+			Error::<Test>::LimitOrderNotFound
+		);
+	});
+}
+
+#[test]
+fn limit_order_should_expire() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::place_limit_order(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			Price::from(3),
+			2
+		));
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(Exchange::limit_orders(0).is_some());
+
+		System::set_block_number(2);
+		<Exchange as OnFinalize<u64>>::on_finalize(2);
+
+		expect_event(RawEvent::LimitOrderExpired(BOB, 0, 1_000_000_000_000));
+
+		assert_eq!(Exchange::limit_orders(0), None);
+		assert_eq!(Exchange::account_limit_orders(BOB), vec![]);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 0);
+	});
+}
+
+#[test]
+fn cancel_intention_should_work() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1,
+			false,
+			FillPolicy::PartialFill,
+			None
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			ETH,
+			2_000_000_000_000,
+			1,
+			false,
+			FillPolicy::PartialFill,
+			None
+		));
+
+		let bob_intention_id = Exchange::get_intentions((HDX, ETH))[0].intention_id;
+		let charlie_intention_id = Exchange::get_intentions((HDX, ETH))[1].intention_id;
+
+		assert_noop!(
+			Exchange::cancel_intention(Origin::signed(CHARLIE), bob_intention_id),
+			Error::<Test>::NotIntentionOwner
+		);
+
+		assert_ok!(Exchange::cancel_intention(Origin::signed(BOB), bob_intention_id));
+
+		expect_event(RawEvent::IntentionCancelled(
+			BOB,
+			HDX,
+			ETH,
+			IntentionType::SELL,
+			bob_intention_id,
+		));
+
+		assert_eq!(Exchange::get_intentions_count((HDX, ETH)), 1);
+		assert_eq!(Exchange::get_intentions((HDX, ETH)).len(), 1);
+		assert_eq!(
+			Exchange::get_intentions((HDX, ETH))[0].intention_id,
+			charlie_intention_id
+		);
+
+		assert_noop!(
+			Exchange::cancel_intention(Origin::signed(BOB), bob_intention_id),
+			Error::<Test>::IntentionNotFound
+		);
+
+		// New intention of the same account gets a new id
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1,
+			false,
+			FillPolicy::PartialFill,
+			None
+		));
+
+		let new_bob_intention_id = Exchange::get_intentions((HDX, ETH))[1].intention_id;
+
+		assert_ne!(new_bob_intention_id, bob_intention_id);
+		assert_ne!(new_bob_intention_id, charlie_intention_id);
+		assert_eq!(Exchange::get_intentions_count((HDX, ETH)), 2);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Exchange::get_intentions_count((HDX, ETH)), 0);
+		assert_eq!(Exchange::get_intention_key(new_bob_intention_id), None);
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 1_000_000_000_000);
+		assert_eq!(
+			Currency::free_balance(HDX, &CHARLIE),
+			ENDOWED_AMOUNT - 2_000_000_000_000
+		);
+	});
+}
+
+#[test]
+fn cancel_not_registered_intention_should_not_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Exchange::cancel_intention(Origin::signed(BOB), generate_intention_id(&BOB, 0)),
+			Error::<Test>::IntentionNotFound
+		);
+	});
+}
+
+#[test]
+fn intention_amount_should_be_reserved_until_resolved() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1,
+			false,
+			FillPolicy::PartialFill,
+			None
+		));
+
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 1_002_000_000_000);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 1_002_000_000_000);
+
+		// Reserved amount cannot be used by another intention
+		assert_noop!(
+			Exchange::sell(
+				Origin::signed(BOB),
+				HDX,
+				ETH,
+				ENDOWED_AMOUNT - 1_000_000_000_000,
+				1,
+				false,
+				FillPolicy::PartialFill,
+				None
+			),
+			Error::<Test>::InsufficientAssetBalance
+		);
+
+		assert_ok!(Exchange::buy(
+			Origin::signed(BOB),
+			ETH,
+			HDX,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None
+		));
+
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 2_004_000_000_000);
+
+		let buy_intention_id = Exchange::get_intentions((HDX, ETH))[1].intention_id;
+
+		assert_ok!(Exchange::cancel_intention(Origin::signed(BOB), buy_intention_id));
+
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 1_002_000_000_000);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 0);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 1_000_000_000_000);
+	});
+}
+
+fn batch_auction_cleared() -> (AssetId, AssetId, Price, Balance, Balance) {
+	system::Module::<Test>::events()
+		.into_iter()
+		.find_map(|record| match record.event {
+			TestEvent::exchange(RawEvent::BatchAuctionCleared(asset_in, asset_out, price, amm_in, amm_out)) => {
+				Some((asset_in, asset_out, price, amm_in, amm_out))
+			}
+			_ => None,
+		})
+		.expect("Batch auction should be cleared")
+}
+
+#[test]
+fn batch_auction_should_match_balanced_intentions_at_spot_price() {
+	new_batch_auction_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let pair_account = AMMModule::get_pair_id(&HDX, &ETH);
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			2_000_000_000_000,
+			4_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			ETH,
+			HDX,
+			4_000_000_000_000,
+			2_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let bob_intention_id = Exchange::get_intentions((HDX, ETH))[0].intention_id;
+		let charlie_intention_id = Exchange::get_intentions((ETH, HDX))[0].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		expect_events(vec![
+			RawEvent::BatchAuctionCleared(HDX, ETH, Price::from(2), 0, 0).into(),
+			RawEvent::IntentionResolvedBatchTrade(
+				BOB,
+				IntentionType::SELL,
+				bob_intention_id,
+				2_000_000_000_000,
+				4_000_000_000_000,
+			)
+			.into(),
+			RawEvent::IntentionResolvedDirectTradeFees(BOB, pair_account, ETH, 8_000_000_000).into(),
+			RawEvent::DirectTradeFeePaid(BOB, pair_account, ETH, 8_000_000_000, Fee::default()).into(),
+			RawEvent::IntentionResolvedBatchTrade(
+				CHARLIE,
+				IntentionType::SELL,
+				charlie_intention_id,
+				4_000_000_000_000,
+				2_000_000_000_000,
+			)
+			.into(),
+			RawEvent::IntentionResolvedDirectTradeFees(CHARLIE, pair_account, HDX, 4_000_000_000).into(),
+			RawEvent::DirectTradeFeePaid(CHARLIE, pair_account, HDX, 4_000_000_000, Fee::default()).into(),
+			RawEvent::IntentionFilled(
+				CHARLIE,
+				IntentionType::SELL,
+				charlie_intention_id,
+				4000000000000,
+				2000000000000,
+				Price::from_inner(500000000000000000),
+				0,
+				4_000_000_000,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				BOB,
+				IntentionType::SELL,
+				bob_intention_id,
+				2000000000000,
+				4000000000000,
+				Price::from_inner(2000000000000000000),
+				0,
+				8_000_000_000,
+			)
+			.into(),
+		]);
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 2_000_000_000_000);
+		assert_eq!(
+			Currency::free_balance(ETH, &BOB),
+			ENDOWED_AMOUNT + 4_000_000_000_000 - 8_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(HDX, &CHARLIE),
+			ENDOWED_AMOUNT + 2_000_000_000_000 - 4_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &CHARLIE),
+			ENDOWED_AMOUNT - 4_000_000_000_000
+		);
+
+		// Nothing was traded via AMM, the pool only received direct trade fees
+		assert_eq!(
+			Currency::free_balance(HDX, &pair_account),
+			100_000_000_000_000 + 4_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &pair_account),
+			200_000_000_000_000 + 8_000_000_000
+		);
+
+		assert_eq!(Currency::free_balance(HDX, &Exchange::settlement_account()), 0);
+		assert_eq!(Currency::free_balance(ETH, &Exchange::settlement_account()), 0);
+	});
+}
+
+#[test]
+fn batch_auction_should_give_all_intentions_same_price() {
+	new_batch_auction_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let pair_account = AMMModule::get_pair_id(&HDX, &ETH);
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			2_000_000_000_000,
+			3_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			ETH,
+			HDX,
+			1_000_000_000_000,
+			400_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(DAVE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_500_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		let (asset_in, asset_out, price, amm_in, amm_out) = batch_auction_cleared();
+
+		// Excess of HDX is sold via AMM, so everybody gets slightly less than spot price
+		assert_eq!((asset_in, asset_out), (HDX, ETH));
+		assert!(price < Price::from(2));
+
+		let bob_bought = price.checked_mul_int(2_000_000_000_000u128).unwrap();
+		let dave_bought = price.checked_mul_int(1_000_000_000_000u128).unwrap();
+		let charlie_bought = 1_000_000_000_000 * Price::accuracy() / price.into_inner();
+
+		assert_eq!(amm_in, 3_000_000_000_000 - charlie_bought);
+		assert!(amm_out + 1_000_000_000_000 >= bob_bought + dave_bought);
+
+		// Direct trade fee is paid only from the part matched with the other side
+		let fee = |amount: Balance| amount * 2 / 1000;
+		let bob_fee = fee(bob_bought * charlie_bought / 3_000_000_000_000);
+		let dave_fee = fee(dave_bought * charlie_bought / 3_000_000_000_000);
+		let charlie_fee = fee(charlie_bought);
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT - 2_000_000_000_000);
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT + bob_bought - bob_fee);
+		assert_eq!(Currency::free_balance(HDX, &DAVE), ENDOWED_AMOUNT - 1_000_000_000_000);
+		assert_eq!(
+			Currency::free_balance(ETH, &DAVE),
+			ENDOWED_AMOUNT + dave_bought - dave_fee
+		);
+		assert_eq!(
+			Currency::free_balance(HDX, &CHARLIE),
+			ENDOWED_AMOUNT + charlie_bought - charlie_fee
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &CHARLIE),
+			ENDOWED_AMOUNT - 1_000_000_000_000
+		);
+
+		// Only the imbalance went through AMM, rounding leftovers and fees stay in the pool
+		assert_eq!(
+			Currency::free_balance(HDX, &pair_account),
+			100_000_000_000_000 + amm_in + charlie_fee
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &pair_account),
+			200_000_000_000_000 + 1_000_000_000_000 - bob_bought - dave_bought + bob_fee + dave_fee
+		);
+
+		assert_eq!(Currency::free_balance(HDX, &Exchange::settlement_account()), 0);
+		assert_eq!(Currency::free_balance(ETH, &Exchange::settlement_account()), 0);
+		assert_eq!(Exchange::get_intentions_count((HDX, ETH)), 0);
+	});
+}
+
+#[test]
+fn batch_auction_should_refund_intention_with_unsatisfied_limit() {
+	new_batch_auction_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		// Limit equals the spot amount - it cannot be satisfied if any imbalance is sold via AMM
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			2_000_000_000_000,
+			4_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			ETH,
+			HDX,
+			1_000_000_000_000,
+			400_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let bob_intention_id = Exchange::get_intentions((HDX, ETH))[0].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(system::Module::<Test>::events().iter().any(|record| record.event
+			== RawEvent::IntentionResolveErrorEvent(
+				BOB,
+				HDX,
+				ETH,
+				IntentionType::SELL,
+				bob_intention_id,
+				Error::<Test>::AssetBalanceLimitExceeded.into(),
+			)
+			.into()));
+
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT);
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 0);
+
+		// The other intention is resolved alone against AMM
+		let (asset_in, asset_out, price, amm_in, _) = batch_auction_cleared();
+
+		assert_eq!((asset_in, asset_out), (ETH, HDX));
+		assert_eq!(amm_in, 1_000_000_000_000);
+		assert_eq!(
+			Currency::free_balance(HDX, &CHARLIE),
+			ENDOWED_AMOUNT + price.checked_mul_int(1_000_000_000_000u128).unwrap()
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &CHARLIE),
+			ENDOWED_AMOUNT - 1_000_000_000_000
+		);
+	});
+}
+
+#[test]
+fn batch_auction_should_charge_discounted_direct_trade_fee() {
+	let discounted_fee = Fee {
+		numerator: 7,
+		denominator: 10_000,
+	};
+
+	let mut ext = ExtBuilder::default()
+		.with_batch_auction()
+		.with_discounted_direct_trade_fee(discounted_fee)
+		.build();
+	ext.execute_with(|| System::set_block_number(1));
+
+	ext.execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let pair_account = AMMModule::get_pair_id(&HDX, &ETH);
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			2_000_000_000_000,
+			4_000_000_000_000,
+			true,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			ETH,
+			HDX,
+			4_000_000_000_000,
+			2_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			BOB,
+			pair_account,
+			ETH,
+			2_800_000_000,
+			discounted_fee
+		)));
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			CHARLIE,
+			pair_account,
+			HDX,
+			4_000_000_000,
+			Fee::default()
+		)));
+
+		assert_eq!(
+			Currency::free_balance(ETH, &BOB),
+			ENDOWED_AMOUNT + 4_000_000_000_000 - 2_800_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(HDX, &CHARLIE),
+			ENDOWED_AMOUNT + 2_000_000_000_000 - 4_000_000_000
+		);
+	});
+}
+
+fn initialize_ring_pools() {
+	// 1 HDX = 2 DOT = 4 ETH
+	initialize_pool(HDX, DOT, ALICE, 100_000_000_000_000, Price::from(2));
+	initialize_pool(DOT, ETH, BOB, 100_000_000_000_000, Price::from(2));
+	initialize_pool(HDX, ETH, GEORGE, 100_000_000_000_000, Price::from(4));
+}
+
+fn ring_trade_resolved() -> bool {
+	system::Module::<Test>::events().iter().any(|record| {
+		matches!(
+			record.event,
+			TestEvent::exchange(RawEvent::IntentionsResolvedRingTrade(_))
+		)
+	})
+}
+
+#[test]
+fn ring_of_intentions_should_be_traded_directly() {
+	new_test_ext().execute_with(|| {
+		initialize_ring_pools();
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			DOT,
+			1_000_000_000_000,
+			1_900_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(DAVE),
+			DOT,
+			ETH,
+			2_000_000_000_000,
+			3_900_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(FERDIE),
+			ETH,
+			HDX,
+			4_000_000_000_000,
+			900_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let charlie_intention_id = Exchange::get_intentions((HDX, DOT))[0].intention_id;
+		let dave_intention_id = Exchange::get_intentions((DOT, ETH))[0].intention_id;
+		let ferdie_intention_id = Exchange::get_intentions((ETH, HDX))[0].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::IntentionsResolvedRingTrade(vec![
+			(
+				CHARLIE,
+				charlie_intention_id,
+				HDX,
+				DOT,
+				1_000_000_000_000,
+				2_000_000_000_000,
+				4_000_000_000,
+			),
+			(
+				DAVE,
+				dave_intention_id,
+				DOT,
+				ETH,
+				2_000_000_000_000,
+				4_000_000_000_000,
+				8_000_000_000,
+			),
+			(
+				FERDIE,
+				ferdie_intention_id,
+				ETH,
+				HDX,
+				4_000_000_000_000,
+				1_000_000_000_000,
+				2_000_000_000,
+			),
+		])));
+
+		// Intentions are completely filled by the ring
+		assert!(event_emitted(RawEvent::IntentionFilled(
+			CHARLIE,
+			IntentionType::SELL,
+			charlie_intention_id,
+			1000000000000,
+			2000000000000,
+			Price::from_inner(2000000000000000000),
+			0,
+			4000000000,
+		)));
+		assert!(event_emitted(RawEvent::IntentionFilled(
+			DAVE,
+			IntentionType::SELL,
+			dave_intention_id,
+			2000000000000,
+			4000000000000,
+			Price::from_inner(2000000000000000000),
+			0,
+			8000000000,
+		)));
+		assert!(event_emitted(RawEvent::IntentionFilled(
+			FERDIE,
+			IntentionType::SELL,
+			ferdie_intention_id,
+			4000000000000,
+			1000000000000,
+			Price::from_inner(250000000000000000),
+			0,
+			2000000000,
+		)));
+
+		assert_eq!(
+			Currency::free_balance(HDX, &CHARLIE),
+			ENDOWED_AMOUNT - 1_000_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(DOT, &CHARLIE),
+			ENDOWED_AMOUNT + 2_000_000_000_000 - 4_000_000_000
+		);
+		assert_eq!(Currency::free_balance(DOT, &DAVE), ENDOWED_AMOUNT - 2_000_000_000_000);
+		assert_eq!(
+			Currency::free_balance(ETH, &DAVE),
+			ENDOWED_AMOUNT + 4_000_000_000_000 - 8_000_000_000
+		);
+		assert_eq!(Currency::free_balance(ETH, &FERDIE), ENDOWED_AMOUNT - 4_000_000_000_000);
+		assert_eq!(
+			Currency::free_balance(HDX, &FERDIE),
+			ENDOWED_AMOUNT + 1_000_000_000_000 - 2_000_000_000
+		);
+
+		// Fees are paid to the pool of each intention's pair
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			CHARLIE,
+			AMMModule::get_pair_id(&HDX, &DOT),
+			DOT,
+			4_000_000_000,
+			Fee::default()
+		)));
+		assert_eq!(
+			Currency::free_balance(DOT, &AMMModule::get_pair_id(&HDX, &DOT)),
+			200_000_000_000_000 + 4_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &AMMModule::get_pair_id(&DOT, &ETH)),
+			200_000_000_000_000 + 8_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(HDX, &AMMModule::get_pair_id(&HDX, &ETH)),
+			100_000_000_000_000 + 2_000_000_000
+		);
+
+		assert_eq!(Currency::reserved_balance(HDX, &CHARLIE), 0);
+		assert_eq!(Currency::reserved_balance(DOT, &DAVE), 0);
+		assert_eq!(Currency::reserved_balance(ETH, &FERDIE), 0);
+	});
+}
+
+#[test]
+fn ring_rest_amount_should_be_traded_via_amm() {
+	new_test_ext().execute_with(|| {
+		initialize_ring_pools();
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			DOT,
+			1_000_000_000_000,
+			1_900_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(DAVE),
+			DOT,
+			ETH,
+			2_000_000_000_000,
+			3_900_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(FERDIE),
+			ETH,
+			HDX,
+			8_000_000_000_000,
+			1_800_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(ring_trade_resolved());
+
+		// Half of the intention is traded in the ring, the rest via AMM
+		assert!(system::Module::<Test>::events().iter().any(|record| matches!(
+			record.event,
+			TestEvent::exchange(RawEvent::IntentionResolvedAMMTrade(
+				FERDIE,
+				IntentionType::SELL,
+				_,
+				4_000_000_000_000,
+				_
+			))
+		)));
+
+		// Both parts are reported in one fill
+		assert!(system::Module::<Test>::events().iter().any(|record| matches!(
+			record.event,
+			TestEvent::exchange(RawEvent::IntentionFilled(
+				FERDIE,
+				IntentionType::SELL,
+				_,
+				8_000_000_000_000,
+				_,
+				_,
+				_,
+				_
+			))
+		)));
+
+		assert_eq!(Currency::free_balance(ETH, &FERDIE), ENDOWED_AMOUNT - 8_000_000_000_000);
+		assert!(Currency::free_balance(HDX, &FERDIE) > ENDOWED_AMOUNT + 1_800_000_000_000);
+	});
+}
+
+#[test]
+fn ring_should_not_be_traded_if_trade_limit_is_not_satisfied() {
+	new_test_ext().execute_with(|| {
+		initialize_ring_pools();
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			DOT,
+			1_000_000_000_000,
+			1_900_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(DAVE),
+			DOT,
+			ETH,
+			2_000_000_000_000,
+			3_900_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		// Trade limit equals the spot amount - it cannot be satisfied with the direct trade fee
+		assert_ok!(Exchange::sell(
+			Origin::signed(FERDIE),
+			ETH,
+			HDX,
+			4_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(!ring_trade_resolved());
+
+		assert_eq!(
+			Currency::free_balance(HDX, &CHARLIE),
+			ENDOWED_AMOUNT - 1_000_000_000_000
+		);
+		assert_eq!(Currency::free_balance(DOT, &DAVE), ENDOWED_AMOUNT - 2_000_000_000_000);
+		assert_eq!(Currency::free_balance(ETH, &FERDIE), ENDOWED_AMOUNT);
+	});
+}
+
+#[test]
+fn ring_search_should_consider_only_first_intentions_of_each_pair() {
+	new_test_ext().execute_with(|| {
+		initialize_ring_pools();
+
+		// Trade limits of earlier intentions of the pair cannot be satisfied by a ring
+		for _ in 0..crate::ring::MAX_RING_CANDIDATES {
+			sell_intention(CHARLIE, HDX, DOT, 1_000_000_000_000, 3_000_000_000_000);
+		}
+
+		sell_intention(CHARLIE, HDX, DOT, 1_000_000_000_000, 1_900_000_000_000);
+		sell_intention(DAVE, DOT, ETH, 2_000_000_000_000, 3_900_000_000_000);
+		sell_intention(FERDIE, ETH, HDX, 4_000_000_000_000, 900_000_000_000);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(!ring_trade_resolved());
+	});
+}
+
+#[test]
+fn intentions_over_block_limit_should_be_queued() {
+	let mut ext = ExtBuilder::default().with_max_intentions_per_block(2).build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		for user in [BOB, CHARLIE, DAVE].iter() {
+			assert_ok!(Exchange::sell(
+				Origin::signed(*user),
+				HDX,
+				ETH,
+				1_000_000_000_000,
+				1_000_000_000_000,
+				false,
+				FillPolicy::PartialFill,
+				None,
+			));
+		}
+
+		let dave_intention_id = Exchange::get_intentions((HDX, ETH))[2].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(system::Module::<Test>::events()
+			.iter()
+			.any(|record| record.event == RawEvent::IntentionQueued(DAVE, dave_intention_id).into()));
+
+		assert_eq!(Exchange::queue_depth(), 1);
+		assert_eq!(Exchange::intention_queue()[0].intention_id, dave_intention_id);
+		assert_eq!(Exchange::get_intentions_count((HDX, ETH)), 0);
+
+		assert!(Currency::free_balance(ETH, &BOB) > ENDOWED_AMOUNT);
+		assert!(Currency::free_balance(ETH, &CHARLIE) > ENDOWED_AMOUNT);
+
+		// Queued intention stays reserved
+		assert_eq!(Currency::free_balance(ETH, &DAVE), ENDOWED_AMOUNT);
+		assert_eq!(Currency::reserved_balance(HDX, &DAVE), 1_002_000_000_000);
+
+		System::set_block_number(2);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(2);
+
+		assert_eq!(Exchange::queue_depth(), 0);
+		assert_eq!(Currency::reserved_balance(HDX, &DAVE), 0);
+		assert_eq!(Currency::free_balance(HDX, &DAVE), ENDOWED_AMOUNT - 1_000_000_000_000);
+		assert!(Currency::free_balance(ETH, &DAVE) > ENDOWED_AMOUNT);
+	});
+}
+
+#[test]
+fn queued_intentions_should_be_resolved_first() {
+	let mut ext = ExtBuilder::default().with_max_intentions_per_block(1).build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Exchange::queue_depth(), 1);
+
+		System::set_block_number(2);
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(DAVE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let dave_intention_id = Exchange::get_intentions((HDX, ETH))[0].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(2);
+
+		// Charlie's intention from previous block is resolved, Dave's intention is queued
+		assert!(Currency::free_balance(ETH, &CHARLIE) > ENDOWED_AMOUNT);
+		assert_eq!(Currency::free_balance(ETH, &DAVE), ENDOWED_AMOUNT);
+
+		assert_eq!(Exchange::queue_depth(), 1);
+		assert_eq!(Exchange::intention_queue()[0].intention_id, dave_intention_id);
+	});
+}
+
+#[test]
+fn cancel_queued_intention_should_work() {
+	let mut ext = ExtBuilder::default().with_max_intentions_per_block(1).build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let charlie_intention_id = Exchange::get_intentions((HDX, ETH))[1].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		System::set_block_number(2);
+
+		assert_noop!(
+			Exchange::cancel_intention(Origin::signed(BOB), charlie_intention_id),
+			Error::<Test>::NotIntentionOwner
+		);
+
+		assert_ok!(Exchange::cancel_intention(
+			Origin::signed(CHARLIE),
+			charlie_intention_id
+		));
+
+		expect_event(RawEvent::IntentionCancelled(
+			CHARLIE,
+			HDX,
+			ETH,
+			IntentionType::SELL,
+			charlie_intention_id,
+		));
+
+		assert_eq!(Exchange::queue_depth(), 0);
+		assert_eq!(Currency::reserved_balance(HDX, &CHARLIE), 0);
+		assert_eq!(Currency::free_balance(HDX, &CHARLIE), ENDOWED_AMOUNT);
+	});
+}
+
+#[test]
+fn expired_queued_intention_should_be_dropped() {
+	let mut ext = ExtBuilder::default().with_max_intentions_per_block(1).build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			Some(1),
+		));
+
+		let charlie_intention_id = Exchange::get_intentions((HDX, ETH))[1].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Exchange::queue_depth(), 1);
+		assert_eq!(Exchange::intention_queue()[0].deadline, Some(1));
+
+		System::set_block_number(2);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(2);
+
+		assert!(event_emitted(RawEvent::IntentionExpired(CHARLIE, charlie_intention_id)));
+
+		assert_eq!(Exchange::queue_depth(), 0);
+		assert_eq!(Currency::reserved_balance(HDX, &CHARLIE), 0);
+		assert_eq!(Currency::free_balance(HDX, &CHARLIE), ENDOWED_AMOUNT);
+		assert_eq!(Currency::free_balance(ETH, &CHARLIE), ENDOWED_AMOUNT);
+	});
+}
+
+#[test]
+fn intention_should_not_be_registered_if_queue_is_full() {
+	let mut ext = ExtBuilder::default().with_max_intentions_per_block(1).build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		// One intention is resolved in the block, two fit into the queue
+		for user in [BOB, CHARLIE, DAVE].iter() {
+			assert_ok!(Exchange::sell(
+				Origin::signed(*user),
+				HDX,
+				ETH,
+				1_000_000_000_000,
+				1_000_000_000_000,
+				false,
+				FillPolicy::PartialFill,
+				None,
+			));
+		}
+
+		assert_noop!(
+			Exchange::buy(
+				Origin::signed(FERDIE),
+				ETH,
+				HDX,
+				1_000_000_000_000,
+				1_000_000_000_000,
+				false,
+				FillPolicy::PartialFill,
+				None,
+			),
+			Error::<Test>::TooManyIntentions
+		);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Exchange::queue_depth(), 2);
+
+		// Queue is still full in next block
+		System::set_block_number(2);
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(FERDIE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_noop!(
+			Exchange::sell(
+				Origin::signed(FERDIE),
+				HDX,
+				ETH,
+				1_000_000_000_000,
+				1_000_000_000_000,
+				false,
+				FillPolicy::PartialFill,
+				None,
+			),
+			Error::<Test>::TooManyIntentions
+		);
+	});
+}
+
+#[test]
+fn limit_orders_should_count_against_block_limit() {
+	let mut ext = ExtBuilder::default().with_max_intentions_per_block(1).build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		// Limit price cannot be reached - the order keeps resting
+		assert_ok!(Exchange::place_limit_order(
+			Origin::signed(ALICE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			Price::from(100),
+			10,
+		));
+
+		let bob_intention_id = sell_intention(BOB, HDX, ETH, 1_000_000_000_000, 1_000_000_000_000);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::IntentionQueued(BOB, bob_intention_id)));
+		assert_eq!(Exchange::queue_depth(), 1);
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT);
+	});
+}
+
+/// Bob's buy intention is partially traded directly with Charlie's sell intention,
+/// its rest cannot be bought via AMM within the trade limit afterwards.
+fn register_partially_fillable_intention(fill_policy: FillPolicy) -> (IntentionId<Test>, IntentionId<Test>) {
+	initialize_pool(ETH, DOT, ALICE, 100_000_000, Price::from(2));
+
+	assert_ok!(Exchange::buy(
+		Origin::signed(BOB),
+		DOT,
+		ETH,
+		2_000,
+		1_500,
+		false,
+		fill_policy,
+		None,
+	));
+	assert_ok!(Exchange::sell(
+		Origin::signed(CHARLIE),
+		DOT,
+		ETH,
+		1_000,
+		400,
+		false,
+		FillPolicy::PartialFill,
+		None,
+	));
+
+	(
+		Exchange::get_intentions((ETH, DOT))[0].intention_id,
+		Exchange::get_intentions((DOT, ETH))[0].intention_id,
+	)
+}
+
+#[test]
+fn partially_filled_intention_should_be_reported() {
+	new_test_ext().execute_with(|| {
+		let (bob_intention_id, charlie_intention_id) = register_partially_fillable_intention(FillPolicy::PartialFill);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		// Direct trade is kept, AMM trade of the rest failed
+		expect_events(vec![
+			RawEvent::IntentionFilled(
+				BOB,
+				IntentionType::BUY,
+				bob_intention_id,
+				500,
+				1_000,
+				Price::from(2),
+				1,
+				0,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				CHARLIE,
+				IntentionType::SELL,
+				charlie_intention_id,
+				1_000,
+				500,
+				Price::saturating_from_rational(1, 2),
+				0,
+				2,
+			)
+			.into(),
+		]);
+
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT - 501);
+		assert_eq!(Currency::free_balance(DOT, &BOB), ENDOWED_AMOUNT + 1_000);
+		assert_eq!(Currency::reserved_balance(ETH, &BOB), 0);
+	});
+}
+
+#[test]
+fn fill_or_kill_intention_should_not_be_partially_filled() {
+	new_test_ext().execute_with(|| {
+		let (bob_intention_id, charlie_intention_id) = register_partially_fillable_intention(FillPolicy::FillOrKill);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::IntentionResolveErrorEvent(
+			BOB,
+			ETH,
+			DOT,
+			IntentionType::BUY,
+			bob_intention_id,
+			Error::<Test>::IntentionNotFilled.into(),
+		)));
+
+		// Direct trade is reverted, matched intention is traded via AMM instead
+		assert!(matches!(
+			last_event(),
+			TestEvent::exchange(RawEvent::IntentionFilled(CHARLIE, IntentionType::SELL, id, 1_000, _, _, 2, 0))
+				if id == charlie_intention_id
+		));
+		assert!(!system::Module::<Test>::events().iter().any(|record| matches!(
+			record.event,
+			TestEvent::exchange(RawEvent::IntentionFilled(BOB, ..))
+				| TestEvent::exchange(RawEvent::IntentionResolvedDirectTrade(..))
+		)));
+
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT);
+		assert_eq!(Currency::free_balance(DOT, &BOB), ENDOWED_AMOUNT);
+		assert_eq!(Currency::reserved_balance(ETH, &BOB), 0);
+		assert_eq!(Currency::free_balance(DOT, &CHARLIE), ENDOWED_AMOUNT - 1_000);
+		assert!(Currency::free_balance(ETH, &CHARLIE) > ENDOWED_AMOUNT + 400);
+		assert_eq!(Exchange::queue_depth(), 0);
+	});
+}
+
+#[test]
+fn all_or_none_intention_should_stay_queued_until_filled() {
+	new_test_ext().execute_with(|| {
+		let (bob_intention_id, _) = register_partially_fillable_intention(FillPolicy::AllOrNone);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::IntentionQueued(BOB, bob_intention_id)));
+
+		assert_eq!(Exchange::queue_depth(), 1);
+		assert_eq!(Currency::free_balance(DOT, &BOB), ENDOWED_AMOUNT);
+		assert_eq!(Currency::reserved_balance(ETH, &BOB), 1_503);
+
+		// Whole intention can be bought via AMM in next block
+		System::set_block_number(2);
+		<Exchange as OnFinalize<u64>>::on_finalize(2);
+
+		assert!(matches!(
+			last_event(),
+			TestEvent::exchange(RawEvent::IntentionFilled(BOB, IntentionType::BUY, id, _, 2_000, _, 0, 4))
+				if id == bob_intention_id
+		));
+
+		assert_eq!(Exchange::queue_depth(), 0);
+		assert_eq!(Currency::free_balance(DOT, &BOB), ENDOWED_AMOUNT + 2_000);
+		assert_eq!(Currency::reserved_balance(ETH, &BOB), 0);
+	});
+}
+
+#[test]
+fn all_or_none_intention_should_be_cancelled_after_max_requeues() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		// Trade limit cannot be satisfied via AMM
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			4_000_000_000_000,
+			false,
+			FillPolicy::AllOrNone,
+			None,
+		));
+
+		let bob_intention_id = Exchange::get_intentions((HDX, ETH))[0].intention_id;
+
+		for block in 1..=MaxIntentionRequeues::get() as u64 {
+			System::set_block_number(block);
+			<Exchange as OnFinalize<u64>>::on_finalize(block);
+
+			assert_eq!(Exchange::queue_depth(), 1);
+			assert_eq!(Exchange::intention_requeues(bob_intention_id), block as u32);
+		}
+
+		let block = MaxIntentionRequeues::get() as u64 + 1;
+		System::set_block_number(block);
+		<Exchange as OnFinalize<u64>>::on_finalize(block);
+
+		expect_event(RawEvent::IntentionCancelled(
+			BOB,
+			HDX,
+			ETH,
+			IntentionType::SELL,
+			bob_intention_id,
+		));
+
+		assert_eq!(Exchange::queue_depth(), 0);
+		assert_eq!(Exchange::intention_requeues(bob_intention_id), 0);
+		assert_eq!(Currency::reserved_balance(HDX, &BOB), 0);
+		assert_eq!(Currency::free_balance(HDX, &BOB), ENDOWED_AMOUNT);
+	});
+}
+
+#[test]
+fn matched_intention_should_be_traded_directly_if_amm_trade_fails() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000, Price::from(2));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			ETH,
+			DOT,
+			1_000,
+			1_500,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		// Rest of the intention cannot be sold via AMM within the trade limit
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			DOT,
+			ETH,
+			4_000,
+			1_999,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let bob_intention_id = Exchange::get_intentions((ETH, DOT))[0].intention_id;
+		let charlie_intention_id = Exchange::get_intentions((DOT, ETH))[0].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		expect_events(vec![
+			RawEvent::IntentionResolvedDirectTrade(BOB, CHARLIE, bob_intention_id, charlie_intention_id, 1_000, 2_000)
+				.into(),
+			RawEvent::IntentionResolvedDirectTradeFees(BOB, AMMModule::get_pair_id(&ETH, &DOT), DOT, 4).into(),
+			RawEvent::DirectTradeFeePaid(BOB, AMMModule::get_pair_id(&ETH, &DOT), DOT, 4, Fee::default()).into(),
+			RawEvent::IntentionResolvedDirectTradeFees(CHARLIE, AMMModule::get_pair_id(&ETH, &DOT), ETH, 2).into(),
+			RawEvent::DirectTradeFeePaid(CHARLIE, AMMModule::get_pair_id(&ETH, &DOT), ETH, 2, Fee::default()).into(),
+			RawEvent::IntentionFilled(
+				BOB,
+				IntentionType::SELL,
+				bob_intention_id,
+				1_000,
+				2_000,
+				Price::from(2),
+				0,
+				4,
+			)
+			.into(),
+			RawEvent::IntentionFilled(
+				CHARLIE,
+				IntentionType::SELL,
+				charlie_intention_id,
+				2_000,
+				1_000,
+				Price::saturating_from_rational(1, 2),
+				0,
+				2,
+			)
+			.into(),
+		]);
+
+		assert_eq!(Currency::free_balance(DOT, &CHARLIE), ENDOWED_AMOUNT - 2_000);
+		assert_eq!(Currency::free_balance(ETH, &CHARLIE), ENDOWED_AMOUNT + 998);
+		assert_eq!(Currency::reserved_balance(DOT, &CHARLIE), 0);
+	});
+}
+
+#[test]
+fn pending_intentions_should_include_queued_intentions() {
+	let mut ext = ExtBuilder::default().with_max_intentions_per_block(1).build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext.execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+		assert_ok!(Exchange::sell(
+			Origin::signed(CHARLIE),
+			HDX,
+			ETH,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let charlie_intention_id = Exchange::get_intentions((HDX, ETH))[1].intention_id;
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		System::set_block_number(2);
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(DAVE),
+			ETH,
+			HDX,
+			1_000_000_000_000,
+			1_000_000_000_000,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let dave_intention_id = Exchange::get_intentions((ETH, HDX))[0].intention_id;
+
+		let pending: Vec<_> = Exchange::pending_intentions(HDX, ETH)
+			.iter()
+			.map(|intention| intention.intention_id)
+			.collect();
+		assert_eq!(pending, vec![charlie_intention_id, dave_intention_id]);
+		assert_eq!(Exchange::pending_intentions(ETH, HDX).len(), 2);
+		assert!(Exchange::pending_intentions(HDX, DOT).is_empty());
+
+		assert_eq!(Exchange::intentions_of(&CHARLIE)[0].intention_id, charlie_intention_id);
+		assert_eq!(Exchange::intentions_of(&DAVE)[0].intention_id, dave_intention_id);
+		assert_eq!(Exchange::intentions_of(&DAVE)[0].amount_sell, 1_000_000_000_000);
+		assert!(Exchange::intentions_of(&BOB).is_empty());
+	});
+}
+
+#[test]
+fn pending_intentions_should_include_pending_calls() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(HDX, ETH, ALICE, 100_000_000_000_000, Price::from(2));
+
+		// Intentions of finalized block are already resolved, intentions waiting in transaction pool are not registered yet
+		let pending = vec![
+			(
+				CHARLIE,
+				crate::Call::<Test>::sell(
+					HDX,
+					ETH,
+					1_000_000_000_000,
+					1_000_000_000_000,
+					false,
+					FillPolicy::PartialFill,
+					None,
+				),
+			),
+			(
+				CHARLIE,
+				crate::Call::<Test>::sell(HDX, DOT, 1_000, 1_000, false, FillPolicy::PartialFill, None),
+			),
+		];
+
+		let intentions = Exchange::with_pending_calls(pending.clone(), || Exchange::pending_intentions(ETH, HDX));
+
+		assert_eq!(intentions.len(), 1);
+		assert_eq!(intentions[0].who, CHARLIE);
+		assert_eq!(intentions[0].amount_sell, 1_000_000_000_000);
+
+		// Call which fails is skipped
+		assert_eq!(
+			Exchange::with_pending_calls(pending, || Exchange::intentions_of(&CHARLIE)).len(),
+			1
+		);
+
+		// Nothing is changed
+		assert!(Exchange::get_intentions((HDX, ETH)).is_empty());
+		assert_eq!(Currency::reserved_balance(HDX, &CHARLIE), 0);
+	});
+}
+
+#[test]
+fn simulate_resolution_should_predict_trades_without_changing_state() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000, Price::from(2));
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			ETH,
+			DOT,
+			1_000,
+			1_500,
+			false,
+			FillPolicy::PartialFill,
+			None,
+		));
+
+		let bob_intention_id = Exchange::get_intentions((ETH, DOT))[0].intention_id;
+		let events_count = system::Module::<Test>::events().len();
+
+		// Only registered intention is resolved via AMM
+		let trades = Exchange::simulate_resolution(vec![], None).unwrap();
+
+		assert_eq!(trades.len(), 1);
+		assert_eq!(trades[0].kind, TradeKind::AMM);
+		assert_eq!(trades[0].who, BOB);
+		assert_eq!(trades[0].intention_id, bob_intention_id);
+		assert_eq!(trades[0].amount_sold, 1_000);
+		assert!(trades[0].amount_bought >= 1_500);
+
+		// Hypothetical intention is matched directly with registered intention
+		let call = crate::Call::<Test>::sell(DOT, ETH, 2_000, 900, false, FillPolicy::PartialFill, None);
+		let trades = Exchange::simulate_resolution(vec![], Some((CHARLIE, call))).unwrap();
+
+		assert_eq!(trades.len(), 2);
+		assert!(trades.contains(&ExchangeTrade {
+			kind: TradeKind::Direct,
+			who: BOB,
+			intention_id: bob_intention_id,
+			asset_sell: ETH,
+			asset_buy: DOT,
+			amount_sold: 1_000,
+			amount_bought: 2_000,
+		}));
+		assert!(trades.iter().any(|trade| trade.kind == TradeKind::Direct
+			&& trade.who == CHARLIE
+			&& trade.amount_sold == 2_000
+			&& trade.amount_bought == 1_000));
+
+		// Nothing is changed
+		assert_eq!(system::Module::<Test>::events().len(), events_count);
+		assert_eq!(Exchange::get_intentions((ETH, DOT)).len(), 1);
+		assert!(Exchange::get_intentions((DOT, ETH)).is_empty());
+		assert_eq!(Currency::reserved_balance(ETH, &BOB), 1_503);
+		assert_eq!(Currency::free_balance(DOT, &BOB), ENDOWED_AMOUNT);
+		assert_eq!(Currency::free_balance(DOT, &CHARLIE), ENDOWED_AMOUNT);
+	});
+}
+
+#[test]
+fn simulate_resolution_should_include_pending_calls() {
+	new_test_ext().execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000, Price::from(2));
+
+		let pending = vec![(
+			BOB,
+			crate::Call::<Test>::sell(ETH, DOT, 1_000, 1_500, false, FillPolicy::PartialFill, None),
+		)];
+
+		// Hypothetical intention is matched directly with intention waiting in transaction pool
+		let call = crate::Call::<Test>::sell(DOT, ETH, 2_000, 900, false, FillPolicy::PartialFill, None);
+		let trades = Exchange::simulate_resolution(pending, Some((CHARLIE, call))).unwrap();
+
+		assert_eq!(trades.len(), 2);
+		assert!(trades.iter().any(|trade| trade.kind == TradeKind::Direct
+			&& trade.who == BOB
+			&& trade.amount_sold == 1_000
+			&& trade.amount_bought == 2_000));
+		assert!(trades.iter().any(|trade| trade.kind == TradeKind::Direct
+			&& trade.who == CHARLIE
+			&& trade.amount_sold == 2_000
+			&& trade.amount_bought == 1_000));
+
+		// Nothing is changed
+		assert!(Exchange::get_intentions((ETH, DOT)).is_empty());
+		assert_eq!(Currency::reserved_balance(ETH, &BOB), 0);
+	});
+}
+
+#[test]
+fn simulate_resolution_should_fail_when_call_fails() {
+	new_test_ext().execute_with(|| {
+		let call = crate::Call::<Test>::sell(HDX, ETH, 2_000, 900, false, FillPolicy::PartialFill, None);
+
+		assert_eq!(
+			Exchange::simulate_resolution(vec![], Some((CHARLIE, call))),
+			Err(Error::<Test>::TokenPoolNotFound.into())
+		);
+	});
+}
+
+fn new_matching_test_ext(matching: Matching) -> sp_io::TestExternalities {
+	let mut ext = ExtBuilder::default().with_matching(matching).build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn sell_intention(
+	who: AccountId,
+	asset_sell: AssetId,
+	asset_buy: AssetId,
+	amount: Balance,
+	limit: Balance,
+) -> IntentionId<Test> {
+	assert_ok!(Exchange::sell(
+		Origin::signed(who),
+		asset_sell,
+		asset_buy,
+		amount,
+		limit,
+		false,
+		FillPolicy::PartialFill,
+		None,
+	));
+
+	Exchange::get_intentions((asset_sell, asset_buy))
+		.last()
+		.expect("Intention should be registered")
+		.intention_id
+}
+
+fn amm_trade_emitted(who: AccountId, intention_id: IntentionId<Test>, amount_sold: Balance) -> bool {
+	system::Module::<Test>::events().iter().any(|record| {
+		matches!(
+			record.event,
+			TestEvent::exchange(RawEvent::IntentionResolvedAMMTrade(w, IntentionType::SELL, id, amount, _))
+				if w == who && id == intention_id && amount == amount_sold
+		)
+	})
+}
+
+#[test]
+fn greedy_by_size_should_match_largest_intention_first() {
+	new_matching_test_ext(Matching::GreedyBySize).execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000, Price::from(2));
+
+		let bob_intention_id = sell_intention(BOB, ETH, DOT, 1_000, 1_500);
+		let charlie_intention_id = sell_intention(CHARLIE, DOT, ETH, 1_000, 400);
+		let dave_intention_id = sell_intention(DAVE, DOT, ETH, 2_000, 900);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::IntentionResolvedDirectTrade(
+			BOB,
+			DAVE,
+			bob_intention_id,
+			dave_intention_id,
+			1_000,
+			2_000
+		)));
+		assert!(amm_trade_emitted(CHARLIE, charlie_intention_id, 1_000));
+
+		assert_eq!(Currency::free_balance(ETH, &DAVE), ENDOWED_AMOUNT + 998);
+		assert_eq!(Currency::free_balance(DOT, &BOB), ENDOWED_AMOUNT + 1_996);
+	});
+}
+
+#[test]
+fn price_time_priority_should_match_best_priced_intention_first() {
+	new_matching_test_ext(Matching::PriceTimePriority).execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000, Price::from(2));
+
+		let bob_intention_id = sell_intention(BOB, ETH, DOT, 1_000, 1_500);
+		let charlie_intention_id = sell_intention(CHARLIE, DOT, ETH, 2_000, 990);
+		let dave_intention_id = sell_intention(DAVE, DOT, ETH, 2_000, 900);
+		let ferdie_intention_id = sell_intention(FERDIE, DOT, ETH, 2_000, 900);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		// Dave asks lower price than Charlie and registered earlier than Ferdie at the same price
+		assert!(event_emitted(RawEvent::IntentionResolvedDirectTrade(
+			BOB,
+			DAVE,
+			bob_intention_id,
+			dave_intention_id,
+			1_000,
+			2_000
+		)));
+		assert!(amm_trade_emitted(CHARLIE, charlie_intention_id, 2_000));
+		assert!(amm_trade_emitted(FERDIE, ferdie_intention_id, 2_000));
+
+		assert_eq!(Currency::free_balance(ETH, &DAVE), ENDOWED_AMOUNT + 998);
+	});
+}
+
+#[test]
+fn pro_rata_should_allocate_smaller_side_proportionally() {
+	new_matching_test_ext(Matching::ProRata).execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000, Price::from(2));
+
+		let bob_intention_id = sell_intention(BOB, ETH, DOT, 1_000, 1_500);
+		let charlie_intention_id = sell_intention(CHARLIE, DOT, ETH, 2_000, 900);
+		let dave_intention_id = sell_intention(DAVE, DOT, ETH, 6_000, 2_700);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		// Bob's 1_000 ETH is split 1:3 between Charlie and Dave, the rest of their intentions is traded via AMM
+		assert!(event_emitted(RawEvent::IntentionResolvedDirectTrade(
+			BOB,
+			CHARLIE,
+			bob_intention_id,
+			charlie_intention_id,
+			250,
+			500
+		)));
+		assert!(event_emitted(RawEvent::IntentionResolvedDirectTrade(
+			BOB,
+			DAVE,
+			bob_intention_id,
+			dave_intention_id,
+			750,
+			1_500
+		)));
+		assert!(amm_trade_emitted(CHARLIE, charlie_intention_id, 1_500));
+		assert!(amm_trade_emitted(DAVE, dave_intention_id, 4_500));
+
+		assert_eq!(Currency::free_balance(ETH, &BOB), ENDOWED_AMOUNT - 1_000);
+		assert_eq!(Currency::free_balance(DOT, &CHARLIE), ENDOWED_AMOUNT - 2_000);
+		assert_eq!(Currency::free_balance(DOT, &DAVE), ENDOWED_AMOUNT - 6_000);
+	});
+}
+
+#[test]
+fn batch_auction_strategy_should_resolve_intentions_at_clearing_price() {
+	new_matching_test_ext(Matching::BatchAuction).execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000, Price::from(2));
+
+		let bob_intention_id = sell_intention(BOB, ETH, DOT, 1_000, 1_500);
+		let charlie_intention_id = sell_intention(CHARLIE, DOT, ETH, 2_000, 900);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::IntentionResolvedBatchTrade(
+			BOB,
+			IntentionType::SELL,
+			bob_intention_id,
+			1_000,
+			2_000
+		)));
+		assert!(event_emitted(RawEvent::IntentionResolvedBatchTrade(
+			CHARLIE,
+			IntentionType::SELL,
+			charlie_intention_id,
+			2_000,
+			1_000
+		)));
+	});
+}
+
+fn new_direct_trade_fee_test_ext(fee: Fee, share: Permill) -> sp_io::TestExternalities {
+	let mut ext = ExtBuilder::default()
+		.with_direct_trade_fee(fee)
+		.with_direct_trade_fee_share(share)
+		.build();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+#[test]
+fn direct_trade_fee_should_be_paid_at_configured_rate() {
+	let fee = Fee {
+		numerator: 1,
+		denominator: 100,
+	};
+
+	new_direct_trade_fee_test_ext(fee, Permill::from_percent(0)).execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let pair_account = AMMModule::get_pair_id(&ETH, &DOT);
+
+		sell_intention(BOB, ETH, DOT, 1_000_000_000_000, 1_500_000_000_000);
+		sell_intention(CHARLIE, DOT, ETH, 2_000_000_000_000, 200_000_000_000);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			BOB,
+			pair_account,
+			DOT,
+			20_000_000_000,
+			fee
+		)));
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			CHARLIE,
+			pair_account,
+			ETH,
+			10_000_000_000,
+			fee
+		)));
+
+		assert_eq!(
+			Currency::free_balance(DOT, &BOB),
+			ENDOWED_AMOUNT + 2_000_000_000_000 - 20_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &CHARLIE),
+			ENDOWED_AMOUNT + 1_000_000_000_000 - 10_000_000_000
+		);
+
+		assert_eq!(Currency::free_balance(ETH, &pair_account), 100_010_000_000_000);
+		assert_eq!(Currency::free_balance(DOT, &pair_account), 200_020_000_000_000);
+
+		assert_eq!(Currency::reserved_balance(ETH, &BOB), 0);
+		assert_eq!(Currency::reserved_balance(DOT, &CHARLIE), 0);
+	});
+}
+
+#[test]
+fn direct_trade_should_charge_discounted_direct_trade_fee() {
+	let discounted_fee = Fee {
+		numerator: 7,
+		denominator: 10_000,
+	};
+
+	let mut ext = ExtBuilder::default()
+		.with_discounted_direct_trade_fee(discounted_fee)
+		.build();
+	ext.execute_with(|| System::set_block_number(1));
+
+	ext.execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let pair_account = AMMModule::get_pair_id(&ETH, &DOT);
+
+		assert_ok!(Exchange::sell(
+			Origin::signed(BOB),
+			ETH,
+			DOT,
+			1_000_000_000_000,
+			1_500_000_000_000,
+			true,
+			FillPolicy::PartialFill,
+			None,
+		));
+		sell_intention(CHARLIE, DOT, ETH, 2_000_000_000_000, 200_000_000_000);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			BOB,
+			pair_account,
+			DOT,
+			1_400_000_000,
+			discounted_fee
+		)));
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			CHARLIE,
+			pair_account,
+			ETH,
+			2_000_000_000,
+			Fee::default()
+		)));
+
+		assert_eq!(
+			Currency::free_balance(DOT, &BOB),
+			ENDOWED_AMOUNT + 2_000_000_000_000 - 1_400_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &CHARLIE),
+			ENDOWED_AMOUNT + 1_000_000_000_000 - 2_000_000_000
+		);
+	});
+}
+
+#[test]
+fn direct_trade_fee_should_be_paid_to_fee_receiver() {
+	new_direct_trade_fee_test_ext(Fee::default(), Permill::from_percent(100)).execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let pair_account = AMMModule::get_pair_id(&ETH, &DOT);
+		let receiver = DirectTradeFeeReceiverAccount::get();
+
+		sell_intention(BOB, ETH, DOT, 1_000_000_000_000, 1_500_000_000_000);
+		sell_intention(CHARLIE, DOT, ETH, 2_000_000_000_000, 200_000_000_000);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			BOB,
+			receiver,
+			DOT,
+			4_000_000_000,
+			Fee::default()
+		)));
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			CHARLIE,
+			receiver,
+			ETH,
+			2_000_000_000,
+			Fee::default()
+		)));
+
+		assert_eq!(Currency::free_balance(DOT, &receiver), 4_000_000_000);
+		assert_eq!(Currency::free_balance(ETH, &receiver), 2_000_000_000);
+
+		// Pool receives nothing
+		assert_eq!(Currency::free_balance(ETH, &pair_account), 100_000_000_000_000);
+		assert_eq!(Currency::free_balance(DOT, &pair_account), 200_000_000_000_000);
+	});
+}
+
+#[test]
+fn direct_trade_fee_should_be_split_between_fee_receiver_and_pool() {
+	new_direct_trade_fee_test_ext(Fee::default(), Permill::from_percent(25)).execute_with(|| {
+		initialize_pool(ETH, DOT, ALICE, 100_000_000_000_000, Price::from(2));
+
+		let pair_account = AMMModule::get_pair_id(&ETH, &DOT);
+		let receiver = DirectTradeFeeReceiverAccount::get();
+
+		sell_intention(BOB, ETH, DOT, 1_000_000_000_000, 1_500_000_000_000);
+		sell_intention(CHARLIE, DOT, ETH, 2_000_000_000_000, 200_000_000_000);
+
+		<Exchange as OnFinalize<u64>>::on_finalize(1);
+
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			BOB,
+			receiver,
+			DOT,
+			1_000_000_000,
+			Fee::default()
+		)));
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			BOB,
+			pair_account,
+			DOT,
+			3_000_000_000,
+			Fee::default()
+		)));
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			CHARLIE,
+			receiver,
+			ETH,
+			500_000_000,
+			Fee::default()
+		)));
+		assert!(event_emitted(RawEvent::DirectTradeFeePaid(
+			CHARLIE,
+			pair_account,
+			ETH,
+			1_500_000_000,
+			Fee::default()
+		)));
+
+		assert_eq!(
+			Currency::free_balance(DOT, &BOB),
+			ENDOWED_AMOUNT + 2_000_000_000_000 - 4_000_000_000
+		);
+		assert_eq!(
+			Currency::free_balance(ETH, &CHARLIE),
+			ENDOWED_AMOUNT + 1_000_000_000_000 - 2_000_000_000
+		);
+
+		assert_eq!(Currency::free_balance(DOT, &receiver), 1_000_000_000);
+		assert_eq!(Currency::free_balance(ETH, &receiver), 500_000_000);
+		assert_eq!(Currency::free_balance(DOT, &pair_account), 200_003_000_000_000);
+		assert_eq!(Currency::free_balance(ETH, &pair_account), 100_001_500_000_000);
+	});
+}
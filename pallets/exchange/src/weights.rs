@@ -56,6 +56,10 @@ pub trait WeightInfo {
 	fn on_finalize_for_one_sell_extrinsic() -> Weight;
 	fn buy_extrinsic() -> Weight;
 	fn on_finalize_for_one_buy_extrinsic() -> Weight;
+	fn cancel_intention(q: u32) -> Weight;
+	fn place_limit_order() -> Weight;
+	fn cancel_limit_order() -> Weight;
+	fn resolve_limit_order() -> Weight;
 }
 
 /// Weights for exchange using the hydraDX node and recommended hardware.
@@ -121,6 +125,28 @@ impl<T: frame_system::Config> WeightInfo for HydraWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(9 as Weight))
 			.saturating_add(T::DbWeight::get().writes(6 as Weight))
 	}
+	fn cancel_intention(q: u32) -> Weight {
+		(41_862_000 as Weight)
+			// Standard Error: 4_000
+			.saturating_add((1_123_000 as Weight).saturating_mul(q as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn place_limit_order() -> Weight {
+		(92_375_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	fn cancel_limit_order() -> Weight {
+		(68_910_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn resolve_limit_order() -> Weight {
+		(412_683_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(10 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
 }
 
 // For backwards compatibility and tests
@@ -185,4 +211,26 @@ impl WeightInfo for () {
 			.saturating_add(RocksDbWeight::get().reads(9 as Weight))
 			.saturating_add(RocksDbWeight::get().writes(6 as Weight))
 	}
+	fn cancel_intention(q: u32) -> Weight {
+		(41_862_000 as Weight)
+			// Standard Error: 4_000
+			.saturating_add((1_123_000 as Weight).saturating_mul(q as Weight))
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn place_limit_order() -> Weight {
+		(92_375_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(4 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(5 as Weight))
+	}
+	fn cancel_limit_order() -> Weight {
+		(68_910_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(3 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+	}
+	fn resolve_limit_order() -> Weight {
+		(412_683_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(10 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(6 as Weight))
+	}
 }
@@ -6,10 +6,12 @@ use sp_std::prelude::*;
 use sp_std::vec;
 
 use frame_benchmarking::{account, benchmarks};
+use frame_support::traits::Get;
 use frame_system::RawOrigin;
 use orml_traits::{MultiCurrency, MultiCurrencyExtended};
 use pallet_transaction_multi_payment::Module as MultiPaymentModule;
-use primitives::{Amount, AssetId, Balance, Price};
+use primitives::{fee::Fee, Amount, AssetId, Balance, Price};
+use sp_runtime::traits::One;
 use sp_runtime::DispatchError;
 
 use pallet_amm as ammpool;
@@ -43,14 +45,27 @@ fn initialize_pool<T: Config>(
 	amount: Balance,
 	price: Price,
 ) -> Result<(), DispatchError> {
-	ammpool::Module::<T>::create_pool(RawOrigin::Signed(caller).into(), HDX, asset, amount, price)?;
+	ammpool::Module::<T>::create_pool(
+		RawOrigin::Signed(caller).into(),
+		HDX,
+		asset,
+		amount,
+		price,
+		Fee::default(),
+		ammpool::PoolType::ConstantProduct,
+	)?;
+
+	// Fees are paid at oracle price only
+	let now = frame_system::Module::<T>::block_number();
+	frame_system::Module::<T>::set_block_number(now + T::OracleWindow::get() + One::one());
+
 	Ok(())
 }
 
 benchmarks! {
 	_ { }
 
-	swap_currency {
+	withdraw_fee_in_currency {
 		let maker = funded_account::<T>("maker", 1);
 		initialize_pool::<T>(maker.clone(), ASSET_ID, 1000, Price::from(1))?;
 		MultiPaymentModule::<T>::add_member(&maker);
@@ -59,10 +74,29 @@ benchmarks! {
 		let caller = funded_account::<T>("caller", 2);
 		MultiPaymentModule::<T>::set_currency(RawOrigin::Signed(caller.clone()).into(), ASSET_ID)?;
 
-	}: { MultiPaymentModule::<T>::swap_currency(&caller, 10)? }
+	}: { MultiPaymentModule::<T>::withdraw_fee_in_currency(&caller, 10)? }
 	verify{
 		assert_eq!(MultiPaymentModule::<T>::get_currency(caller.clone()), Some(ASSET_ID));
-		assert_eq!(T::MultiCurrency::free_balance(ASSET_ID, &caller),2000 - 10 -1 );
+		assert_eq!(T::MultiCurrency::free_balance(ASSET_ID, &caller), 2000 - 10);
+		assert_eq!(MultiPaymentModule::<T>::accrued_fees(ASSET_ID), 10);
+	}
+
+	convert_fees {
+		let maker = funded_account::<T>("maker", 1);
+		T::MultiCurrency::update_balance(ASSET_ID, &maker, 2_000_000_000_000)?;
+		T::MultiCurrency::update_balance(HDX, &maker, 2_000_000_000_000)?;
+		initialize_pool::<T>(maker.clone(), ASSET_ID, 1_000_000_000_000, Price::from(1))?;
+		MultiPaymentModule::<T>::add_member(&maker);
+		MultiPaymentModule::<T>::add_currency(RawOrigin::Signed(maker).into(), ASSET_ID)?;
+
+		let caller = funded_account::<T>("caller", 2);
+		T::MultiCurrency::update_balance(ASSET_ID, &caller, 1_000_000)?;
+		MultiPaymentModule::<T>::set_currency(RawOrigin::Signed(caller.clone()).into(), ASSET_ID)?;
+		MultiPaymentModule::<T>::withdraw_fee_in_currency(&caller, 1_000_000)?;
+
+	}: { MultiPaymentModule::<T>::convert_fees() }
+	verify{
+		assert_eq!(MultiPaymentModule::<T>::accrued_fees(ASSET_ID), 0);
 	}
 
 	set_currency {
@@ -109,7 +143,8 @@ mod tests {
 	#[test]
 	fn test_benchmarks() {
 		ExtBuilder::default().base_weight(5).build().execute_with(|| {
-			assert_ok!(test_benchmark_swap_currency::<Test>());
+			assert_ok!(test_benchmark_withdraw_fee_in_currency::<Test>());
+			assert_ok!(test_benchmark_convert_fees::<Test>());
 			assert_ok!(test_benchmark_set_currency::<Test>());
 			assert_ok!(test_benchmark_add_currency::<Test>());
 			assert_ok!(test_benchmark_remove_currency::<Test>());
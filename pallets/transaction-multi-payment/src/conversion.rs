@@ -0,0 +1,92 @@
+use super::*;
+use frame_support::storage::IterableStorageMap;
+use sp_runtime::traits::AccountIdConversion;
+use sp_runtime::{FixedPointNumber, PerThing};
+
+/// Fee conversion implementation
+impl<T: Config> Module<T> {
+	/// Account which holds fees paid in non-native currencies until they are converted.
+	pub fn fee_account() -> T::AccountId {
+		T::ModuleId::get().into_account()
+	}
+
+	/// Amount of `currency` equal to `fee` of native currency, at oracle price.
+	///
+	/// Currency without oracle price cannot be used to pay fees - spot price of the pool can be moved within the block.
+	pub fn fee_in_currency(currency: AssetId, fee: Balance) -> Option<Balance> {
+		Self::convert(CORE_ASSET_ID, currency, fee).filter(|amount| !amount.is_zero())
+	}
+
+	/// Amount of `asset_b` equal to `amount` of `asset_a` at oracle price.
+	fn convert(asset_a: AssetId, asset_b: AssetId, amount: Balance) -> Option<Balance> {
+		T::PriceOracle::get_twap(asset_a, asset_b, T::OracleWindow::get())?.checked_mul_int(amount)
+	}
+
+	/// Maximum amount of `currency` converted in one block.
+	///
+	/// Selling `amount` to a pool with `reserve` of the currency moves the price by about `amount / (reserve + amount)`.
+	/// Half of `MaxConversionSlippage` of the reserve is sold at most, the rest of the bound is left for the pool fee
+	/// and difference of the spot price from the oracle price.
+	fn max_conversion_amount(currency: AssetId) -> Balance {
+		let pair_account = T::AMMPool::get_pair_id(&currency, &CORE_ASSET_ID);
+		let reserve = T::MultiCurrency::free_balance(currency, &pair_account);
+
+		T::MaxConversionSlippage::get().mul_floor(reserve) / 2
+	}
+
+	/// Sell fees accrued in each non-native currency for native currency.
+	///
+	/// At most `max_conversion_amount` of each currency is sold in a block, the rest is converted in next blocks.
+	/// Fees are not converted if the native amount received would be lower than the oracle price allows by `MaxConversionSlippage`.
+	/// Such fees are kept and conversion is tried again in next blocks.
+	pub fn convert_fees() {
+		let fee_account = Self::fee_account();
+
+		let accrued: Vec<(AssetId, Balance)> = AccruedFees::iter().collect();
+
+		for (currency, accrued_amount) in accrued {
+			let amount = accrued_amount.min(Self::max_conversion_amount(currency));
+
+			let expected = Self::convert(currency, CORE_ASSET_ID, amount).unwrap_or_else(Zero::zero);
+
+			if expected.is_zero() {
+				Self::deposit_event(RawEvent::FeeConversionFailed(
+					currency,
+					amount,
+					Error::<T>::PriceNotAvailable.into(),
+				));
+				continue;
+			}
+
+			let min_bought = T::MaxConversionSlippage::get().left_from_one() * expected;
+
+			let native_before = <T as Config>::Currency::free_balance(&fee_account);
+
+			if let Err(error) = T::AMMPool::sell(&fee_account, currency, CORE_ASSET_ID, amount, min_bought, false) {
+				Self::deposit_event(RawEvent::FeeConversionFailed(currency, amount, error));
+				continue;
+			}
+
+			let remaining = accrued_amount.saturating_sub(amount);
+
+			if remaining.is_zero() {
+				AccruedFees::remove(currency);
+			} else {
+				AccruedFees::insert(currency, remaining);
+			}
+
+			let converted = <T as Config>::Currency::free_balance(&fee_account).saturating_sub(native_before);
+
+			if let Ok(imbalance) = <T as Config>::Currency::withdraw(
+				&fee_account,
+				converted,
+				WithdrawReasons::TRANSACTION_PAYMENT,
+				ExistenceRequirement::AllowDeath,
+			) {
+				T::OnConvertedFees::on_unbalanced(imbalance);
+			}
+
+			Self::deposit_event(RawEvent::FeesConverted(currency, amount, converted));
+		}
+	}
+}
@@ -4,6 +4,8 @@ pub mod weights;
 
 use weights::WeightInfo;
 
+mod conversion;
+
 #[cfg(test)]
 mod mock;
 
@@ -12,14 +14,16 @@ mod tests;
 
 use frame_support::{
 	decl_error, decl_event, decl_module, decl_storage,
-	dispatch::DispatchResult,
+	dispatch::{DispatchError, DispatchResult},
 	ensure,
 	traits::{Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced, WithdrawReasons},
+	weights::Weight,
 };
 use frame_system::ensure_signed;
 use sp_runtime::{
 	traits::{DispatchInfoOf, PostDispatchInfoOf, Saturating, Zero},
 	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	ModuleId, Permill,
 };
 use sp_std::prelude::*;
 
@@ -28,7 +32,8 @@ use sp_std::marker::PhantomData;
 
 use frame_support::weights::Pays;
 use orml_traits::{MultiCurrency, MultiCurrencyExtended};
-use primitives::traits::{CurrencySwap, AMM};
+use primitive_types::U256;
+use primitives::traits::{CurrencyWithdraw, TWAPOracle, AMM};
 use primitives::{AssetId, Balance, CORE_ASSET_ID};
 
 type NegativeImbalanceOf<C, T> = <C as Currency<<T as frame_system::Config>::AccountId>>::NegativeImbalance;
@@ -38,7 +43,7 @@ pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
 
 	/// The currency type in which fees will be paid.
-	type Currency: Currency<Self::AccountId> + Send + Sync;
+	type Currency: Currency<Self::AccountId, Balance = Balance> + Send + Sync;
 
 	/// Multi Currency
 	type MultiCurrency: MultiCurrency<Self::AccountId>
@@ -49,6 +54,21 @@ pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
 
 	/// Weight information for the extrinsics.
 	type WeightInfo: WeightInfo;
+
+	/// Oracle of prices of fee currencies in native currency
+	type PriceOracle: TWAPOracle<AssetId, Self::BlockNumber>;
+
+	/// Number of blocks the oracle price is averaged over
+	type OracleWindow: Get<Self::BlockNumber>;
+
+	/// Maximum difference of native amount received in fee conversion from the oracle price
+	type MaxConversionSlippage: Get<Permill>;
+
+	/// Module id - its account holds fees paid in non-native currencies until they are converted
+	type ModuleId: Get<ModuleId>;
+
+	/// Handler of native currency obtained by converting fees paid in non-native currencies
+	type OnConvertedFees: OnUnbalanced<NegativeImbalanceOf<<Self as Config>::Currency, Self>>;
 }
 
 decl_event!(
@@ -67,6 +87,18 @@ decl_event!(
 		/// Accepted currency removed
 		/// [who, currency]
 		CurrencyRemoved(AccountId, AssetId),
+
+		/// Transaction fee paid
+		/// [who, currency, amount paid, amount refunded]
+		FeePaid(AccountId, AssetId, Balance, Balance),
+
+		/// Fees paid in non-native currency converted to native currency
+		/// [currency, amount, native amount]
+		FeesConverted(AssetId, Balance, Balance),
+
+		/// Fees paid in non-native currency could not be converted - they are kept for next blocks
+		/// [currency, amount, error]
+		FeeConversionFailed(AssetId, Balance, DispatchError),
 	}
 );
 
@@ -87,6 +119,9 @@ decl_error! {
 
 		/// Currency being added is already in the list of accpeted currencies
 		CoreAssetNotAllowed,
+
+		/// Oracle price of selected currency is not available
+		PriceNotAvailable,
 	}
 }
 
@@ -96,6 +131,9 @@ decl_storage! {
 		pub AccountCurrencyMap get(fn get_currency): map hasher(blake2_128_concat) T::AccountId => Option<AssetId>;
 		pub AcceptedCurrencies get(fn currencies) config(): Vec<AssetId>;
 		pub Authorities get(fn authorities) config(): Vec<T::AccountId>;
+
+		/// Fees paid in non-native currencies which have not been converted to native currency yet
+		pub AccruedFees get(fn accrued_fees): map hasher(twox_64_concat) AssetId => Balance;
 	}
 }
 
@@ -190,22 +228,73 @@ decl_module! {
 				}
 			}
 		}
+
+		fn on_initialize() -> Weight {
+			// Fees accrued in each accepted currency are converted in on_finalize.
+			let currencies = AcceptedCurrencies::decode_len().unwrap_or(0) as Weight;
+
+			<T as Config>::WeightInfo::convert_fees().saturating_mul(currencies)
+		}
+
+		/// Convert fees accrued in non-native currencies to native currency.
+		fn on_finalize(){
+			Self::convert_fees();
+		}
 	}
 }
 impl<T: Config> Module<T> {
-	pub fn swap_currency(who: &T::AccountId, fee: Balance) -> DispatchResult {
+	/// Withdraw `fee` denominated in native currency from `who` in the currency it selected.
+	///
+	/// Fee is collected at oracle price to the module account and converted to native currency in bulk at the end of the block.
+	/// Returns the currency and the amount withdrawn, `None` if `who` pays in native currency.
+	pub fn withdraw_fee_in_currency(
+		who: &T::AccountId,
+		fee: Balance,
+	) -> Result<Option<(AssetId, Balance)>, DispatchError> {
 		// Let's determine currency in which user would like to pay the fee
 		let fee_currency = match Module::<T>::get_currency(who) {
-			Some(c) => c,
-			_ => CORE_ASSET_ID,
+			Some(c) if c != CORE_ASSET_ID => c,
+			_ => return Ok(None),
 		};
 
-		// If not native currency, let's buy CORE asset first and then pay with that.
-		if fee_currency != CORE_ASSET_ID {
-			T::AMMPool::buy(&who, CORE_ASSET_ID, fee_currency, fee, 2u128 * fee, false)?;
+		let amount = Self::fee_in_currency(fee_currency, fee).ok_or(Error::<T>::PriceNotAvailable)?;
+
+		T::MultiCurrency::transfer(fee_currency, who, &Self::fee_account(), amount)?;
+
+		AccruedFees::mutate(fee_currency, |accrued| *accrued = accrued.saturating_add(amount));
+
+		Ok(Some((fee_currency, amount)))
+	}
+
+	/// Refund part of `paid` amount of `currency` not used for the fee - `fee` corrected to `corrected_fee`.
+	///
+	/// Returns the amount refunded, zero if the refund cannot be transferred.
+	fn refund_fee_in_currency(
+		who: &T::AccountId,
+		currency: AssetId,
+		paid: Balance,
+		fee: Balance,
+		corrected_fee: Balance,
+	) -> Balance {
+		if fee.is_zero() || corrected_fee >= fee {
+			return Balance::zero();
 		}
 
-		Ok(())
+		let refund = (U256::from(paid) * U256::from(fee - corrected_fee) / U256::from(fee)).low_u128();
+
+		if refund.is_zero() || T::MultiCurrency::transfer(currency, &Self::fee_account(), who, refund).is_err() {
+			return Balance::zero();
+		}
+
+		let accrued = Self::accrued_fees(currency).saturating_sub(refund);
+
+		if accrued.is_zero() {
+			AccruedFees::remove(currency);
+		} else {
+			AccruedFees::insert(currency, accrued);
+		}
+
+		refund
 	}
 
 	pub fn add_member(who: &T::AccountId) {
@@ -213,12 +302,20 @@ impl<T: Config> Module<T> {
 	}
 }
 
-impl<T: Config> CurrencySwap<<T as frame_system::Config>::AccountId, Balance> for Module<T> {
-	fn swap_currency(who: &T::AccountId, fee: u128) -> DispatchResult {
-		Self::swap_currency(who, fee)
+impl<T: Config> CurrencyWithdraw<<T as frame_system::Config>::AccountId, AssetId, Balance> for Module<T> {
+	fn withdraw_fee(who: &T::AccountId, fee: Balance) -> Result<Option<(AssetId, Balance)>, DispatchError> {
+		Self::withdraw_fee_in_currency(who, fee)
 	}
 }
 
+/// Transaction fee withdrawn from the transaction origin
+pub enum PaymentInfo<Imbalance> {
+	/// Fee paid in native currency
+	Native(Imbalance),
+	/// Fee paid in non-native currency - currency, amount paid, fee in native currency
+	NonNative(AssetId, Balance, Balance),
+}
+
 /// Implements the transaction payment for native as well as non-native currencies
 pub struct MultiCurrencyAdapter<C, OU, SW>(PhantomData<(C, OU, SW)>);
 
@@ -233,9 +330,9 @@ where
 		Imbalance<<C as Currency<<T as frame_system::Config>::AccountId>>::Balance, Opposite = C::PositiveImbalance>,
 	OU: OnUnbalanced<NegativeImbalanceOf<C, T>>,
 	C::Balance: Into<Balance>,
-	SW: CurrencySwap<T::AccountId, Balance>,
+	SW: CurrencyWithdraw<T::AccountId, AssetId, Balance>,
 {
-	type LiquidityInfo = Option<NegativeImbalanceOf<C, T>>;
+	type LiquidityInfo = Option<PaymentInfo<NegativeImbalanceOf<C, T>>>;
 	type Balance = <C as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 	/// Withdraw the predicted fee from the transaction origin.
@@ -258,12 +355,14 @@ where
 			WithdrawReasons::TRANSACTION_PAYMENT | WithdrawReasons::TIP
 		};
 
-		if SW::swap_currency(&who, fee.into()).is_err() {
-			return Err(InvalidTransaction::Payment.into());
+		match SW::withdraw_fee(&who, fee.into()) {
+			Ok(Some((currency, amount))) => return Ok(Some(PaymentInfo::NonNative(currency, amount, fee.into()))),
+			Ok(None) => {}
+			Err(_) => return Err(InvalidTransaction::Payment.into()),
 		}
 
 		match C::withdraw(who, fee, withdraw_reason, ExistenceRequirement::KeepAlive) {
-			Ok(imbalance) => Ok(Some(imbalance)),
+			Ok(imbalance) => Ok(Some(PaymentInfo::Native(imbalance))),
 			Err(_) => Err(InvalidTransaction::Payment.into()),
 		}
 	}
@@ -282,21 +381,44 @@ where
 		tip: Self::Balance,
 		already_withdrawn: Self::LiquidityInfo,
 	) -> Result<(), TransactionValidityError> {
-		if let Some(paid) = already_withdrawn {
-			// Calculate how much refund we should return
-			let refund_amount = paid.peek().saturating_sub(corrected_fee);
-			// refund to the the account that paid the fees. If this fails, the
-			// account might have dropped below the existential balance. In
-			// that case we don't refund anything.
-			let refund_imbalance =
-				C::deposit_into_existing(&who, refund_amount).unwrap_or_else(|_| C::PositiveImbalance::zero());
-			// merge the imbalance caused by paying the fees and refunding parts of it again.
-			let adjusted_paid = paid
-				.offset(refund_imbalance)
-				.map_err(|_| TransactionValidityError::Invalid(InvalidTransaction::Payment))?;
-			// Call someone else to handle the imbalance (fee and tip separately)
-			let imbalances = adjusted_paid.split(tip);
-			OU::on_unbalanceds(Some(imbalances.0).into_iter().chain(Some(imbalances.1)));
+		match already_withdrawn {
+			Some(PaymentInfo::Native(paid)) => {
+				// Calculate how much refund we should return
+				let refund_amount = paid.peek().saturating_sub(corrected_fee);
+				// refund to the the account that paid the fees. If this fails, the
+				// account might have dropped below the existential balance. In
+				// that case we don't refund anything.
+				let refund_imbalance =
+					C::deposit_into_existing(&who, refund_amount).unwrap_or_else(|_| C::PositiveImbalance::zero());
+				let refunded = refund_imbalance.peek();
+				// merge the imbalance caused by paying the fees and refunding parts of it again.
+				let adjusted_paid = paid
+					.offset(refund_imbalance)
+					.map_err(|_| TransactionValidityError::Invalid(InvalidTransaction::Payment))?;
+
+				Module::<T>::deposit_event(RawEvent::FeePaid(
+					who.clone(),
+					CORE_ASSET_ID,
+					adjusted_paid.peek().into(),
+					refunded.into(),
+				));
+
+				// Call someone else to handle the imbalance (fee and tip separately)
+				let imbalances = adjusted_paid.split(tip);
+				OU::on_unbalanceds(Some(imbalances.0).into_iter().chain(Some(imbalances.1)));
+			}
+			Some(PaymentInfo::NonNative(currency, paid, fee)) => {
+				// Refund is paid in the currency the fee was paid in, at the rate the fee was collected.
+				let refunded = Module::<T>::refund_fee_in_currency(who, currency, paid, fee, corrected_fee.into());
+
+				Module::<T>::deposit_event(RawEvent::FeePaid(
+					who.clone(),
+					currency,
+					paid.saturating_sub(refunded),
+					refunded,
+				));
+			}
+			None => {}
 		}
 		Ok(())
 	}
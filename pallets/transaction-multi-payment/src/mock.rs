@@ -9,7 +9,7 @@ use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup, Zero},
-	Perbill,
+	ModuleId, Perbill, Permill,
 };
 
 use frame_support::weights::IdentityFee;
@@ -100,7 +100,14 @@ parameter_types! {
 		.avg_block_initialization(Perbill::from_percent(0))
 		.build_or_panic();
 
-	pub ExchangeFeeRate: fee::Fee = fee::Fee::default();
+	pub MaxExchangeFeeRate: fee::Fee = fee::Fee { numerator: 1, denominator: 10 };
+	pub const PriceHistoryLength: u32 = 10;
+	pub const ProtocolFeeRate: Permill = Permill::from_percent(0);
+	pub const ProtocolFeeReceiverAccount: AccountId = 100;
+
+	pub const OracleWindow: u64 = 10;
+	pub const MaxConversionSlippage: Permill = Permill::from_percent(10);
+	pub const MultiPaymentModuleId: ModuleId = ModuleId(*b"hdx/fees");
 }
 
 impl system::Config for Test {
@@ -133,6 +140,11 @@ impl Config for Test {
 	type MultiCurrency = Currencies;
 	type AMMPool = AMMModule;
 	type WeightInfo = ();
+	type PriceOracle = AMMModule;
+	type OracleWindow = OracleWindow;
+	type MaxConversionSlippage = MaxConversionSlippage;
+	type ModuleId = MultiPaymentModuleId;
+	type OnConvertedFees = ();
 }
 
 impl pallet_asset_registry::Config for Test {
@@ -170,6 +182,14 @@ impl AssetPairAccountIdFor<AssetId, u64> for AssetPairAccountIdTest {
 		}
 		return (a * 1000 + b) as u64;
 	}
+
+	fn from_asset_set(assets: &[AssetId]) -> u64 {
+		let mut sorted = assets.to_vec();
+		sorted.sort_unstable();
+		sorted.iter().fold(0u64, |acc, asset| {
+			acc.wrapping_mul(1_000_003).wrapping_add(*asset as u64)
+		}) | (1 << 62)
+	}
 }
 
 impl pallet_amm::Config for Test {
@@ -178,7 +198,11 @@ impl pallet_amm::Config for Test {
 	type Currency = Currencies;
 	type HDXAssetId = HdxAssetId;
 	type WeightInfo = ();
-	type GetExchangeFee = ExchangeFeeRate;
+	type MaxExchangeFee = MaxExchangeFeeRate;
+	type UpdatePoolFeeOrigin = frame_system::EnsureRoot<AccountId>;
+	type ProtocolFee = ProtocolFeeRate;
+	type ProtocolFeeReceiver = ProtocolFeeReceiverAccount;
+	type PriceHistoryLength = PriceHistoryLength;
 }
 
 parameter_type_with_key! {
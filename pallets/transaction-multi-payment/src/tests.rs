@@ -1,12 +1,14 @@
-pub use crate::{mock::*, Error};
+pub use crate::{mock::*, Error, RawEvent};
+use frame_support::traits::OnFinalize;
 use frame_support::{assert_noop, assert_ok};
 use pallet_transaction_payment::ChargeTransactionPayment;
 use sp_runtime::traits::SignedExtension;
 
-use frame_support::weights::DispatchInfo;
+use frame_support::weights::{DispatchInfo, Pays, PostDispatchInfo};
 use orml_traits::MultiCurrency;
 use pallet_balances::Call as BalancesCall;
-use primitives::Price;
+use primitives::traits::AMM;
+use primitives::{fee::Fee, Price};
 
 const CALL: &<Test as frame_system::Config>::Call = &Call::Balances(BalancesCall::transfer(2, 69));
 
@@ -130,8 +132,13 @@ fn fee_payment_in_non_native_currency() {
 				HDX,
 				SUPPORTED_CURRENCY_WITH_BALANCE,
 				100000,
-				Price::from(1)
+				Price::from(1),
+				Fee::default(),
+				pallet_amm::PoolType::ConstantProduct
 			));
+
+			System::set_block_number(11);
+
 			assert_ok!(PaymentModule::set_currency(
 				Origin::signed(CHARLIE),
 				SUPPORTED_CURRENCY_WITH_BALANCE
@@ -150,11 +157,12 @@ fn fee_payment_in_non_native_currency() {
 			//Native balance check - Charlie should be still broke!
 			assert_eq!(Balances::free_balance(CHARLIE), 0);
 
-			// token check should be less by the fee amount and -1 as fee in amm swap
+			// token check should be less by the fee amount at oracle price
 			assert_eq!(
 				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &CHARLIE),
-				1000 - 20 - 1
+				1000 - 20
 			);
+			assert_eq!(PaymentModule::accrued_fees(SUPPORTED_CURRENCY_WITH_BALANCE), 20);
 		});
 }
 
@@ -173,9 +181,13 @@ fn fee_payment_non_native_insufficient_balance() {
 				HDX,
 				SUPPORTED_CURRENCY_WITH_BALANCE,
 				100000,
-				Price::from(1)
+				Price::from(1),
+				Fee::default(),
+				pallet_amm::PoolType::ConstantProduct
 			));
 
+			System::set_block_number(11);
+
 			assert_ok!(PaymentModule::set_currency(
 				Origin::signed(CHARLIE),
 				SUPPORTED_CURRENCY_WITH_BALANCE
@@ -241,3 +253,402 @@ fn removed_accepted_currency() {
 		assert_eq!(PaymentModule::currencies(), vec![2000, 3000]);
 	});
 }
+
+fn multi_payment_event_emitted(e: RawEvent<AccountId>) -> bool {
+	System::events()
+		.iter()
+		.any(|record| record.event == TestEvent::multi_payment(e.clone()))
+}
+
+#[test]
+fn fee_in_non_native_currency_should_be_collected_at_oracle_price() {
+	const CHARLIE: AccountId = 5;
+
+	ExtBuilder::default()
+		.account_native_balance(CHARLIE, 0)
+		.account_tokens(CHARLIE, SUPPORTED_CURRENCY_WITH_BALANCE, 1_000_000)
+		.build()
+		.execute_with(|| {
+			assert_ok!(pallet_amm::Module::<Test>::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				100_000_000_000,
+				Price::from(2),
+				Fee::default(),
+				pallet_amm::PoolType::ConstantProduct
+			));
+			assert_ok!(PaymentModule::set_currency(
+				Origin::signed(CHARLIE),
+				SUPPORTED_CURRENCY_WITH_BALANCE
+			));
+
+			System::set_block_number(11);
+
+			// Spot price moves, oracle price over last 10 blocks is still 2
+			assert_ok!(pallet_amm::Module::<Test>::sell(
+				Origin::signed(ALICE),
+				HDX,
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				10_000_000_000,
+				0,
+				false,
+				None
+			));
+
+			assert_eq!(
+				PaymentModule::withdraw_fee_in_currency(&CHARLIE, 1_000),
+				Ok(Some((SUPPORTED_CURRENCY_WITH_BALANCE, 2_000)))
+			);
+
+			assert_eq!(
+				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &CHARLIE),
+				1_000_000 - 2_000
+			);
+			assert_eq!(
+				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &PaymentModule::fee_account()),
+				2_000
+			);
+			assert_eq!(PaymentModule::accrued_fees(SUPPORTED_CURRENCY_WITH_BALANCE), 2_000);
+		});
+}
+
+#[test]
+fn fee_in_native_currency_should_not_be_accrued() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(PaymentModule::withdraw_fee_in_currency(&ALICE, 1_000), Ok(None));
+
+		assert_ok!(PaymentModule::set_currency(Origin::signed(ALICE), HDX));
+
+		assert_eq!(PaymentModule::withdraw_fee_in_currency(&ALICE, 1_000), Ok(None));
+		assert_eq!(PaymentModule::accrued_fees(HDX), 0);
+	});
+}
+
+#[test]
+fn fee_in_currency_without_price_should_not_work() {
+	const CHARLIE: AccountId = 5;
+
+	ExtBuilder::default()
+		.account_tokens(CHARLIE, SUPPORTED_CURRENCY_WITH_BALANCE, 1_000_000)
+		.build()
+		.execute_with(|| {
+			assert_ok!(PaymentModule::set_currency(
+				Origin::signed(CHARLIE),
+				SUPPORTED_CURRENCY_WITH_BALANCE
+			));
+
+			assert_noop!(
+				PaymentModule::withdraw_fee_in_currency(&CHARLIE, 1_000),
+				Error::<Test>::PriceNotAvailable
+			);
+		});
+}
+
+#[test]
+fn fee_in_currency_without_oracle_price_should_not_work() {
+	const CHARLIE: AccountId = 5;
+
+	ExtBuilder::default()
+		.account_tokens(CHARLIE, SUPPORTED_CURRENCY_WITH_BALANCE, 1_000_000)
+		.build()
+		.execute_with(|| {
+			assert_ok!(pallet_amm::Module::<Test>::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				100_000,
+				Price::from(1),
+				Fee::default(),
+				pallet_amm::PoolType::ConstantProduct
+			));
+			assert_ok!(PaymentModule::set_currency(
+				Origin::signed(CHARLIE),
+				SUPPORTED_CURRENCY_WITH_BALANCE
+			));
+
+			// Spot price of a new pool is not used
+			assert_noop!(
+				PaymentModule::withdraw_fee_in_currency(&CHARLIE, 1_000),
+				Error::<Test>::PriceNotAvailable
+			);
+		});
+}
+
+#[test]
+fn accrued_fees_should_be_converted_on_finalize() {
+	const CHARLIE: AccountId = 5;
+
+	ExtBuilder::default()
+		.account_native_balance(CHARLIE, 0)
+		.account_tokens(CHARLIE, SUPPORTED_CURRENCY_WITH_BALANCE, 10_000_000_000)
+		.build()
+		.execute_with(|| {
+			System::set_block_number(1);
+
+			assert_ok!(pallet_amm::Module::<Test>::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				100_000_000_000_000,
+				Price::from(1),
+				Fee::default(),
+				pallet_amm::PoolType::ConstantProduct
+			));
+
+			System::set_block_number(11);
+
+			assert_ok!(PaymentModule::set_currency(
+				Origin::signed(CHARLIE),
+				SUPPORTED_CURRENCY_WITH_BALANCE
+			));
+
+			assert_ok!(PaymentModule::withdraw_fee_in_currency(&CHARLIE, 1_000_000_000));
+			assert_ok!(PaymentModule::withdraw_fee_in_currency(&CHARLIE, 1_000_000_000));
+
+			assert_eq!(
+				PaymentModule::accrued_fees(SUPPORTED_CURRENCY_WITH_BALANCE),
+				2_000_000_000
+			);
+
+			let pair_account = AMMModule::get_pair_id(&HDX, &SUPPORTED_CURRENCY_WITH_BALANCE);
+			let pool_native = Balances::free_balance(pair_account);
+
+			<PaymentModule as OnFinalize<u64>>::on_finalize(11);
+
+			// Both fees are sold in one trade
+			let converted = pool_native - Balances::free_balance(pair_account);
+
+			assert!(converted > 0);
+			assert!(multi_payment_event_emitted(RawEvent::FeesConverted(
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				2_000_000_000,
+				converted
+			)));
+
+			assert_eq!(PaymentModule::accrued_fees(SUPPORTED_CURRENCY_WITH_BALANCE), 0);
+			assert_eq!(
+				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &PaymentModule::fee_account()),
+				0
+			);
+
+			// Native currency received is handed over to `OnConvertedFees`
+			assert_eq!(Balances::free_balance(PaymentModule::fee_account()), 0);
+		});
+}
+
+#[test]
+fn accrued_fees_should_be_converted_in_chunks() {
+	const CHARLIE: AccountId = 5;
+
+	ExtBuilder::default()
+		.account_native_balance(CHARLIE, 0)
+		.account_tokens(CHARLIE, SUPPORTED_CURRENCY_WITH_BALANCE, 100_000)
+		.build()
+		.execute_with(|| {
+			System::set_block_number(1);
+
+			assert_ok!(pallet_amm::Module::<Test>::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				100_000,
+				Price::from(1),
+				Fee::default(),
+				pallet_amm::PoolType::ConstantProduct
+			));
+
+			System::set_block_number(11);
+
+			assert_ok!(PaymentModule::set_currency(
+				Origin::signed(CHARLIE),
+				SUPPORTED_CURRENCY_WITH_BALANCE
+			));
+
+			assert_ok!(PaymentModule::withdraw_fee_in_currency(&CHARLIE, 30_000));
+
+			let pair_account = AMMModule::get_pair_id(&HDX, &SUPPORTED_CURRENCY_WITH_BALANCE);
+			let pool_native = Balances::free_balance(pair_account);
+
+			<PaymentModule as OnFinalize<u64>>::on_finalize(11);
+
+			// Half of the 10% slippage bound of the pool reserve is sold in a block
+			let converted = pool_native - Balances::free_balance(pair_account);
+
+			assert!(converted > 0);
+			assert!(multi_payment_event_emitted(RawEvent::FeesConverted(
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				5_000,
+				converted
+			)));
+
+			// The rest is kept for next blocks
+			assert_eq!(PaymentModule::accrued_fees(SUPPORTED_CURRENCY_WITH_BALANCE), 25_000);
+			assert_eq!(
+				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &PaymentModule::fee_account()),
+				25_000
+			);
+		});
+}
+
+#[test]
+fn accrued_fees_should_not_be_converted_over_max_slippage() {
+	const CHARLIE: AccountId = 5;
+
+	ExtBuilder::default()
+		.account_native_balance(CHARLIE, 0)
+		.account_tokens(CHARLIE, SUPPORTED_CURRENCY_WITH_BALANCE, 100_000)
+		.build()
+		.execute_with(|| {
+			System::set_block_number(1);
+
+			assert_ok!(pallet_amm::Module::<Test>::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				100_000,
+				Price::from(1),
+				Fee::default(),
+				pallet_amm::PoolType::ConstantProduct
+			));
+
+			System::set_block_number(11);
+
+			assert_ok!(PaymentModule::set_currency(
+				Origin::signed(CHARLIE),
+				SUPPORTED_CURRENCY_WITH_BALANCE
+			));
+
+			assert_ok!(PaymentModule::withdraw_fee_in_currency(&CHARLIE, 1_000));
+
+			// Spot price of the currency drops by more than 10% allowed, oracle price is still 1
+			assert_ok!(pallet_amm::Module::<Test>::sell(
+				Origin::signed(ALICE),
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				HDX,
+				30_000,
+				0,
+				false,
+				None
+			));
+
+			<PaymentModule as OnFinalize<u64>>::on_finalize(11);
+
+			assert!(multi_payment_event_emitted(RawEvent::FeeConversionFailed(
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				1_000,
+				pallet_amm::Error::<Test>::AssetBalanceLimitExceeded.into()
+			)));
+
+			// Fees are kept for next blocks
+			assert_eq!(PaymentModule::accrued_fees(SUPPORTED_CURRENCY_WITH_BALANCE), 1_000);
+			assert_eq!(
+				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &PaymentModule::fee_account()),
+				1_000
+			);
+		});
+}
+
+#[test]
+fn fee_refund_in_native_currency_should_work() {
+	const CHARLIE: AccountId = 5;
+
+	ExtBuilder::default()
+		.base_weight(5)
+		.account_native_balance(CHARLIE, 100)
+		.build()
+		.execute_with(|| {
+			System::set_block_number(1);
+
+			let len = 10;
+			let info = DispatchInfo {
+				weight: 5,
+				..Default::default()
+			};
+			let post_info = PostDispatchInfo {
+				actual_weight: Some(2),
+				pays_fee: Pays::Yes,
+			};
+
+			let pre = ChargeTransactionPayment::<Test>::from(0)
+				.pre_dispatch(&CHARLIE, CALL, &info, len)
+				.unwrap();
+			assert_eq!(Balances::free_balance(CHARLIE), 100 - 5 - 5 - 10);
+
+			assert!(ChargeTransactionPayment::<Test>::post_dispatch(pre, &info, &post_info, len, &Ok(())).is_ok());
+
+			assert_eq!(Balances::free_balance(CHARLIE), 100 - 5 - 2 - 10);
+			assert!(multi_payment_event_emitted(RawEvent::FeePaid(CHARLIE, HDX, 17, 3)));
+		});
+}
+
+#[test]
+fn fee_refund_in_non_native_currency_should_be_paid_in_that_currency() {
+	const CHARLIE: AccountId = 5;
+
+	ExtBuilder::default()
+		.base_weight(5)
+		.account_native_balance(CHARLIE, 0)
+		.account_tokens(CHARLIE, SUPPORTED_CURRENCY_WITH_BALANCE, 1000)
+		.build()
+		.execute_with(|| {
+			System::set_block_number(1);
+
+			assert_ok!(pallet_amm::Module::<Test>::create_pool(
+				Origin::signed(ALICE),
+				HDX,
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				100000,
+				Price::from(1),
+				Fee::default(),
+				pallet_amm::PoolType::ConstantProduct
+			));
+
+			System::set_block_number(11);
+
+			assert_ok!(PaymentModule::set_currency(
+				Origin::signed(CHARLIE),
+				SUPPORTED_CURRENCY_WITH_BALANCE
+			));
+
+			let len = 10;
+			let info = DispatchInfo {
+				weight: 5,
+				..Default::default()
+			};
+			let post_info = PostDispatchInfo {
+				actual_weight: Some(2),
+				pays_fee: Pays::Yes,
+			};
+
+			let pre = ChargeTransactionPayment::<Test>::from(0)
+				.pre_dispatch(&CHARLIE, CALL, &info, len)
+				.unwrap();
+			assert_eq!(
+				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &CHARLIE),
+				1000 - 20
+			);
+
+			assert!(ChargeTransactionPayment::<Test>::post_dispatch(pre, &info, &post_info, len, &Ok(())).is_ok());
+
+			// Overcharge is refunded in the currency the fee was paid in, no native currency is received
+			assert_eq!(
+				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &CHARLIE),
+				1000 - 17
+			);
+			assert_eq!(Balances::free_balance(CHARLIE), 0);
+
+			assert_eq!(PaymentModule::accrued_fees(SUPPORTED_CURRENCY_WITH_BALANCE), 17);
+			assert_eq!(
+				Tokens::free_balance(SUPPORTED_CURRENCY_WITH_BALANCE, &PaymentModule::fee_account()),
+				17
+			);
+
+			assert!(multi_payment_event_emitted(RawEvent::FeePaid(
+				CHARLIE,
+				SUPPORTED_CURRENCY_WITH_BALANCE,
+				17,
+				3
+			)));
+		});
+}
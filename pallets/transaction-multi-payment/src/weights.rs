@@ -46,7 +46,8 @@ use sp_std::marker::PhantomData;
 
 /// Weight functions needed for transaction_multi_payment.
 pub trait WeightInfo {
-	fn swap_currency() -> Weight;
+	fn withdraw_fee_in_currency() -> Weight;
+	fn convert_fees() -> Weight;
 	fn set_currency() -> Weight;
 	fn add_currency() -> Weight;
 	fn remove_currency() -> Weight;
@@ -55,10 +56,15 @@ pub trait WeightInfo {
 /// Weights for transaction_multi_payment using the hydraDX node and recommended hardware.
 pub struct HydraWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for HydraWeight<T> {
-	fn swap_currency() -> Weight {
-		(168_058_000 as Weight)
+	fn withdraw_fee_in_currency() -> Weight {
+		(98_417_000 as Weight)
 			.saturating_add(T::DbWeight::get().reads(6 as Weight))
-			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn convert_fees() -> Weight {
+		(176_312_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
 	}
 	fn set_currency() -> Weight {
 		(41_621_000 as Weight)
@@ -79,10 +85,15 @@ impl<T: frame_system::Config> WeightInfo for HydraWeight<T> {
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
-	fn swap_currency() -> Weight {
-		(168_058_000 as Weight)
+	fn withdraw_fee_in_currency() -> Weight {
+		(98_417_000 as Weight)
 			.saturating_add(RocksDbWeight::get().reads(6 as Weight))
-			.saturating_add(RocksDbWeight::get().writes(4 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(3 as Weight))
+	}
+	fn convert_fees() -> Weight {
+		(176_312_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(8 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(6 as Weight))
 	}
 	fn set_currency() -> Weight {
 		(41_621_000 as Weight)
@@ -36,6 +36,9 @@ pub const MAX_OUT_RATIO: u128 = 3;
 // Max fraction of pool to sell in single transaction
 pub const MAX_IN_RATIO: u128 = 3;
 
+/// Max number of assets in a route of a multi-hop trade
+pub const MAX_ROUTE_LENGTH: usize = 5;
+
 /// Scaled Unsigned of Balance
 pub type HighPrecisionBalance = U256;
 pub type LowPrecisionBalance = u128;
@@ -53,9 +56,27 @@ impl Default for IntentionType {
 	}
 }
 
+/// How much of an intention has to be filled in its block.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub enum FillPolicy {
+	/// Intention is filled completely in its block or cancelled.
+	FillOrKill,
+	/// Any part of intention can be filled, rest is cancelled.
+	PartialFill,
+	/// Intention is filled completely or stays queued for next blocks.
+	AllOrNone,
+}
+
+impl Default for FillPolicy {
+	fn default() -> FillPolicy {
+		FillPolicy::PartialFill
+	}
+}
+
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
-pub struct ExchangeIntention<AccountId, AssetId, Balance, IntentionID> {
+pub struct ExchangeIntention<AccountId, AssetId, Balance, IntentionID, BlockNumber> {
 	pub who: AccountId,
 	pub asset_sell: AssetId,
 	pub asset_buy: AssetId,
@@ -65,12 +86,44 @@ pub struct ExchangeIntention<AccountId, AssetId, Balance, IntentionID> {
 	pub discount: bool,
 	pub sell_or_buy: IntentionType,
 	pub intention_id: IntentionID,
+	pub fill_policy: FillPolicy,
+	pub deadline: Option<BlockNumber>,
+}
+
+/// How an intention has been traded.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub enum TradeKind {
+	/// Direct trade with another intention.
+	Direct,
+	/// Trade through AMM pool.
+	AMM,
+	/// Direct trade with a resting limit order.
+	LimitOrder,
+	/// Trade at uniform clearing price of a batch auction.
+	BatchAuction,
+	/// Direct trade within a ring of intentions across asset pairs.
+	Ring,
+}
+
+/// Single trade of an intention.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub struct ExchangeTrade<AccountId, AssetId, Balance, IntentionID> {
+	pub kind: TradeKind,
+	pub who: AccountId,
+	pub intention_id: IntentionID,
+	pub asset_sell: AssetId,
+	pub asset_buy: AssetId,
+	pub amount_sold: Balance,
+	pub amount_bought: Balance,
 }
 
 pub mod fee {
 	use crate::Balance;
+	use codec::{Decode, Encode};
 
-	#[derive(Clone, Copy, Eq, PartialEq)]
+	#[derive(Debug, Encode, Decode, Clone, Copy, Eq, PartialEq)]
 	pub struct Fee {
 		pub numerator: u32,
 		pub denominator: u32,
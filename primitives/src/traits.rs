@@ -1,5 +1,4 @@
 use frame_support::dispatch;
-use frame_support::dispatch::DispatchResult;
 use sp_std::vec::Vec;
 
 /// Hold information to perform amm transfer
@@ -12,6 +11,8 @@ pub struct AMMTransfer<AccountId, AssetId, Balance> {
 	pub amount_out: Balance,
 	pub discount: bool,
 	pub discount_amount: Balance,
+	/// Trading fee which stays in the pool - asset, amount
+	pub fee: (AssetId, Balance),
 }
 
 /// Traits for handling AMM Pool trades.
@@ -102,6 +103,15 @@ pub trait Resolver<AccountId, Intention, E> {
 	fn resolve_matched_intentions(pair_account: &AccountId, intention: &Intention, matched: &[Intention]);
 }
 
-pub trait CurrencySwap<AccountId, Balance> {
-	fn swap_currency(who: &AccountId, fee: Balance) -> DispatchResult;
+/// Withdraws transaction fees in currencies other than native currency.
+pub trait CurrencyWithdraw<AccountId, AssetId, Balance> {
+	/// Withdraw `fee` denominated in native currency from `who` in its selected fee currency.
+	/// Returns the currency and the amount withdrawn, `None` if `who` pays in native currency.
+	fn withdraw_fee(who: &AccountId, fee: Balance) -> Result<Option<(AssetId, Balance)>, dispatch::DispatchError>;
+}
+
+/// Provides time weighted average prices of asset pairs.
+pub trait TWAPOracle<AssetId, BlockNumber> {
+	/// Return average price of `asset_a` denominated in `asset_b` over last `window` blocks.
+	fn get_twap(asset_a: AssetId, asset_b: AssetId, window: BlockNumber) -> Option<crate::Price>;
 }
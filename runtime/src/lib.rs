@@ -18,13 +18,13 @@ use sp_core::{
 	OpaqueMetadata,
 };
 use sp_runtime::traits::{
-	BlakeTwo256, Block as BlockT, IdentifyAccount, IdentityLookup, NumberFor, OpaqueKeys, Verify,
+	AccountIdConversion, BlakeTwo256, Block as BlockT, IdentifyAccount, IdentityLookup, NumberFor, OpaqueKeys, Verify,
 };
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::Zero,
 	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
-	ApplyExtrinsicResult, ModuleId, MultiSignature, Percent,
+	ApplyExtrinsicResult, FixedPointNumber, ModuleId, MultiSignature, Percent,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
@@ -54,6 +54,7 @@ use sp_runtime::curve::PiecewiseLinear;
 use pallet_session::historical as session_historical;
 
 use module_amm_rpc_runtime_api as amm_rpc;
+use module_exchange_rpc_runtime_api as exchange_rpc;
 
 use orml_currencies::BasicCurrencyAdapter;
 use orml_traits::parameter_type_with_key;
@@ -128,10 +129,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("hydra-dx"),
 	impl_name: create_runtime_str!("hydra-dx"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 2,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 /// We assume that an on-initialize consumes 2.5% of the weight on average, hence a single extrinsic
@@ -211,7 +212,7 @@ parameter_types! {
 		.avg_block_initialization(AVERAGE_ON_INITIALIZE_RATIO)
 		.build_or_panic();
 
-	pub ExtrinsicPaymentExtraWeight: Weight =  <Runtime as pallet_transaction_multi_payment::Config>::WeightInfo::swap_currency();
+	pub ExtrinsicPaymentExtraWeight: Weight =  <Runtime as pallet_transaction_multi_payment::Config>::WeightInfo::withdraw_fee_in_currency();
 	pub ExtrinsicBaseWeight: Weight = frame_support::weights::constants::ExtrinsicBaseWeight::get() + ExtrinsicPaymentExtraWeight::get();
 }
 
@@ -321,12 +322,23 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ();
 }
 
+parameter_types! {
+	pub const MultiPaymentOracleWindow: BlockNumber = 10;
+	pub const MultiPaymentMaxConversionSlippage: Permill = Permill::from_percent(3);
+	pub const MultiPaymentModuleId: ModuleId = ModuleId(*b"hdx/fees");
+}
+
 impl pallet_transaction_multi_payment::Config for Runtime {
 	type Event = Event;
 	type Currency = Balances;
 	type MultiCurrency = Currencies;
 	type AMMPool = AMM;
 	type WeightInfo = pallet_transaction_multi_payment::weights::HydraWeight<Runtime>;
+	type PriceOracle = AMM;
+	type OracleWindow = MultiPaymentOracleWindow;
+	type MaxConversionSlippage = MultiPaymentMaxConversionSlippage;
+	type ModuleId = MultiPaymentModuleId;
+	type OnConvertedFees = ();
 }
 
 impl pallet_sudo::Config for Runtime {
@@ -366,7 +378,13 @@ impl pallet_asset_registry::Config for Runtime {
 }
 
 parameter_types! {
-	pub ExchangeFee: fee::Fee = fee::Fee::default();
+	pub MaxExchangeFee: fee::Fee = fee::Fee {
+		numerator: 1,
+		denominator: 100,
+	}; // 1%
+	pub const AMMProtocolFee: Permill = Permill::from_percent(20);
+	pub AMMProtocolFeeReceiver: AccountId = TreasuryModuleId::get().into_account();
+	pub const AMMPriceHistoryLength: u32 = 256;
 }
 
 impl pallet_amm::Config for Runtime {
@@ -375,7 +393,30 @@ impl pallet_amm::Config for Runtime {
 	type Currency = Currencies;
 	type HDXAssetId = HDXAssetId;
 	type WeightInfo = pallet_amm::weights::HydraWeight<Runtime>;
-	type GetExchangeFee = ExchangeFee;
+	type MaxExchangeFee = MaxExchangeFee;
+	type UpdatePoolFeeOrigin = EnsureOneOf<
+		AccountId,
+		EnsureRoot<AccountId>,
+		pallet_collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>,
+	>;
+	type ProtocolFee = AMMProtocolFee;
+	type ProtocolFeeReceiver = AMMProtocolFeeReceiver;
+	type PriceHistoryLength = AMMPriceHistoryLength;
+}
+
+parameter_types! {
+	pub const ExchangeModuleId: ModuleId = ModuleId(*b"hdx/xchg");
+	pub const ExchangeMaxIntentionsPerBlock: u32 = 256;
+	pub const ExchangeMaxQueuedIntentions: u32 = 1_024;
+	pub const ExchangeMaxIntentionRequeues: u32 = 100;
+	pub ExchangeDirectTradeFee: fee::Fee = fee::Fee::default(); // 0.2%
+	pub ExchangeDiscountedDirectTradeFee: fee::Fee = fee::Fee { numerator: 7, denominator: 10_000 }; // 0.07%
+	pub const ExchangeDirectTradeFeeShare: Permill = Permill::from_percent(0);
+	pub ExchangeDirectTradeFeeReceiver: AccountId = TreasuryModuleId::get().into_account();
+	pub const ExchangeMaxLimitOrders: u32 = 1_000;
+	pub const ExchangeMaxLimitOrdersPerBlock: u32 = 16;
+	pub const ExchangeMinLimitOrderAmount: Balance = DOLLARS;
+	pub const ExchangeMaxLimitOrderExpiry: BlockNumber = 30 * DAYS;
 }
 
 impl pallet_exchange::Config for Runtime {
@@ -384,6 +425,19 @@ impl pallet_exchange::Config for Runtime {
 	type Resolver = Exchange;
 	type Currency = Currencies;
 	type WeightInfo = pallet_exchange::weights::HydraWeight<Runtime>;
+	type ModuleId = ExchangeModuleId;
+	type MatchingStrategy = pallet_exchange::BatchAuction;
+	type MaxIntentionsPerBlock = ExchangeMaxIntentionsPerBlock;
+	type MaxQueuedIntentions = ExchangeMaxQueuedIntentions;
+	type MaxIntentionRequeues = ExchangeMaxIntentionRequeues;
+	type DirectTradeFee = ExchangeDirectTradeFee;
+	type DiscountedDirectTradeFee = ExchangeDiscountedDirectTradeFee;
+	type DirectTradeFeeShare = ExchangeDirectTradeFeeShare;
+	type DirectTradeFeeReceiver = ExchangeDirectTradeFeeReceiver;
+	type MaxLimitOrders = ExchangeMaxLimitOrders;
+	type MaxLimitOrdersPerBlock = ExchangeMaxLimitOrdersPerBlock;
+	type MinLimitOrderAmount = ExchangeMinLimitOrderAmount;
+	type MaxLimitOrderExpiry = ExchangeMaxLimitOrderExpiry;
 }
 
 impl pallet_faucet::Config for Runtime {
@@ -703,14 +757,47 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_amm::CheckDeadline<Runtime>,
+	pallet_exchange::CheckDeadline<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
-pub type Executive =
-	frame_executive::Executive<Runtime, Block, frame_system::ChainContext<Runtime>, Runtime, AllModules>;
+pub type Executive = frame_executive::Executive<
+	Runtime,
+	Block,
+	frame_system::ChainContext<Runtime>,
+	Runtime,
+	AllModules,
+	AmmFeeSharesMigration,
+>;
+
+/// Call of exchange pallet, eg. hypothetical intention of resolution simulation.
+pub type ExchangeCall = pallet_exchange::Call<Runtime>;
+
+/// Exchange calls of signed extrinsics with their signers.
+fn exchange_calls(extrinsics: Vec<UncheckedExtrinsic>) -> Vec<(AccountId, ExchangeCall)> {
+	extrinsics
+		.into_iter()
+		.filter_map(|xt| match (xt.signature, xt.function) {
+			(Some((who, _, _)), Call::Exchange(call)) => Some((who, call)),
+			_ => None,
+		})
+		.collect()
+}
+
+/// Registers AMM pool shares held before fee shares were introduced, so that they earn trading fees.
+pub struct AmmFeeSharesMigration;
+
+impl frame_support::traits::OnRuntimeUpgrade for AmmFeeSharesMigration {
+	fn on_runtime_upgrade() -> Weight {
+		let holders = orml_tokens::Accounts::<Runtime>::iter().map(|(who, asset, account)| (who, asset, account.free));
+
+		AMM::migrate_fee_shares(holders)
+	}
+}
 
 impl_runtime_apis! {
 	impl sp_consensus_babe::BabeApi<Block> for Runtime {
@@ -936,6 +1023,135 @@ impl_runtime_apis! {
 			vec
 		}
 
+		fn get_best_route(
+			asset_in: AssetId,
+			asset_out: AssetId,
+			amount: Balance,
+			max_hops: u32,
+		) -> amm_rpc::RouteInfo<AssetId, Balance> {
+			match AMM::get_best_sell_route(asset_in, asset_out, amount, max_hops) {
+				Some((route, amount)) => amm_rpc::RouteInfo{
+					route,
+					amount
+				},
+				None => amm_rpc::RouteInfo::default(),
+			}
+		}
+
+		fn get_twap(
+			asset_a: AssetId,
+			asset_b: AssetId,
+			amount: Balance,
+			window: u32,
+		) -> Option<amm_rpc::BalanceInfo<AssetId, Balance>> {
+			AMM::get_twap(asset_a, asset_b, window).map(|price| amm_rpc::BalanceInfo{
+				asset: None,
+				amount: price.saturating_mul_int(amount)
+			})
+		}
+
+		fn get_weighted_twap(
+			pool_assets: Vec<AssetId>,
+			asset_a: AssetId,
+			asset_b: AssetId,
+			amount: Balance,
+			window: u32,
+		) -> Option<amm_rpc::BalanceInfo<AssetId, Balance>> {
+			AMM::get_weighted_twap(&pool_assets, asset_a, asset_b, window).map(|price| amm_rpc::BalanceInfo{
+				asset: None,
+				amount: price.saturating_mul_int(amount)
+			})
+		}
+
+		fn get_accrued_fees(
+			who: AccountId,
+			pool_address: AccountId,
+		) -> Vec<amm_rpc::BalanceInfo<AssetId, Balance>> {
+			AMM::get_accrued_fees(&who, &pool_address)
+				.into_iter()
+				.map(|(asset, amount)| amm_rpc::BalanceInfo {
+					asset: Some(asset),
+					amount,
+				})
+				.collect()
+		}
+
+		fn get_positions(
+			who: AccountId,
+		) -> Vec<amm_rpc::PositionInfo<AccountId, AssetId, Balance>> {
+			let to_balance_info = |balances: Vec<(AssetId, Balance)>| -> Vec<amm_rpc::BalanceInfo<AssetId, Balance>> {
+				balances
+					.into_iter()
+					.map(|(asset, amount)| amm_rpc::BalanceInfo {
+						asset: Some(asset),
+						amount,
+					})
+					.collect()
+			};
+
+			AMM::get_positions(&who)
+				.into_iter()
+				.map(|details| amm_rpc::PositionInfo {
+					position_id: details.position_id,
+					pool_address: details.position.pool,
+					tick_lower: details.position.tick_lower,
+					tick_upper: details.position.tick_upper,
+					liquidity: details.position.liquidity,
+					amounts: to_balance_info(details.amounts),
+					fees: to_balance_info(details.fees),
+				})
+				.collect()
+		}
+
+	}
+
+	impl exchange_rpc::ExchangeApi<
+		Block,
+		AccountId,
+		AssetId,
+		Balance,
+		Hash,
+	> for Runtime {
+		fn get_queue_depth() -> u32 {
+			Exchange::queue_depth()
+		}
+
+		fn get_pending_intentions(
+			asset_a: AssetId,
+			asset_b: AssetId,
+			pending: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Vec<exchange_rpc::IntentionInfo<AccountId, AssetId, Balance, Hash>> {
+			Exchange::with_pending_calls(exchange_calls(pending), || Exchange::pending_intentions(asset_a, asset_b))
+				.into_iter()
+				.map(Into::into)
+				.collect()
+		}
+
+		fn get_intentions_of(
+			who: AccountId,
+			pending: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Vec<exchange_rpc::IntentionInfo<AccountId, AssetId, Balance, Hash>> {
+			Exchange::with_pending_calls(exchange_calls(pending), || Exchange::intentions_of(&who))
+				.into_iter()
+				.map(Into::into)
+				.collect()
+		}
+	}
+
+	impl exchange_rpc::ExchangeSimulationApi<
+		Block,
+		AccountId,
+		AssetId,
+		Balance,
+		Hash,
+		ExchangeCall,
+	> for Runtime {
+		fn simulate_resolution(
+			pending: Vec<<Block as BlockT>::Extrinsic>,
+			call: Option<(AccountId, ExchangeCall)>,
+		) -> Result<Vec<primitives::ExchangeTrade<AccountId, AssetId, Balance, Hash>>, sp_runtime::DispatchError> {
+			Exchange::simulate_resolution(exchange_calls(pending), call)
+		}
 	}
 
 	#[cfg(feature = "runtime-benchmarks")]